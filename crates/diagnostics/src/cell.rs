@@ -1,9 +1,10 @@
-use crate::display::{dump_kv, dump_kvi};
-use holochain_state::{db, env::EnvironmentWrite, prelude::*};
+use crate::display::{dump_kv, dump_kvi, dump_kvv};
+use holochain_state::{db, env::EnvironmentRead, prelude::*};
 use holochain_types::{app::CellNick, cell::CellId};
 
+/// Print size stats of each database of a cell
 pub async fn dump_cell_state(
-    env: EnvironmentWrite,
+    env: EnvironmentRead,
     _cell_id: CellId,
     cell_nick: &CellNick,
 ) -> anyhow::Result<()> {
@@ -25,6 +26,13 @@ pub async fn dump_cell_state(
         };
     }
 
+    macro_rules! kvv {
+        ($name: expr, $db: ident) => {
+            let db = env.get_db(&$db)?;
+            dump_kvv(&r, $name, db)?;
+        };
+    }
+
     println!();
     println!(
         "+++++++++++++++++++++++++  cell \"{}\"  +++++++++++++++++++++++++",
@@ -44,15 +52,20 @@ pub async fn dump_cell_state(
     kv!("element vault - headers", ELEMENT_VAULT_HEADERS);
     kv!("metadata vault - links", META_VAULT_LINKS);
     kv!("metadata vault - misc", META_VAULT_MISC);
+    kvv!("metadata vault - system", META_VAULT_SYS);
 
     kv!("element cache - entries", ELEMENT_CACHE_ENTRIES);
     kv!("element cache - headers", ELEMENT_CACHE_HEADERS);
     kv!("metadata cache - links", CACHE_LINKS_META);
     kv!("metadata cache - status", CACHE_STATUS_META);
+    kvv!("metadata cache - system", CACHE_SYSTEM_META);
 
-    kv!("integration queue", integration_limbo);
+    kv!("validation limbo", VALIDATION_LIMBO);
+    kv!("integration limbo", INTEGRATION_LIMBO);
     kv!("integrated dht ops", INTEGRATED_DHT_OPS);
     kv!("authored dht ops", AUTHORED_DHT_OPS);
+    kvv!("validation receipts", VALIDATION_RECEIPTS);
+    kvv!("warrants", WARRANTS);
 
     Ok(())
 }
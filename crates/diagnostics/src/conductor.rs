@@ -1,11 +1,57 @@
 use crate::display::human_size;
 use holochain::conductor::{state::ConductorState, ConductorStateDb};
-use holochain_state::{db::CONDUCTOR_STATE, env::EnvironmentWrite, prelude::*};
+use holochain_state::{db::CONDUCTOR_STATE, env::EnvironmentRead, prelude::*};
+use holochain_types::{
+    app::{CellNick, InstalledAppId},
+    cell::CellId,
+};
 
-pub async fn dump_conductor_state(env: EnvironmentWrite) -> anyhow::Result<ConductorState> {
+/// A cell installed in the conductor
+#[derive(Clone, Debug)]
+pub struct InstalledCellInfo {
+    /// The app the cell is part of
+    pub installed_app_id: InstalledAppId,
+    /// The cell
+    pub cell_id: CellId,
+    /// The nick of the cell within the app
+    pub cell_nick: CellNick,
+    /// Whether the app is active
+    pub active: bool,
+}
+
+/// Read the state of the conductor
+pub fn conductor_state(env: &EnvironmentRead) -> anyhow::Result<ConductorState> {
+    let g = env.guard();
+    let r = g.reader()?;
+    let db = ConductorStateDb::new(env.get_db(&CONDUCTOR_STATE)?);
+    Ok(db.get(&r, &().into())?.unwrap_or_default())
+}
+
+/// List the cells of every installed app, active or not
+pub fn list_cells(env: &EnvironmentRead) -> anyhow::Result<Vec<InstalledCellInfo>> {
+    let state = conductor_state(env)?;
+    let active = state.active_apps.into_iter().map(|app| (app, true));
+    let inactive = state.inactive_apps.into_iter().map(|app| (app, false));
+    let mut cells = Vec::new();
+    for ((installed_app_id, installed_cells), active) in active.chain(inactive) {
+        for cell in installed_cells {
+            let (cell_id, cell_nick) = cell.into_inner();
+            cells.push(InstalledCellInfo {
+                installed_app_id: installed_app_id.clone(),
+                cell_id,
+                cell_nick,
+                active,
+            });
+        }
+    }
+    Ok(cells)
+}
+
+/// Print the size and contents of the conductor state
+pub async fn dump_conductor_state(env: EnvironmentRead) -> anyhow::Result<ConductorState> {
     let g = env.guard();
     let r = g.reader()?;
-    let db = ConductorStateDb::new(env.get_db(&CONDUCTOR_STATE)?)?;
+    let db = ConductorStateDb::new(env.get_db(&CONDUCTOR_STATE)?);
     let bytes = db.get_bytes(&r, &().into())?.unwrap();
     let state = db.get(&r, &().into())?.unwrap();
 
@@ -0,0 +1,222 @@
+//! Find ops of a cell which the stores of ops and elements disagree about
+
+use fallible_iterator::FallibleIterator;
+use holo_hash::{DhtOpHash, HeaderHash};
+use holochain::core::state::{
+    dht_op_integration::{AuthoredDhtOpsStore, IntegratedDhtOpsStore, IntegrationLimboStore},
+    element_buf::ElementBuf,
+    validation_db::ValidationLimboStore,
+};
+use holochain_state::{
+    buffer::{KvBufFresh, KvBufUsed},
+    db::{AUTHORED_DHT_OPS, INTEGRATED_DHT_OPS, INTEGRATION_LIMBO},
+    env::EnvironmentRead,
+    error::DatabaseResult,
+    key::BufVal,
+    prelude::*,
+};
+use holochain_types::dht_op::DhtOpLight;
+use std::{collections::HashMap, fmt};
+
+/// An op which the stores of a cell disagree about
+#[derive(Clone, Debug, PartialEq)]
+pub enum OpInconsistency {
+    /// The op is held by more than one of the validation limbo,
+    /// the integration limbo and the integrated ops, which are meant to be
+    /// stages it moves through one at a time
+    InSeveralStages(DhtOpHash, Vec<&'static str>),
+    /// The op is integrated, but its header is neither in the vault
+    /// nor among the rejected elements
+    IntegratedWithoutHeader(DhtOpHash, HeaderHash),
+    /// The op was authored, but its header isn't on the source chain
+    AuthoredWithoutHeader(DhtOpHash, HeaderHash),
+    /// The op is in a limbo, but its header isn't among the pending elements
+    LimboWithoutHeader(DhtOpHash, HeaderHash),
+}
+
+impl fmt::Display for OpInconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpInconsistency::InSeveralStages(op, stages) => {
+                write!(f, "op {} is in: {}", op, stages.join(", "))
+            }
+            OpInconsistency::IntegratedWithoutHeader(op, header) => write!(
+                f,
+                "op {} is integrated but header {} is not in the vault",
+                op, header
+            ),
+            OpInconsistency::AuthoredWithoutHeader(op, header) => write!(
+                f,
+                "op {} was authored but header {} is not on the chain",
+                op, header
+            ),
+            OpInconsistency::LimboWithoutHeader(op, header) => write!(
+                f,
+                "op {} is in limbo but header {} is not pending",
+                op, header
+            ),
+        }
+    }
+}
+
+/// Cross-check the ops of a cell against each other and against the
+/// elements they were produced from. An empty list means the stores agree.
+pub fn check_ops(env: &EnvironmentRead) -> DatabaseResult<Vec<OpInconsistency>> {
+    let authored: AuthoredDhtOpsStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*AUTHORED_DHT_OPS)?);
+    let validation_limbo = ValidationLimboStore::new(env.clone().into())?;
+    let integration_limbo: IntegrationLimboStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*INTEGRATION_LIMBO)?);
+    let integrated: IntegratedDhtOpsStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*INTEGRATED_DHT_OPS)?);
+
+    let authored_elements = ElementBuf::authored(env.clone().into(), true)?;
+    let vault = ElementBuf::vault(env.clone().into(), true)?;
+    let pending = ElementBuf::pending(env.clone().into())?;
+    let rejected = ElementBuf::rejected(env.clone().into())?;
+
+    let g = env.guard();
+    let r = g.reader()?;
+    let authored = op_headers(&*authored, &r, |v| &v.op)?;
+    let validation_limbo = op_headers(&*validation_limbo.0, &r, |v| &v.op)?;
+    let integration_limbo = op_headers(&*integration_limbo, &r, |v| &v.op)?;
+    let integrated = op_headers(&*integrated, &r, |v| &v.op)?;
+
+    let mut problems = Vec::new();
+
+    let mut stages: HashMap<&DhtOpHash, Vec<&'static str>> = HashMap::new();
+    for (name, ops) in &[
+        ("validation limbo", &validation_limbo),
+        ("integration limbo", &integration_limbo),
+        ("integrated", &integrated),
+    ] {
+        for op in ops.keys() {
+            stages.entry(op).or_default().push(*name);
+        }
+    }
+    let mut several: Vec<_> = stages.into_iter().filter(|(_, s)| s.len() > 1).collect();
+    several.sort_by(|a, b| a.0.cmp(b.0));
+    for (op, stages) in several {
+        problems.push(OpInconsistency::InSeveralStages(op.clone(), stages));
+    }
+
+    for (op, header) in sorted(&integrated) {
+        if !vault.contains_header(header)? && !rejected.contains_header(header)? {
+            problems.push(OpInconsistency::IntegratedWithoutHeader(
+                op.clone(),
+                header.clone(),
+            ));
+        }
+    }
+    for (op, header) in sorted(&authored) {
+        if !authored_elements.contains_header(header)? {
+            problems.push(OpInconsistency::AuthoredWithoutHeader(
+                op.clone(),
+                header.clone(),
+            ));
+        }
+    }
+    for (op, header) in sorted(&validation_limbo)
+        .into_iter()
+        .chain(sorted(&integration_limbo))
+    {
+        if !pending.contains_header(header)? {
+            problems.push(OpInconsistency::LimboWithoutHeader(
+                op.clone(),
+                header.clone(),
+            ));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// The header of each op in a store
+fn op_headers<R: Readable, V: BufVal>(
+    store: &KvBufUsed<DhtOpHash, V>,
+    r: &R,
+    op: fn(&V) -> &DhtOpLight,
+) -> DatabaseResult<HashMap<DhtOpHash, HeaderHash>> {
+    store
+        .iter(r)?
+        .map(|(k, v)| {
+            Ok((
+                DhtOpHash::from_raw_39_panicky(k.to_vec()),
+                op(&v).header_hash().clone(),
+            ))
+        })
+        .collect()
+}
+
+/// Report problems in a stable order
+fn sorted(ops: &HashMap<DhtOpHash, HeaderHash>) -> Vec<(&DhtOpHash, &HeaderHash)> {
+    let mut ops: Vec<_> = ops.iter().collect();
+    ops.sort();
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::genesis_env;
+    use holochain::core::state::{
+        dht_op_integration::{AuthoredDhtOpsValue, IntegratedDhtOpsValue},
+        source_chain::SourceChainBuf,
+    };
+    use holochain_types::{validate::ValidationStatus, Timestamp};
+
+    #[tokio::test(threaded_scheduler)]
+    async fn genesis_ops_are_consistent() {
+        let test_env = genesis_env().await;
+        let env: EnvironmentRead = test_env.env().into();
+        assert_eq!(check_ops(&env).unwrap(), vec![]);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn integrated_op_without_header_is_reported() {
+        let test_env = genesis_env().await;
+        let env = test_env.env();
+        let header = SourceChainBuf::new(env.clone().into())
+            .unwrap()
+            .sequence()
+            .get(0)
+            .unwrap()
+            .unwrap();
+        let op_hash = DhtOpHash::from_raw_36(vec![1; 36]);
+        let op = DhtOpLight::RegisterAgentActivity(header.clone(), header.clone().into());
+
+        // The op agrees with the authored chain, but its header was never
+        // put in the vault
+        let mut authored: AuthoredDhtOpsStore =
+            KvBufFresh::new(env.clone().into(), env.get_db(&*AUTHORED_DHT_OPS).unwrap());
+        authored
+            .put(op_hash.clone(), AuthoredDhtOpsValue::from_light(op.clone()))
+            .unwrap();
+        let mut integrated: IntegratedDhtOpsStore = KvBufFresh::new(
+            env.clone().into(),
+            env.get_db(&*INTEGRATED_DHT_OPS).unwrap(),
+        );
+        integrated
+            .put(
+                op_hash.clone(),
+                IntegratedDhtOpsValue {
+                    validation_status: ValidationStatus::Valid,
+                    op,
+                    when_integrated: Timestamp::now(),
+                },
+            )
+            .unwrap();
+        env.guard()
+            .with_commit(|writer| {
+                authored.flush_to_txn(writer)?;
+                integrated.flush_to_txn(writer)
+            })
+            .unwrap();
+
+        let env: EnvironmentRead = env.into();
+        assert_eq!(
+            check_ops(&env).unwrap(),
+            vec![OpInconsistency::IntegratedWithoutHeader(op_hash, header)]
+        );
+    }
+}
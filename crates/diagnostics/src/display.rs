@@ -1,7 +1,13 @@
-use holochain_state::{error::DatabaseResult, transaction::Reader};
+use holochain_state::{
+    backend::RawIter,
+    error::DatabaseResult,
+    exports::{IntegerStore, MultiStore, SingleStore},
+    transaction::Readable,
+};
 
 const BYTE_SIZE_MARKERS: [char; 6] = [' ', 'K', 'M', 'G', 'T', 'P'];
 
+/// Format a number of bytes with a binary unit
 pub fn human_size(size: usize) -> String {
     fn recurse(size: f32, marker_index: usize) -> String {
         if size > 1024. {
@@ -17,54 +23,31 @@ pub fn human_size(size: usize) -> String {
     recurse(size as f32, 0)
 }
 
-fn dump_iter<'i>(
-    name: &str,
-    it: impl Iterator<Item = Result<(&'i [u8], Option<rkv::Value<'i>>), rkv::StoreError>>,
-) -> DatabaseResult<()> {
+fn dump_iter(name: &str, it: RawIter) -> DatabaseResult<()> {
     let items = it
-        .map(|kv| {
-            let (k, v) = kv.unwrap();
-            let key = k.len();
-            let val = v.map(|v| v.to_bytes().unwrap().len()).unwrap_or(0);
-            key + val
-        })
-        .collect();
+        .map(|kv| kv.map(|(k, v)| k.len() + v.len()))
+        .collect::<DatabaseResult<_>>()?;
     println!("<DB \"{}\">", name);
     println!("{}", SizeStats::new(items));
     Ok(())
 }
 
-#[allow(dead_code)]
-fn dump_iter_multi<'i>(
-    name: &str,
-    it: impl Iterator<Item = Result<(&'i [u8], rkv::Value<'i>), rkv::StoreError>>,
-) -> DatabaseResult<()> {
-    let items = it
-        .map(|kv| {
-            // FIXME: we're ignoring the key here because its duplicated across items
-            let (_, v) = kv.unwrap();
-            let val = v.to_bytes().unwrap().len();
-            val
-        })
-        .collect();
-    println!("<DB \"{}\">", name);
-    println!("{}", SizeStats::new(items));
-    Ok(())
+/// Print size stats of a single-value database
+pub fn dump_kv<R: Readable>(reader: &R, name: &str, db: SingleStore) -> DatabaseResult<()> {
+    dump_iter(name, db.iter_start(reader)?)
 }
 
-pub fn dump_kv(reader: &Reader, name: &str, db: rkv::SingleStore) -> DatabaseResult<()> {
+/// Print size stats of an integer-keyed database
+pub fn dump_kvi<R: Readable>(reader: &R, name: &str, db: IntegerStore) -> DatabaseResult<()> {
     dump_iter(name, db.iter_start(reader)?)
 }
 
-pub fn dump_kvi(reader: &Reader, name: &str, db: rkv::IntegerStore<u32>) -> DatabaseResult<()> {
+/// Print size stats of a multi-value database, counting each value as an item
+pub fn dump_kvv<R: Readable>(reader: &R, name: &str, db: MultiStore) -> DatabaseResult<()> {
     dump_iter(name, db.iter_start(reader)?)
 }
 
-// TODO:
-// pub fn dump_kvv(reader: &Reader, name: &str, db: rkv::MultiStore) -> DatabaseResult<()> {
-//     dump_iter_multi(name, db.iter_start(reader)?)
-// }
-
+/// The number of items in a database and statistics of their sizes
 pub struct SizeStats {
     count: usize,
     total: usize,
@@ -73,6 +56,7 @@ pub struct SizeStats {
 }
 
 impl SizeStats {
+    /// Compute the stats of the sizes of some items
     pub fn new(items: Vec<usize>) -> Self {
         let count = items.iter().count();
         let total = items.iter().sum();
@@ -0,0 +1,55 @@
+use holochain_keystore::{test_keystore::spawn_test_keystore, KeystoreSender};
+use holochain_state::env::{EnvironmentKind, EnvironmentRead};
+use holochain_types::cell::CellId;
+use std::path::{Path, PathBuf};
+
+/// The environments of a conductor, under its root environment directory
+pub struct Environments {
+    path: PathBuf,
+    keystore: KeystoreSender,
+}
+
+impl Environments {
+    /// Read the environments under this directory
+    pub async fn open(path: &Path) -> anyhow::Result<Self> {
+        // throwaway keystore that we'll never use to sign.
+        let keystore = spawn_test_keystore().await?;
+        Ok(Self {
+            path: path.to_owned(),
+            keystore,
+        })
+    }
+
+    /// The conductor's environment
+    pub fn conductor(&self) -> anyhow::Result<EnvironmentRead> {
+        self.env(EnvironmentKind::Conductor)
+    }
+
+    /// The environment of the Dnas and their wasm
+    pub fn wasm(&self) -> anyhow::Result<EnvironmentRead> {
+        self.env(EnvironmentKind::Wasm)
+    }
+
+    /// The environment of the peer store
+    pub fn p2p(&self) -> anyhow::Result<EnvironmentRead> {
+        self.env(EnvironmentKind::P2p)
+    }
+
+    /// The environment of a cell
+    pub fn cell(&self, cell_id: CellId) -> anyhow::Result<EnvironmentRead> {
+        self.env(EnvironmentKind::Cell(cell_id))
+    }
+
+    fn env(&self, kind: EnvironmentKind) -> anyhow::Result<EnvironmentRead> {
+        let path = self.path.join(kind.path());
+        if !path.is_dir() {
+            anyhow::bail!("there is no environment at {}", path.display());
+        }
+        // Never write to the environments being inspected
+        Ok(EnvironmentRead::open_read_only(
+            &self.path,
+            kind,
+            self.keystore.clone(),
+        )?)
+    }
+}
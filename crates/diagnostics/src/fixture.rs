@@ -0,0 +1,27 @@
+//! Cell environments to run the checks against
+
+use holochain::core::state::source_chain::SourceChainBuf;
+use holochain_state::{
+    prelude::*,
+    test_utils::{test_cell_env, TestEnvironment},
+};
+use holochain_types::test_utils::{fake_agent_pubkey_1, fake_dna_file};
+
+/// A cell environment holding a chain of just the genesis elements
+pub(crate) async fn genesis_env() -> TestEnvironment {
+    let test_env = test_cell_env();
+    let env = test_env.env();
+    let mut chain = SourceChainBuf::new(env.clone().into()).unwrap();
+    chain
+        .genesis(
+            fake_dna_file("a").dna_hash().clone(),
+            fake_agent_pubkey_1(),
+            None,
+        )
+        .await
+        .unwrap();
+    env.guard()
+        .with_commit(|writer| chain.flush_to_txn(writer))
+        .unwrap();
+    test_env
+}
@@ -0,0 +1,206 @@
+//! Look up headers, entries, ops and metadata of a cell by hash
+
+use fallible_iterator::FallibleIterator;
+use holo_hash::{DhtOpHash, EntryHash, HeaderHash};
+use holochain::core::state::{
+    dht_op_integration::{
+        AuthoredDhtOpsStore, AuthoredDhtOpsValue, IntegratedDhtOpsStore, IntegratedDhtOpsValue,
+        IntegrationLimboStore, IntegrationLimboValue,
+    },
+    element_buf::ElementBuf,
+    metadata::{MetadataBuf, MetadataBufT},
+    validation_db::{ValidationLimboStore, ValidationLimboValue},
+};
+use holochain_state::{
+    buffer::KvBufFresh,
+    db::{AUTHORED_DHT_OPS, INTEGRATED_DHT_OPS, INTEGRATION_LIMBO},
+    env::EnvironmentRead,
+    error::DatabaseResult,
+    prelude::*,
+};
+use holochain_types::{
+    element::SignedHeaderHashed,
+    metadata::{EntryDhtStatus, TimedHeaderHash},
+    EntryHashed,
+};
+use holochain_zome_types::validate::ValidationStatus;
+
+/// Look something up in every element store of a cell,
+/// keeping the stores which have it
+macro_rules! find_in_element_stores {
+    ($env: expr, |$buf: ident| $get: expr) => {{
+        let env: EnvironmentRead = $env.clone().into();
+        let mut found = Vec::new();
+
+        macro_rules! look {
+            ($name: expr, $store: expr) => {
+                let $buf = $store;
+                if let Some(t) = $get {
+                    found.push(($name, t));
+                }
+            };
+        }
+
+        look!("authored", ElementBuf::authored(env.clone(), true)?);
+        look!("integrated", ElementBuf::vault(env.clone(), true)?);
+        look!("pending", ElementBuf::pending(env.clone())?);
+        look!("rejected", ElementBuf::rejected(env.clone())?);
+        look!("cache", ElementBuf::cache(env)?);
+        Ok(found)
+    }};
+}
+
+/// Find a header in every element store of a cell which holds it
+pub fn find_header(
+    env: &EnvironmentRead,
+    hash: &HeaderHash,
+) -> DatabaseResult<Vec<(&'static str, SignedHeaderHashed)>> {
+    find_in_element_stores!(env, |buf| buf.get_header(hash)?)
+}
+
+/// Find an entry in every element store of a cell which holds it,
+/// including private entries
+pub fn find_entry(
+    env: &EnvironmentRead,
+    hash: &EntryHash,
+) -> DatabaseResult<Vec<(&'static str, EntryHashed)>> {
+    find_in_element_stores!(env, |buf| buf.get_entry(hash)?)
+}
+
+/// Where an op is held, with what each store knows about it
+#[derive(Clone, Debug, Default)]
+pub struct OpRecords {
+    /// The op was authored by this cell
+    pub authored: Option<AuthoredDhtOpsValue>,
+    /// The op is waiting to be validated
+    pub validation_limbo: Option<ValidationLimboValue>,
+    /// The op is validated and waiting to be integrated
+    pub integration_limbo: Option<IntegrationLimboValue>,
+    /// The op is integrated
+    pub integrated: Option<IntegratedDhtOpsValue>,
+}
+
+/// Find an op in every store of ops of a cell
+pub fn find_op(env: &EnvironmentRead, hash: &DhtOpHash) -> DatabaseResult<OpRecords> {
+    let authored: AuthoredDhtOpsStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*AUTHORED_DHT_OPS)?);
+    let validation_limbo = ValidationLimboStore::new(env.clone().into())?;
+    let integration_limbo: IntegrationLimboStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*INTEGRATION_LIMBO)?);
+    let integrated: IntegratedDhtOpsStore =
+        KvBufFresh::new(env.clone().into(), env.get_db(&*INTEGRATED_DHT_OPS)?);
+    Ok(OpRecords {
+        authored: authored.get(hash)?,
+        validation_limbo: validation_limbo.0.get(hash)?,
+        integration_limbo: integration_limbo.get(hash)?,
+        integrated: integrated.get(hash)?,
+    })
+}
+
+/// What the integrated metadata says about an entry
+#[derive(Clone, Debug)]
+pub struct EntryMetadata {
+    /// The headers which create the entry
+    pub headers: Vec<TimedHeaderHash>,
+    /// The headers which update the entry
+    pub updates: Vec<TimedHeaderHash>,
+    /// The headers which delete the entry
+    pub deletes: Vec<TimedHeaderHash>,
+    /// Whether the entry is live
+    pub dht_status: EntryDhtStatus,
+}
+
+/// What the integrated metadata says about a header
+#[derive(Clone, Debug)]
+pub struct HeaderMetadata {
+    /// The validation status of the header, if it has been validated
+    pub validation_status: Option<ValidationStatus>,
+    /// The headers which update the header
+    pub updates: Vec<TimedHeaderHash>,
+    /// The headers which delete the header
+    pub deletes: Vec<TimedHeaderHash>,
+}
+
+/// Read the integrated metadata of an entry
+pub fn entry_metadata(env: &EnvironmentRead, hash: EntryHash) -> DatabaseResult<EntryMetadata> {
+    let meta = MetadataBuf::vault(env.clone().into())?;
+    let g = env.guard();
+    let r = g.reader()?;
+    Ok(EntryMetadata {
+        headers: meta.get_all_headers(&r, hash.clone())?.collect()?,
+        updates: meta.get_updates(&r, hash.clone().into())?.collect()?,
+        deletes: meta.get_deletes_on_entry(&r, hash.clone())?.collect()?,
+        dht_status: meta.get_dht_status(&r, &hash)?,
+    })
+}
+
+/// Read the integrated metadata of a header
+pub fn header_metadata(env: &EnvironmentRead, hash: HeaderHash) -> DatabaseResult<HeaderMetadata> {
+    let meta = MetadataBuf::vault(env.clone().into())?;
+    let g = env.guard();
+    let r = g.reader()?;
+    Ok(HeaderMetadata {
+        validation_status: meta.get_validation_status(&r, &hash)?.resolve(),
+        updates: meta.get_updates(&r, hash.clone().into())?.collect()?,
+        deletes: meta.get_deletes_on_header(&r, hash)?.collect()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::genesis_env;
+    use holochain::core::state::source_chain::SourceChainBuf;
+    use holochain_types::dht_op::DhtOpLight;
+
+    #[tokio::test(threaded_scheduler)]
+    async fn finds_genesis_elements() {
+        let test_env = genesis_env().await;
+        let env: EnvironmentRead = test_env.env().into();
+        let chain = SourceChainBuf::new(env.clone()).unwrap();
+        let agent_header = chain.sequence().get(2).unwrap().unwrap();
+
+        let found = find_header(&env, &agent_header).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "authored");
+        assert_eq!(found[0].1.header_address(), &agent_header);
+
+        let entry_hash = found[0].1.header().entry_hash().unwrap().clone();
+        let found = find_entry(&env, &entry_hash).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "authored");
+
+        let missing = HeaderHash::from_raw_36(vec![1; 36]);
+        assert!(find_header(&env, &missing).unwrap().is_empty());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn finds_op_in_each_store_holding_it() {
+        let test_env = genesis_env().await;
+        let env = test_env.env();
+        let header = SourceChainBuf::new(env.clone().into())
+            .unwrap()
+            .sequence()
+            .get(0)
+            .unwrap()
+            .unwrap();
+        let op_hash = DhtOpHash::from_raw_36(vec![1; 36]);
+        let value = AuthoredDhtOpsValue::from_light(DhtOpLight::RegisterAgentActivity(
+            header.clone(),
+            header.into(),
+        ));
+        let mut authored: AuthoredDhtOpsStore =
+            KvBufFresh::new(env.clone().into(), env.get_db(&*AUTHORED_DHT_OPS).unwrap());
+        authored.put(op_hash.clone(), value.clone()).unwrap();
+        env.guard()
+            .with_commit(|writer| authored.flush_to_txn(writer))
+            .unwrap();
+
+        let env: EnvironmentRead = env.into();
+        let records = find_op(&env, &op_hash).unwrap();
+        assert_eq!(records.authored, Some(value));
+        assert_eq!(records.validation_limbo, None);
+        assert_eq!(records.integration_limbo, None);
+        assert_eq!(records.integrated, None);
+    }
+}
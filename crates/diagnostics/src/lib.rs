@@ -0,0 +1,28 @@
+//! Holochain Diagnostics
+//!
+//! Offline inspection of the LMDB environments of a conductor, such as ones
+//! pulled from a user's device. [Environments] opens the environments under
+//! a root directory, and the modules below read them:
+//!
+//! - [conductor] lists the installed cells
+//! - [inspect] decodes headers, entries, ops and metadata by hash
+//! - [verify] checks the integrity of a source chain
+//! - [consistency] finds ops which disagree between the authored,
+//!   integrated and limbo stores
+//! - [cell], [conductor] and [wasm] print size stats of each database
+//!
+//! The environments are opened read-only, so inspecting them changes nothing,
+//! and with a throwaway keystore, so encrypted environments can't be read.
+
+pub mod cell;
+pub mod conductor;
+pub mod consistency;
+pub mod display;
+mod env;
+#[cfg(test)]
+mod fixture;
+pub mod inspect;
+pub mod verify;
+pub mod wasm;
+
+pub use env::Environments;
@@ -1,63 +1,167 @@
 //! Holochain Diagnostics
 //!
-//! This is a simple program that spits out some info about LMDB databases.
-//! It is written as a separate binary so that builds can be fast for quick
-//! feedback loops when debugging. In the future, this could be reorganized
-//! as a library of helper functions alongside a binary that calls into the lib,
-//! so that the binary can be freely modifiable while still accumulating a
-//! useful set of tools for querying LMDB state.
+//! A command line tool for inspecting the LMDB environments of a conductor
+//! offline, such as ones pulled from a user's device. The queries themselves
+//! live in the `holochain_diagnostics` library, so that this binary stays a
+//! thin, freely modifiable layer over them.
 
-use cell::dump_cell_state;
-use conductor::dump_conductor_state;
-use holochain_keystore::test_keystore::spawn_test_keystore;
-use holochain_state::env::{EnvironmentKind, EnvironmentWrite};
-use std::path::PathBuf;
+use holo_hash::{
+    AgentPubKey, DhtOpHash, DnaHash, EntryHash, HeaderHash, HoloHash, PrimitiveHashType,
+};
+use holochain_diagnostics::{
+    cell::dump_cell_state,
+    conductor::{dump_conductor_state, list_cells},
+    consistency::check_ops,
+    inspect,
+    verify::verify_source_chain,
+    wasm::dump_wasm_state,
+    Environments,
+};
+use holochain_types::cell::CellId;
+use std::{convert::TryFrom, path::PathBuf};
 use structopt::StructOpt;
-use wasm::dump_wasm_state;
-
-mod cell;
-mod conductor;
-mod display;
-mod wasm;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
+    /// The root environment directory of the conductor
     lmdb_path: PathBuf,
+    #[structopt(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Cmd {
+    /// Print size stats of every database (the default)
+    Stats,
+    /// List the cells of every installed app
+    ListCells,
+    /// Find a header in the element stores of a cell
+    Header {
+        #[structopt(flatten)]
+        cell: CellArgs,
+        #[structopt(parse(try_from_str = parse_hash))]
+        hash: HeaderHash,
+    },
+    /// Find an entry in the element stores of a cell
+    Entry {
+        #[structopt(flatten)]
+        cell: CellArgs,
+        #[structopt(parse(try_from_str = parse_hash))]
+        hash: EntryHash,
+    },
+    /// Find an op in the stores of ops of a cell
+    Op {
+        #[structopt(flatten)]
+        cell: CellArgs,
+        #[structopt(parse(try_from_str = parse_hash))]
+        hash: DhtOpHash,
+    },
+    /// Print the integrated metadata of an entry or a header
+    Metadata {
+        #[structopt(flatten)]
+        cell: CellArgs,
+        /// An entry hash or a header hash
+        hash: String,
+    },
+    /// Check the integrity of the source chain of a cell
+    VerifyChain {
+        #[structopt(flatten)]
+        cell: CellArgs,
+    },
+    /// Cross-check the ops of a cell against each other and their elements
+    Check {
+        #[structopt(flatten)]
+        cell: CellArgs,
+    },
+}
+
+/// Which cell to inspect
+#[derive(Debug, StructOpt)]
+struct CellArgs {
+    /// The hash of the cell's Dna
+    #[structopt(long, parse(try_from_str = parse_hash))]
+    dna: DnaHash,
+    /// The agent of the cell
+    #[structopt(long, parse(try_from_str = parse_hash))]
+    agent: AgentPubKey,
+}
+
+impl CellArgs {
+    fn cell_id(self) -> CellId {
+        CellId::new(self.dna, self.agent)
+    }
+}
+
+fn parse_hash<P: PrimitiveHashType>(s: &str) -> Result<HoloHash<P>, String> {
+    HoloHash::try_from(s).map_err(|e| format!("{:?}", e))
 }
 
 async fn run() -> anyhow::Result<()> {
     let opt = Opt::from_args();
+    let envs = Environments::open(&opt.lmdb_path).await?;
 
-    // throwaway keystore that we'll never use.
-    let keystore = spawn_test_keystore(Vec::new()).await.unwrap();
-
-    // set up the various environments
-    let wasm_env = EnvironmentWrite::new(
-        opt.lmdb_path.as_ref(),
-        EnvironmentKind::Wasm,
-        keystore.clone(),
-    )?;
+    match opt.cmd.unwrap_or(Cmd::Stats) {
+        Cmd::Stats => stats(&envs).await?,
+        Cmd::ListCells => {
+            for cell in list_cells(&envs.conductor()?)? {
+                println!(
+                    "{} \"{}\" ({}): --dna {} --agent {}",
+                    cell.installed_app_id,
+                    cell.cell_nick,
+                    if cell.active { "active" } else { "inactive" },
+                    cell.cell_id.dna_hash(),
+                    cell.cell_id.agent_pubkey(),
+                );
+            }
+        }
+        Cmd::Header { cell, hash } => {
+            let found = inspect::find_header(&envs.cell(cell.cell_id())?, &hash)?;
+            print_found(found);
+        }
+        Cmd::Entry { cell, hash } => {
+            let found = inspect::find_entry(&envs.cell(cell.cell_id())?, &hash)?;
+            print_found(found);
+        }
+        Cmd::Op { cell, hash } => {
+            let records = inspect::find_op(&envs.cell(cell.cell_id())?, &hash)?;
+            println!("{:#?}", records);
+        }
+        Cmd::Metadata { cell, hash } => {
+            let env = envs.cell(cell.cell_id())?;
+            if let Ok(hash) = EntryHash::try_from(&hash) {
+                println!("{:#?}", inspect::entry_metadata(&env, hash)?);
+            } else {
+                let hash = HeaderHash::try_from(&hash).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+                println!("{:#?}", inspect::header_metadata(&env, hash)?);
+            }
+        }
+        Cmd::VerifyChain { cell } => {
+            let problems = verify_source_chain(&envs.cell(cell.cell_id())?).await?;
+            report(problems, "the source chain is sound")?;
+        }
+        Cmd::Check { cell } => {
+            let problems = check_ops(&envs.cell(cell.cell_id())?)?;
+            report(problems, "the ops are consistent")?;
+        }
+    }
+    Ok(())
+}
 
+/// Print the size stats of every environment of the conductor
+async fn stats(envs: &Environments) -> anyhow::Result<()> {
     println!();
     println!("        +++++++++++++++++++++++++++++++++");
     println!("        ++++++++   WASM  STATE   ++++++++");
     println!("        +++++++++++++++++++++++++++++++++");
     println!();
-    dump_wasm_state(wasm_env).await?;
-
-    // set up the various environments
-    let conductor_env = EnvironmentWrite::new(
-        opt.lmdb_path.as_ref(),
-        EnvironmentKind::Conductor,
-        keystore.clone(),
-    )?;
+    dump_wasm_state(envs.wasm()?).await?;
 
     println!();
     println!("        +++++++++++++++++++++++++++++++++");
     println!("        +++++++  CONDUCTOR STATE  +++++++");
     println!("        +++++++++++++++++++++++++++++++++");
     println!();
-    let conductor_state = dump_conductor_state(conductor_env).await?;
+    let conductor_state = dump_conductor_state(envs.conductor()?).await?;
 
     println!();
     println!("        +++++++++++++++++++++++++++++++++");
@@ -68,22 +172,37 @@ async fn run() -> anyhow::Result<()> {
     for (_app_id, cells) in conductor_state.active_apps {
         for cell in cells {
             let (cell_id, cell_nick) = cell.into_inner();
-            let cell_env = EnvironmentWrite::new(
-                opt.lmdb_path.as_ref(),
-                EnvironmentKind::Cell(cell_id.clone()),
-                keystore.clone(),
-            )?;
-            dump_cell_state(cell_env, cell_id, &cell_nick).await?;
+            dump_cell_state(envs.cell(cell_id.clone())?, cell_id, &cell_nick).await?;
         }
     }
-
     Ok(())
 }
 
+fn print_found<T: std::fmt::Debug>(found: Vec<(&str, T)>) {
+    if found.is_empty() {
+        println!("not found");
+    }
+    for (store, t) in found {
+        println!("{}: {:#?}", store, t);
+    }
+}
+
+/// Print the problems found, failing if there are any
+fn report<P: std::fmt::Display>(problems: Vec<P>, ok: &str) -> anyhow::Result<()> {
+    if problems.is_empty() {
+        println!("{}", ok);
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    anyhow::bail!("found {} problems", problems.len())
+}
+
 #[tokio::main(threaded_scheduler)]
 async fn main() {
     if let Err(err) = run().await {
-        eprintln!("holochain-analyzer: {}", err);
+        eprintln!("holochain-diagnostics: {}", err);
         std::process::exit(1);
     }
 }
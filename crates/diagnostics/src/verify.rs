@@ -0,0 +1,152 @@
+//! Check the integrity of a cell's source chain
+
+use holo_hash::{EntryHash, HeaderHash};
+use holochain::core::state::source_chain::{SourceChainBuf, SourceChainResult};
+use holochain_state::env::EnvironmentRead;
+use holochain_types::element::SignedHeaderHashedExt;
+use holochain_zome_types::Header;
+use std::fmt;
+
+/// Something wrong with a header of a source chain
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainProblem {
+    /// The chain sequence has no header at this position,
+    /// though the chain is longer
+    MissingSeq(u32),
+    /// The chain sequence points at a header which isn't stored
+    MissingHeader(u32, HeaderHash),
+    /// The header stored under this hash hashes to something else
+    HashMismatch(u32, HeaderHash),
+    /// The header says it's at another position in the chain
+    WrongSeq(u32, u32),
+    /// The header doesn't point back at the header before it
+    BrokenPrevHeader(u32),
+    /// The chain doesn't start with a Dna header
+    NotDnaFirst,
+    /// The signature of the header doesn't match its author
+    BadSignature(u32),
+    /// The header creates an entry which isn't stored
+    MissingEntry(u32, EntryHash),
+    /// The entry stored under the header's entry hash hashes to something else
+    EntryHashMismatch(u32, EntryHash),
+}
+
+impl fmt::Display for ChainProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainProblem::MissingSeq(i) => write!(f, "[{}] no header in the chain sequence", i),
+            ChainProblem::MissingHeader(i, hash) => {
+                write!(f, "[{}] header {} is not stored", i, hash)
+            }
+            ChainProblem::HashMismatch(i, hash) => {
+                write!(f, "[{}] header stored as {} has another hash", i, hash)
+            }
+            ChainProblem::WrongSeq(i, seq) => write!(f, "[{}] header has seq {}", i, seq),
+            ChainProblem::BrokenPrevHeader(i) => {
+                write!(f, "[{}] header doesn't follow the previous header", i)
+            }
+            ChainProblem::NotDnaFirst => write!(f, "[0] header is not a Dna header"),
+            ChainProblem::BadSignature(i) => write!(f, "[{}] header has a bad signature", i),
+            ChainProblem::MissingEntry(i, hash) => {
+                write!(f, "[{}] entry {} is not stored", i, hash)
+            }
+            ChainProblem::EntryHashMismatch(i, hash) => {
+                write!(f, "[{}] entry stored as {} has another hash", i, hash)
+            }
+        }
+    }
+}
+
+/// Walk the source chain from the Dna header to the head, checking that each
+/// header is stored, hashed, linked and signed correctly, and that its entry
+/// is stored. An empty list means the chain is sound.
+pub async fn verify_source_chain(env: &EnvironmentRead) -> SourceChainResult<Vec<ChainProblem>> {
+    let source_chain = SourceChainBuf::new(env.clone().into())?;
+    let mut problems = Vec::new();
+    let mut prev_header = None;
+    // After a gap in the sequence there's no header to check the link against
+    let mut after_gap = false;
+    for i in 0..source_chain.len() as u32 {
+        let hash = match source_chain.sequence().get(i)? {
+            Some(hash) => hash,
+            None => {
+                problems.push(ChainProblem::MissingSeq(i));
+                after_gap = true;
+                continue;
+            }
+        };
+        let signed_header = match source_chain.get_header(&hash)? {
+            Some(signed_header) => signed_header,
+            None => {
+                problems.push(ChainProblem::MissingHeader(i, hash.clone()));
+                prev_header = Some(hash);
+                after_gap = false;
+                continue;
+            }
+        };
+        let header = signed_header.header();
+
+        if HeaderHash::with_data_sync(header) != hash {
+            problems.push(ChainProblem::HashMismatch(i, hash.clone()));
+        }
+        if header.header_seq() != i {
+            problems.push(ChainProblem::WrongSeq(i, header.header_seq()));
+        }
+        if !after_gap && header.prev_header() != prev_header.as_ref() {
+            problems.push(ChainProblem::BrokenPrevHeader(i));
+        }
+        if i == 0 && !matches!(header, Header::Dna(_)) {
+            problems.push(ChainProblem::NotDnaFirst);
+        }
+        if signed_header.validate().await.is_err() {
+            problems.push(ChainProblem::BadSignature(i));
+        }
+        if let Some(entry_hash) = header.entry_hash() {
+            match source_chain.get_entry(entry_hash)? {
+                Some(entry) => {
+                    if &EntryHash::with_data_sync(entry.as_content()) != entry_hash {
+                        problems.push(ChainProblem::EntryHashMismatch(i, entry_hash.clone()));
+                    }
+                }
+                None => problems.push(ChainProblem::MissingEntry(i, entry_hash.clone())),
+            }
+        }
+
+        prev_header = Some(hash);
+        after_gap = false;
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::genesis_env;
+    use holochain::core::state::chain_sequence::ChainSequenceItem;
+    use holochain_state::{buffer::KvIntBufFresh, db::CHAIN_SEQUENCE, prelude::*};
+
+    #[tokio::test(threaded_scheduler)]
+    async fn genesis_chain_is_sound() {
+        let test_env = genesis_env().await;
+        let env: EnvironmentRead = test_env.env().into();
+        assert_eq!(verify_source_chain(&env).await.unwrap(), vec![]);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn gap_in_sequence_is_reported() {
+        let test_env = genesis_env().await;
+        let env = test_env.env();
+        let mut sequence: KvIntBufFresh<ChainSequenceItem> =
+            KvIntBufFresh::new(env.clone().into(), env.get_db(&*CHAIN_SEQUENCE).unwrap());
+        sequence.delete(1u32.into()).unwrap();
+        env.guard()
+            .with_commit(|writer| sequence.flush_to_txn(writer))
+            .unwrap();
+
+        let env: EnvironmentRead = env.into();
+        assert_eq!(
+            verify_source_chain(&env).await.unwrap(),
+            vec![ChainProblem::MissingSeq(1)]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 #![forbid(missing_docs)]
 //! Binary `dna_util` command executable.
 
-use dna_util::DnaUtilResult;
+use dna_util::{DnaUtilError, DnaUtilResult};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -19,11 +19,88 @@ struct Opt {
     /// (`dna-util -c my-dna.dna.workdir` creates file `my-dna.dna.gz`)
     #[structopt(short = "c", long)]
     compress: Option<std::path::PathBuf>,
+
+    #[structopt(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+/// Commands to check Dnas, which take either a DnaFile or a Dna Working
+/// Directory, and to bundle them into apps.
+#[derive(Debug, StructOpt)]
+enum Cmd {
+    /// Print the hash, properties and zomes of a Dna as json,
+    /// with the wasm hash and exported functions of each zome.
+    Inspect {
+        /// The DnaFile or Dna Working Directory
+        path: std::path::PathBuf,
+    },
+
+    /// Check that each zome's wasm is valid, that every callback it exports
+    /// can be called, and that it exports the required callbacks.
+    ///
+    /// Exits with an error if there are any problems.
+    Validate {
+        /// The DnaFile or Dna Working Directory
+        path: std::path::PathBuf,
+
+        /// A callback which every zome must export, e.g. `validate`.
+        /// Can be given more than once.
+        #[structopt(long = "require-callback")]
+        required_callbacks: Vec<String>,
+    },
+
+    /// Print the hash the Dna would have if installed with these properties
+    /// and UUID, without installing it.
+    Hash {
+        /// The DnaFile or Dna Working Directory
+        path: std::path::PathBuf,
+
+        /// Json properties to replace the Dna's properties with
+        #[structopt(long, parse(try_from_str = serde_json::from_str))]
+        properties: Option<serde_json::Value>,
+
+        /// A UUID to replace the Dna's UUID with
+        #[structopt(long)]
+        uuid: Option<String>,
+    },
+
+    /// Print the differences between two Dnas.
+    ///
+    /// Exits with an error if they differ.
+    Diff {
+        /// The first DnaFile or Dna Working Directory
+        a: std::path::PathBuf,
+
+        /// The second DnaFile or Dna Working Directory
+        b: std::path::PathBuf,
+    },
+
+    /// Pack an app manifest and the DnaFiles it refers to into an app bundle.
+    ///
+    /// (`dna-util bundle my-app.json` creates file `<app name>.happ`
+    /// next to the manifest)
+    Bundle {
+        /// The json app manifest
+        manifest: std::path::PathBuf,
+
+        /// Where to write the bundle instead
+        #[structopt(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
 }
 
 async fn run() -> DnaUtilResult<()> {
     let opt = Opt::from_args();
 
+    if let Some(cmd) = opt.cmd {
+        if opt.expand.is_some() || opt.compress.is_some() {
+            eprintln!("INPUT ERROR: 'expand' and 'compress' can't be used with a command.\n");
+            Opt::clap().print_long_help().unwrap();
+            return Ok(());
+        }
+        return run_cmd(cmd).await;
+    }
+
     if opt.expand.is_none() && opt.compress.is_none() {
         eprintln!("INPUT ERROR: no command selected.\n");
         Opt::clap().print_long_help().unwrap();
@@ -55,6 +132,59 @@ async fn run() -> DnaUtilResult<()> {
     }
 }
 
+async fn run_cmd(cmd: Cmd) -> DnaUtilResult<()> {
+    match cmd {
+        Cmd::Inspect { path } => {
+            let info = dna_util::inspect(&dna_util::load(&path).await?)?;
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        }
+        Cmd::Validate {
+            path,
+            required_callbacks,
+        } => {
+            let required_callbacks: Vec<_> =
+                required_callbacks.into_iter().map(Into::into).collect();
+            let problems = dna_util::validate(&dna_util::load(&path).await?, &required_callbacks)?;
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if !problems.is_empty() {
+                return Err(DnaUtilError::InvalidInput(format!(
+                    "the Dna has {} problems",
+                    problems.len()
+                )));
+            }
+        }
+        Cmd::Hash {
+            path,
+            properties,
+            uuid,
+        } => {
+            let hash = dna_util::hash(dna_util::load(&path).await?, properties, uuid).await?;
+            println!("{}", hash);
+        }
+        Cmd::Diff { a, b } => {
+            let a = dna_util::inspect(&dna_util::load(&a).await?)?;
+            let b = dna_util::inspect(&dna_util::load(&b).await?)?;
+            let differences = dna_util::diff(&a, &b);
+            for difference in &differences {
+                println!("{}", difference);
+            }
+            if !differences.is_empty() {
+                return Err(DnaUtilError::InvalidInput(format!(
+                    "the Dnas differ: {} -> {}",
+                    a.dna_hash, b.dna_hash
+                )));
+            }
+        }
+        Cmd::Bundle { manifest, output } => {
+            let path = dna_util::bundle(&manifest, output).await?;
+            println!("{}", path.display());
+        }
+    }
+    Ok(())
+}
+
 /// Main `dna-util` executable entrypoint.
 #[tokio::main(threaded_scheduler)]
 pub async fn main() {
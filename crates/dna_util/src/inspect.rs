@@ -0,0 +1,205 @@
+//! Describe a DnaFile, and compare two of them
+
+use crate::{
+    wasm::{exported_fns, is_zome_callback},
+    DnaUtilResult, JsonValueDecodeHelper,
+};
+use holo_hash::{DnaHash, WasmHash};
+use holochain_serialized_bytes::prelude::*;
+use holochain_types::dna::DnaFile;
+use holochain_zome_types::{
+    header::ZomeId,
+    zome::{FunctionName, ZomeName},
+};
+use std::fmt;
+
+/// What a DnaFile contains
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DnaInfo {
+    /// The name of the Dna
+    pub name: String,
+    /// The hash of the Dna
+    pub dna_hash: DnaHash,
+    /// The UUID of the Dna
+    pub uuid: String,
+    /// The properties of the Dna
+    pub properties: serde_json::Value,
+    /// The zomes of the Dna, in order
+    pub zomes: Vec<ZomeInfo>,
+}
+
+/// What a zome of a DnaFile contains
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ZomeInfo {
+    /// The name of the zome
+    pub name: ZomeName,
+    /// The hash of the zome's wasm
+    pub wasm_hash: WasmHash,
+    /// The size of the zome's wasm in bytes
+    pub wasm_size: usize,
+    /// The functions the wasm exports which can be called with a zome call
+    pub zome_fns: Vec<FunctionName>,
+    /// The callbacks the wasm exports
+    pub callbacks: Vec<FunctionName>,
+}
+
+/// Describe a DnaFile, reading its wasm to list what each zome exports
+pub fn inspect(dna_file: &DnaFile) -> DnaUtilResult<DnaInfo> {
+    let dna = dna_file.dna();
+    let properties: JsonValueDecodeHelper = dna.properties.clone().try_into()?;
+    let mut zomes = Vec::new();
+    for (index, (name, zome)) in dna.zomes.iter().enumerate() {
+        let zome_id = ZomeId::from(index as u8);
+        let wasm = dna_file.get_wasm_for_zome(name)?;
+        let (callbacks, zome_fns) = exported_fns(&wasm.code)?
+            .into_iter()
+            .map(|export| export.name)
+            .partition(|fn_name| is_zome_callback(fn_name.as_ref(), zome_id));
+        zomes.push(ZomeInfo {
+            name: name.clone(),
+            wasm_hash: zome.wasm_hash.clone(),
+            wasm_size: wasm.code.len(),
+            zome_fns,
+            callbacks,
+        });
+    }
+    Ok(DnaInfo {
+        name: dna.name.clone(),
+        dna_hash: dna_file.dna_hash().clone(),
+        uuid: dna.uuid.clone(),
+        properties: properties.0,
+        zomes,
+    })
+}
+
+/// A way in which two Dnas differ
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnaDifference {
+    /// The names differ
+    Name(String, String),
+    /// The UUIDs differ
+    Uuid(String, String),
+    /// The properties differ
+    Properties(serde_json::Value, serde_json::Value),
+    /// Only the second Dna has this zome
+    ZomeAdded(ZomeName),
+    /// Only the first Dna has this zome
+    ZomeRemoved(ZomeName),
+    /// Both Dnas have the zomes, in another order
+    ZomeOrder(Vec<ZomeName>, Vec<ZomeName>),
+    /// The zome's wasm differs
+    Wasm(ZomeName, WasmHash, WasmHash),
+    /// Only the second Dna's zome exports this function or callback
+    ExternAdded(ZomeName, FunctionName),
+    /// Only the first Dna's zome exports this function or callback
+    ExternRemoved(ZomeName, FunctionName),
+}
+
+impl fmt::Display for DnaDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnaDifference::Name(a, b) => write!(f, "name: {} -> {}", a, b),
+            DnaDifference::Uuid(a, b) => write!(f, "uuid: {} -> {}", a, b),
+            DnaDifference::Properties(a, b) => write!(f, "properties: {} -> {}", a, b),
+            DnaDifference::ZomeAdded(zome) => write!(f, "+ zome {}", zome),
+            DnaDifference::ZomeRemoved(zome) => write!(f, "- zome {}", zome),
+            DnaDifference::ZomeOrder(a, b) => write!(
+                f,
+                "zome order: [{}] -> [{}]",
+                join(a.iter()),
+                join(b.iter())
+            ),
+            DnaDifference::Wasm(zome, a, b) => write!(f, "zome {} wasm: {} -> {}", zome, a, b),
+            DnaDifference::ExternAdded(zome, extern_name) => {
+                write!(f, "+ zome {} exports {}", zome, extern_name)
+            }
+            DnaDifference::ExternRemoved(zome, extern_name) => {
+                write!(f, "- zome {} exports {}", zome, extern_name)
+            }
+        }
+    }
+}
+
+/// List the ways in which two Dnas differ, apart from their hashes.
+/// An empty list means the Dnas are the same.
+pub fn diff(a: &DnaInfo, b: &DnaInfo) -> Vec<DnaDifference> {
+    let mut differences = Vec::new();
+    if a.name != b.name {
+        differences.push(DnaDifference::Name(a.name.clone(), b.name.clone()));
+    }
+    if a.uuid != b.uuid {
+        differences.push(DnaDifference::Uuid(a.uuid.clone(), b.uuid.clone()));
+    }
+    if a.properties != b.properties {
+        differences.push(DnaDifference::Properties(
+            a.properties.clone(),
+            b.properties.clone(),
+        ));
+    }
+
+    for zome_a in &a.zomes {
+        match find_zome(&b.zomes, &zome_a.name) {
+            Some(zome_b) => diff_zome(zome_a, zome_b, &mut differences),
+            None => differences.push(DnaDifference::ZomeRemoved(zome_a.name.clone())),
+        }
+    }
+    for zome_b in &b.zomes {
+        if find_zome(&a.zomes, &zome_b.name).is_none() {
+            differences.push(DnaDifference::ZomeAdded(zome_b.name.clone()));
+        }
+    }
+
+    // The order of the zomes is part of the Dna hash
+    let common = |x: &DnaInfo, y: &DnaInfo| -> Vec<ZomeName> {
+        x.zomes
+            .iter()
+            .filter(|z| find_zome(&y.zomes, &z.name).is_some())
+            .map(|z| z.name.clone())
+            .collect()
+    };
+    let (order_a, order_b) = (common(a, b), common(b, a));
+    if order_a != order_b {
+        differences.push(DnaDifference::ZomeOrder(order_a, order_b));
+    }
+    differences
+}
+
+fn find_zome<'a>(zomes: &'a [ZomeInfo], name: &ZomeName) -> Option<&'a ZomeInfo> {
+    zomes.iter().find(|z| &z.name == name)
+}
+
+fn diff_zome(a: &ZomeInfo, b: &ZomeInfo, differences: &mut Vec<DnaDifference>) {
+    if a.wasm_hash == b.wasm_hash {
+        return;
+    }
+    differences.push(DnaDifference::Wasm(
+        a.name.clone(),
+        a.wasm_hash.clone(),
+        b.wasm_hash.clone(),
+    ));
+    let externs = |z: &ZomeInfo| -> Vec<FunctionName> {
+        let mut externs: Vec<_> = z.zome_fns.iter().chain(&z.callbacks).cloned().collect();
+        externs.sort();
+        externs
+    };
+    let (externs_a, externs_b) = (externs(a), externs(b));
+    for extern_name in externs_a.iter().filter(|e| !externs_b.contains(e)) {
+        differences.push(DnaDifference::ExternRemoved(
+            a.name.clone(),
+            extern_name.clone(),
+        ));
+    }
+    for extern_name in externs_b.iter().filter(|e| !externs_a.contains(e)) {
+        differences.push(DnaDifference::ExternAdded(
+            a.name.clone(),
+            extern_name.clone(),
+        ));
+    }
+}
+
+fn join<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -8,6 +8,33 @@
 //! ├── test-zome-1.wasm
 //! └── test-zome-2.wasm
 //! ```
+//!
+//! It can also check a Dna without installing it into a conductor. Each of
+//! these commands takes either a DnaFile or a Dna Working Directory:
+//!
+//! - `dna-util inspect <path>` prints the hash, properties and zomes of a Dna
+//!   as json, with the wasm hash and exported functions of each zome
+//! - `dna-util validate <path> [--require-callback <name>]...` checks that
+//!   each zome's wasm is valid, that the host can call every callback it
+//!   exports, such as `entry_defs`, and that it exports the required callbacks.
+//!   It also calls each zome's `entry_defs`, in a sandbox where every host
+//!   function fails
+//! - `dna-util hash <path> [--properties <json>] [--uuid <uuid>]` prints the
+//!   hash the Dna would have if installed with these properties and UUID
+//! - `dna-util diff <a> <b>` prints the differences between two Dnas
+//!
+//! Otherwise the wasm is read, never run, so which entry types a zome
+//! defines isn't known: a function named like the `validate` callback of an
+//! entry type is taken to be one.
+//!
+//! `dna-util bundle <manifest.json> [--output <path>]` packs an app manifest
+//! and the DnaFiles it refers to into an app bundle, which can be installed
+//! with one admin call. The bundle is written next to the manifest, as
+//! `<app name>.happ`, unless another path is given.
+//!
+//! `validate` and `diff` exit with an error if they find problems or
+//! differences.
+//!
 //! Usage instructions from the `--help` flag:
 //! ```sh
 //! $ dna_util --help
@@ -37,11 +64,23 @@
 //!     (`dna_util -e my-dna.dna.gz` creates dir `my-dna.dna_work_dir`)
 //! ```
 
+use holo_hash::DnaHash;
 use holochain_serialized_bytes::prelude::*;
-use holochain_types::dna::{wasm::DnaWasm, zome::Zome, DnaDef, DnaFile};
+use holochain_types::{
+    app::bundle::AppBundle,
+    dna::{wasm::DnaWasm, zome::Zome, DnaDef, DnaFile},
+};
 use holochain_zome_types::zome::ZomeName;
 use std::{collections::BTreeMap, path::PathBuf};
 
+mod inspect;
+mod sandbox;
+mod validate;
+mod wasm;
+
+pub use inspect::{diff, inspect, DnaDifference, DnaInfo, ZomeInfo};
+pub use validate::{validate, DnaProblem};
+
 /// DnaUtilError type.
 #[derive(Debug, thiserror::Error)]
 pub enum DnaUtilError {
@@ -65,6 +104,14 @@ pub enum DnaUtilError {
     #[error("JSON serialization error: {0}")]
     SerdeJsonError(#[from] serde_json::Error),
 
+    /// BinaryReaderError
+    #[error("Wasm error: {0}")]
+    WasmError(#[from] wasmparser::BinaryReaderError),
+
+    /// AppBundleError
+    #[error("App bundle error: {0}")]
+    AppBundleError(#[from] holochain_types::app::error::AppBundleError),
+
     /// InvalidInput
     #[error("Invalid input: {0}")]
     InvalidInput(String),
@@ -157,8 +204,63 @@ pub async fn compress(dna_work_dir: &impl AsRef<std::path::Path>) -> DnaUtilResu
     let dna_work_dir = dna_work_dir.as_ref().canonicalize()?;
     let dna_file_path = dna_file_path_convert(&dna_work_dir, false)?;
 
-    let mut json_filename = dna_work_dir.clone();
-    json_filename.push("dna.json");
+    let dna_file_content = compile_work_dir(&dna_work_dir).await?;
+    let dna_file_content = dna_file_content.to_file_content().await?;
+
+    tokio::fs::write(dna_file_path, &dna_file_content).await?;
+
+    Ok(())
+}
+
+/// Load a DnaFile, or compile a Dna Working Directory without writing the DnaFile
+pub async fn load(path: &impl AsRef<std::path::Path>) -> DnaUtilResult<DnaFile> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        compile_work_dir(path).await
+    } else {
+        let content = tokio::fs::read(path)
+            .await
+            .map_err(|e| DnaUtilError::PathNotFound(e, path.to_owned()))?;
+        Ok(DnaFile::from_file_content(&content).await?)
+    }
+}
+
+/// Compute the hash a Dna would have with these properties and UUID,
+/// without installing it
+pub async fn hash(
+    dna_file: DnaFile,
+    properties: Option<serde_json::Value>,
+    uuid: Option<String>,
+) -> DnaUtilResult<DnaHash> {
+    let (mut dna, wasm): (DnaDef, Vec<DnaWasm>) = dna_file.into();
+    if let Some(properties) = properties {
+        dna.properties = JsonValueDecodeHelper(properties).try_into()?;
+    }
+    if let Some(uuid) = uuid {
+        dna.uuid = uuid;
+    }
+    Ok(DnaFile::new(dna, wasm).await?.dna_hash().clone())
+}
+
+/// Pack an app manifest and the DnaFiles it refers to into an app bundle.
+/// The bundle is written to `output`, or next to the manifest as
+/// `<app name>.happ`, and its path is returned.
+pub async fn bundle(
+    manifest_path: &impl AsRef<std::path::Path>,
+    output: Option<PathBuf>,
+) -> DnaUtilResult<PathBuf> {
+    let manifest_path = manifest_path.as_ref();
+    let bundle = AppBundle::from_manifest_file(manifest_path).await?;
+    let output = output.unwrap_or_else(|| {
+        manifest_path.with_file_name(format!("{}.happ", bundle.manifest().name))
+    });
+    tokio::fs::write(&output, bundle.to_file_content()?).await?;
+    Ok(output)
+}
+
+/// internal compile a Dna Working Directory into a DnaFile
+async fn compile_work_dir(dna_work_dir: &std::path::Path) -> DnaUtilResult<DnaFile> {
+    let json_filename = dna_work_dir.join("dna.json");
 
     let json_data = tokio::fs::read(json_filename.clone())
         .await
@@ -166,12 +268,7 @@ pub async fn compress(dna_work_dir: &impl AsRef<std::path::Path>) -> DnaUtilResu
 
     let json_file: DnaDefJson = serde_json::from_slice(&json_data)?;
 
-    let dna_file_content = json_file.compile_dna_file(&dna_work_dir).await?;
-    let dna_file_content = dna_file_content.to_file_content().await?;
-
-    tokio::fs::write(dna_file_path, &dna_file_content).await?;
-
-    Ok(())
+    json_file.compile_dna_file(dna_work_dir).await
 }
 
 /// See `holochain_types::dna::zome::Zome`.
@@ -254,6 +351,174 @@ impl DnaDefJson {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use holochain_wasm_test_utils::TestWasm;
+    use holochain_zome_types::zome::FunctionName;
+
+    fn test_wasm_dna(uuid: &str, zomes: Vec<TestWasm>) -> DnaFile {
+        holochain_types::test_utils::fake_dna_zomes(
+            uuid,
+            zomes.into_iter().map(|w| (w.into(), w.into())).collect(),
+        )
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn hash_matches_installed_dna() {
+        let dna_file = test_wasm_dna("a", vec![TestWasm::Foo]);
+        assert_eq!(
+            &hash(dna_file.clone(), None, None).await.unwrap(),
+            dna_file.dna_hash()
+        );
+
+        let properties = serde_json::json!({ "p": "other" });
+        let expected = dna_file
+            .clone()
+            .with_properties(
+                JsonValueDecodeHelper(properties.clone())
+                    .try_into()
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .with_uuid("b".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            &hash(dna_file, Some(properties), Some("b".into()))
+                .await
+                .unwrap(),
+            expected.dna_hash()
+        );
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn inspect_and_diff() {
+        let a = inspect(&test_wasm_dna("a", vec![TestWasm::Foo])).unwrap();
+        assert_eq!(a.zomes.len(), 1);
+        assert_eq!(a.zomes[0].zome_fns, vec![FunctionName::from("foo")]);
+        assert_eq!(a.zomes[0].callbacks, vec![FunctionName::from("init")]);
+        assert!(diff(&a, &a).is_empty());
+
+        let b = inspect(&test_wasm_dna(
+            "b",
+            vec![TestWasm::EntryDefs, TestWasm::Foo],
+        ))
+        .unwrap();
+        assert_eq!(
+            diff(&a, &b),
+            vec![
+                DnaDifference::Uuid("a".into(), "b".into()),
+                DnaDifference::ZomeAdded(TestWasm::EntryDefs.into()),
+            ]
+        );
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn validate_checks_wasm_and_callbacks() {
+        let dna_file = test_wasm_dna("a", vec![TestWasm::Foo, TestWasm::EntryDefs]);
+        assert!(validate(&dna_file, &[]).unwrap().is_empty());
+        assert_eq!(
+            validate(&dna_file, &["entry_defs".into()]).unwrap(),
+            vec![DnaProblem::CallbackMissing(
+                TestWasm::Foo.into(),
+                "entry_defs".into()
+            )]
+        );
+        assert!(validate(&dna_file, &["foo".into()]).is_err());
+        // Only the first zome's validation package is called by this name
+        assert!(validate(&dna_file, &["validation_package_0".into()]).is_err());
+        assert_eq!(
+            validate(&dna_file, &["validate_create_entry_post".into()]).unwrap(),
+            vec![
+                DnaProblem::CallbackMissing(
+                    TestWasm::Foo.into(),
+                    "validate_create_entry_post".into()
+                ),
+                DnaProblem::CallbackMissing(
+                    TestWasm::EntryDefs.into(),
+                    "validate_create_entry_post".into()
+                ),
+            ]
+        );
+
+        let broken = holochain_types::test_utils::fake_dna_zomes(
+            "a",
+            vec![("broken".into(), vec![1, 2, 3].into())],
+        );
+        assert!(matches!(
+            validate(&broken, &[]).unwrap().as_slice(),
+            [DnaProblem::WasmInvalid(..)]
+        ));
+
+        // A module exporting `init` as a function taking and returning nothing
+        let init_without_pointers = vec![
+            0, 97, 115, 109, 1, 0, 0, 0, // header
+            1, 4, 1, 96, 0, 0, // type () -> ()
+            3, 2, 1, 0, // one function of that type
+            7, 8, 1, 4, 105, 110, 105, 116, 0, 0, // exported as `init`
+            10, 4, 1, 2, 0, 11, // with an empty body
+        ];
+        let not_callable = holochain_types::test_utils::fake_dna_zomes(
+            "a",
+            vec![("zome".into(), init_without_pointers.into())],
+        );
+        assert_eq!(
+            validate(&not_callable, &["init".into()]).unwrap(),
+            vec![DnaProblem::CallbackNotCallable(
+                "zome".into(),
+                "init".into()
+            )]
+        );
+
+        // A module exporting an `entry_defs` which traps
+        let trapping_entry_defs = vec![
+            0, 97, 115, 109, 1, 0, 0, 0, // header
+            1, 6, 1, 96, 1, 127, 1, 127, // type (i32) -> i32
+            3, 2, 1, 0, // one function of that type
+            7, 14, 1, 10, 101, 110, 116, 114, 121, 95, 100, 101, 102, 115, 0,
+            0, // exported as `entry_defs`
+            10, 5, 1, 3, 0, 0, 11, // with a body which is unreachable
+        ];
+        let failing = holochain_types::test_utils::fake_dna_zomes(
+            "a",
+            vec![("zome".into(), trapping_entry_defs.into())],
+        );
+        assert!(matches!(
+            validate(&failing, &[]).unwrap().as_slice(),
+            [DnaProblem::EntryDefs(zome, _)] if zome == &ZomeName::from("zome")
+        ));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn bundle_from_manifest() {
+        let tmp_dir = tempdir::TempDir::new("dna_util_test").unwrap();
+        let dna_file = test_wasm_dna("a", vec![TestWasm::Foo]);
+        tokio::fs::write(
+            tmp_dir.path().join("foo.dna.gz"),
+            dna_file.to_file_content().await.unwrap(),
+        )
+        .await
+        .unwrap();
+        let manifest = serde_json::json!({
+            "name": "app",
+            "dnas": [{ "nick": "foo", "path": "foo.dna.gz" }],
+        });
+        let manifest_path = tmp_dir.path().join("app.json");
+        tokio::fs::write(&manifest_path, manifest.to_string())
+            .await
+            .unwrap();
+
+        let path = bundle(&manifest_path, None).await.unwrap();
+        assert_eq!(path, tmp_dir.path().join("app.happ"));
+        let packed = AppBundle::from_file_content(&tokio::fs::read(path).await.unwrap()).unwrap();
+        assert_eq!(packed.manifest().name, "app");
+        assert_eq!(packed.manifest().dnas[0].nick, "foo");
+
+        // The Dna files are packed too, so a missing one is caught now
+        tokio::fs::remove_file(tmp_dir.path().join("foo.dna.gz"))
+            .await
+            .unwrap();
+        assert!(bundle(&manifest_path, None).await.is_err());
+    }
 
     #[tokio::test(threaded_scheduler)]
     async fn test_extract_then_compile() {
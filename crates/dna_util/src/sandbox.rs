@@ -0,0 +1,66 @@
+//! Call a zome's callbacks without a conductor
+
+use crate::wasm::imported_fns;
+use holo_hash::WasmHash;
+use holochain_wasmer_host::prelude::*;
+use holochain_zome_types::{entry_def::EntryDefsCallbackResult, ExternInput, ExternOutput};
+use std::{collections::HashMap, convert::TryInto, sync::mpsc, time::Duration};
+
+/// How long a callback may run before it is given up on
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Call the zome's `entry_defs` callback in a sandbox.
+/// Every host function the zome imports fails if it is called,
+/// and a call which doesn't return in time is given up on.
+pub(crate) fn call_entry_defs(
+    wasm_hash: &WasmHash,
+    wasm: &[u8],
+) -> Result<EntryDefsCallbackResult, String> {
+    let cache_key = wasm_hash.get_raw_39().to_vec();
+    let wasm = wasm.to_vec();
+    let (tx, rx) = mpsc::channel();
+    // The thread is left behind if the call doesn't return
+    std::thread::spawn(move || {
+        let call = || -> Result<EntryDefsCallbackResult, String> {
+            let imports = sandbox_imports(&wasm)?;
+            let module = holochain_wasmer_host::instantiate::module(&cache_key, &wasm, None)
+                .map_err(|e| e.to_string())?;
+            let mut instance = module.instantiate(&imports).map_err(|e| e.to_string())?;
+            let input = ExternInput::new(().try_into().map_err(|e| format!("{:?}", e))?);
+            let output: ExternOutput =
+                holochain_wasmer_host::guest::call(&mut instance, "entry_defs", input)
+                    .map_err(|e| e.to_string())?;
+            Ok(EntryDefsCallbackResult::from(output))
+        };
+        tx.send(call()).ok();
+    });
+    rx.recv_timeout(CALLBACK_TIMEOUT)
+        .map_err(|_| format!("didn't return within {:?}", CALLBACK_TIMEOUT))?
+}
+
+/// Imports for every function the wasm imports. Only the memory handling
+/// used by the guest works, the host functions all fail.
+fn sandbox_imports(wasm: &[u8]) -> Result<ImportObject, String> {
+    let mut namespaces: HashMap<String, Namespace> = HashMap::new();
+    for (module, name) in imported_fns(wasm).map_err(|e| e.to_string())? {
+        let ns = namespaces.entry(module).or_insert_with(Namespace::new);
+        if name == "__import_data" {
+            ns.insert(name, func!(holochain_wasmer_host::import::__import_data));
+        } else {
+            let error = format!("{} can't be called outside of a conductor", name);
+            ns.insert(
+                name,
+                func!(move |_ctx: &mut Ctx,
+                            _guest_allocation_ptr: GuestPtr|
+                      -> Result<Len, WasmError> {
+                    Err(WasmError::Zome(error.clone()))
+                }),
+            );
+        }
+    }
+    let mut imports = imports! {};
+    for (module, ns) in namespaces {
+        imports.register(module, ns);
+    }
+    Ok(imports)
+}
@@ -0,0 +1,110 @@
+//! Check that a Dna will work when installed, without installing it
+
+use crate::{
+    sandbox::call_entry_defs,
+    wasm::{exported_fns, is_zome_callback},
+    DnaUtilError, DnaUtilResult,
+};
+use holochain_types::dna::DnaFile;
+use holochain_zome_types::{
+    entry_def::EntryDefsCallbackResult,
+    header::ZomeId,
+    zome::{FunctionName, ZomeName},
+};
+use std::fmt;
+
+/// Something which would stop a Dna from working when installed
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnaProblem {
+    /// The zome's wasm isn't valid
+    WasmInvalid(ZomeName, String),
+    /// The zome exports a callback which the host can't call,
+    /// because it doesn't take and return a pointer like an extern
+    CallbackNotCallable(ZomeName, FunctionName),
+    /// The zome doesn't export a callback which is required of every zome
+    CallbackMissing(ZomeName, FunctionName),
+    /// Calling the zome's `entry_defs` callback failed
+    EntryDefs(ZomeName, String),
+}
+
+impl fmt::Display for DnaProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnaProblem::WasmInvalid(zome, e) => write!(f, "zome {}: invalid wasm: {}", zome, e),
+            DnaProblem::CallbackNotCallable(zome, callback) => {
+                write!(
+                    f,
+                    "zome {}: {} can't be called as a callback",
+                    zome, callback
+                )
+            }
+            DnaProblem::CallbackMissing(zome, callback) => {
+                write!(f, "zome {}: doesn't export {}", zome, callback)
+            }
+            DnaProblem::EntryDefs(zome, e) => write!(f, "zome {}: entry_defs failed: {}", zome, e),
+        }
+    }
+}
+
+/// Check that the wasm of every zome is valid, that every callback it
+/// exports, such as `entry_defs` or `validate`, can be called by the host,
+/// and that it exports each of the `required_callbacks`. A zome's
+/// `entry_defs` is also called, in a sandbox without any host functions.
+/// An empty list means the Dna is valid.
+pub fn validate(
+    dna_file: &DnaFile,
+    required_callbacks: &[FunctionName],
+) -> DnaUtilResult<Vec<DnaProblem>> {
+    let zome_ids = (0..dna_file.dna().zomes.len()).map(|index| ZomeId::from(index as u8));
+    for callback in required_callbacks {
+        if zome_ids
+            .clone()
+            .any(|zome_id| !is_zome_callback(callback.as_ref(), zome_id))
+        {
+            return Err(DnaUtilError::InvalidInput(format!(
+                "{} is not a callback of every zome",
+                callback
+            )));
+        }
+    }
+
+    let mut problems = Vec::new();
+    for ((zome_name, zome), zome_id) in dna_file.dna().zomes.iter().zip(zome_ids) {
+        let wasm = dna_file.get_wasm_for_zome(zome_name)?;
+        let exports = match exported_fns(&wasm.code) {
+            Ok(exports) => exports,
+            Err(e) => {
+                problems.push(DnaProblem::WasmInvalid(zome_name.clone(), e.to_string()));
+                continue;
+            }
+        };
+        for export in &exports {
+            if !export.callable && is_zome_callback(export.name.as_ref(), zome_id) {
+                problems.push(DnaProblem::CallbackNotCallable(
+                    zome_name.clone(),
+                    export.name.clone(),
+                ));
+            }
+        }
+        for callback in required_callbacks {
+            if !exports.iter().any(|export| &export.name == callback) {
+                problems.push(DnaProblem::CallbackMissing(
+                    zome_name.clone(),
+                    callback.clone(),
+                ));
+            }
+        }
+        if exports
+            .iter()
+            .any(|export| export.callable && export.name.as_ref() == "entry_defs")
+        {
+            match call_entry_defs(&zome.wasm_hash, &wasm.code) {
+                Ok(EntryDefsCallbackResult::Defs(_)) => {}
+                Ok(EntryDefsCallbackResult::Err(e)) | Err(e) => {
+                    problems.push(DnaProblem::EntryDefs(zome_name.clone(), e))
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
@@ -0,0 +1,104 @@
+//! Read what a zome's wasm exports without running it
+
+use holochain_types::dna::zome::{is_callback, validate_callback_entry_def_id};
+use holochain_zome_types::{header::ZomeId, zome::FunctionName};
+use wasmparser::{ExternalKind, ImportSectionEntryType, ModuleReader, SectionCode};
+
+/// A function a zome's wasm exports
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WasmExport {
+    /// The name the function is exported by
+    pub name: FunctionName,
+    /// Whether the host can call the function: externs take a pointer to
+    /// their input and return a pointer to their output
+    pub callable: bool,
+}
+
+/// Check that the wasm is valid and list the functions it exports, sorted
+/// by name. The functions the guest and compiler export for the host's own
+/// use are left out.
+pub(crate) fn exported_fns(wasm: &[u8]) -> wasmparser::Result<Vec<WasmExport>> {
+    wasmparser::validate(wasm, None)?;
+
+    let mut types = Vec::new();
+    // The type of each function, imported functions first
+    let mut fn_types = Vec::new();
+    let mut exports = Vec::new();
+    let mut reader = ModuleReader::new(wasm)?;
+    while !reader.eof() {
+        let section = reader.read()?;
+        match section.code {
+            SectionCode::Type => {
+                for ty in section.get_type_section_reader()? {
+                    types.push(ty?);
+                }
+            }
+            SectionCode::Import => {
+                for import in section.get_import_section_reader()? {
+                    if let ImportSectionEntryType::Function(ty) = import?.ty {
+                        fn_types.push(ty);
+                    }
+                }
+            }
+            SectionCode::Function => {
+                for ty in section.get_function_section_reader()? {
+                    fn_types.push(ty?);
+                }
+            }
+            SectionCode::Export => {
+                for export in section.get_export_section_reader()? {
+                    let export = export?;
+                    if export.kind == ExternalKind::Function && !export.field.starts_with("__") {
+                        exports.push((export.field.to_string(), export.index));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The wasm is valid, so every index points at something
+    let mut fns: Vec<_> = exports
+        .into_iter()
+        .map(|(name, index)| {
+            let ty = &types[fn_types[index as usize] as usize];
+            WasmExport {
+                name: name.into(),
+                callable: ty.params.len() == 1 && ty.returns == ty.params,
+            }
+        })
+        .collect();
+    fns.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fns)
+}
+
+/// List the module and name of each function the wasm imports
+pub(crate) fn imported_fns(wasm: &[u8]) -> wasmparser::Result<Vec<(String, String)>> {
+    let mut imports = Vec::new();
+    let mut reader = ModuleReader::new(wasm)?;
+    while !reader.eof() {
+        let section = reader.read()?;
+        if section.code == SectionCode::Import {
+            for import in section.get_import_section_reader()? {
+                let import = import?;
+                if let ImportSectionEntryType::Function(_) = import.ty {
+                    imports.push((import.module.to_string(), import.field.to_string()));
+                }
+            }
+        }
+    }
+    Ok(imports)
+}
+
+/// Is this exported function one of the zome's callbacks?
+///
+/// Which entry types a zome defines is only known by calling its
+/// `entry_defs`, so a function named like the `validate` callback of an
+/// entry type is taken to be one.
+pub(crate) fn is_zome_callback(fn_name: &str, zome_id: ZomeId) -> bool {
+    let entry_def_ids: Vec<String> = validate_callback_entry_def_id(fn_name)
+        .map(ToString::to_string)
+        .into_iter()
+        .collect();
+    is_callback(fn_name, zome_id, &entry_def_ids)
+}
@@ -145,6 +145,7 @@ entry_def!(Path EntryDef {
     required_validations: RequiredValidations::default(),
     visibility: EntryVisibility::Public,
     required_validation_type: RequiredValidationType::default(),
+    properties: None,
 });
 
 /// Wrap components vector.
@@ -1,4 +1,5 @@
 pub mod agent_info;
+pub mod app_cells;
 pub mod call;
 pub mod call_remote;
 pub mod create;
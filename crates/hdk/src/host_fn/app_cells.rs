@@ -0,0 +1,17 @@
+use crate::prelude::*;
+
+/// Trivial wrapper for __app_cells host function.
+/// App cells input struct is `()` so the function call simply looks like this:
+///
+/// ```ignore
+/// let app_cells = app_cells()?;
+/// ```
+///
+/// Lists every cell in the same app as the current cell, including itself,
+/// by the nick it was installed with. Any of them can be called with [call_nick].
+pub fn app_cells() -> HdkResult<Vec<(CellNick, CellId)>> {
+    Ok(
+        host_call::<AppCellsInput, AppCellsOutput>(__app_cells, &AppCellsInput::new(()))?
+            .into_inner(),
+    )
+}
@@ -4,6 +4,8 @@ use crate::prelude::*;
 /// Make a Zome call in another Zome.
 /// The Zome can be in another Cell or the
 /// same Cell but must be installed on the same conductor.
+/// The call is always made as the current agent and the
+/// called Cell checks it has a capability grant for that.
 ///
 /// ## Parameters
 /// - to_cell: The cell you want to call (If None will call the current cell).
@@ -18,18 +20,61 @@ pub fn call<'a, I: 'a, O>(
     cap_secret: Option<CapSecret>,
     payload: &'a I,
 ) -> HdkResult<O>
+where
+    SerializedBytes: TryFrom<&'a I, Error = SerializedBytesError>,
+    O: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+{
+    call_target(to_cell.into(), zome_name, fn_name, cap_secret, payload)
+}
+
+/// # Call Nick
+/// Make a Zome call in a sibling Cell of the same app,
+/// found by the [CellNick] it was installed with.
+/// This means the zome doesn't need to know the [CellId]
+/// the sibling was given when the app was installed.
+/// Otherwise this is the same as [call].
+///
+/// ## Parameters
+/// - cell_nick: The nick of the cell you want to call.
+/// - zome_name: The name of the zome you want to call.
+/// - fn_name: The name of the function in the zome you are calling.
+/// - cap_secret: The capability secret if required.
+/// - payload: The arguments to the function you are calling.
+pub fn call_nick<'a, I: 'a, O>(
+    cell_nick: CellNick,
+    zome_name: ZomeName,
+    fn_name: FunctionName,
+    cap_secret: Option<CapSecret>,
+    payload: &'a I,
+) -> HdkResult<O>
+where
+    SerializedBytes: TryFrom<&'a I, Error = SerializedBytesError>,
+    O: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+{
+    call_target(
+        CallTarget::CellNick(cell_nick),
+        zome_name,
+        fn_name,
+        cap_secret,
+        payload,
+    )
+}
+
+fn call_target<'a, I: 'a, O>(
+    target: CallTarget,
+    zome_name: ZomeName,
+    fn_name: FunctionName,
+    cap_secret: Option<CapSecret>,
+    payload: &'a I,
+) -> HdkResult<O>
 where
     SerializedBytes: TryFrom<&'a I, Error = SerializedBytesError>,
     O: TryFrom<SerializedBytes, Error = SerializedBytesError>,
 {
     let payload = SerializedBytes::try_from(payload)?;
-    // @todo is this secure to set this in the wasm rather than have the host inject it?
-    let provenance = agent_info()?.agent_latest_pubkey;
     let out: ZomeCallResponse = host_call::<CallInput, CallOutput>(
         __call,
-        &CallInput::new(Call::new(
-            to_cell, zome_name, fn_name, cap_secret, payload, provenance,
-        )),
+        &CallInput::new(Call::new(target, zome_name, fn_name, cap_secret, payload)),
     )?
     .into_inner();
 
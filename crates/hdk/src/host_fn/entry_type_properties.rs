@@ -1 +1,41 @@
-//! @todo
+use crate::prelude::*;
+
+/// Read the properties declared on one of this zome's entry defs.
+///
+/// Entry defs can carry arbitrary serialized properties alongside their
+/// visibility and validation settings. `#[hdk_entry]` takes them as an
+/// expression which is serialized when the entry def is built:
+///
+/// ```no_run
+/// use hdk3::prelude::*;
+///
+/// #[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+/// pub struct PostLimits {
+///     max_length: u32,
+/// }
+///
+/// #[hdk_entry(id = "post", properties = "PostLimits { max_length: 140 }")]
+/// pub struct Post(String);
+///
+/// fn post_limits() -> HdkResult<Option<PostLimits>> {
+///     entry_type_properties("post")
+/// }
+/// ```
+///
+/// Returns `None` if the entry def has no properties, or an error if this
+/// zome has no entry def with the id.
+pub fn entry_type_properties<I, T>(entry_def_id: I) -> HdkResult<Option<T>>
+where
+    I: Into<EntryDefId>,
+    T: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+{
+    match host_call::<EntryTypePropertiesInput, EntryTypePropertiesOutput>(
+        __entry_type_properties,
+        &EntryTypePropertiesInput::new(entry_def_id.into()),
+    )?
+    .into_inner()
+    {
+        Some(serialized) => Ok(Some(T::try_from(serialized)?)),
+        None => Ok(None),
+    }
+}
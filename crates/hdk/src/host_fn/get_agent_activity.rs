@@ -1,5 +1,15 @@
 use crate::prelude::*;
 
+/// Gets the activity of an agent's source chain from its agent activity authorities.
+///
+/// The returned `status` is `ChainStatus::Forked` when the authorities have seen two
+/// headers from this agent at the same sequence number. Both header hashes are included
+/// so the fork can be checked with `get`.
+///
+/// This can be called from validation callbacks, e.g. to reject entries from a forked chain.
+/// `ActivityRequest::Status` skips fetching the chain items when only the status is needed.
+///
+/// Note: `get_agent_activity` __always triggers and blocks on a network call__.
 pub fn get_agent_activity(
     agent: AgentPubKey,
     query: ChainQueryFilter,
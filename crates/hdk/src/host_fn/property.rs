@@ -1 +1,39 @@
-//! @todo
+use crate::prelude::*;
+
+/// Read a single dna property by its path of object keys and array indexes.
+///
+/// Dna properties are set in the dna file and can be overridden for each
+/// deployment when the dna is installed, so they are a good place for things
+/// like admin keys and limits.
+///
+/// The property is deserialized to whatever type is expected, so given the
+/// properties `{ "limits": { "max_posts": 10 }, "admin_keys": ["..."] }`:
+///
+/// ```ignore
+/// #[derive(serde::Serialize, serde::Deserialize, SerializedBytes)]
+/// struct Limits {
+///     max_posts: u32,
+/// }
+///
+/// #[derive(serde::Serialize, serde::Deserialize, SerializedBytes)]
+/// struct AdminKey(String);
+///
+/// let limits: Option<Limits> = property(&["limits"])?;
+/// let first_admin: Option<AdminKey> = property(&["admin_keys", "0"])?;
+/// ```
+///
+/// Returns `None` if there is nothing at the path.
+/// An empty path reads all the properties.
+/// @see zome_info for the properties as a single serialized blob.
+pub fn property<T>(path: &[&str]) -> HdkResult<Option<T>>
+where
+    T: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+{
+    let path = path.iter().map(|key| key.to_string()).collect();
+    match host_call::<PropertyInput, PropertyOutput>(__property, &PropertyInput::new(path))?
+        .into_inner()
+    {
+        Some(serialized) => Ok(Some(T::try_from(serialized)?)),
+        None => Ok(None),
+    }
+}
@@ -19,19 +19,23 @@ pub use crate::hash_path::anchor::list_anchor_type_addresses;
 pub use crate::hash_path::anchor::Anchor;
 pub use crate::hash_path::path::Path;
 pub use crate::host_fn::agent_info::agent_info;
+pub use crate::host_fn::app_cells::app_cells;
 pub use crate::host_fn::call::call;
+pub use crate::host_fn::call::call_nick;
 pub use crate::host_fn::call_remote::call_remote;
 pub use crate::host_fn::create::create;
 pub use crate::host_fn::create_link::create_link;
 pub use crate::host_fn::delete::delete;
 pub use crate::host_fn::delete_link::delete_link;
 pub use crate::host_fn::emit_signal::emit_signal;
+pub use crate::host_fn::entry_type_properties::entry_type_properties;
 pub use crate::host_fn::get::get;
 pub use crate::host_fn::get_agent_activity::get_agent_activity;
 pub use crate::host_fn::get_details::get_details;
 pub use crate::host_fn::get_link_details::get_link_details;
 pub use crate::host_fn::get_links::get_links;
 pub use crate::host_fn::hash_entry::hash_entry;
+pub use crate::host_fn::property::property;
 pub use crate::host_fn::query::query;
 pub use crate::host_fn::random_bytes::random_bytes;
 pub use crate::host_fn::sign::sign;
@@ -55,6 +59,7 @@ pub use holochain_zome_types;
 pub use holochain_zome_types::agent_info::AgentInfo;
 pub use holochain_zome_types::bytes::Bytes;
 pub use holochain_zome_types::call::Call;
+pub use holochain_zome_types::call::CallTarget;
 pub use holochain_zome_types::call_remote::CallRemote;
 pub use holochain_zome_types::capability::*;
 pub use holochain_zome_types::cell::*;
@@ -76,6 +81,7 @@ pub use holochain_zome_types::query::ActivityRequest;
 pub use holochain_zome_types::query::AgentActivity;
 pub use holochain_zome_types::query::ChainQueryFilter as QueryFilter;
 pub use holochain_zome_types::query::ChainQueryFilter;
+pub use holochain_zome_types::query::ChainQueryOrder;
 pub use holochain_zome_types::signature::Sign;
 pub use holochain_zome_types::signature::Signature;
 pub use holochain_zome_types::signature::VerifySignature;
@@ -96,3 +102,9 @@ pub use std::convert::TryFrom;
 
 // This needs to be called at least once _somewhere_ and is idempotent.
 holochain_externs!();
+
+// Host functions which `holochain_externs!` doesn't declare yet.
+// They are declared here, once, alongside the rest.
+extern "C" {
+    pub fn __app_cells(guest_allocation_ptr: GuestPtr) -> GuestPtr;
+}
@@ -5,7 +5,8 @@ use quote::TokenStreamExt;
 use syn::parse::{Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
 
-struct EntryDef(holochain_zome_types::entry_def::EntryDef);
+/// The entry def and the expression for its properties, if it has any
+struct EntryDef(holochain_zome_types::entry_def::EntryDef, Option<syn::Expr>);
 struct EntryDefId(holochain_zome_types::entry_def::EntryDefId);
 struct EntryVisibility(holochain_zome_types::entry_def::EntryVisibility);
 struct CrdtType(holochain_zome_types::crdt::CrdtType);
@@ -21,6 +22,7 @@ impl Parse for EntryDef {
         let crdt_type = holochain_zome_types::crdt::CrdtType::default();
         let mut required_validation_type =
             holochain_zome_types::validate::RequiredValidationType::default();
+        let mut properties = None;
 
         let vars = Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated(input)?;
         for var in vars {
@@ -86,17 +88,25 @@ impl Parse for EntryDef {
                     "crdt_type" => {
                         unimplemented!();
                     }
+                    "properties" => match var.lit {
+                        syn::Lit::Str(s) => properties = Some(s.parse::<syn::Expr>()?),
+                        _ => unreachable!(),
+                    },
                     _ => {}
                 }
             }
         }
-        Ok(EntryDef(holochain_zome_types::entry_def::EntryDef {
-            id,
-            required_validations,
-            visibility,
-            crdt_type,
-            required_validation_type,
-        }))
+        Ok(EntryDef(
+            holochain_zome_types::entry_def::EntryDef {
+                id,
+                required_validations,
+                visibility,
+                crdt_type,
+                required_validation_type,
+                properties: None,
+            },
+            properties,
+        ))
     }
 }
 
@@ -169,6 +179,15 @@ impl quote::ToTokens for EntryDef {
         let crdt_type = CrdtType(self.0.crdt_type);
         let required_validations = RequiredValidations(self.0.required_validations);
         let required_validation_type = RequiredValidationType(self.0.required_validation_type);
+        let properties = match &self.1 {
+            Some(expr) => quote::quote! {
+                Some(
+                    std::convert::TryFrom::try_from(#expr)
+                        .expect("Failed to serialize the entry def properties"),
+                )
+            },
+            None => quote::quote! { None },
+        };
 
         tokens.append_all(quote::quote! {
             hdk3::prelude::EntryDef {
@@ -177,6 +196,7 @@ impl quote::ToTokens for EntryDef {
                 crdt_type: #crdt_type,
                 required_validations: #required_validations,
                 required_validation_type: #required_validation_type,
+                properties: #properties,
             }
         });
     }
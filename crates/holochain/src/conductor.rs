@@ -29,9 +29,12 @@ pub mod handle;
 pub mod interactive;
 pub mod interface;
 pub mod manager;
+pub mod metrics;
+pub mod migrations;
 pub mod p2p_store;
 pub mod paths;
 pub mod state;
+pub mod state_dump;
 
 pub use cell::{error::CellError, Cell};
 pub use conductor::{Conductor, ConductorBuilder, ConductorStateDb};
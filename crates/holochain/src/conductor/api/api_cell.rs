@@ -7,16 +7,16 @@ use crate::core::ribosome::ZomeCallInvocation;
 use crate::core::workflow::ZomeCallInvocationResult;
 use crate::{
     conductor::{
-        entry_def_store::EntryDefBufferKey, interface::SignalBroadcaster, ConductorHandle,
+        config::WasmLimits, entry_def_store::EntryDefBufferKey, interface::SignalBroadcaster,
+        ConductorHandle,
     },
     core::workflow::CallZomeWorkspaceLock,
 };
 use async_trait::async_trait;
-use holo_hash::DnaHash;
+use holo_hash::{AgentPubKey, DnaHash};
 use holochain_keystore::KeystoreSender;
-use holochain_types::{autonomic::AutonomicCue, cell::CellId, dna::DnaFile};
-use holochain_zome_types::entry_def::EntryDef;
-use tracing::*;
+use holochain_types::{app::InstalledCell, autonomic::AutonomicCue, cell::CellId, dna::DnaFile};
+use holochain_zome_types::{dpki::KeyState, entry_def::EntryDef};
 
 /// The concrete implementation of [CellConductorApiT], which is used to give
 /// Cells an API for calling back to their [Conductor].
@@ -65,9 +65,20 @@ impl CellConductorApiT for CellConductorApi {
         }
     }
 
-    async fn dpki_request(&self, _method: String, _args: String) -> ConductorApiResult<String> {
-        warn!("Using placeholder dpki");
-        Ok("TODO".to_string())
+    async fn dpki_key_state(
+        &self,
+        agent_key: &AgentPubKey,
+    ) -> ConductorApiResult<Option<KeyState>> {
+        // The DPKI app's own cells can't wait on themselves to validate
+        match self.conductor_handle.dpki_cell_id().await? {
+            Some(dpki_cell_id) if dpki_cell_id.dna_hash() == self.cell_id.dna_hash() => Ok(None),
+            Some(_) => self.conductor_handle.dpki_key_state(agent_key).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()> {
+        self.conductor_handle.dpki_register_key(agent_key).await
     }
 
     async fn autonomic_cue(&self, cue: AutonomicCue) -> ConductorApiResult<()> {
@@ -80,10 +91,22 @@ impl CellConductorApiT for CellConductorApi {
         self.conductor_handle.keystore()
     }
 
+    fn wasm_limits(&self) -> &WasmLimits {
+        self.conductor_handle.wasm_limits()
+    }
+
+    fn wasm_instance_pool_size(&self) -> usize {
+        self.conductor_handle.wasm_instance_pool_size()
+    }
+
     async fn signal_broadcaster(&self) -> SignalBroadcaster {
         self.conductor_handle.signal_broadcaster().await
     }
 
+    async fn admin_signal_broadcaster(&self) -> SignalBroadcaster {
+        self.conductor_handle.admin_signal_broadcaster().await
+    }
+
     async fn get_dna(&self, dna_hash: &DnaHash) -> Option<DnaFile> {
         self.conductor_handle.get_dna(dna_hash).await
     }
@@ -115,9 +138,13 @@ pub trait CellConductorApiT: Clone + Send + Sync + Sized {
         invocation: ZomeCallInvocation,
     ) -> ConductorApiResult<ZomeCallInvocationResult>;
 
-    /// Make a request to the DPKI service running for this Conductor.
-    /// TODO: decide on actual signature
-    async fn dpki_request(&self, method: String, args: String) -> ConductorApiResult<String>;
+    /// Ask the conductor's DPKI app what it knows about a key.
+    /// Returns `None` if there is no DPKI app, or this cell is part of it.
+    async fn dpki_key_state(&self, agent_key: &AgentPubKey)
+        -> ConductorApiResult<Option<KeyState>>;
+
+    /// Register a new key with the conductor's DPKI app, if there is one
+    async fn dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()>;
 
     /// Cue the autonomic system to run an [AutonomicProcess] earlier than its scheduled time.
     /// This is basically a heuristic designed to help things run more smoothly.
@@ -126,10 +153,20 @@ pub trait CellConductorApiT: Clone + Send + Sync + Sized {
     /// Request access to this conductor's keystore
     fn keystore(&self) -> &KeystoreSender;
 
+    /// The limits applied to every call into this cell's wasm
+    fn wasm_limits(&self) -> &WasmLimits;
+
+    /// How many idle instances of each zome to keep for zome calls
+    fn wasm_instance_pool_size(&self) -> usize;
+
     /// Access the broadcast Sender which will send a Signal across every
     /// attached app interface
     async fn signal_broadcaster(&self) -> SignalBroadcaster;
 
+    /// Access the broadcast Sender which will send a Signal across every
+    /// attached admin interface
+    async fn admin_signal_broadcaster(&self) -> SignalBroadcaster;
+
     /// Get a [Dna] from the [DnaStore]
     async fn get_dna(&self, dna_hash: &DnaHash) -> Option<DnaFile>;
 
@@ -155,6 +192,12 @@ pub trait CellConductorReadHandleT: Send + Sync {
         invocation: ZomeCallInvocation,
         workspace_lock: &CallZomeWorkspaceLock,
     ) -> ConductorApiResult<ZomeCallInvocationResult>;
+
+    /// List the cells of the app this cell was installed in, including itself
+    async fn list_app_cells(&self) -> ConductorApiResult<Vec<InstalledCell>>;
+
+    /// Get a [EntryDef] from the [EntryDefBuf]
+    async fn get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef>;
 }
 
 #[async_trait]
@@ -175,4 +218,12 @@ impl CellConductorReadHandleT for CellConductorApi {
             self.conductor_handle.call_zome(invocation).await
         }
     }
+
+    async fn list_app_cells(&self) -> ConductorApiResult<Vec<InstalledCell>> {
+        self.conductor_handle.get_app_cells(&self.cell_id).await
+    }
+
+    async fn get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef> {
+        self.conductor_handle.get_entry_def(key).await
+    }
 }
@@ -6,16 +6,23 @@ use crate::conductor::{
     config::AdminInterfaceConfig,
     error::CreateAppError,
     interface::error::{InterfaceError, InterfaceResult},
+    state_dump::{FullStateDump, StateDumpFilter},
     ConductorHandle,
 };
+use crate::core::state::source_chain::SourceChainExport;
 use holo_hash::*;
 use holochain_keystore::KeystoreSenderExt;
 use holochain_serialized_bytes::prelude::*;
 use holochain_types::{
-    app::{InstallAppDnaPayload, InstallAppPayload, InstalledApp, InstalledAppId, InstalledCell},
+    app::{
+        bundle::{AppBundle, BundledDna},
+        InstallAppBundlePayload, InstallAppDnaPayload, InstallAppPayload, InstalledApp,
+        InstalledAppId, InstalledCell, MembraneProof,
+    },
     cell::CellId,
-    dna::{DnaFile, JsonProperties},
+    dna::{zome::ZomeExterns, DnaFile, JsonProperties},
 };
+use holochain_zome_types::zome::ZomeName;
 use std::path::PathBuf;
 use tracing::*;
 
@@ -55,6 +62,29 @@ impl RealAdminInterfaceApi {
     pub(crate) fn new(conductor_handle: ConductorHandle) -> Self {
         RealAdminInterfaceApi { conductor_handle }
     }
+
+    /// Install an app made of cells whose Dnas are already installed,
+    /// which runs genesis on each of them
+    async fn install_app(
+        &self,
+        installed_app_id: InstalledAppId,
+        cell_ids_with_proofs: Vec<(InstalledCell, Option<MembraneProof>)>,
+    ) -> ConductorApiResult<InstalledApp> {
+        // Call genesis
+        self.conductor_handle
+            .clone()
+            .install_app(installed_app_id.clone(), cell_ids_with_proofs.clone())
+            .await?;
+
+        let cell_data = cell_ids_with_proofs
+            .into_iter()
+            .map(|(cell_data, _)| cell_data)
+            .collect();
+        Ok(InstalledApp {
+            installed_app_id,
+            cell_data,
+        })
+    }
 }
 
 #[async_trait::async_trait]
@@ -87,41 +117,119 @@ impl AdminInterfaceApi for RealAdminInterfaceApi {
                         properties,
                         membrane_proof,
                         nick,
+                        source_chain,
                     } = dna_payload;
                     let dna = read_parse_dna(path, properties).await?;
                     let hash = dna.dna_hash().clone();
                     let cell_id = CellId::from((hash.clone(), agent_key.clone()));
                     self.conductor_handle.install_dna(dna).await?;
-                    ConductorApiResult::Ok((InstalledCell::new(cell_id, nick), membrane_proof))
+                    // A restored source chain already has its genesis elements
+                    let restored = if let Some(source_chain) = source_chain {
+                        let export = read_source_chain(source_chain).await?;
+                        self.conductor_handle
+                            .import_source_chain(&cell_id, export)
+                            .await?;
+                        true
+                    } else {
+                        false
+                    };
+                    ConductorApiResult::Ok((
+                        InstalledCell::new(cell_id, nick),
+                        membrane_proof,
+                        restored,
+                    ))
                 });
 
                 // Join all the install tasks
-                let cell_ids_with_proofs = futures::future::join_all(tasks)
-                    .await
-                    .into_iter()
+                let results = futures::future::join_all(tasks).await;
+                let restored: Vec<CellId> = results
+                    .iter()
+                    .filter_map(|r| match r {
+                        Ok((cell, _, true)) => Some(cell.as_id().clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let app = async {
                     // Check all passed and return the proofs
-                    .collect::<Result<Vec<_>, _>>()?;
-
-                // Call genesis
-                self.conductor_handle
-                    .clone()
-                    .install_app(installed_app_id.clone(), cell_ids_with_proofs.clone())
-                    .await?;
+                    let cell_ids_with_proofs = results
+                        .into_iter()
+                        .map(|r| r.map(|(cell, proof, _)| (cell, proof)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.install_app(installed_app_id, cell_ids_with_proofs)
+                        .await
+                }
+                .await;
 
-                let cell_data = cell_ids_with_proofs
-                    .into_iter()
-                    .map(|(cell_data, _)| cell_data)
-                    .collect();
-                let app = InstalledApp {
+                // The restored chains are removed if the app isn't installed,
+                // or trying again would find them in the way
+                if app.is_err() {
+                    for cell_id in restored {
+                        if let Err(e) = self.conductor_handle.remove_source_chain(&cell_id).await {
+                            warn!(?e, ?cell_id, "Failed to remove a restored source chain");
+                        }
+                    }
+                }
+                Ok(AdminResponse::AppInstalled(app?))
+            }
+            InstallAppBundle(payload) => {
+                let InstallAppBundlePayload {
+                    path,
+                    agent_key,
                     installed_app_id,
-                    cell_data,
-                };
-                Ok(AdminResponse::AppInstalled(app))
+                    properties,
+                    uuid,
+                    membrane_proofs,
+                } = *payload;
+                let bundle = read_app_bundle(path).await?;
+                let installed_app_id =
+                    installed_app_id.unwrap_or_else(|| bundle.manifest().name.clone());
+                trace!(?installed_app_id, ?bundle);
+
+                // Check the whole bundle before installing any of its Dnas
+                let dnas = bundle.resolve(properties, uuid, membrane_proofs).await?;
+                let installed_dnas = self.conductor_handle.list_dnas().await?;
+                let mut new_dnas = Vec::new();
+                let app = async {
+                    let mut cell_ids_with_proofs = Vec::with_capacity(dnas.len());
+                    for BundledDna {
+                        nick,
+                        dna,
+                        membrane_proof,
+                    } in dnas
+                    {
+                        let hash = dna.dna_hash().clone();
+                        let cell_id = CellId::from((hash.clone(), agent_key.clone()));
+                        if !installed_dnas.contains(&hash) {
+                            new_dnas.push(hash);
+                        }
+                        self.conductor_handle.install_dna(dna).await?;
+                        cell_ids_with_proofs
+                            .push((InstalledCell::new(cell_id, nick), membrane_proof));
+                    }
+                    self.install_app(installed_app_id, cell_ids_with_proofs)
+                        .await
+                }
+                .await;
+
+                // Genesis needs the Dnas installed, so the ones this bundle
+                // added are removed again if the app isn't installed
+                if app.is_err() {
+                    for hash in new_dnas {
+                        if let Err(e) = self.conductor_handle.remove_dna(&hash).await {
+                            warn!(?e, ?hash, "Failed to remove a Dna of an app bundle");
+                        }
+                    }
+                }
+                Ok(AdminResponse::AppInstalled(app?))
             }
             ListDnas => {
                 let dna_list = self.conductor_handle.list_dnas().await?;
                 Ok(AdminResponse::DnasListed(dna_list))
             }
+            ListZomeExterns { dna_hash } => {
+                let externs = self.conductor_handle.list_zome_externs(&dna_hash).await?;
+                Ok(AdminResponse::ZomeExternsListed(externs))
+            }
             GenerateAgentPubKey => {
                 let agent_pub_key = self
                     .conductor_handle
@@ -183,6 +291,34 @@ impl AdminInterfaceApi for RealAdminInterfaceApi {
                 let state = self.conductor_handle.dump_cell_state(&cell_id).await?;
                 Ok(AdminResponse::StateDumped(state))
             }
+            DumpFullState { cell_id, filter } => {
+                let dump = self
+                    .conductor_handle
+                    .dump_full_cell_state(&cell_id, &filter.unwrap_or_default())
+                    .await?;
+                Ok(AdminResponse::FullStateDumped(dump))
+            }
+            ExportSourceChain {
+                cell_id,
+                path,
+                include_private_entries,
+            } => {
+                let export = self
+                    .conductor_handle
+                    .export_source_chain(&cell_id, include_private_entries)
+                    .await?;
+                let bytes = SerializedBytes::try_from(export).map_err(SerializationError::from)?;
+                tokio::fs::write(path, bytes.bytes()).await?;
+                Ok(AdminResponse::SourceChainExported)
+            }
+            RotateAgentKey { cell_id } => {
+                let new_key = self.conductor_handle.rotate_agent_key(&cell_id).await?;
+                Ok(AdminResponse::AgentKeyRotated(new_key))
+            }
+            RevokeAgentKey { agent_key } => {
+                self.conductor_handle.revoke_agent_key(&agent_key).await?;
+                Ok(AdminResponse::AgentKeyRevoked)
+            }
         }
     }
 }
@@ -203,6 +339,22 @@ async fn read_parse_dna(
     Ok(dna)
 }
 
+/// Reads an [AppBundle] from disk
+async fn read_app_bundle(path: PathBuf) -> ConductorApiResult<AppBundle> {
+    let content = tokio::fs::read(path)
+        .await
+        .map_err(|e| ConductorApiError::AppBundleReadError(format!("{:?}", e)))?;
+    Ok(AppBundle::from_file_content(&content)?)
+}
+
+/// Reads a source chain written by [`AdminRequest::ExportSourceChain`]
+async fn read_source_chain(path: PathBuf) -> ConductorApiResult<SourceChainExport> {
+    let bytes = tokio::fs::read(path).await?;
+    let export = SourceChainExport::try_from(SerializedBytes::from(UnsafeBytes::from(bytes)))
+        .map_err(SerializationError::from)?;
+    Ok(export)
+}
+
 #[async_trait::async_trait]
 impl InterfaceApi for RealAdminInterfaceApi {
     type ApiRequest = AdminRequest;
@@ -237,7 +389,13 @@ impl InterfaceApi for RealAdminInterfaceApi {
 /// and the enum variant on a key `type`, e.g.
 /// `{ type: 'activate_app', data: { installed_app_id: 'test_app' } }`
 ///
+/// Admin connections are also sent a [`SystemSignal`] whenever an app is
+/// installed, activated or deactivated, a cell fails to start, an app
+/// interface is attached, or a cell makes progress validating and
+/// integrating ops.
+///
 /// [`AdminResponse`]: enum.AdminResponse.html
+/// [`SystemSignal`]: ../../../core/signal/enum.SystemSignal.html
 #[derive(Debug, serde::Serialize, serde::Deserialize, SerializedBytes)]
 #[cfg_attr(test, derive(Clone))]
 #[serde(rename_all = "snake_case", tag = "type", content = "data")]
@@ -271,6 +429,26 @@ pub enum AdminRequest {
     /// [`AdminResponse::AppInstalled`]: enum.AdminResponse.html#variant.AppInstalled
     /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
     InstallApp(Box<InstallAppPayload>),
+    /// Install an app from an [`AppBundle`] file, which describes the whole
+    /// app: the `Dna`s it is made of, with their nicks, default properties,
+    /// UUIDs and whether they need a membrane proof.
+    /// Like [`AdminRequest::InstallApp`], it installs all the Dnas with
+    /// the given `AgentPubKey` and triggers genesis on the new `Cell`s.
+    /// See [`InstallAppBundlePayload`] for the properties, UUID and membrane
+    /// proofs which can be given at install time.
+    ///
+    /// Nothing is installed if the bundle is invalid, or a membrane proof
+    /// which the manifest requires is missing.
+    ///
+    /// Will be responded to with an [`AdminResponse::AppInstalled`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AppBundle`]: ../../../holochain_types/app/bundle/struct.AppBundle.html
+    /// [`InstallAppBundlePayload`]: ../../../holochain_types/app/struct.InstallAppBundlePayload.html
+    /// [`AdminRequest::InstallApp`]: enum.AdminRequest.html#variant.InstallApp
+    /// [`AdminResponse::AppInstalled`]: enum.AdminResponse.html#variant.AppInstalled
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    InstallAppBundle(Box<InstallAppBundlePayload>),
     /// List the hashes of all installed `Dna`s.
     /// Takes no arguments.
     ///
@@ -280,6 +458,17 @@ pub enum AdminRequest {
     /// [`AdminResponse::DnasListed`]: enum.AdminResponse.html#variant.DnasListed
     /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
     ListDnas,
+    /// List the zome functions and callbacks exported by each zome of an installed `Dna`.
+    ///
+    /// Will be responded to with an [`AdminResponse::ZomeExternsListed`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::ZomeExternsListed`]: enum.AdminResponse.html#variant.ZomeExternsListed
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    ListZomeExterns {
+        /// The hash of the Dna to list
+        dna_hash: DnaHash,
+    },
     /// Generate a new AgentPubKey.
     /// Takes no arguments.
     ///
@@ -364,6 +553,75 @@ pub enum AdminRequest {
         /// The `CellId` for which to dump state
         cell_id: Box<CellId>,
     },
+    /// Dump the state of a cell as structured data: the headers of its source
+    /// chain, the number of records in each of its single-value databases,
+    /// the ops in its validation and integration limbo, the ops it authored,
+    /// and the peers known in its space. The multi-value databases are not
+    /// counted, see [`FullStateDump::db_counts`].
+    ///
+    /// [`FullStateDump::db_counts`]: ../state_dump/struct.FullStateDump.html#structfield.db_counts
+    ///
+    /// Will be responded to with an [`AdminResponse::FullStateDumped`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    /// [`AdminResponse::FullStateDumped`]: enum.AdminResponse.html#variant.FullStateDumped
+    DumpFullState {
+        /// The `CellId` for which to dump state
+        cell_id: Box<CellId>,
+        /// Narrows down the source chain and ops which are dumped.
+        /// Everything is dumped if this is `None`.
+        filter: Option<StateDumpFilter>,
+    },
+    /// Write the whole source chain of a cell to a file. The file can be given
+    /// as the `source_chain` of an [`InstallAppDnaPayload`] to restore the chain
+    /// into another conductor, which skips genesis and publishes the chain again.
+    /// Private entries are left out unless `include_private_entries` is set,
+    /// as the file is not encrypted.
+    /// Authoring new entries on the restored chain needs the agent's private
+    /// key to be in the other conductor's keystore.
+    ///
+    /// Will be responded to with an [`AdminResponse::SourceChainExported`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`InstallAppDnaPayload`]: ../../../holochain_types/app/struct.InstallAppDnaPayload.html
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    /// [`AdminResponse::SourceChainExported`]: enum.AdminResponse.html#variant.SourceChainExported
+    ExportSourceChain {
+        /// The `CellId` whose source chain to export
+        cell_id: Box<CellId>,
+        /// The path of the file to write the source chain to
+        path: PathBuf,
+        /// Whether to write the private entries too, in the clear
+        #[serde(default)]
+        include_private_entries: bool,
+    },
+    /// Replace the key a cell's source chain is authored with by a newly
+    /// generated key. The cell keeps its `CellId`.
+    /// Requires the conductor to be configured with a DPKI app,
+    /// which records that the old key was replaced.
+    ///
+    /// Will be responded to with an [`AdminResponse::AgentKeyRotated`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::AgentKeyRotated`]: enum.AdminResponse.html#variant.AgentKeyRotated
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    RotateAgentKey {
+        /// The `CellId` whose key to rotate
+        cell_id: Box<CellId>,
+    },
+    /// Revoke an agent key in the DPKI app, for example because it was
+    /// compromised. Headers the key signs from now on are rejected.
+    ///
+    /// Will be responded to with an [`AdminResponse::AgentKeyRevoked`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::AgentKeyRevoked`]: enum.AdminResponse.html#variant.AgentKeyRevoked
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    RevokeAgentKey {
+        /// The key to revoke
+        agent_key: AgentPubKey,
+    },
 }
 
 /// Represents the possible responses to an [`AdminRequest`]
@@ -387,13 +645,15 @@ pub enum AdminResponse {
     /// [`AdminRequest`]: enum.AdminRequest.html
     /// [`ExternalApiWireError`]: error/enum.ExternalApiWireError.html
     Error(ExternalApiWireError),
-    /// The succesful response to an [`AdminRequest::InstallApp`].
+    /// The succesful response to an [`AdminRequest::InstallApp`]
+    /// or an [`AdminRequest::InstallAppBundle`].
     ///
     /// The resulting [`InstalledApp`] contains the App id,
     /// the [`CellNick`]s and, most usefully, the new [`CellId`]s
     /// of the newly installed `Dna`s. See the [`InstalledApp`] docs for details.
     ///
     /// [`AdminRequest::InstallApp`]: enum.AdminRequest.html#variant.InstallApp
+    /// [`AdminRequest::InstallAppBundle`]: enum.AdminRequest.html#variant.InstallAppBundle
     /// [`InstalledApp`]: ../../../holochain_types/app/struct.InstalledApp.html
     /// [`CellNick`]: ../../../holochain_types/app/type.CellNick.html
     /// [`CellId`]: ../../../holochain_types/cell/struct.CellId.html
@@ -416,6 +676,13 @@ pub enum AdminResponse {
     ///
     /// [`AdminRequest::ListDnas`]: enum.AdminRequest.html#variant.ListDnas
     DnasListed(Vec<DnaHash>),
+    /// The successful response to an [`AdminRequest::ListZomeExterns`].
+    ///
+    /// Contains the zome functions and callbacks of each zome in the `Dna`,
+    /// in the order the zomes appear in the `Dna`
+    ///
+    /// [`AdminRequest::ListZomeExterns`]: enum.AdminRequest.html#variant.ListZomeExterns
+    ZomeExternsListed(Vec<(ZomeName, ZomeExterns)>),
     /// The succesful response to an [`AdminRequest::ListCellIds`].
     ///
     /// Contains a list of all the `Cell` ids in the conductor
@@ -458,21 +725,53 @@ pub enum AdminResponse {
     ///
     /// [`AdminRequest::DumpState`]: enum.AdminRequest.html#variant.DumpState
     StateDumped(String),
+    /// The successful response to an [`AdminRequest::DumpFullState`].
+    ///
+    /// Contains the state of the cell, narrowed down by the filter
+    ///
+    /// [`AdminRequest::DumpFullState`]: enum.AdminRequest.html#variant.DumpFullState
+    FullStateDumped(FullStateDump),
+    /// The successful response to an [`AdminRequest::ExportSourceChain`].
+    ///
+    /// The source chain has been written to the requested file
+    ///
+    /// [`AdminRequest::ExportSourceChain`]: enum.AdminRequest.html#variant.ExportSourceChain
+    SourceChainExported,
+    /// The successful response to an [`AdminRequest::RotateAgentKey`].
+    ///
+    /// Contains the new key the cell's source chain is authored with
+    ///
+    /// [`AdminRequest::RotateAgentKey`]: enum.AdminRequest.html#variant.RotateAgentKey
+    AgentKeyRotated(AgentPubKey),
+    /// The successful response to an [`AdminRequest::RevokeAgentKey`].
+    ///
+    /// [`AdminRequest::RevokeAgentKey`]: enum.AdminRequest.html#variant.RevokeAgentKey
+    AgentKeyRevoked,
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::conductor::Conductor;
+    use crate::conductor::{
+        config::{ConductorConfig, DpkiConfig},
+        Conductor,
+    };
+    use crate::core::state::source_chain::SourceChain;
+    use crate::test_utils::install_app;
     use anyhow::Result;
     use holochain_state::test_utils::test_environments;
     use holochain_types::{
-        app::InstallAppDnaPayload,
+        app::{
+            bundle::{AppDnaManifest, AppManifest},
+            InstallAppDnaPayload,
+        },
         observability,
         test_utils::{fake_agent_pubkey_1, fake_dna_file, fake_dna_zomes, write_fake_dna_file},
     };
     use holochain_wasm_test_utils::TestWasm;
+    use holochain_zome_types::dpki::KeyState;
     use matches::assert_matches;
+    use std::collections::{BTreeMap, HashMap};
     use uuid::Uuid;
 
     #[tokio::test(threaded_scheduler)]
@@ -509,6 +808,20 @@ mod test {
             install_response,
             AdminResponse::AppInstalled(cell_ids) if cell_ids == expected_cell_ids
         );
+        let res = admin_api
+            .handle_admin_request(AdminRequest::ListZomeExterns {
+                dna_hash: dna_hash.clone(),
+            })
+            .await;
+        let expects = vec![(
+            TestWasm::Foo.into(),
+            ZomeExterns {
+                zome_fns: vec!["foo".into()],
+                callbacks: vec!["init".into()],
+            },
+        )];
+        assert_matches!(res, AdminResponse::ZomeExternsListed(v) if v == expects);
+
         let dna_list = admin_api.handle_admin_request(AdminRequest::ListDnas).await;
         let expects = vec![dna_hash];
         assert_matches!(dna_list, AdminResponse::DnasListed(a) if a == expects);
@@ -540,6 +853,311 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn install_app_bundle() -> Result<()> {
+        observability::test_run().ok();
+        let envs = test_environments();
+        let handle = Conductor::builder().test(&envs).await?;
+        let shutdown = handle.take_shutdown_handle().await.unwrap();
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+        let dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        );
+        let manifest = AppManifest {
+            name: "bundled".to_string(),
+            description: None,
+            dnas: vec![AppDnaManifest {
+                nick: "foo".to_string(),
+                path: "foo.dna.gz".to_string(),
+                properties: None,
+                uuid: None,
+                membrane_proof_required: true,
+            }],
+        };
+        let mut dnas = BTreeMap::new();
+        dnas.insert("foo.dna.gz".to_string(), dna.to_file_content().await?);
+        let bundle = AppBundle::new(manifest, dnas)?;
+        let tmp_dir = tempdir::TempDir::new("app_bundle")?;
+        let path = tmp_dir.path().join("test.happ");
+        tokio::fs::write(&path, bundle.to_file_content()?).await?;
+
+        let agent_key = fake_agent_pubkey_1();
+        let uuid = Uuid::new_v4().to_string();
+        let mut payload = InstallAppBundlePayload {
+            path,
+            agent_key: agent_key.clone(),
+            installed_app_id: None,
+            properties: HashMap::new(),
+            uuid: Some(uuid.clone()),
+            membrane_proofs: HashMap::new(),
+        };
+
+        // The manifest requires a membrane proof
+        let res = admin_api
+            .handle_admin_request(AdminRequest::InstallAppBundle(Box::new(payload.clone())))
+            .await;
+        assert_matches!(res, AdminResponse::Error(_));
+        let dna_list = admin_api.handle_admin_request(AdminRequest::ListDnas).await;
+        assert_matches!(dna_list, AdminResponse::DnasListed(a) if a.is_empty());
+
+        payload.membrane_proofs.insert(
+            "foo".to_string(),
+            SerializedBytes::from(UnsafeBytes::from(vec![0])),
+        );
+        let res = admin_api
+            .handle_admin_request(AdminRequest::InstallAppBundle(Box::new(payload.clone())))
+            .await;
+        let dna_hash = dna.with_uuid(uuid).await?.dna_hash().clone();
+        let expected = InstalledApp {
+            installed_app_id: "bundled".to_string(),
+            cell_data: vec![InstalledCell::new(
+                CellId::new(dna_hash.clone(), agent_key),
+                "foo".to_string(),
+            )],
+        };
+        assert_matches!(res, AdminResponse::AppInstalled(app) if app == expected);
+        let dna_list = admin_api.handle_admin_request(AdminRequest::ListDnas).await;
+        assert_matches!(dna_list, AdminResponse::DnasListed(a) if a == vec![dna_hash.clone()]);
+
+        // The app id is taken, so the Dna with another UUID is removed again
+        payload.uuid = Some(Uuid::new_v4().to_string());
+        let res = admin_api
+            .handle_admin_request(AdminRequest::InstallAppBundle(Box::new(payload)))
+            .await;
+        assert_matches!(res, AdminResponse::Error(_));
+        let dna_list = admin_api.handle_admin_request(AdminRequest::ListDnas).await;
+        assert_matches!(dna_list, AdminResponse::DnasListed(a) if a == vec![dna_hash]);
+
+        handle.shutdown().await;
+        tokio::time::timeout(std::time::Duration::from_secs(1), shutdown)
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// The key a cell's chain is currently authored with
+    async fn latest_agent_pubkey(
+        handle: &ConductorHandle,
+        cell_id: &CellId,
+    ) -> Result<AgentPubKey> {
+        let env = handle.get_cell_env(cell_id).await?;
+        Ok(SourceChain::new(env.into())?.latest_agent_pubkey()?)
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn rotate_and_revoke_agent_keys_with_dpki() -> Result<()> {
+        observability::test_run().ok();
+        let envs = test_environments();
+        let config = ConductorConfig {
+            dpki: Some(DpkiConfig {
+                instance_id: "dpki".to_string(),
+                init_params: "".to_string(),
+            }),
+            ..Default::default()
+        };
+        let handle = Conductor::builder().config(config).test(&envs).await?;
+        let shutdown = handle.take_shutdown_handle().await.unwrap();
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+
+        // The DPKI app must be active before the app whose key it registers
+        let dpki_dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Dpki.into(), TestWasm::Dpki.into())],
+        );
+        let dpki_key = AgentPubKey::new_from_pure_entropy(handle.keystore()).await?;
+        let dpki_cell_id = CellId::new(dpki_dna.dna_hash().clone(), dpki_key);
+        install_app(
+            "dpki",
+            vec![(InstalledCell::new(dpki_cell_id.clone(), "".into()), None)],
+            vec![dpki_dna],
+            handle.clone(),
+        )
+        .await;
+        assert_eq!(handle.dpki_cell_id().await?, Some(dpki_cell_id));
+
+        let dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        );
+        let old_key = AgentPubKey::new_from_pure_entropy(handle.keystore()).await?;
+        let cell_id = CellId::new(dna.dna_hash().clone(), old_key.clone());
+        install_app(
+            "test",
+            vec![(InstalledCell::new(cell_id.clone(), "".into()), None)],
+            vec![dna],
+            handle.clone(),
+        )
+        .await;
+
+        // # Genesis registered the key
+        assert_matches!(
+            handle.dpki_key_state(&old_key).await?,
+            Some(KeyState::Valid { .. })
+        );
+
+        // # Rotating replaces the key with DPKI and on the chain
+        let res = admin_api
+            .handle_admin_request(AdminRequest::RotateAgentKey {
+                cell_id: cell_id.clone(),
+            })
+            .await;
+        let new_key = match res {
+            AdminResponse::AgentKeyRotated(new_key) => new_key,
+            res => panic!("unexpected response {:?}", res),
+        };
+        assert_matches!(
+            handle.dpki_key_state(&old_key).await?,
+            Some(KeyState::Replaced { new_key: k, .. }) if k == new_key
+        );
+        assert_matches!(
+            handle.dpki_key_state(&new_key).await?,
+            Some(KeyState::Valid { .. })
+        );
+        assert_eq!(latest_agent_pubkey(&handle, &cell_id).await?, new_key);
+
+        // # Revoking marks the key revoked
+        let res = admin_api
+            .handle_admin_request(AdminRequest::RevokeAgentKey {
+                agent_key: new_key.clone(),
+            })
+            .await;
+        assert_matches!(res, AdminResponse::AgentKeyRevoked);
+        assert_matches!(
+            handle.dpki_key_state(&new_key).await?,
+            Some(KeyState::Revoked { .. })
+        );
+
+        // # DPKI refuses to rotate a revoked key, so the chain keeps it
+        let res = admin_api
+            .handle_admin_request(AdminRequest::RotateAgentKey {
+                cell_id: cell_id.clone(),
+            })
+            .await;
+        assert_matches!(res, AdminResponse::Error(_));
+        assert_eq!(latest_agent_pubkey(&handle, &cell_id).await?, new_key);
+
+        handle.shutdown().await;
+        tokio::time::timeout(std::time::Duration::from_secs(1), shutdown)
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// Dump a cell's state once it has authored some ops
+    async fn dump_with_authored_ops(
+        admin_api: &RealAdminInterfaceApi,
+        cell_id: &CellId,
+    ) -> FullStateDump {
+        for _ in 0..50 {
+            let res = admin_api
+                .handle_admin_request(AdminRequest::DumpFullState {
+                    cell_id: Box::new(cell_id.clone()),
+                    filter: None,
+                })
+                .await;
+            match res {
+                AdminResponse::FullStateDumped(dump) if !dump.authored_ops.is_empty() => {
+                    return dump
+                }
+                AdminResponse::FullStateDumped(_) => {
+                    tokio::time::delay_for(std::time::Duration::from_millis(100)).await
+                }
+                res => panic!("Unexpected response: {:?}", res),
+            }
+        }
+        panic!("Timed out waiting for authored ops")
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn export_and_restore_source_chain() -> Result<()> {
+        observability::test_run().ok();
+        let dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        );
+        let (dna_path, _tempdir) = write_fake_dna_file(dna.clone()).await.unwrap();
+        let agent_key = fake_agent_pubkey_1();
+        let cell_id = CellId::new(dna.dna_hash().clone(), agent_key.clone());
+        let install = |source_chain| {
+            let mut dna_payload = InstallAppDnaPayload::path_only(dna_path.clone(), "".to_string());
+            dna_payload.source_chain = source_chain;
+            AdminRequest::InstallApp(Box::new(InstallAppPayload {
+                dnas: vec![dna_payload],
+                installed_app_id: "test".to_string(),
+                agent_key: agent_key.clone(),
+            }))
+        };
+        let activate = || AdminRequest::ActivateApp {
+            installed_app_id: "test".to_string(),
+        };
+        let export_dir = tempdir::TempDir::new("source_chain")?;
+        let path = export_dir.path().join("chain");
+
+        // Run genesis on one conductor and export the chain
+        let envs = test_environments();
+        let handle = Conductor::builder().test(&envs).await?;
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+        let res = admin_api.handle_admin_request(install(None)).await;
+        assert_matches!(res, AdminResponse::AppInstalled(_));
+        let res = admin_api.handle_admin_request(activate()).await;
+        assert_matches!(res, AdminResponse::AppActivated);
+        let res = admin_api
+            .handle_admin_request(AdminRequest::ExportSourceChain {
+                cell_id: Box::new(cell_id.clone()),
+                path: path.clone(),
+                include_private_entries: false,
+            })
+            .await;
+        assert_matches!(res, AdminResponse::SourceChainExported);
+        let original = dump_with_authored_ops(&admin_api, &cell_id).await;
+        handle.shutdown().await;
+
+        // Restore it into another conductor
+        let envs = test_environments();
+        let handle = Conductor::builder().test(&envs).await?;
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+
+        // An install which fails takes the restored chain away with it,
+        // so that it doesn't get in the way of trying again
+        let mut failing = install(Some(path.clone()));
+        if let AdminRequest::InstallApp(payload) = &mut failing {
+            payload.dnas.push(InstallAppDnaPayload::path_only(
+                export_dir.path().join("missing.dna.gz"),
+                "missing".to_string(),
+            ));
+        }
+        let res = admin_api.handle_admin_request(failing).await;
+        assert_matches!(res, AdminResponse::Error(_));
+
+        let res = admin_api.handle_admin_request(install(Some(path))).await;
+        assert_matches!(res, AdminResponse::AppInstalled(_));
+
+        let res = admin_api.handle_admin_request(activate()).await;
+        assert_matches!(res, AdminResponse::AppActivated);
+        let restored = dump_with_authored_ops(&admin_api, &cell_id).await;
+
+        // Genesis was skipped, so the chain is exactly the exported one
+        let headers = |dump: &FullStateDump| {
+            dump.source_chain
+                .iter()
+                .map(|item| item.header_address.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(headers(&restored), headers(&original));
+
+        // and it was published again
+        let op_hashes = |dump: &FullStateDump| {
+            let mut hashes: Vec<_> = dump.authored_ops.iter().map(|(h, _)| h.clone()).collect();
+            hashes.sort();
+            hashes
+        };
+        assert_eq!(op_hashes(&restored), op_hashes(&original));
+
+        handle.shutdown().await;
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn dna_read_parses() -> Result<()> {
         let uuid = Uuid::new_v4();
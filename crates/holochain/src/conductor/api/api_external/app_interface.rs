@@ -9,7 +9,7 @@ use crate::conductor::{
 };
 use crate::core::ribosome::ZomeCallInvocation;
 use holochain_serialized_bytes::prelude::*;
-use holochain_types::app::{InstalledApp, InstalledAppId};
+use holochain_types::app::{AppExterns, InstalledApp, InstalledAppId};
 use holochain_zome_types::ExternOutput;
 use holochain_zome_types::ZomeCallResponse;
 
@@ -66,6 +66,11 @@ impl AppInterfaceApi for RealAppInterfaceApi {
                     .get_app_info(&installed_app_id)
                     .await?,
             )),
+            AppRequest::AppExterns { installed_app_id } => Ok(AppResponse::AppExterns(
+                self.conductor_handle
+                    .get_app_externs(&installed_app_id)
+                    .await?,
+            )),
             AppRequest::ZomeCallInvocation(request) => {
                 let req = request.clone();
                 match self.conductor_handle.call_zome(*request).await? {
@@ -129,6 +134,22 @@ pub enum AppRequest {
         /// The InstalledAppId for which to get information
         installed_app_id: InstalledAppId,
     },
+    /// List the zome functions and callbacks of each Dna of the App
+    /// identified by the given `installed_app_id` argument.
+    /// These are not part of [`AppRequest::AppInfo`]'s response so that
+    /// its [`InstalledApp`] keeps the shape existing clients decode.
+    ///
+    /// Will be responded to with an [`AppResponse::AppExterns`]
+    /// or an [`AppResponse::Error`]
+    ///
+    /// [`AppRequest::AppInfo`]: enum.AppRequest.html#variant.AppInfo
+    /// [`InstalledApp`]: ../../../holochain_types/app/struct.InstalledApp.html
+    /// [`AppResponse::AppExterns`]: enum.AppResponse.html#variant.AppExterns
+    /// [`AppResponse::Error`]: enum.AppResponse.html#variant.Error
+    AppExterns {
+        /// The InstalledAppId for which to list the externs
+        installed_app_id: InstalledAppId,
+    },
     /// Asks the conductor to do some crypto.
     ///
     /// Is currently unimplemented and will return
@@ -177,6 +198,13 @@ pub enum AppResponse {
     /// [`AppRequest::AppInfo`]: enum.AppRequest.html#variant.AppInfo
     AppInfo(Option<InstalledApp>),
 
+    /// The succesful response to an [`AppRequest::AppExterns`].
+    ///
+    /// Option will be `None` if there is no installed app with the given `installed_app_id` value from the request.
+    ///
+    /// [`AppRequest::AppExterns`]: enum.AppRequest.html#variant.AppExterns
+    AppExterns(Option<AppExterns>),
+
     /// The succesful response to an [`AppRequest::ZomeCallInvocation`].
     ///
     /// Note that [`ExternOutput`] is simply a structure of [`SerializedBytes`] so the client will have
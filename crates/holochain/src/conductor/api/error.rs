@@ -14,7 +14,11 @@ use crate::{
 };
 use holochain_serialized_bytes::prelude::*;
 use holochain_state::error::DatabaseError;
-use holochain_types::cell::CellId;
+use holochain_types::{
+    app::{CellNick, InstalledAppId},
+    cell::CellId,
+    dna::DnaHash,
+};
 use thiserror::Error;
 
 /// Errors occurring during a [CellConductorApi] or [InterfaceApi] call
@@ -24,6 +28,14 @@ pub enum ConductorApiError {
     #[error("Cell was referenced, but is missing from the conductor. CellId: {0:?}")]
     CellMissing(CellId),
 
+    /// A Cell nick was referenced, but no Cell in the app has that nick.
+    #[error("No Cell in the app has the nick: {0}")]
+    CellNickMissing(CellNick),
+
+    /// The cells of a Cell's app were needed, but the Cell is in more than one active app.
+    #[error("Cell {0:?} is in more than one active app: {1:?}")]
+    CellInSeveralApps(CellId, Vec<InstalledAppId>),
+
     /// Cell was referenced, but is missing from the conductor.
     #[error("A Cell attempted to use an CellConductorApi it was not given.\nAPI CellId: {api_cell_id:?}\nInvocation CellId: {invocation_cell_id:?}")]
     ZomeCallInvocationCellMismatch {
@@ -63,10 +75,26 @@ pub enum ConductorApiError {
     #[error("DnaError: {0}")]
     DnaError(#[from] holochain_types::dna::DnaError),
 
+    /// The app bundle was invalid, or couldn't be installed as requested
+    #[error(transparent)]
+    AppBundleError(#[from] holochain_types::app::error::AppBundleError),
+
+    /// Dna was referenced, but is missing from the conductor.
+    #[error("Dna was referenced, but is missing from the conductor. DnaHash: {0}")]
+    DnaMissing(DnaHash),
+
+    /// Error in the ribosome
+    #[error(transparent)]
+    RibosomeError(#[from] RibosomeError),
+
     /// The Dna file path provided was invalid
     #[error("The Dna file path provided was invalid")]
     DnaReadError(String),
 
+    /// The app bundle file path provided was invalid
+    #[error("The app bundle file path provided was invalid")]
+    AppBundleReadError(String),
+
     /// KeystoreError
     #[error("KeystoreError: {0}")]
     KeystoreError(#[from] holochain_keystore::KeystoreError),
@@ -75,6 +103,10 @@ pub enum ConductorApiError {
     #[error(transparent)]
     CellError(#[from] CellError),
 
+    /// The DPKI app could not be used or returned an unexpected response
+    #[error("DPKI error: {0}")]
+    DpkiError(String),
+
     /// Error in the Interface
     #[error("An error occurred in the interface: {0:?}")]
     InterfaceError(#[from] InterfaceError),
@@ -113,6 +145,8 @@ pub enum ExternalApiWireError {
     Deserialization(String),
     /// The dna path provided was invalid
     DnaReadError(String),
+    /// The app bundle path provided was invalid
+    AppBundleReadError(String),
     /// There was an error in the ribosome
     RibosomeError(String),
     /// Error activating app
@@ -134,6 +168,8 @@ impl From<ConductorApiError> for ExternalApiWireError {
     fn from(err: ConductorApiError) -> Self {
         match err {
             ConductorApiError::DnaReadError(e) => ExternalApiWireError::DnaReadError(e),
+            ConductorApiError::AppBundleReadError(e) => ExternalApiWireError::AppBundleReadError(e),
+            ConductorApiError::RibosomeError(e) => e.into(),
             e => ExternalApiWireError::internal(e),
         }
     }
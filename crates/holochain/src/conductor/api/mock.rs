@@ -3,17 +3,17 @@
 
 use super::CellConductorApiT;
 use crate::conductor::{
-    api::error::ConductorApiResult, entry_def_store::EntryDefBufferKey,
+    api::error::ConductorApiResult, config::WasmLimits, entry_def_store::EntryDefBufferKey,
     interface::SignalBroadcaster,
 };
 use crate::core::ribosome::ZomeCallInvocation;
 use crate::core::workflow::ZomeCallInvocationResult;
 use async_trait::async_trait;
-use holo_hash::DnaHash;
+use holo_hash::{AgentPubKey, DnaHash};
 use holochain_keystore::KeystoreSender;
 use holochain_types::dna::DnaFile;
 use holochain_types::{autonomic::AutonomicCue, cell::CellId};
-use holochain_zome_types::entry_def::EntryDef;
+use holochain_zome_types::{dpki::KeyState, entry_def::EntryDef};
 use mockall::mock;
 
 // Unfortunate workaround to get mockall to work with async_trait, due to the complexity of each.
@@ -33,10 +33,15 @@ mock! {
 
         fn sync_autonomic_cue(&self, cue: AutonomicCue) -> ConductorApiResult<()>;
 
-        fn sync_dpki_request(&self, method: String, args: String) -> ConductorApiResult<String>;
+        fn sync_dpki_key_state(&self, agent_key: &AgentPubKey) -> ConductorApiResult<Option<KeyState>>;
+
+        fn sync_dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()>;
 
         fn mock_keystore(&self) -> &KeystoreSender;
+        fn mock_wasm_limits(&self) -> &WasmLimits;
+        fn mock_wasm_instance_pool_size(&self) -> usize;
         fn mock_signal_broadcaster(&self) -> SignalBroadcaster;
+        fn mock_admin_signal_broadcaster(&self) -> SignalBroadcaster;
         fn sync_get_dna(&self, dna_hash: &DnaHash) -> Option<DnaFile>;
         fn sync_get_this_dna(&self) -> Option<DnaFile>;
         fn sync_get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef>;
@@ -62,8 +67,15 @@ impl CellConductorApiT for MockCellConductorApi {
         self.sync_call_zome(cell_id, invocation)
     }
 
-    async fn dpki_request(&self, method: String, args: String) -> ConductorApiResult<String> {
-        self.sync_dpki_request(method, args)
+    async fn dpki_key_state(
+        &self,
+        agent_key: &AgentPubKey,
+    ) -> ConductorApiResult<Option<KeyState>> {
+        self.sync_dpki_key_state(agent_key)
+    }
+
+    async fn dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()> {
+        self.sync_dpki_register_key(agent_key)
     }
 
     async fn autonomic_cue(&self, cue: AutonomicCue) -> ConductorApiResult<()> {
@@ -74,10 +86,22 @@ impl CellConductorApiT for MockCellConductorApi {
         self.mock_keystore()
     }
 
+    fn wasm_limits(&self) -> &WasmLimits {
+        self.mock_wasm_limits()
+    }
+
+    fn wasm_instance_pool_size(&self) -> usize {
+        self.mock_wasm_instance_pool_size()
+    }
+
     async fn signal_broadcaster(&self) -> SignalBroadcaster {
         self.mock_signal_broadcaster()
     }
 
+    async fn admin_signal_broadcaster(&self) -> SignalBroadcaster {
+        self.mock_admin_signal_broadcaster()
+    }
+
     async fn get_dna(&self, dna_hash: &DnaHash) -> Option<DnaFile> {
         self.sync_get_dna(dna_hash)
     }
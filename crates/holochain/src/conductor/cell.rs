@@ -4,7 +4,7 @@
 //! Elements can be added. A constructed Cell is guaranteed to have a valid
 //! SourceChain which has already undergone Genesis.
 
-use super::{interface::SignalBroadcaster, manager::ManagedTaskAdd};
+use super::{interface::SignalBroadcaster, manager::ManagedTaskAdd, metrics};
 use crate::conductor::handle::ConductorHandle;
 use crate::conductor::{api::error::ConductorApiError, entry_def_store::get_entry_def_from_ids};
 use crate::core::queue_consumer::{spawn_queue_consumer_tasks, InitialQueueTriggers};
@@ -30,9 +30,12 @@ use crate::{
             element_buf::ElementBuf,
             metadata::{LinkMetaKey, MetadataBuf, MetadataBufT},
             source_chain::{SourceChain, SourceChainBuf},
+            warrants_db::{verify_warrant, WarrantsBuf},
         },
+        verify_header_signature,
         workflow::{
-            call_zome_workflow, error::WorkflowError, genesis_workflow::genesis_workflow,
+            app_validation_workflow::confirm_warranted_element, call_zome_workflow,
+            error::WorkflowError, genesis_workflow::genesis_workflow,
             incoming_dht_ops_workflow::incoming_dht_ops_workflow, initialize_zomes_workflow,
             CallZomeWorkflowArgs, CallZomeWorkspace, GenesisWorkflowArgs, GenesisWorkspace,
             InitializeZomesWorkflowArgs, ZomeCallInvocationResult,
@@ -47,8 +50,9 @@ use holo_hash::*;
 use holochain_p2p::HolochainP2pCellT;
 use holochain_serialized_bytes::SerializedBytes;
 use holochain_state::{
+    buffer::BufferedStore,
     db::GetDb,
-    env::{EnvironmentRead, EnvironmentWrite, ReadManager},
+    env::{EnvironmentRead, EnvironmentWrite, ReadManager, WriteManager},
 };
 use holochain_types::{
     autonomic::AutonomicProcess,
@@ -63,9 +67,11 @@ use holochain_zome_types::capability::CapSecret;
 use holochain_zome_types::header::{CreateLink, DeleteLink};
 use holochain_zome_types::signature::Signature;
 use holochain_zome_types::validate::RequiredValidationType;
+use holochain_zome_types::warrant::{SignedWarrant, Warrant};
 use holochain_zome_types::zome::ZomeName;
 use holochain_zome_types::ExternInput;
 use observability::OpenSpanExt;
+use publish_throttle::PublishThrottle;
 use std::{
     collections::{BTreeMap, BTreeSet},
     convert::TryInto,
@@ -76,6 +82,7 @@ use tracing::*;
 use tracing_futures::Instrument;
 
 mod authority;
+mod publish_throttle;
 mod validation_package;
 
 #[allow(missing_docs)]
@@ -124,6 +131,7 @@ where
     env: EnvironmentWrite,
     holochain_p2p_cell: P2pCell,
     queue_triggers: InitialQueueTriggers,
+    publish_throttle: PublishThrottle,
 }
 
 impl Cell {
@@ -139,6 +147,7 @@ impl Cell {
         managed_task_stop_broadcaster: sync::broadcast::Sender<()>,
     ) -> CellResult<Self> {
         let conductor_api = CellConductorApi::new(conductor_handle.clone(), id.clone());
+        let publish_throttle = PublishThrottle::new(conductor_handle.spam_protection());
 
         // check if genesis has been run
         let has_genesis = {
@@ -163,6 +172,7 @@ impl Cell {
                 env,
                 holochain_p2p_cell,
                 queue_triggers,
+                publish_throttle,
             })
         } else {
             Err(CellError::CellWithoutGenesis(id))
@@ -185,6 +195,11 @@ impl Cell {
         cell_env: EnvironmentWrite,
         membrane_proof: Option<SerializedBytes>,
     ) -> CellResult<()> {
+        // a source chain restored from an export already has its genesis elements
+        if SourceChainBuf::new(cell_env.clone().into())?.has_genesis() {
+            return Ok(());
+        }
+
         // get the dna
         let dna_file = conductor_handle
             .get_dna(id.dna_hash())
@@ -284,6 +299,23 @@ impl Cell {
                 .instrument(debug_span!("cell_handle_publish"))
                 .await;
             }
+            PutWarrant {
+                span_context: _,
+                respond,
+                from_agent,
+                warrant,
+                ..
+            } => {
+                async {
+                    let res = self
+                        .handle_put_warrant(from_agent, warrant)
+                        .await
+                        .map_err(holochain_p2p::HolochainP2pError::other);
+                    respond.respond(Ok(async move { res }.boxed().into()));
+                }
+                .instrument(debug_span!("cell_handle_put_warrant"))
+                .await;
+            }
             GetValidationPackage {
                 span_context: _,
                 respond,
@@ -436,7 +468,8 @@ impl Cell {
     }
 
     #[instrument(skip(self, _request_validation_receipt, _dht_hash, ops))]
-    /// we are receiving a "publish" event from the network
+    /// we are receiving a "publish" event from the network.
+    /// Gossiped ops arrive here too, so both are throttled.
     async fn handle_publish(
         &self,
         from_agent: AgentPubKey,
@@ -444,6 +477,31 @@ impl Cell {
         _dht_hash: holo_hash::AnyDhtHash,
         ops: Vec<(holo_hash::DhtOpHash, holochain_types::dht_op::DhtOp)>,
     ) -> CellResult<()> {
+        // Only ops signed by their author count against the author
+        let mut verified = Vec::new();
+        if self.publish_throttle.limits_authors() {
+            for (_, op) in &ops {
+                let header = op.header();
+                if verify_header_signature(op.signature(), &header)
+                    .await
+                    .unwrap_or(false)
+                {
+                    verified.push(header);
+                }
+            }
+        }
+        // Dropped ops will be gossiped to us again later
+        if !self
+            .publish_throttle
+            .allow(&from_agent, ops.len(), &verified)
+        {
+            warn!(
+                ?from_agent,
+                num_ops = ops.len(),
+                "Dropping publish over the rate limit"
+            );
+            return Ok(());
+        }
         incoming_dht_ops_workflow(
             &self.env,
             self.queue_triggers.sys_validation.clone(),
@@ -457,6 +515,112 @@ impl Cell {
         Ok(())
     }
 
+    #[instrument(skip(self, warrant))]
+    /// a remote node is publishing a warrant against an agent whose activity we hold
+    async fn handle_put_warrant(
+        &self,
+        from_agent: AgentPubKey,
+        warrant: SignedWarrant,
+    ) -> CellResult<()> {
+        // Checking a warrant means validating a header,
+        // so warrants count against the sender's publish limit
+        if !self.publish_throttle.allow(&from_agent, 1, &[]) {
+            warn!(?from_agent, "Dropping warrant over the rate limit");
+            return Ok(());
+        }
+        // The signature only proves who issued the warrant,
+        // so re-validate the offending header to check the claim
+        if !verify_warrant(&warrant).await? {
+            warn!(?from_agent, "Dropping warrant with an invalid signature");
+            return Ok(());
+        }
+        let mut warrants = WarrantsBuf::new(&self.env)?;
+        // One confirmed warrant per header is enough
+        let held = {
+            let env_ref = self.env.guard();
+            let reader = env_ref.reader()?;
+            let w = warrant.warrant();
+            warrants.has_warrant_for(&reader, &w.offender, &w.header)?
+        };
+        if held {
+            return Ok(());
+        }
+        if !self.confirm_warrant(warrant.warrant()).await? {
+            warn!(
+                ?from_agent,
+                warrant = ?warrant.warrant(),
+                "Dropping warrant for a header we could not find invalid"
+            );
+            return Ok(());
+        }
+        warrants.add_if_unique(warrant)?;
+        self.env
+            .with_commit(|writer| warrants.flush_to_txn(writer))?;
+        Ok(())
+    }
+
+    /// Check for ourselves that the header a warrant accuses was authored
+    /// by the offender and is invalid
+    async fn confirm_warrant(&self, warrant: &Warrant) -> CellResult<bool> {
+        // We may have already rejected it ourselves
+        let rejected_here = {
+            let env_ref = self.env.guard();
+            let reader = env_ref.reader()?;
+            let meta_vault = MetadataBuf::vault(self.env.clone().into())?;
+            meta_vault
+                .get_validation_status(&reader, &warrant.header)?
+                .resolve()
+                == Some(ValidationStatus::Rejected)
+        };
+        if rejected_here {
+            return Ok(true);
+        }
+
+        let element = {
+            let mut workspace = CallZomeWorkspace::new(self.env.clone().into())?;
+            workspace
+                .cascade(self.holochain_p2p_cell.clone())
+                .retrieve(warrant.header.clone().into(), Default::default())
+                .await?
+        };
+        let element = match element {
+            Some(element) if element.header().author() == &warrant.offender => element,
+            _ => return Ok(false),
+        };
+        Ok(confirm_warranted_element(
+            element,
+            &self.conductor_api,
+            self.env.clone().into(),
+            self.holochain_p2p_cell.clone(),
+        )
+        .await
+        .map_err(Box::new)?)
+    }
+
+    /// Write the update to the agent entry which replaces the key this
+    /// cell's chain is authored with, without committing it yet.
+    /// Returns the key that is replaced and the chain to commit with
+    /// [Cell::commit_agent_key_update].
+    pub(super) async fn prepare_agent_key_update(
+        &self,
+        new_key: AgentPubKey,
+    ) -> CellResult<(AgentPubKey, SourceChain)> {
+        let mut source_chain = SourceChain::new(self.env.clone().into())?;
+        let old_key = source_chain.latest_agent_pubkey()?;
+        source_chain.put_agent_key_update(new_key).await?;
+        Ok((old_key, source_chain))
+    }
+
+    /// Commit an agent key update from [Cell::prepare_agent_key_update]
+    /// and publish it.
+    /// Fails if the chain moved since the update was prepared.
+    pub(super) fn commit_agent_key_update(&self, mut source_chain: SourceChain) -> CellResult<()> {
+        self.env
+            .with_commit(|writer| source_chain.flush_to_txn_ref(writer))?;
+        self.queue_triggers.produce_dht_ops.clone().trigger();
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     /// a remote node is attempting to retrieve a validation package
     #[tracing::instrument(skip(self), level = "trace")]
@@ -490,13 +654,7 @@ impl Cell {
             )
             .await
         } else {
-            validation_package::get_as_authority(
-                header,
-                env,
-                &ribosome.dna_file,
-                &self.conductor_api,
-            )
-            .await
+            validation_package::get_as_authority(header, env, &ribosome, &self.conductor_api).await
         }
     }
 
@@ -642,6 +800,8 @@ impl Cell {
         let integrated_dht_ops = IntegratedDhtOpsBuf::new(self.env().clone().into())?;
         let result: Vec<DhtOpHash> = integrated_dht_ops
             .query(&reader, Some(since), Some(until), Some(dht_arc))?
+            // We don't hold the data of abandoned ops
+            .filter(|(_, v)| Ok(v.validation_status != ValidationStatus::Abandoned))
             .map(|(k, _)| Ok(k))
             .collect()?;
         Ok(result)
@@ -673,7 +833,9 @@ impl Cell {
                         let cas = ElementBuf::rejected(self.env.clone().into())?;
                         light_to_op(val.op, &cas)?
                     }
-                    ValidationStatus::Abandoned => todo!("Add when abandoned store is added"),
+                    // Abandoned ops are thrown away on integration,
+                    // there is nothing to send
+                    ValidationStatus::Abandoned => continue,
                 };
                 let basis = full_op.dht_basis();
                 out.push((basis, op_hash, full_op));
@@ -746,6 +908,8 @@ impl Cell {
         let signal_tx = self.signal_broadcaster().await;
         let ribosome = self.get_ribosome().await?;
 
+        let zome_name = invocation.zome_name.clone();
+        let fn_name = invocation.fn_name.clone();
         let args = CallZomeWorkflowArgs {
             ribosome,
             invocation,
@@ -753,7 +917,8 @@ impl Cell {
             signal_tx,
             is_root_zome_call,
         };
-        Ok(call_zome_workflow(
+        let start = std::time::Instant::now();
+        let result = call_zome_workflow(
             workspace_lock,
             self.holochain_p2p_cell.clone(),
             keystore,
@@ -761,8 +926,15 @@ impl Cell {
             args,
             self.queue_triggers.produce_dht_ops.clone(),
         )
-        .await
-        .map_err(Box::new)?)
+        .await;
+        metrics::record_zome_call(
+            &self.id,
+            &zome_name,
+            &fn_name,
+            start.elapsed(),
+            !matches!(result, Ok(Ok(_))),
+        );
+        Ok(result.map_err(Box::new)?)
     }
 
     /// Check if each Zome's init callback has been run, and if not, run it.
@@ -792,7 +964,7 @@ impl Cell {
         let dna_def = dna_file.dna().clone();
 
         // Get the ribosome
-        let ribosome = WasmRibosome::new(dna_file);
+        let ribosome = WasmRibosome::new(dna_file).with_limits(conductor_api.wasm_limits().clone());
 
         // Run the workflow
         let args = InitializeZomesWorkflowArgs { dna_def, ribosome };
@@ -831,7 +1003,9 @@ impl Cell {
     // TODO: reevaluate once Workflows are fully implemented (after B-01567)
     pub(crate) async fn get_ribosome(&self) -> CellResult<WasmRibosome> {
         match self.conductor_api.get_dna(self.dna_hash()).await {
-            Some(dna) => Ok(WasmRibosome::new(dna)),
+            Some(dna) => Ok(WasmRibosome::new(dna)
+                .with_limits(self.conductor_api.wasm_limits().clone())
+                .with_instance_pool(self.conductor_api.wasm_instance_pool_size())),
             None => Err(CellError::DnaMissing),
         }
     }
@@ -4,6 +4,7 @@ use crate::{
     core::state::{
         element_buf::ElementBuf,
         metadata::{ChainItemKey, MetadataBuf, MetadataBufT},
+        warrants_db::WarrantsBuf,
     },
 };
 use fallible_iterator::FallibleIterator;
@@ -265,6 +266,7 @@ pub fn handle_get_agent_activity(
     let element_integrated = ElementBuf::vault(env.clone(), false)?;
     let meta_integrated = MetadataBuf::vault(env.clone())?;
     let element_rejected = ElementBuf::rejected(env.clone())?;
+    let warrants_buf = WarrantsBuf::new(&env)?;
 
     // Status
     let status = meta_integrated
@@ -272,6 +274,12 @@ pub fn handle_get_agent_activity(
         .unwrap_or(ChainStatus::Empty);
     let highest_observed = meta_integrated.get_activity_observed(&agent)?;
 
+    // Warrants are always returned because they are evidence
+    // about the chain regardless of which activity was requested
+    let warrants = fresh_reader!(env, |r| warrants_buf
+        .list_warrants(&r, &agent)?
+        .collect::<Vec<_>>())?;
+
     // Valid headers
     let valid_activity = if options.include_valid_activity {
         fresh_reader!(env, |r| {
@@ -310,6 +318,7 @@ pub fn handle_get_agent_activity(
         agent,
         status,
         highest_observed,
+        warrants,
     })
 }
 
@@ -356,24 +365,26 @@ fn check_headers<P: PrefixType, R: Readable>(
 fn _show_agent_activity_read_times(env: EnvironmentRead, agent: AgentPubKey) {
     {
         let g = env.guard();
-        let rkv = g.rkv();
-        let stat = rkv.stat().unwrap();
-        let info = rkv.info().unwrap();
-        debug!(
-            map_size = info.map_size(),
-            last_pgno = info.last_pgno(),
-            last_txnid = info.last_txnid(),
-            max_readers = info.max_readers(),
-            num_readers = info.num_readers()
-        );
-        debug!(
-            page_size = stat.page_size(),
-            depth = stat.depth(),
-            branch_pages = stat.branch_pages(),
-            leaf_pages = stat.leaf_pages(),
-            overflow_pages = stat.overflow_pages(),
-            entries = stat.entries(),
-        );
+        // only LMDB environments have stats to show
+        if let Some(rkv) = g.rkv() {
+            let stat = rkv.stat().unwrap();
+            let info = rkv.info().unwrap();
+            debug!(
+                map_size = info.map_size(),
+                last_pgno = info.last_pgno(),
+                last_txnid = info.last_txnid(),
+                max_readers = info.max_readers(),
+                num_readers = info.num_readers()
+            );
+            debug!(
+                page_size = stat.page_size(),
+                depth = stat.depth(),
+                branch_pages = stat.branch_pages(),
+                leaf_pages = stat.leaf_pages(),
+                overflow_pages = stat.overflow_pages(),
+                entries = stat.entries(),
+            );
+        }
     }
     let element_integrated = ElementBuf::vault(env.clone(), false).unwrap();
     let meta_integrated = MetadataBuf::vault(env.clone()).unwrap();
@@ -1,13 +1,22 @@
 use crate::{
     conductor::p2p_store::{AgentKv, AgentKvKey},
-    test_utils::{conductor_setup::ConductorTestData, new_invocation},
+    test_utils::{
+        conductor_network::TestConductorNetwork, conductor_setup::ConductorTestData,
+        new_invocation, test_network_mem,
+    },
 };
 use fallible_iterator::FallibleIterator;
 use hdk3::prelude::*;
 use holochain_state::buffer::KvStoreT;
 use holochain_state::fresh_reader_test;
 use holochain_wasm_test_utils::TestWasm;
-use kitsune_p2p::{KitsuneBinType, KitsuneP2pConfig};
+use kitsune_p2p::{
+    dependencies::kitsune_p2p_types::{
+        transport::TransportListenerSender,
+        transport_mem::{heal_mem_partition, set_mem_partition, spawn_bind_transport_mem},
+    },
+    KitsuneBinType, KitsuneP2pConfig,
+};
 use matches::assert_matches;
 use test_wasm_common::{AnchorInput, TestString};
 
@@ -66,6 +75,84 @@ async fn gossip_test() {
     conductor_test.shutdown_conductor().await;
 }
 
+/// How many times to poll a conductor for anchors before giving up
+const ANCHOR_ATTEMPTS: usize = 100;
+/// How long to wait between polls for anchors
+const ANCHOR_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The number of "alice" anchors the conductor at index `i` can see
+async fn count_anchors(network: &TestConductorNetwork, i: usize) -> usize {
+    let invocation = new_invocation(
+        network[i].cell_id(),
+        "list_anchor_addresses",
+        TestString("alice".into()),
+        TestWasm::Anchor,
+    )
+    .unwrap();
+    let response = network[i]
+        .handle()
+        .call_zome(invocation)
+        .await
+        .unwrap()
+        .unwrap();
+    match response {
+        ZomeCallResponse::Ok(r) => {
+            let hashes: EntryHashes = r.into_inner().try_into().unwrap();
+            hashes.0.len()
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Poll the conductor at index `i` until it can see `expected` anchors
+async fn wait_for_anchors(network: &TestConductorNetwork, i: usize, expected: usize) {
+    for _ in 0..ANCHOR_ATTEMPTS {
+        if count_anchors(network, i).await == expected {
+            return;
+        }
+        tokio::time::delay_for(ANCHOR_DELAY).await;
+    }
+    panic!("Conductor {} never saw {} anchors", i, expected);
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn conductor_network_partition_test() {
+    observability::test_run().ok();
+    let mut network = TestConductorNetwork::new(3, vec![TestWasm::Anchor]).await;
+
+    // Cut the last conductor off from the others
+    network.partition(vec![vec![0, 1], vec![2]]).await;
+
+    let invocation = new_invocation(
+        network[0].cell_id(),
+        "anchor",
+        AnchorInput("alice".into(), "0".into()),
+        TestWasm::Anchor,
+    )
+    .unwrap();
+    let response = network[0]
+        .handle()
+        .call_zome(invocation)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_matches!(response, ZomeCallResponse::Ok(_));
+
+    // Once the anchor has reached the other side of the partition
+    // it would have reached the last conductor too if it could
+    wait_for_anchors(&network, 1, 1).await;
+    assert_eq!(count_anchors(&network, 2).await, 0);
+
+    // After healing, gossip brings the last conductor up to date
+    network.heal().await;
+    wait_for_anchors(&network, 2, 1).await;
+    network
+        .wait_for_consistency(ANCHOR_ATTEMPTS, ANCHOR_DELAY)
+        .await;
+
+    network.shutdown().await;
+}
+
 #[tokio::test(threaded_scheduler)]
 async fn signature_smoke_test() {
     observability::test_run().ok();
@@ -95,14 +182,8 @@ async fn agent_info_test() {
     let alice_agent_id = alice_cell_id.agent_pubkey();
 
     // Kitsune types
-    let dna_kit = kitsune_p2p::KitsuneSpace::new(
-        alice_call_data
-            .ribosome
-            .dna_file
-            .dna_hash()
-            .get_raw_36()
-            .to_vec(),
-    );
+    let dna_kit =
+        kitsune_p2p::KitsuneSpace::new(alice_call_data.ribosome.dna_hash.get_raw_36().to_vec());
 
     let alice_kit = kitsune_p2p::KitsuneAgent::new(alice_agent_id.get_raw_36().to_vec());
 
@@ -152,3 +233,26 @@ async fn agent_info_test() {
     conductor_test.shutdown_conductor().await;
     bob_conductor.shutdown_conductor().await;
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn test_network_mem_partition_test() {
+    observability::test_run().ok();
+    let network = test_network_mem(None, None).await;
+    let urls = network.transport_urls().await;
+    assert!(!urls.is_empty());
+
+    let (probe, _probe_events) = spawn_bind_transport_mem().await.unwrap();
+    let probe_url = probe.bound_url().await.unwrap();
+
+    // A node on the other side of a partition can't reach the network
+    set_mem_partition("test-network-mem-partition/network", urls.clone()).await;
+    set_mem_partition("test-network-mem-partition/probe", vec![probe_url.clone()]).await;
+    for url in urls.iter() {
+        assert!(probe.create_channel(url.clone()).await.is_err());
+    }
+
+    heal_mem_partition(urls.iter().cloned().chain(Some(probe_url))).await;
+    for url in urls {
+        probe.create_channel(url).await.unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+//! Limits how many ops each remote peer may publish to a cell
+
+use crate::conductor::config::SpamProtectionConfig;
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{header::EntryType, Header};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Counts the ops each peer has published, and the ops each author
+/// has had published, in the current window.
+///
+/// This only decides what this cell takes in through publish and gossip.
+/// Dropped ops are gossiped again in later rounds and are never
+/// treated as invalid, so every validator still reaches the same outcome.
+pub(super) struct PublishThrottle {
+    peers: Windows,
+    authors: Windows,
+}
+
+/// The number of ops counted against each key in its current window
+struct Windows {
+    max_ops: Option<usize>,
+    window: Duration,
+    counts: Mutex<HashMap<AgentPubKey, Window>>,
+}
+
+struct Window {
+    start: Instant,
+    ops: usize,
+}
+
+impl PublishThrottle {
+    /// Create a throttle from the conductor's spam protection policy
+    pub(super) fn new(policy: &SpamProtectionConfig) -> Self {
+        Self {
+            peers: Windows::new(policy.max_published_ops_per_window, policy.window()),
+            authors: Windows::new(policy.max_authored_ops_per_window, policy.window()),
+        }
+    }
+
+    /// Whether ops count against their authors, so the caller
+    /// knows if it needs to verify their signatures
+    pub(super) fn limits_authors(&self) -> bool {
+        self.authors.max_ops.is_some()
+    }
+
+    /// Count `num_ops` ops against the peer that sent them and the
+    /// headers among them whose signature was verified against their
+    /// authors, so a peer can't use up someone else's limit with forged ops.
+    /// Returns false, without counting them, if they would take
+    /// the peer or any author over the limit for this window.
+    pub(super) fn allow(&self, peer: &AgentPubKey, num_ops: usize, verified: &[Header]) -> bool {
+        let mut authored: HashMap<AgentPubKey, usize> = HashMap::new();
+        if self.limits_authors() {
+            for header in verified {
+                *authored.entry(header.author().clone()).or_insert(0) += 1;
+            }
+        }
+        let now = Instant::now();
+        let mut peers = self.peers.counts.lock();
+        let mut authors = self.authors.counts.lock();
+        if !self.peers.fits(&mut peers, now, peer, num_ops)
+            || authored
+                .iter()
+                .any(|(author, n)| !self.authors.fits(&mut authors, now, author, *n))
+        {
+            return false;
+        }
+        self.peers.count(&mut peers, peer, num_ops);
+        for (author, n) in authored {
+            self.authors.count(&mut authors, &author, n);
+        }
+        if self.limits_authors() {
+            for header in verified {
+                if let Header::Update(update) = header {
+                    if update.entry_type == EntryType::AgentPubKey {
+                        let new_key = update.entry_hash.clone().into();
+                        self.authors
+                            .carry_over(&mut authors, &update.author, new_key);
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Windows {
+    fn new(max_ops: Option<u32>, window: Duration) -> Self {
+        Self {
+            max_ops: max_ops.map(|m| m as usize),
+            window,
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether this many more ops fit in the key's window.
+    /// Starts a new window for the key if its last one has ended.
+    fn fits(
+        &self,
+        counts: &mut HashMap<AgentPubKey, Window>,
+        now: Instant,
+        key: &AgentPubKey,
+        num_ops: usize,
+    ) -> bool {
+        let max_ops = match self.max_ops {
+            Some(max_ops) => max_ops,
+            None => return true,
+        };
+        // Forget keys whose window has ended so this doesn't grow forever
+        let window = self.window;
+        counts.retain(|_, w| now.duration_since(w.start) < window);
+        let w = counts
+            .entry(key.clone())
+            .or_insert(Window { start: now, ops: 0 });
+        w.ops + num_ops <= max_ops
+    }
+
+    fn count(&self, counts: &mut HashMap<AgentPubKey, Window>, key: &AgentPubKey, num_ops: usize) {
+        if let Some(w) = counts.get_mut(key) {
+            w.ops += num_ops;
+        }
+    }
+
+    /// An author rotating their key keeps counting against the
+    /// same window, so rotating can't be used to reset the limit.
+    /// Only a key without a window of its own takes over the old one,
+    /// so naming someone else's key can't add to their count.
+    fn carry_over(
+        &self,
+        counts: &mut HashMap<AgentPubKey, Window>,
+        old_key: &AgentPubKey,
+        new_key: AgentPubKey,
+    ) {
+        if counts.contains_key(&new_key) {
+            return;
+        }
+        if let Some(w) = counts.get(old_key) {
+            let w = Window {
+                start: w.start,
+                ops: w.ops,
+            };
+            counts.insert(new_key, w);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::fixt::prelude::*;
+    use holo_hash::fixt::AgentPubKeyFixturator;
+    use holochain_types::test_utils::{fake_agent_pubkey_1, fake_agent_pubkey_2};
+    use holochain_zome_types::fixt::{CreateFixturator, UpdateFixturator};
+
+    /// Make some verified headers authored by this agent
+    fn ops_by(author: &AgentPubKey, num_ops: usize) -> Vec<Header> {
+        (0..num_ops)
+            .map(|_| {
+                let mut header = fixt!(Create);
+                header.author = author.clone();
+                header.into()
+            })
+            .collect()
+    }
+
+    /// Publish verified ops
+    fn publish(throttle: &PublishThrottle, peer: &AgentPubKey, ops: &[Header]) -> bool {
+        throttle.allow(peer, ops.len(), ops)
+    }
+
+    #[test]
+    fn limits_ops_per_peer() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig {
+            window_ms: 60_000,
+            max_published_ops_per_window: Some(3),
+            max_authored_ops_per_window: None,
+        });
+        let alice = fake_agent_pubkey_1();
+        let bob = fake_agent_pubkey_2();
+        let carol = fixt!(AgentPubKey);
+
+        assert!(publish(&throttle, &alice, &ops_by(&carol, 2)));
+        assert!(!publish(&throttle, &alice, &ops_by(&carol, 2)));
+        // Rejected ops aren't counted
+        assert!(publish(&throttle, &alice, &ops_by(&carol, 1)));
+        assert!(!publish(&throttle, &alice, &ops_by(&carol, 1)));
+        // Other peers have their own limit
+        assert!(publish(&throttle, &bob, &ops_by(&carol, 3)));
+    }
+
+    #[test]
+    fn limits_ops_per_author() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig {
+            window_ms: 60_000,
+            max_published_ops_per_window: None,
+            max_authored_ops_per_window: Some(3),
+        });
+        let alice = fake_agent_pubkey_1();
+        let bob = fake_agent_pubkey_2();
+        let carol = fixt!(AgentPubKey);
+
+        assert!(publish(&throttle, &alice, &ops_by(&carol, 2)));
+        // Carol's ops are counted whoever publishes them
+        assert!(!publish(&throttle, &bob, &ops_by(&carol, 2)));
+        assert!(publish(&throttle, &bob, &ops_by(&carol, 1)));
+        // A publish is dropped if any of its authors is over the limit
+        let mut ops = ops_by(&alice, 1);
+        ops.extend(ops_by(&carol, 1));
+        assert!(!publish(&throttle, &bob, &ops));
+        // Other authors have their own limit
+        assert!(publish(&throttle, &bob, &ops_by(&alice, 3)));
+    }
+
+    #[test]
+    fn unverified_ops_only_count_against_the_peer() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig {
+            window_ms: 60_000,
+            max_published_ops_per_window: Some(4),
+            max_authored_ops_per_window: Some(3),
+        });
+        let alice = fake_agent_pubkey_1();
+        let bob = fake_agent_pubkey_2();
+        let carol = fixt!(AgentPubKey);
+
+        // Alice claims four ops by Carol, none of them signed by her
+        assert!(throttle.allow(&alice, 4, &[]));
+        assert!(!throttle.allow(&alice, 1, &[]));
+        // Carol's limit is untouched
+        assert!(publish(&throttle, &bob, &ops_by(&carol, 3)));
+    }
+
+    #[test]
+    fn rotated_key_keeps_the_author_limit() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig {
+            window_ms: 60_000,
+            max_published_ops_per_window: None,
+            max_authored_ops_per_window: Some(3),
+        });
+        let alice = fake_agent_pubkey_1();
+        let carol = fixt!(AgentPubKey);
+        let carol_new_key = fixt!(AgentPubKey);
+
+        let mut rotation = fixt!(Update);
+        rotation.author = carol.clone();
+        rotation.entry_type = EntryType::AgentPubKey;
+        rotation.entry_hash = carol_new_key.clone().into();
+        let mut ops = ops_by(&carol, 1);
+        ops.push(rotation.into());
+
+        assert!(publish(&throttle, &alice, &ops));
+        // The new key starts with the two ops of the old one
+        assert!(!publish(&throttle, &alice, &ops_by(&carol_new_key, 2)));
+        assert!(publish(&throttle, &alice, &ops_by(&carol_new_key, 1)));
+    }
+
+    #[test]
+    fn new_window_resets_limit() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig {
+            window_ms: 0,
+            max_published_ops_per_window: Some(1),
+            max_authored_ops_per_window: Some(1),
+        });
+        let alice = fake_agent_pubkey_1();
+        let bob = fake_agent_pubkey_2();
+        assert!(publish(&throttle, &alice, &ops_by(&bob, 1)));
+        assert!(publish(&throttle, &alice, &ops_by(&bob, 1)));
+        assert!(!publish(&throttle, &alice, &ops_by(&bob, 2)));
+    }
+
+    #[test]
+    fn no_limit_allows_everything() {
+        let throttle = PublishThrottle::new(&SpamProtectionConfig::default());
+        assert!(publish(
+            &throttle,
+            &fake_agent_pubkey_1(),
+            &ops_by(&fake_agent_pubkey_2(), 1000)
+        ));
+    }
+}
@@ -1,18 +1,33 @@
 use crate::{
-    conductor::manager::spawn_task_manager,
+    conductor::{
+        config::{SpamProtectionConfig, WasmLimits},
+        manager::spawn_task_manager,
+    },
+    core::state::{
+        dht_op_integration::{IntegratedDhtOpsBuf, IntegratedDhtOpsValue},
+        source_chain::SourceChain,
+        warrants_db::{issue_warrant, WarrantsBuf},
+    },
     core::workflow::incoming_dht_ops_workflow::IncomingDhtOpsWorkspace,
-    fixt::{DnaFileFixturator, SignatureFixturator},
+    fixt::{AnyDhtHashFixturator, DnaFileFixturator, SignatureFixturator},
     test_utils::test_network,
 };
 use ::fixt::prelude::*;
-use holo_hash::HasHash;
-use holochain_state::test_utils::test_cell_env;
+use fallible_iterator::FallibleIterator;
+use holo_hash::{
+    fixt::{DhtOpHashFixturator, HeaderHashFixturator},
+    HasHash,
+};
+use holochain_p2p::dht_arc::DhtArc;
+use holochain_state::{
+    buffer::BufferedStore, env::WriteManager, fresh_reader_test, test_utils::test_cell_env,
+};
 use holochain_types::{
-    dht_op::{DhtOp, DhtOpHashed},
+    dht_op::{DhtOp, DhtOpHashed, DhtOpLight},
     test_utils::{fake_agent_pubkey_2, fake_cell_id},
     HeaderHashed, Timestamp,
 };
-use holochain_zome_types::header;
+use holochain_zome_types::{header, query::ChainQueryFilter, validate::ValidationStatus};
 use std::sync::Arc;
 use tokio::sync;
 
@@ -32,6 +47,16 @@ async fn test_cell_handle_publish() {
     mock_handler
         .expect_get_dna()
         .returning(|_| Some(fixt!(DnaFile)));
+    mock_handler
+        .expect_spam_protection()
+        .return_const(SpamProtectionConfig::default());
+    mock_handler
+        .expect_wasm_limits()
+        .return_const(WasmLimits::default());
+    mock_handler
+        .expect_wasm_instance_pool_size()
+        .return_const(0usize);
+    mock_handler.expect_dpki_cell_id().returning(|| Ok(None));
 
     let mock_handler: crate::conductor::handle::ConductorHandle = Arc::new(mock_handler);
 
@@ -80,3 +105,167 @@ async fn test_cell_handle_publish() {
     stop_tx.send(()).unwrap();
     shutdown.await.unwrap();
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn test_cell_drops_warrant_for_valid_header() {
+    let cell_env = test_cell_env();
+    let env = cell_env.env();
+
+    let cell_id = fake_cell_id(1);
+    let dna = cell_id.dna_hash().clone();
+    let agent = cell_id.agent_pubkey().clone();
+
+    let test_network = test_network(Some(dna.clone()), Some(agent.clone())).await;
+    let holochain_p2p_cell = test_network.cell_network();
+
+    let mut mock_handler = crate::conductor::handle::MockConductorHandleT::new();
+    mock_handler
+        .expect_get_dna()
+        .returning(|_| Some(fixt!(DnaFile)));
+    mock_handler
+        .expect_spam_protection()
+        .return_const(SpamProtectionConfig::default());
+    mock_handler
+        .expect_wasm_limits()
+        .return_const(WasmLimits::default());
+    mock_handler
+        .expect_wasm_instance_pool_size()
+        .return_const(0usize);
+    mock_handler.expect_dpki_cell_id().returning(|| Ok(None));
+
+    let mock_handler: crate::conductor::handle::ConductorHandle = Arc::new(mock_handler);
+
+    super::Cell::genesis(cell_id.clone(), mock_handler.clone(), env.clone(), None)
+        .await
+        .unwrap();
+
+    let (add_task_sender, shutdown) = spawn_task_manager();
+    let (stop_tx, _) = sync::broadcast::channel(1);
+
+    let cell = super::Cell::create(
+        cell_id,
+        mock_handler,
+        env.clone(),
+        holochain_p2p_cell,
+        add_task_sender,
+        stop_tx.clone(),
+    )
+    .await
+    .unwrap();
+
+    // A validly signed warrant against the agent's genesis Dna header,
+    // which is valid
+    let dna_header_hash = SourceChain::new(env.clone().into())
+        .unwrap()
+        .query(&ChainQueryFilter::new().ascending().limit(1))
+        .unwrap()
+        .remove(0)
+        .header_address()
+        .clone();
+    let warrant = issue_warrant(
+        env.keystore(),
+        fake_agent_pubkey_2(),
+        agent.clone(),
+        dna_header_hash,
+        "made up".to_string(),
+    )
+    .await
+    .unwrap();
+
+    cell.handle_put_warrant(fake_agent_pubkey_2(), warrant)
+        .await
+        .unwrap();
+
+    let warrants = WarrantsBuf::new(&env).unwrap();
+    let held = fresh_reader_test!(env, |r| warrants
+        .list_warrants(&r, &agent)
+        .unwrap()
+        .collect::<Vec<_>>()
+        .unwrap());
+    assert!(held.is_empty());
+
+    stop_tx.send(()).unwrap();
+    shutdown.await.unwrap();
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn test_cell_does_not_gossip_abandoned_ops() {
+    let cell_env = test_cell_env();
+    let env = cell_env.env();
+
+    let cell_id = fake_cell_id(1);
+    let dna = cell_id.dna_hash().clone();
+    let agent = cell_id.agent_pubkey().clone();
+
+    let test_network = test_network(Some(dna.clone()), Some(agent.clone())).await;
+    let holochain_p2p_cell = test_network.cell_network();
+
+    let mut mock_handler = crate::conductor::handle::MockConductorHandleT::new();
+    mock_handler
+        .expect_get_dna()
+        .returning(|_| Some(fixt!(DnaFile)));
+    mock_handler
+        .expect_spam_protection()
+        .return_const(SpamProtectionConfig::default());
+    mock_handler
+        .expect_wasm_limits()
+        .return_const(WasmLimits::default());
+    mock_handler
+        .expect_wasm_instance_pool_size()
+        .return_const(0usize);
+    mock_handler.expect_dpki_cell_id().returning(|| Ok(None));
+
+    let mock_handler: crate::conductor::handle::ConductorHandle = Arc::new(mock_handler);
+
+    super::Cell::genesis(cell_id.clone(), mock_handler.clone(), env.clone(), None)
+        .await
+        .unwrap();
+
+    let (add_task_sender, shutdown) = spawn_task_manager();
+    let (stop_tx, _) = sync::broadcast::channel(1);
+
+    let cell = super::Cell::create(
+        cell_id,
+        mock_handler,
+        env.clone(),
+        holochain_p2p_cell,
+        add_task_sender,
+        stop_tx.clone(),
+    )
+    .await
+    .unwrap();
+
+    // An op abandoned after going over the wasm limits is integrated
+    // without its data
+    let basis = fixt!(AnyDhtHash);
+    let op_hash = fixt!(DhtOpHash);
+    let mut integrated = IntegratedDhtOpsBuf::new(env.clone().into()).unwrap();
+    integrated
+        .put(
+            op_hash.clone(),
+            IntegratedDhtOpsValue {
+                validation_status: ValidationStatus::Abandoned,
+                op: DhtOpLight::RegisterAgentActivity(fixt!(HeaderHash), basis.clone()),
+                when_integrated: Timestamp::now(),
+            },
+        )
+        .unwrap();
+    env.guard()
+        .with_commit(|writer| integrated.flush_to_txn(writer))
+        .unwrap();
+
+    let hashes = cell
+        .handle_fetch_op_hashes_for_constraints(
+            DhtArc::new(basis.get_loc(), 1),
+            Timestamp(0, 0),
+            (chrono::Utc::now() + chrono::Duration::hours(1)).into(),
+        )
+        .unwrap();
+    assert!(!hashes.contains(&op_hash));
+
+    let data = cell.handle_fetch_op_hash_data(vec![op_hash]).await.unwrap();
+    assert!(data.is_empty());
+
+    stop_tx.send(()).unwrap();
+    shutdown.await.unwrap();
+}
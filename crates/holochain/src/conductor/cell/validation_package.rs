@@ -1,7 +1,7 @@
 use call_zome_workflow::CallZomeWorkspaceLock;
 use holochain_p2p::HolochainP2pCell;
 use holochain_state::{env::EnvironmentRead, error::DatabaseResult, prelude::*};
-use holochain_types::{dna::DnaFile, HeaderHashed};
+use holochain_types::HeaderHashed;
 
 use crate::core::{
     ribosome::{guest_callback::validation_package::ValidationPackageResult, RibosomeT},
@@ -70,7 +70,7 @@ pub(super) async fn get_as_author(
     let entry_def = get_entry_def_from_ids(
         app_entry_type.zome_id(),
         app_entry_type.id(),
-        ribosome.dna_file(),
+        ribosome,
         conductor_api,
     )
     .await?;
@@ -158,7 +158,7 @@ pub(super) async fn get_as_author(
 pub(super) async fn get_as_authority(
     header: HeaderHashed,
     env: EnvironmentRead,
-    dna_file: &DnaFile,
+    ribosome: &impl RibosomeT,
     conductor_api: &impl CellConductorApiT,
 ) -> CellResult<ValidationPackageResponse> {
     // Get author and hash
@@ -178,7 +178,7 @@ pub(super) async fn get_as_authority(
     let entry_def = get_entry_def_from_ids(
         app_entry_type.zome_id(),
         app_entry_type.id(),
-        dna_file,
+        ribosome,
         conductor_api,
     )
     .await?;
@@ -27,33 +27,42 @@ use super::{
         keep_alive_task, spawn_task_manager, ManagedTaskAdd, ManagedTaskHandle,
         TaskManagerRunHandle,
     },
+    metrics,
     paths::EnvironmentRootPath,
     state::AppInterfaceId,
     state::ConductorState,
     CellError,
 };
 use crate::conductor::p2p_store::{AgentKv, AgentKvKey};
+use crate::conductor::state_dump::{dump_state, FullStateDump, StateDumpFilter};
 use crate::{
     conductor::{
         api::error::ConductorApiResult, cell::Cell, config::ConductorConfig,
         dna_store::MockDnaStore, error::ConductorResult, handle::ConductorHandle,
     },
+    core::ribosome::wasm_cache,
     core::signal::Signal,
-    core::state::{source_chain::SourceChainBuf, wasm::WasmBuf},
+    core::state::{
+        source_chain::{SourceChainBuf, SourceChainExport},
+        wasm::WasmBuf,
+    },
 };
 pub use builder::*;
 use fallible_iterator::FallibleIterator;
 use futures::future::{self, TryFutureExt};
 use holo_hash::DnaHash;
 use holochain_keystore::{
-    lair_keystore::spawn_lair_keystore, test_keystore::spawn_test_keystore, KeystoreSender,
-    KeystoreSenderExt,
+    lair_keystore::{spawn_lair_keystore, spawn_lair_keystore_with_passphrase},
+    test_keystore::spawn_test_keystore,
+    KeystoreSender, KeystoreSenderExt,
 };
 use holochain_state::{
+    backend::StorageBackend,
     buffer::BufferedStore,
     buffer::{KvStore, KvStoreT},
     db,
     env::{EnvironmentKind, EnvironmentWrite, ReadManager},
+    error::DatabaseResult,
     exports::SingleStore,
     fresh_reader,
     prelude::*,
@@ -129,6 +138,14 @@ where
     app_interface_signal_broadcasters:
         HashMap<AppInterfaceId, tokio::sync::broadcast::Sender<Signal>>,
 
+    /// Signal broadcaster shared by all admin interfaces
+    admin_signal_broadcaster: tokio::sync::broadcast::Sender<Signal>,
+
+    /// Admin signals emitted before the first admin interface was attached,
+    /// such as cells failing to start. The first admin client to connect
+    /// is sent these, since nobody could have received them at the time.
+    startup_signals: Vec<Signal>,
+
     /// Channel on which to send info about tasks we want to manage
     managed_task_add_sender: mpsc::Sender<ManagedTaskAdd>,
 
@@ -148,6 +165,12 @@ where
     /// The root environment directory where all environments are created
     root_env_dir: EnvironmentRootPath,
 
+    /// Whether environments are encrypted at rest
+    encrypt_environments: bool,
+
+    /// The backend environments are kept in
+    storage_backend: StorageBackend,
+
     /// Handle to the network actor.
     holochain_p2p: holochain_p2p::HolochainP2pRef,
 }
@@ -232,11 +255,17 @@ where
     {
         let admin_api = RealAdminInterfaceApi::new(handle);
         let stop_tx = self.managed_task_stop_broadcaster.clone();
+        let signal_broadcaster = self.admin_signal_broadcaster.clone();
+        let startup_signals = Arc::new(parking_lot::Mutex::new(std::mem::take(
+            &mut self.startup_signals,
+        )));
 
         // Closure to process each admin config item
         let spawn_from_config = |AdminInterfaceConfig { driver, .. }| {
             let admin_api = admin_api.clone();
             let stop_tx = stop_tx.clone();
+            let signal_broadcaster = signal_broadcaster.clone();
+            let startup_signals = startup_signals.clone();
             async move {
                 match driver {
                     InterfaceDriver::Websocket { port } => {
@@ -245,6 +274,8 @@ where
                         let handle: ManagedTaskHandle = spawn_admin_interface_task(
                             listener,
                             admin_api.clone(),
+                            signal_broadcaster,
+                            startup_signals,
                             stop_tx.subscribe(),
                         )?;
                         InterfaceResult::Ok((port, handle))
@@ -316,6 +347,14 @@ where
         Ok(port)
     }
 
+    /// Serve metrics on this local port, managed like the interface tasks
+    pub(super) async fn add_metrics_endpoint(&mut self, port: u16) -> ConductorResult<u16> {
+        let stop_rx = self.managed_task_stop_broadcaster.subscribe();
+        let (port, task) = metrics::spawn_metrics_endpoint(port, stop_rx).await?;
+        self.manage_task(ManagedTaskAdd::dont_handle(task)).await?;
+        Ok(port)
+    }
+
     pub(super) fn signal_broadcaster(&self) -> SignalBroadcaster {
         SignalBroadcaster::new(
             self.app_interface_signal_broadcasters
@@ -325,6 +364,22 @@ where
         )
     }
 
+    pub(super) fn admin_signal_broadcaster(&self) -> SignalBroadcaster {
+        SignalBroadcaster::new(vec![self.admin_signal_broadcaster.clone()])
+    }
+
+    /// Send a signal to every connected admin client, keeping it for
+    /// clients yet to connect if no admin interface is attached yet
+    pub(super) fn emit_admin_signal(&mut self, signal: Signal) {
+        if self.admin_websocket_ports.is_empty() && self.startup_signals.len() < SIGNAL_BUFFER_SIZE
+        {
+            self.startup_signals.push(signal.clone());
+        }
+        // Sending only fails when no admin clients are connected,
+        // in which case there is nobody to tell
+        self.admin_signal_broadcaster.send(signal).ok();
+    }
+
     /// Perform Genesis on the source chains for each of the specified CellIds.
     ///
     /// If genesis fails for any cell, this entire function fails, and all other
@@ -336,6 +391,8 @@ where
     ) -> ConductorResult<()> {
         let root_env_dir = std::path::PathBuf::from(self.root_env_dir.clone());
         let keystore = self.keystore.clone();
+        let encrypted = self.encrypt_environments;
+        let storage_backend = self.storage_backend;
 
         let cells_tasks = cell_ids_with_proofs.into_iter().map(|(cell_id, proof)| {
             let root_env_dir = root_env_dir.clone();
@@ -343,11 +400,14 @@ where
             let conductor_handle = conductor_handle.clone();
             let cell_id_inner = cell_id.clone();
             tokio::spawn(async move {
-                let env = EnvironmentWrite::new(
+                let env = open_environment(
                     &root_env_dir,
                     EnvironmentKind::Cell(cell_id_inner.clone()),
                     keystore.clone(),
-                )?;
+                    encrypted,
+                    storage_backend,
+                )
+                .await?;
                 Cell::genesis(cell_id_inner, conductor_handle, env, proof).await
             })
             .map_err(CellError::from)
@@ -364,11 +424,14 @@ where
         // If there were errors, cleanup and return the errors
         if !errors.is_empty() {
             for cell_id in success {
-                let env = EnvironmentWrite::new(
+                let env = open_environment(
                     &root_env_dir,
                     EnvironmentKind::Cell(cell_id),
                     keystore.clone(),
-                )?;
+                    encrypted,
+                    storage_backend,
+                )
+                .await?;
                 env.remove().await?;
             }
 
@@ -434,11 +497,14 @@ where
                                 cell_id.agent_pubkey().clone(),
                             );
 
-                            let env = EnvironmentWrite::new_cell(
+                            let env = open_environment(
                                 &dir,
-                                cell_id.clone(),
+                                EnvironmentKind::Cell(cell_id.clone()),
                                 keystore.clone(),
-                            )?;
+                                self.encrypt_environments,
+                                self.storage_backend,
+                            )
+                            .await?;
                             Cell::create(
                                 cell_id.clone(),
                                 conductor_handle.clone(),
@@ -633,6 +699,8 @@ where
     pub(super) fn remove_cells(&mut self, cell_ids: Vec<CellId>) {
         for cell_id in cell_ids {
             self.cells.remove(&cell_id);
+            metrics::remove_cell(&cell_id);
+            wasm_cache::remove_cell(&cell_id);
         }
     }
 
@@ -796,6 +864,78 @@ where
         Ok(source_chain.dump_as_json().await?)
     }
 
+    pub(super) async fn dump_full_cell_state(
+        &self,
+        cell_id: &CellId,
+        filter: &StateDumpFilter,
+    ) -> ConductorApiResult<FullStateDump> {
+        let cell = self.cell_by_id(cell_id)?;
+        Ok(dump_state(cell.env(), &self.p2p_env, cell_id, filter)?)
+    }
+
+    pub(super) async fn export_source_chain(
+        &self,
+        cell_id: &CellId,
+        include_private_entries: bool,
+    ) -> ConductorApiResult<SourceChainExport> {
+        let cell = self.cell_by_id(cell_id)?;
+        let arc = cell.env();
+        let source_chain = SourceChainBuf::new(arc.clone().into())?;
+        Ok(source_chain.export(include_private_entries)?)
+    }
+
+    /// Restore an exported source chain into the environment of a cell which
+    /// isn't installed yet, so that installing it skips genesis
+    pub(super) async fn import_source_chain(
+        &self,
+        cell_id: &CellId,
+        export: SourceChainExport,
+    ) -> ConductorApiResult<()> {
+        let env = open_environment(
+            &std::path::PathBuf::from(self.root_env_dir.clone()),
+            EnvironmentKind::Cell(cell_id.clone()),
+            self.keystore.clone(),
+            self.encrypt_environments,
+            self.storage_backend,
+        )
+        .await?;
+        let mut source_chain = SourceChainBuf::new(env.clone().into())?;
+        source_chain
+            .import(cell_id.dna_hash(), cell_id.agent_pubkey(), export)
+            .await?;
+        env.guard()
+            .with_commit(|writer| source_chain.flush_to_txn_ref(writer))?;
+        Ok(())
+    }
+
+    /// Remove a Dna's definition, so it isn't loaded again on restart.
+    /// Its wasm and entry defs are kept, as other Dnas may share them.
+    pub(super) async fn remove_dna_def(&self, hash: &DnaHash) -> ConductorResult<()> {
+        let environ = self.wasm_env.clone();
+        let dna_def_db = environ.get_db(&*holochain_state::db::DNA_DEF)?;
+        let mut dna_def_buf = DnaDefBuf::new(environ.clone().into(), dna_def_db)?;
+        dna_def_buf.delete(hash.clone());
+        environ
+            .guard()
+            .with_commit(|writer| dna_def_buf.flush_to_txn(writer))?;
+        Ok(())
+    }
+
+    /// Remove the environment a source chain was restored into,
+    /// for a cell which isn't installed
+    pub(super) async fn remove_source_chain(&self, cell_id: &CellId) -> ConductorApiResult<()> {
+        let env = open_environment(
+            &std::path::PathBuf::from(self.root_env_dir.clone()),
+            EnvironmentKind::Cell(cell_id.clone()),
+            self.keystore.clone(),
+            self.encrypt_environments,
+            self.storage_backend,
+        )
+        .await?;
+        env.remove().await?;
+        Ok(())
+    }
+
     #[cfg(any(test, feature = "test_utils"))]
     pub(super) async fn get_state_from_handle(&self) -> ConductorResult<ConductorState> {
         self.get_state().await
@@ -822,12 +962,17 @@ where
         dna_store: DS,
         keystore: KeystoreSender,
         root_env_dir: EnvironmentRootPath,
+        encrypt_environments: bool,
+        storage_backend: StorageBackend,
         holochain_p2p: holochain_p2p::HolochainP2pRef,
     ) -> ConductorResult<Self> {
         let db: SingleStore = env.get_db(&db::CONDUCTOR_STATE)?;
         let (task_tx, task_manager_run_handle) = spawn_task_manager();
         let task_manager_run_handle = Some(task_manager_run_handle);
         let (stop_tx, _) = tokio::sync::broadcast::channel::<()>(1);
+        // This receiver is thrown away because each admin connection
+        // subscribes its own
+        let (admin_signal_tx, _) = tokio::sync::broadcast::channel(SIGNAL_BUFFER_SIZE);
         Ok(Self {
             env,
             wasm_env,
@@ -836,6 +981,8 @@ where
             cells: HashMap::new(),
             shutting_down: false,
             app_interface_signal_broadcasters: HashMap::new(),
+            admin_signal_broadcaster: admin_signal_tx,
+            startup_signals: Vec::new(),
             managed_task_add_sender: task_tx,
             managed_task_stop_broadcaster: stop_tx,
             task_manager_run_handle,
@@ -843,6 +990,8 @@ where
             dna_store,
             keystore,
             root_env_dir,
+            encrypt_environments,
+            storage_backend,
             holochain_p2p,
         })
     }
@@ -943,6 +1092,16 @@ mod builder {
 
             tracing::info!(?self.config);
 
+            // the keystore holds the key environments are encrypted with,
+            // so it must be locked with a passphrase
+            if self.config.encrypt_environments == Some(true)
+                && self.config.passphrase_service.is_none()
+            {
+                return Err(ConductorError::ConfigError(
+                    "encrypt_environments needs a passphrase_service to lock the keystore".into(),
+                ));
+            }
+
             let keystore = if let Some(keystore) = self.keystore {
                 keystore
             } else if self.config.use_dangerous_test_keystore {
@@ -957,22 +1116,45 @@ mod builder {
                     .await
                     .unwrap();
                 keystore
+            } else if let Some(passphrase_service) = self.config.passphrase_service.clone() {
+                spawn_lair_keystore_with_passphrase(
+                    self.config.keystore_path.as_deref(),
+                    move || passphrase_service.request_passphrase(),
+                )
+                .await?
             } else {
                 spawn_lair_keystore(self.config.keystore_path.as_deref()).await?
             };
             let env_path = self.config.environment_path.clone();
+            let encrypted = self.config.encrypt_environments.unwrap_or(false);
+            let storage_backend = self.config.storage_backend.unwrap_or_default();
 
-            let environment = EnvironmentWrite::new(
+            let environment = open_environment(
                 env_path.as_ref(),
                 EnvironmentKind::Conductor,
                 keystore.clone(),
-            )?;
+                encrypted,
+                storage_backend,
+            )
+            .await?;
 
-            let wasm_environment =
-                EnvironmentWrite::new(env_path.as_ref(), EnvironmentKind::Wasm, keystore.clone())?;
+            let wasm_environment = open_environment(
+                env_path.as_ref(),
+                EnvironmentKind::Wasm,
+                keystore.clone(),
+                encrypted,
+                storage_backend,
+            )
+            .await?;
 
-            let p2p_environment =
-                EnvironmentWrite::new(env_path.as_ref(), EnvironmentKind::P2p, keystore.clone())?;
+            let p2p_environment = open_environment(
+                env_path.as_ref(),
+                EnvironmentKind::P2p,
+                keystore.clone(),
+                encrypted,
+                storage_backend,
+            )
+            .await?;
 
             #[cfg(any(test, feature = "test_utils"))]
             let state = self.state;
@@ -995,6 +1177,8 @@ mod builder {
                 dna_store,
                 keystore,
                 env_path,
+                encrypted,
+                storage_backend,
                 holochain_p2p,
             )
             .await?;
@@ -1006,10 +1190,14 @@ mod builder {
         }
 
         async fn finish(
-            conductor: Conductor<DS>,
+            mut conductor: Conductor<DS>,
             conductor_config: ConductorConfig,
             p2p_evt: holochain_p2p::event::HolochainP2pEventReceiver,
         ) -> ConductorResult<ConductorHandle> {
+            if let Some(port) = conductor_config.metrics_port {
+                conductor.add_metrics_endpoint(port).await?;
+            }
+
             // Get data before handle
             let keystore = conductor.keystore.clone();
             let holochain_p2p = conductor.holochain_p2p.clone();
@@ -1019,15 +1207,23 @@ mod builder {
                 conductor: RwLock::new(conductor),
                 keystore,
                 holochain_p2p,
+                spam_protection: conductor_config.spam_protection.unwrap_or_default(),
+                wasm_limits: conductor_config.wasm_limits.unwrap_or_default(),
+                wasm_instance_pool_size: conductor_config
+                    .wasm_instance_pool_size
+                    .unwrap_or_default(),
+                dpki: conductor_config.dpki,
+                dpki_key_states: Default::default(),
             });
 
             handle.add_dnas().await?;
 
             tokio::task::spawn(p2p_event_task(p2p_evt, handle.clone()));
 
+            // The signals `setup_cells` emits for these errors are replayed
+            // to admin clients once the admin interfaces are attached
             let cell_startup_errors = handle.clone().setup_cells().await?;
 
-            // TODO: This should probably be emitted over the admin interface
             if !cell_startup_errors.is_empty() {
                 error!(
                     msg = "Failed to create the following active apps",
@@ -1089,6 +1285,8 @@ mod builder {
                 self.dna_store,
                 keystore,
                 envs.tempdir().path().to_path_buf().into(),
+                false,
+                StorageBackend::default(),
                 holochain_p2p,
             )
             .await?;
@@ -1101,6 +1299,27 @@ mod builder {
     }
 }
 
+/// Open an environment in the given backend, encrypted at rest if
+/// `encrypted` is set, and bring its schema up to date.
+/// Memory environments are never at rest, so they are never encrypted.
+async fn open_environment(
+    path_prefix: &std::path::Path,
+    kind: EnvironmentKind,
+    keystore: KeystoreSender,
+    encrypted: bool,
+    backend: StorageBackend,
+) -> DatabaseResult<EnvironmentWrite> {
+    let migrations = super::migrations::migrations(&kind);
+    let env = match (encrypted, backend) {
+        (true, StorageBackend::Lmdb) => {
+            EnvironmentWrite::new_encrypted(path_prefix, kind, keystore).await?
+        }
+        _ => EnvironmentWrite::new_with_backend(path_prefix, kind, keystore, backend)?,
+    };
+    holochain_state::schema::migrate(&env, migrations)?;
+    Ok(env)
+}
+
 async fn p2p_event_task(
     mut p2p_evt: holochain_p2p::event::HolochainP2pEventReceiver,
     handle: ConductorHandle,
@@ -1140,6 +1359,8 @@ pub mod tests {
             dna_store,
             keystore,
             envs.tempdir().path().to_path_buf().into(),
+            false,
+            StorageBackend::default(),
             holochain_p2p,
         )
         .await
@@ -1184,6 +1405,8 @@ pub mod tests {
             dna_store,
             environments.keystore().clone(),
             environments.tempdir().path().to_path_buf().into(),
+            false,
+            StorageBackend::default(),
             holochain_p2p,
         )
         .await
@@ -1228,4 +1451,79 @@ pub mod tests {
             .unwrap();
         assert_eq!(state, conductor.get_state_from_handle().await.unwrap());
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn encryption_needs_a_passphrase_service() {
+        let result = ConductorBuilder::new()
+            .config(ConductorConfig {
+                encrypt_environments: Some(true),
+                use_dangerous_test_keystore: true,
+                ..Default::default()
+            })
+            .build()
+            .await;
+        assert_matches!(result.err(), Some(ConductorError::ConfigError(_)));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn startup_errors_are_sent_to_admin_clients() {
+        use crate::core::signal::SystemSignal;
+        use holochain_websocket::{websocket_connect, WebsocketConfig, WebsocketMessage};
+        use tokio::stream::StreamExt;
+
+        let envs = test_environments();
+        // Genesis never ran for this cell, so it can't be created
+        let mut state = ConductorState::default();
+        state.active_apps.insert(
+            "app".to_string(),
+            vec![InstalledCell::new(fake_cell_id(1), "nick".to_string())],
+        );
+        let handle = ConductorBuilder::new()
+            .config(ConductorConfig {
+                admin_interfaces: Some(vec![AdminInterfaceConfig {
+                    driver: InterfaceDriver::Websocket { port: 0 },
+                }]),
+                ..Default::default()
+            })
+            .fake_state(state)
+            .test(&envs)
+            .await
+            .unwrap();
+
+        let port = handle.get_arbitrary_admin_websocket_port().await.unwrap();
+        let (_client, mut rx) = websocket_connect(
+            url2::url2!("ws://127.0.0.1:{}", port),
+            Arc::new(WebsocketConfig::default()),
+        )
+        .await
+        .unwrap();
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(1), rx.next())
+            .await
+            .expect("Timed out waiting for the startup error")
+            .unwrap();
+        let bytes = match msg {
+            WebsocketMessage::Signal(bytes) => bytes,
+            msg => panic!("Unexpected message: {:?}", msg),
+        };
+        assert_matches!(
+            Signal::try_from(bytes).unwrap(),
+            Signal::System(SystemSignal::CellCreationFailed { installed_app_id, .. })
+            if installed_app_id == "app"
+        );
+
+        // The startup error was delivered, so later clients aren't sent it
+        let (_client, mut rx) = websocket_connect(
+            url2::url2!("ws://127.0.0.1:{}", port),
+            Arc::new(WebsocketConfig::default()),
+        )
+        .await
+        .unwrap();
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(200), rx.next())
+                .await
+                .is_err()
+        );
+
+        handle.shutdown().await;
+    }
 }
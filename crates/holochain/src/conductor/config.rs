@@ -6,6 +6,7 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 mod admin_interface_config;
 mod dpki_config;
 mod passphrase_service_config;
+mod spam_protection_config;
 //mod logger_config;
 //mod signal_config;
 use super::{
@@ -18,7 +19,10 @@ pub use admin_interface_config::AdminInterfaceConfig;
 pub use dpki_config::DpkiConfig;
 //pub use logger_config::LoggerConfig;
 pub use passphrase_service_config::PassphraseServiceConfig;
+pub use spam_protection_config::SpamProtectionConfig;
 //pub use signal_config::SignalConfig;
+pub use crate::core::ribosome::limits::WasmLimits;
+pub use holochain_state::backend::StorageBackend;
 use std::path::{Path, PathBuf};
 
 // TODO change types from "stringly typed" to Url2
@@ -66,6 +70,7 @@ pub struct ConductorConfig {
     /// The conductor is independent of the specialized implementation of the trait
     /// PassphraseService. It just needs something to provide a passphrase when needed.
     /// This config setting selects one of the available services (i.e. CLI prompt, IPC, FromConfig)
+    /// If omitted, the keystore is unlocked with a blank passphrase.
     pub passphrase_service: Option<PassphraseServiceConfig>,
 
     /// Setup admin interfaces to control this conductor through a websocket connection
@@ -73,6 +78,34 @@ pub struct ConductorConfig {
 
     /// Config options for the network module. Optional.
     pub network: Option<holochain_p2p::kitsune_p2p::KitsuneP2pConfig>,
+
+    /// Optional local port to serve metrics on, in the Prometheus text format,
+    /// at `http://127.0.0.1:<port>/metrics`. If omitted, metrics are not served.
+    pub metrics_port: Option<u16>,
+
+    /// Optional rate limits on how many ops peers may publish to each cell,
+    /// per peer and per author. If omitted, nothing is rate limited.
+    pub spam_protection: Option<SpamProtectionConfig>,
+
+    /// Optional limits on the time, memory and host calls each call into
+    /// a zome's wasm may use. If omitted, wasm calls are not limited.
+    pub wasm_limits: Option<WasmLimits>,
+
+    /// How many idle instances of each zome's wasm to keep for reuse by
+    /// zome calls. Reusing an instance skips instantiating the wasm but
+    /// keeps its memory allocated. If omitted, instances are not reused.
+    pub wasm_instance_pool_size: Option<usize>,
+
+    /// Whether to encrypt the conductor's and cells' environments at rest,
+    /// with a key held by the keystore. Environments created unencrypted stay
+    /// unencrypted. Needs a `passphrase_service`, so the keystore is locked.
+    /// If omitted, environments are not encrypted.
+    pub encrypt_environments: Option<bool>,
+
+    /// Where to keep the conductor's and cells' environments: `lmdb`, on disk
+    /// under `environment_path`, or `memory`, lost when the conductor exits.
+    /// Memory environments are never encrypted. If omitted, LMDB is used.
+    pub storage_backend: Option<StorageBackend>,
     //
     //
     // /// Which signals to emit
@@ -151,6 +184,12 @@ pub mod tests {
                 keystore_path: None,
                 admin_interfaces: None,
                 use_dangerous_test_keystore: false,
+                metrics_port: None,
+                spam_protection: None,
+                wasm_limits: None,
+                wasm_instance_pool_size: None,
+                encrypt_environments: None,
+                storage_backend: None,
             }
         );
     }
@@ -176,6 +215,22 @@ pub mod tests {
           type: websocket
           port: 1234
 
+    metrics_port: 9100
+
+    spam_protection:
+      window_ms: 2000
+      max_authored_ops_per_window: 20
+
+    wasm_limits:
+      max_duration_ms: 5000
+      max_memory_pages: 256
+
+    wasm_instance_pool_size: 4
+
+    encrypt_environments: true
+
+    storage_backend: lmdb
+
     network:
       bootstrap_service: https://bootstrap.holo.host
       transport_pool:
@@ -220,6 +275,20 @@ pub mod tests {
                     driver: InterfaceDriver::Websocket { port: 1234 }
                 }]),
                 network: Some(network_config),
+                metrics_port: Some(9100),
+                spam_protection: Some(SpamProtectionConfig {
+                    window_ms: 2000,
+                    max_published_ops_per_window: None,
+                    max_authored_ops_per_window: Some(20),
+                }),
+                wasm_limits: Some(WasmLimits {
+                    max_duration_ms: Some(5000),
+                    max_memory_pages: Some(256),
+                    max_host_calls: None,
+                }),
+                wasm_instance_pool_size: Some(4),
+                encrypt_environments: Some(true),
+                storage_backend: Some(StorageBackend::Lmdb),
             }
         );
     }
@@ -251,6 +320,12 @@ pub mod tests {
                 keystore_path: Some(PathBuf::from("/path/to/keystore").into()),
                 admin_interfaces: None,
                 use_dangerous_test_keystore: true,
+                metrics_port: None,
+                spam_protection: None,
+                wasm_limits: None,
+                wasm_instance_pool_size: None,
+                encrypt_environments: None,
+                storage_backend: None,
             }
         );
     }
@@ -7,6 +7,9 @@ use serde::{self, Deserialize, Serialize};
 /// as well as what parameters to pass it on its initialization
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub struct DpkiConfig {
+    /// The installed app id of the DPKI app.
+    /// The conductor calls its first cell to register, rotate,
+    /// revoke and look up agent keys.
     pub instance_id: String,
     pub init_params: String,
 }
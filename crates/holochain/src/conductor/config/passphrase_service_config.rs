@@ -1,5 +1,11 @@
+use futures::future::FutureExt;
+use holochain_keystore::keystore_actor::KeystoreApiFuture;
 use serde::{self, Deserialize, Serialize};
 use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+/// The request sent over a unix socket to ask for the passphrase
+const PASSPHRASE_REQUEST: &[u8] = b"request_passphrase\n";
 
 /// The default passphrase service is `Cmd` which will ask for a passphrase via stdout stdin.
 /// In the context of a UI that wraps the conductor, this way of providing passphrases
@@ -30,3 +36,40 @@ impl Default for PassphraseServiceConfig {
         PassphraseServiceConfig::Cmd
     }
 }
+
+impl PassphraseServiceConfig {
+    /// Ask for the passphrase which unlocks the keystore.
+    /// On the command line the passphrase is not echoed as it is typed.
+    pub fn request_passphrase(&self) -> KeystoreApiFuture<String> {
+        let config = self.clone();
+        async move {
+            let line = match config {
+                PassphraseServiceConfig::Cmd => tokio::task::spawn_blocking(|| {
+                    rpassword::read_password_from_tty(Some("Passphrase to unlock the keystore: "))
+                })
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?,
+                PassphraseServiceConfig::UnixSocket { path } => {
+                    let mut stream = tokio::net::UnixStream::connect(&path)
+                        .await
+                        .map_err(|e| format!("passphrase socket {:?}: {}", path, e))?;
+                    stream
+                        .write_all(PASSPHRASE_REQUEST)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    let mut line = String::new();
+                    tokio::io::BufReader::new(stream)
+                        .read_line(&mut line)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    line
+                }
+                PassphraseServiceConfig::FromConfig { passphrase } => return Ok(passphrase),
+            };
+            Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+        }
+        .boxed()
+        .into()
+    }
+}
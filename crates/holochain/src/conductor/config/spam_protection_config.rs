@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// Rate limits applied by every cell this conductor runs, to stop a
+/// single author or peer flooding it with publishes.
+/// Ops over a limit are only dropped locally; they can still arrive
+/// through gossip and are never rejected or warranted.
+/// Omitted limits are not enforced.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct SpamProtectionConfig {
+    /// The length of the window the limits below are counted over,
+    /// in milliseconds.
+    #[serde(default = "default_window_ms")]
+    pub window_ms: u64,
+
+    /// The most ops a single remote peer may publish to a cell
+    /// within one window. Publishes over this limit are dropped.
+    pub max_published_ops_per_window: Option<u32>,
+
+    /// The most ops by a single author that may be published to a cell
+    /// within one window, whichever peers publish them.
+    /// Publishes over this limit are dropped.
+    pub max_authored_ops_per_window: Option<u32>,
+}
+
+fn default_window_ms() -> u64 {
+    1000
+}
+
+impl Default for SpamProtectionConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: default_window_ms(),
+            max_published_ops_per_window: None,
+            max_authored_ops_per_window: None,
+        }
+    }
+}
+
+impl SpamProtectionConfig {
+    /// The window as a [std::time::Duration]
+    pub fn window(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.window_ms)
+    }
+}
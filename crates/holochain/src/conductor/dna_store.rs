@@ -32,6 +32,7 @@ pub struct DnaDefBuf {
 pub trait DnaStore: Default + Send + Sync {
     fn add(&mut self, dna: DnaFile);
     fn add_dnas<T: IntoIterator<Item = (DnaHash, DnaFile)> + 'static>(&mut self, dnas: T);
+    fn remove(&mut self, hash: &DnaHash);
     fn add_entry_def(&mut self, k: EntryDefBufferKey, entry_def: EntryDef);
     fn add_entry_defs<T: IntoIterator<Item = (EntryDefBufferKey, EntryDef)> + 'static>(
         &mut self,
@@ -52,6 +53,10 @@ impl DnaStore for RealDnaStore {
         self.dnas.extend(dnas);
     }
     #[instrument]
+    fn remove(&mut self, hash: &DnaHash) {
+        self.dnas.remove(hash);
+    }
+    #[instrument]
     fn list(&self) -> Vec<DnaHash> {
         self.dnas.keys().cloned().collect()
     }
@@ -98,6 +103,10 @@ impl DnaDefBuf {
         Ok(())
     }
 
+    pub fn delete(&mut self, dna_hash: DnaHash) {
+        self.dna_defs.delete(dna_hash);
+    }
+
     pub fn get_all(&self) -> DatabaseResult<Vec<DnaDefHashed>> {
         fresh_reader!(self.dna_defs.env(), |r| self
             .dna_defs
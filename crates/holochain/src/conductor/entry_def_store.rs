@@ -6,7 +6,7 @@ use crate::core::ribosome::{
     RibosomeT,
 };
 
-use super::api::CellConductorApiT;
+use super::api::{CellConductorApiT, CellConductorReadHandle};
 use error::{EntryDefStoreError, EntryDefStoreResult};
 use fallible_iterator::FallibleIterator;
 use holochain_serialized_bytes::prelude::*;
@@ -17,7 +17,7 @@ use holochain_state::{
     prelude::*,
 };
 use holochain_types::dna::{zome::Zome, DnaFile};
-use holochain_zome_types::entry_def::EntryDef;
+use holochain_zome_types::entry_def::{EntryDef, EntryDefId};
 use holochain_zome_types::header::EntryDefIndex;
 use holochain_zome_types::header::ZomeId;
 use std::{collections::HashMap, convert::TryInto};
@@ -129,7 +129,7 @@ impl BufferedStore for EntryDefBuf {
 pub(crate) async fn get_entry_def(
     entry_def_index: EntryDefIndex,
     zome: Zome,
-    dna_file: &DnaFile,
+    ribosome: &impl RibosomeT,
     conductor_api: &impl CellConductorApiT,
 ) -> EntryDefStoreResult<Option<EntryDef>> {
     // Try to get the entry def from the entry def store
@@ -139,7 +139,7 @@ pub(crate) async fn get_entry_def(
     // If it's not found run the ribosome and get the entry defs
     match &entry_def {
         Some(_) => Ok(entry_def),
-        None => Ok(get_entry_defs(dna_file.clone())?
+        None => Ok(run_entry_defs(ribosome)?
             .get(entry_def_index.index())
             .map(|(_, v)| v.clone())),
     }
@@ -148,34 +148,68 @@ pub(crate) async fn get_entry_def(
 pub(crate) async fn get_entry_def_from_ids(
     zome_id: ZomeId,
     entry_def_index: EntryDefIndex,
-    dna_file: &DnaFile,
+    ribosome: &impl RibosomeT,
     conductor_api: &impl CellConductorApiT,
 ) -> EntryDefStoreResult<Option<EntryDef>> {
-    match dna_file.dna.zomes.get(zome_id.index()) {
+    match ribosome.dna_def().zomes.get(zome_id.index()) {
         Some((_, zome)) => {
-            get_entry_def(entry_def_index, zome.clone(), dna_file, conductor_api).await
+            get_entry_def(entry_def_index, zome.clone(), ribosome, conductor_api).await
         }
         None => Ok(None),
     }
 }
 
-#[tracing::instrument(skip(dna))]
+/// Get the [EntryDef] with this id from a zome's entry defs.
+/// Reads the entry def store when the caller can reach the conductor
+/// and only runs the zome if the store has none of its entry defs.
+pub(crate) async fn get_entry_def_by_id(
+    entry_def_id: &EntryDefId,
+    zome: Zome,
+    ribosome: &impl RibosomeT,
+    call_zome_handle: Option<&CellConductorReadHandle>,
+) -> EntryDefStoreResult<Option<EntryDef>> {
+    if let Some(handle) = call_zome_handle {
+        for index in 0..=u8::MAX {
+            let key = EntryDefBufferKey::new(zome.clone(), index.into());
+            match handle.get_entry_def(&key).await {
+                Some(entry_def) if &entry_def.id == entry_def_id => return Ok(Some(entry_def)),
+                Some(_) => (),
+                // A zome's entry defs are stored together
+                // so it has no more after a gap
+                None if index > 0 => return Ok(None),
+                None => break,
+            }
+        }
+    }
+    Ok(run_entry_defs(ribosome)?
+        .into_iter()
+        .find(|(key, entry_def)| key.zome == zome && &entry_def.id == entry_def_id)
+        .map(|(_, entry_def)| entry_def))
+}
+
 /// Get all the [EntryDef] for this dna
 pub(crate) fn get_entry_defs(
     dna: DnaFile,
+) -> EntryDefStoreResult<Vec<(EntryDefBufferKey, EntryDef)>> {
+    run_entry_defs(&WasmRibosome::new(dna))
+}
+
+#[tracing::instrument(skip(ribosome))]
+/// Run the entry defs callback of every zome in the ribosome's dna
+fn run_entry_defs(
+    ribosome: &impl RibosomeT,
 ) -> EntryDefStoreResult<Vec<(EntryDefBufferKey, EntryDef)>> {
     let invocation = EntryDefsInvocation;
 
     // Get the zomes hashes
-    let zomes = dna
-        .dna
+    let zomes = ribosome
+        .dna_def()
         .zomes
         .iter()
         .cloned()
         .map(|(zome_name, zome)| (zome_name, zome))
         .collect::<HashMap<_, _>>();
 
-    let ribosome = WasmRibosome::new(dna);
     match ribosome.run_entry_defs(EntryDefsHostAccess, invocation)? {
         EntryDefsResult::Defs(map) => {
             // Turn the defs map into a vec of keys and entry defs
@@ -249,6 +283,7 @@ mod tests {
             crdt_type: CrdtType,
             required_validations: 5.into(),
             required_validation_type: Default::default(),
+            properties: None,
         };
         let comment_def = EntryDef {
             id: "comment".into(),
@@ -256,6 +291,7 @@ mod tests {
             crdt_type: CrdtType,
             required_validations: 5.into(),
             required_validation_type: Default::default(),
+            properties: None,
         };
         let dna_wasm = DnaWasmHashed::from_content(TestWasm::EntryDefs.into())
             .await
@@ -42,29 +42,46 @@
 //! code which interacted with the Conductor would also have to be highly generic.
 
 use super::{
-    api::error::ConductorApiResult,
-    config::AdminInterfaceConfig,
+    api::error::{ConductorApiError, ConductorApiResult, SerializationError},
+    config::{AdminInterfaceConfig, DpkiConfig, SpamProtectionConfig, WasmLimits},
     dna_store::DnaStore,
     entry_def_store::EntryDefBufferKey,
-    error::{ConductorResult, CreateAppError},
+    error::{ConductorError, ConductorResult, CreateAppError},
     interface::SignalBroadcaster,
     manager::TaskManagerRunHandle,
+    state_dump::{FullStateDump, StateDumpFilter},
     Cell, Conductor,
 };
+use crate::core::signal::SystemSignal;
 use crate::core::workflow::ZomeCallInvocationResult;
-use crate::core::{ribosome::ZomeCallInvocation, workflow::CallZomeWorkspaceLock};
+use crate::core::{
+    ribosome::{wasm_ribosome::WasmRibosome, ZomeCallInvocation},
+    state::source_chain::SourceChainExport,
+    workflow::CallZomeWorkspaceLock,
+};
 use derive_more::From;
 use futures::future::FutureExt;
+use holochain_keystore::KeystoreSenderExt;
 use holochain_p2p::event::HolochainP2pEvent::*;
 use holochain_types::{
-    app::{InstalledApp, InstalledAppId, InstalledCell, MembraneProof},
+    app::{AppExterns, InstalledApp, InstalledAppId, InstalledCell, MembraneProof},
     autonomic::AutonomicCue,
     cell::CellId,
-    dna::DnaFile,
+    dna::{zome::ZomeExterns, DnaFile},
     prelude::*,
 };
-use holochain_zome_types::entry_def::EntryDef;
+use holochain_zome_types::{
+    dpki::{
+        KeyRotation, KeyState, DPKI_ZOME_NAME, KEY_STATE_FN, REGISTER_KEY_FN, REVOKE_KEY_FN,
+        ROTATE_KEY_FN,
+    },
+    entry_def::EntryDef,
+    zome::ZomeName,
+    ExternInput, ZomeCallResponse,
+};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::*;
 
@@ -75,6 +92,10 @@ use crate::core::queue_consumer::InitialQueueTriggers;
 #[cfg(any(test, feature = "test_utils"))]
 use holochain_state::env::EnvironmentWrite;
 
+/// How long a key state from the DPKI app is trusted before asking again.
+/// Revocations are final so they are kept for good.
+const DPKI_KEY_STATE_TTL: Duration = Duration::from_secs(60);
+
 /// A handle to the Conductor that can easily be passed around and cheaply cloned
 pub type ConductorHandle = Arc<dyn ConductorHandleT>;
 
@@ -104,6 +125,10 @@ pub trait ConductorHandleT: Send + Sync {
     /// Install a [Dna] in this Conductor
     async fn install_dna(&self, dna: DnaFile) -> ConductorResult<()>;
 
+    /// Remove an installed [Dna] from this Conductor,
+    /// such as one installed for an app which then failed to install
+    async fn remove_dna(&self, hash: &DnaHash) -> ConductorResult<()>;
+
     /// Get the list of hashes of installed Dnas in this Conductor
     async fn list_dnas(&self) -> ConductorResult<Vec<DnaHash>>;
 
@@ -158,6 +183,15 @@ pub trait ConductorHandleT: Send + Sync {
     /// Request access to this conductor's networking handle
     fn holochain_p2p(&self) -> &holochain_p2p::HolochainP2pRef;
 
+    /// The rate limits every cell on this conductor applies
+    fn spam_protection(&self) -> &SpamProtectionConfig;
+
+    /// The limits applied to every call into wasm on this conductor
+    fn wasm_limits(&self) -> &WasmLimits;
+
+    /// How many idle instances of each zome to keep for zome calls
+    fn wasm_instance_pool_size(&self) -> usize;
+
     /// Install Cells into ConductorState based on installation info, and run
     /// genesis on all new source chains
     #[allow(clippy::ptr_arg)]
@@ -189,10 +223,46 @@ pub trait ConductorHandleT: Send + Sync {
     #[allow(clippy::ptr_arg)]
     async fn dump_cell_state(&self, cell_id: &CellId) -> ConductorApiResult<String>;
 
+    /// Dump the cell's state as structured data, narrowed down by the filter
+    #[allow(clippy::ptr_arg)]
+    async fn dump_full_cell_state(
+        &self,
+        cell_id: &CellId,
+        filter: &StateDumpFilter,
+    ) -> ConductorApiResult<FullStateDump>;
+
+    /// Copy the whole source chain of a cell,
+    /// with its private entries only if `include_private_entries` is set
+    #[allow(clippy::ptr_arg)]
+    async fn export_source_chain(
+        &self,
+        cell_id: &CellId,
+        include_private_entries: bool,
+    ) -> ConductorApiResult<SourceChainExport>;
+
+    /// Restore an exported source chain for a cell which isn't installed yet.
+    /// Installing the cell afterwards skips genesis, and the restored chain
+    /// is published again once the cell is activated.
+    #[allow(clippy::ptr_arg)]
+    async fn import_source_chain(
+        &self,
+        cell_id: &CellId,
+        export: SourceChainExport,
+    ) -> ConductorApiResult<()>;
+
+    /// Remove a restored source chain when the cell it was restored for
+    /// couldn't be installed, so that it can be restored again
+    #[allow(clippy::ptr_arg)]
+    async fn remove_source_chain(&self, cell_id: &CellId) -> ConductorApiResult<()>;
+
     /// Access the broadcast Sender which will send a Signal across every
     /// attached app interface
     async fn signal_broadcaster(&self) -> SignalBroadcaster;
 
+    /// Access the broadcast Sender which will send a Signal across every
+    /// attached admin interface
+    async fn admin_signal_broadcaster(&self) -> SignalBroadcaster;
+
     /// Get info about an installed App, whether active or inactive
     #[allow(clippy::ptr_arg)]
     async fn get_app_info(
@@ -200,6 +270,45 @@ pub trait ConductorHandleT: Send + Sync {
         installed_app_id: &InstalledAppId,
     ) -> ConductorResult<Option<InstalledApp>>;
 
+    /// List the zome functions and callbacks of each Dna of an installed App
+    #[allow(clippy::ptr_arg)]
+    async fn get_app_externs(
+        &self,
+        installed_app_id: &InstalledAppId,
+    ) -> ConductorApiResult<Option<AppExterns>>;
+
+    /// List the cells of the active App which this cell is part of,
+    /// including the cell itself.
+    /// Errors if the cell is part of more than one active App.
+    async fn get_app_cells(&self, cell_id: &CellId) -> ConductorApiResult<Vec<InstalledCell>>;
+
+    /// List the zome functions and callbacks each zome of a [Dna] exports
+    async fn list_zome_externs(
+        &self,
+        dna_hash: &DnaHash,
+    ) -> ConductorApiResult<Vec<(ZomeName, ZomeExterns)>>;
+
+    /// The cell of the DPKI app, if this conductor is configured with one
+    /// and the app is active
+    async fn dpki_cell_id(&self) -> ConductorApiResult<Option<CellId>>;
+
+    /// Ask the DPKI app what it knows about a key.
+    /// Returns `None` if there is no DPKI app to ask.
+    async fn dpki_key_state(&self, agent_key: &AgentPubKey)
+        -> ConductorApiResult<Option<KeyState>>;
+
+    /// Register a new key with the DPKI app, if there is one
+    async fn dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()>;
+
+    /// Replace the key a cell's chain is authored with by a newly generated
+    /// key, and record the rotation with the DPKI app.
+    /// Returns the new key.
+    async fn rotate_agent_key(&self, cell_id: &CellId) -> ConductorApiResult<AgentPubKey>;
+
+    /// Mark a key as revoked in the DPKI app, so headers signed with it
+    /// after now are rejected
+    async fn revoke_agent_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()>;
+
     /// Retrieve the LMDB environment for this cell. FOR TESTING ONLY.
     #[cfg(any(test, feature = "test_utils"))]
     async fn get_cell_env(&self, cell_id: &CellId) -> ConductorApiResult<EnvironmentWrite>;
@@ -230,6 +339,70 @@ pub struct ConductorHandleImpl<DS: DnaStore + 'static> {
     pub(crate) conductor: RwLock<Conductor<DS>>,
     pub(crate) keystore: KeystoreSender,
     pub(crate) holochain_p2p: holochain_p2p::HolochainP2pRef,
+    pub(crate) spam_protection: SpamProtectionConfig,
+    pub(crate) wasm_limits: WasmLimits,
+    pub(crate) wasm_instance_pool_size: usize,
+    pub(crate) dpki: Option<DpkiConfig>,
+    /// Key states recently looked up in the DPKI app, so validating
+    /// ops doesn't make a zome call for every op
+    pub(crate) dpki_key_states: parking_lot::Mutex<HashMap<AgentPubKey, (Instant, KeyState)>>,
+}
+
+impl<DS: DnaStore + 'static> ConductorHandleImpl<DS> {
+    /// Send a system signal to every connected admin client
+    async fn emit_admin_signal(&self, signal: SystemSignal) {
+        self.conductor
+            .write()
+            .await
+            .emit_admin_signal(signal.into());
+    }
+
+    /// Call a function on the DPKI zome of the DPKI app's cell
+    async fn call_dpki<I, O>(
+        &self,
+        dpki_cell_id: CellId,
+        fn_name: &str,
+        payload: I,
+    ) -> ConductorApiResult<O>
+    where
+        I: TryInto<SerializedBytes, Error = SerializedBytesError>,
+        O: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+    {
+        let invocation = ZomeCallInvocation {
+            provenance: dpki_cell_id.agent_pubkey().clone(),
+            cell_id: dpki_cell_id,
+            zome_name: DPKI_ZOME_NAME.into(),
+            cap: None,
+            fn_name: fn_name.into(),
+            payload: ExternInput::new(payload.try_into().map_err(SerializationError::from)?),
+        };
+        match self.call_zome(invocation).await?? {
+            ZomeCallResponse::Ok(output) => Ok(output
+                .into_inner()
+                .try_into()
+                .map_err(SerializationError::from)?),
+            response => Err(ConductorApiError::DpkiError(format!(
+                "{} returned {:?}",
+                fn_name, response
+            ))),
+        }
+    }
+
+    /// A key state looked up recently enough to still be trusted
+    fn cached_key_state(&self, agent_key: &AgentPubKey) -> Option<KeyState> {
+        let mut key_states = self.dpki_key_states.lock();
+        match key_states.get(agent_key) {
+            Some((_, state @ KeyState::Revoked { .. })) => Some(state.clone()),
+            Some((looked_up_at, state)) if looked_up_at.elapsed() < DPKI_KEY_STATE_TTL => {
+                Some(state.clone())
+            }
+            Some(_) => {
+                key_states.remove(agent_key);
+                None
+            }
+            None => None,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -249,8 +422,14 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
     }
 
     async fn add_app_interface(self: Arc<Self>, port: u16) -> ConductorResult<u16> {
-        let mut lock = self.conductor.write().await;
-        lock.add_app_interface_via_handle(port, self.clone()).await
+        let port = {
+            let mut lock = self.conductor.write().await;
+            lock.add_app_interface_via_handle(port, self.clone())
+                .await?
+        };
+        self.emit_admin_signal(SystemSignal::AppInterfaceAttached { port })
+            .await;
+        Ok(port)
     }
 
     async fn install_dna(&self, dna: DnaFile) -> ConductorResult<()> {
@@ -261,6 +440,12 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         Ok(())
     }
 
+    async fn remove_dna(&self, hash: &DnaHash) -> ConductorResult<()> {
+        self.conductor.read().await.remove_dna_def(hash).await?;
+        self.conductor.write().await.dna_store_mut().remove(hash);
+        Ok(())
+    }
+
     async fn add_dnas(&self) -> ConductorResult<()> {
         let (dnas, entry_defs) = self
             .conductor
@@ -399,12 +584,25 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         &self.holochain_p2p
     }
 
+    fn spam_protection(&self) -> &SpamProtectionConfig {
+        &self.spam_protection
+    }
+
+    fn wasm_limits(&self) -> &WasmLimits {
+        &self.wasm_limits
+    }
+
+    fn wasm_instance_pool_size(&self) -> usize {
+        self.wasm_instance_pool_size
+    }
+
     async fn install_app(
         self: Arc<Self>,
         installed_app_id: InstalledAppId,
         cell_data: Vec<(InstalledCell, Option<MembraneProof>)>,
     ) -> ConductorResult<()> {
-        self.conductor
+        let genesis_result = self
+            .conductor
             .read()
             .await
             .genesis_cells(
@@ -414,11 +612,19 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
                     .collect(),
                 self.clone(),
             )
-            .await?;
+            .await;
+        if let Err(ConductorError::GenesisFailed { errors }) = &genesis_result {
+            self.emit_admin_signal(SystemSignal::CellGenesisFailed {
+                installed_app_id: installed_app_id.clone(),
+                errors: errors.iter().map(ToString::to_string).collect(),
+            })
+            .await;
+        }
+        genesis_result?;
 
         let cell_data = cell_data.into_iter().map(|(c, _)| c).collect();
         let app = InstalledApp {
-            installed_app_id,
+            installed_app_id: installed_app_id.clone(),
             cell_data,
         };
 
@@ -427,7 +633,10 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
             .write()
             .await
             .add_inactive_app_to_db(app)
-            .await
+            .await?;
+        self.emit_admin_signal(SystemSignal::AppInstalled(installed_app_id))
+            .await;
+        Ok(())
     }
 
     async fn setup_cells(self: Arc<Self>) -> ConductorResult<Vec<CreateAppError>> {
@@ -446,7 +655,7 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
                 Err(e) => Some(e),
             }
         });
-        let r = futures::future::join_all(add_cells_tasks)
+        let r: Vec<CreateAppError> = futures::future::join_all(add_cells_tasks)
             .await
             .into_iter()
             // Remove successful and collect the errors
@@ -455,6 +664,17 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         {
             self.conductor.write().await.initialize_cell_workflows();
         }
+        for CreateAppError::Failed {
+            installed_app_id,
+            errors,
+        } in r.iter()
+        {
+            self.emit_admin_signal(SystemSignal::CellCreationFailed {
+                installed_app_id: installed_app_id.clone(),
+                errors: errors.iter().map(ToString::to_string).collect(),
+            })
+            .await;
+        }
         Ok(r)
     }
 
@@ -462,8 +682,11 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         self.conductor
             .write()
             .await
-            .activate_app_in_db(installed_app_id)
-            .await
+            .activate_app_in_db(installed_app_id.clone())
+            .await?;
+        self.emit_admin_signal(SystemSignal::AppActivated(installed_app_id))
+            .await;
+        Ok(())
     }
 
     async fn deactivate_app(&self, installed_app_id: InstalledAppId) -> ConductorResult<()> {
@@ -471,12 +694,14 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
             .conductor
             .write()
             .await
-            .deactivate_app_in_db(installed_app_id)
+            .deactivate_app_in_db(installed_app_id.clone())
             .await?;
         self.conductor
             .write()
             .await
             .remove_cells(cell_ids_to_remove);
+        self.emit_admin_signal(SystemSignal::AppDeactivated(installed_app_id))
+            .await;
         Ok(())
     }
 
@@ -492,10 +717,58 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         self.conductor.read().await.dump_cell_state(cell_id).await
     }
 
+    async fn dump_full_cell_state(
+        &self,
+        cell_id: &CellId,
+        filter: &StateDumpFilter,
+    ) -> ConductorApiResult<FullStateDump> {
+        self.conductor
+            .read()
+            .await
+            .dump_full_cell_state(cell_id, filter)
+            .await
+    }
+
+    async fn export_source_chain(
+        &self,
+        cell_id: &CellId,
+        include_private_entries: bool,
+    ) -> ConductorApiResult<SourceChainExport> {
+        self.conductor
+            .read()
+            .await
+            .export_source_chain(cell_id, include_private_entries)
+            .await
+    }
+
+    async fn import_source_chain(
+        &self,
+        cell_id: &CellId,
+        export: SourceChainExport,
+    ) -> ConductorApiResult<()> {
+        self.conductor
+            .read()
+            .await
+            .import_source_chain(cell_id, export)
+            .await
+    }
+
+    async fn remove_source_chain(&self, cell_id: &CellId) -> ConductorApiResult<()> {
+        self.conductor
+            .read()
+            .await
+            .remove_source_chain(cell_id)
+            .await
+    }
+
     async fn signal_broadcaster(&self) -> SignalBroadcaster {
         self.conductor.read().await.signal_broadcaster()
     }
 
+    async fn admin_signal_broadcaster(&self) -> SignalBroadcaster {
+        self.conductor.read().await.admin_signal_broadcaster()
+    }
+
     async fn get_app_info(
         &self,
         installed_app_id: &InstalledAppId,
@@ -509,6 +782,156 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
             .get_app_info(installed_app_id))
     }
 
+    async fn get_app_externs(
+        &self,
+        installed_app_id: &InstalledAppId,
+    ) -> ConductorApiResult<Option<AppExterns>> {
+        let InstalledApp {
+            installed_app_id,
+            cell_data,
+        } = match self.get_app_info(installed_app_id).await? {
+            Some(app) => app,
+            None => return Ok(None),
+        };
+        let mut dna_externs: Vec<(DnaHash, Vec<(ZomeName, ZomeExterns)>)> = Vec::new();
+        for cell in &cell_data {
+            let dna_hash = cell.as_id().dna_hash();
+            // Cells can share a Dna
+            if dna_externs.iter().any(|(hash, _)| hash == dna_hash) {
+                continue;
+            }
+            let externs = self.list_zome_externs(dna_hash).await?;
+            dna_externs.push((dna_hash.clone(), externs));
+        }
+        Ok(Some(AppExterns {
+            installed_app_id,
+            dna_externs,
+        }))
+    }
+
+    async fn get_app_cells(&self, cell_id: &CellId) -> ConductorApiResult<Vec<InstalledCell>> {
+        let mut apps = self
+            .conductor
+            .read()
+            .await
+            .get_state()
+            .await?
+            .get_app_cells(cell_id);
+        // Nicks are only unique within an app so a cell in several
+        // apps can't tell which app's cells it means
+        match apps.len() {
+            0 => Err(ConductorApiError::CellMissing(cell_id.clone())),
+            1 => Ok(apps.remove(0).cell_data),
+            _ => Err(ConductorApiError::CellInSeveralApps(
+                cell_id.clone(),
+                apps.into_iter().map(|app| app.installed_app_id).collect(),
+            )),
+        }
+    }
+
+    async fn list_zome_externs(
+        &self,
+        dna_hash: &DnaHash,
+    ) -> ConductorApiResult<Vec<(ZomeName, ZomeExterns)>> {
+        let dna = self
+            .get_dna(dna_hash)
+            .await
+            .ok_or_else(|| ConductorApiError::DnaMissing(dna_hash.clone()))?;
+        Ok(WasmRibosome::new(dna).list_zome_externs()?)
+    }
+
+    async fn dpki_cell_id(&self) -> ConductorApiResult<Option<CellId>> {
+        let installed_app_id = match &self.dpki {
+            Some(dpki) => &dpki.instance_id,
+            None => return Ok(None),
+        };
+        let state = self.conductor.read().await.get_state().await?;
+        match state.active_apps.get(installed_app_id) {
+            Some(cells) => Ok(cells.first().map(|cell| cell.as_id().clone())),
+            None => {
+                warn!(
+                    ?installed_app_id,
+                    "DPKI is configured but its app is not active"
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn dpki_key_state(
+        &self,
+        agent_key: &AgentPubKey,
+    ) -> ConductorApiResult<Option<KeyState>> {
+        let dpki_cell_id = match self.dpki_cell_id().await? {
+            Some(dpki_cell_id) => dpki_cell_id,
+            None => return Ok(None),
+        };
+        if let Some(state) = self.cached_key_state(agent_key) {
+            return Ok(Some(state));
+        }
+        let state: KeyState = self
+            .call_dpki(dpki_cell_id, KEY_STATE_FN, agent_key.clone())
+            .await?;
+        // DPKI may see an unknown key soon, so ask again next time
+        if state != KeyState::NotFound {
+            self.dpki_key_states
+                .lock()
+                .insert(agent_key.clone(), (Instant::now(), state.clone()));
+        }
+        Ok(Some(state))
+    }
+
+    async fn dpki_register_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()> {
+        if let Some(dpki_cell_id) = self.dpki_cell_id().await? {
+            self.call_dpki::<_, ()>(dpki_cell_id, REGISTER_KEY_FN, agent_key.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn rotate_agent_key(&self, cell_id: &CellId) -> ConductorApiResult<AgentPubKey> {
+        let dpki_cell_id = self.dpki_cell_id().await?.ok_or_else(|| {
+            ConductorApiError::DpkiError("Keys can only be rotated with DPKI".to_string())
+        })?;
+        let new_key = self
+            .keystore
+            .clone()
+            .generate_sign_keypair_from_pure_entropy()
+            .await?;
+        // The update is prepared first to find the old key, but only
+        // committed once DPKI has recorded the rotation, so a failed
+        // call leaves the chain authored with the old key
+        let (old_key, source_chain) = {
+            let lock = self.conductor.read().await;
+            lock.cell_by_id(cell_id)?
+                .prepare_agent_key_update(new_key.clone())
+                .await?
+        };
+        let rotation = KeyRotation {
+            old_key: old_key.clone(),
+            new_key: new_key.clone(),
+        };
+        self.call_dpki::<_, ()>(dpki_cell_id, ROTATE_KEY_FN, rotation)
+            .await?;
+        self.dpki_key_states.lock().remove(&old_key);
+        self.conductor
+            .read()
+            .await
+            .cell_by_id(cell_id)?
+            .commit_agent_key_update(source_chain)?;
+        Ok(new_key)
+    }
+
+    async fn revoke_agent_key(&self, agent_key: &AgentPubKey) -> ConductorApiResult<()> {
+        let dpki_cell_id = self.dpki_cell_id().await?.ok_or_else(|| {
+            ConductorApiError::DpkiError("Keys can only be revoked with DPKI".to_string())
+        })?;
+        self.call_dpki::<_, ()>(dpki_cell_id, REVOKE_KEY_FN, agent_key.clone())
+            .await?;
+        self.dpki_key_states.lock().remove(agent_key);
+        Ok(())
+    }
+
     #[cfg(any(test, feature = "test_utils"))]
     async fn get_cell_env(&self, cell_id: &CellId) -> ConductorApiResult<EnvironmentWrite> {
         let lock = self.conductor.read().await;
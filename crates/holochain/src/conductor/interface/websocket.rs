@@ -6,6 +6,7 @@ use crate::conductor::{
     conductor::StopReceiver,
     interface::*,
     manager::{ManagedTaskHandle, ManagedTaskResult},
+    metrics::{InterfaceConnection, InterfaceKind},
 };
 use crate::core::signal::Signal;
 use holochain_serialized_bytes::SerializedBytes;
@@ -39,11 +40,16 @@ pub async fn spawn_websocket_listener(port: u16) -> InterfaceResult<WebsocketLis
     Ok(listener)
 }
 
-/// Create an Admin Interface, which only receives AdminRequest messages
-/// from the external client
+/// Create an Admin Interface, which receives AdminRequest messages
+/// from the external client and pushes out system signals from the
+/// conductor via a broadcast channel.
+/// The first client to connect is sent the `startup_signals` nobody
+/// received, which are then dropped so they aren't replayed forever.
 pub fn spawn_admin_interface_task<A: InterfaceApi>(
     mut listener: WebsocketListener,
     api: A,
+    signal_broadcaster: broadcast::Sender<Signal>,
+    startup_signals: Arc<parking_lot::Mutex<Vec<Signal>>>,
     mut stop_rx: StopReceiver,
 ) -> InterfaceResult<ManagedTaskHandle> {
     Ok(tokio::task::spawn(async move {
@@ -58,10 +64,13 @@ pub fn spawn_admin_interface_task<A: InterfaceApi>(
                 maybe_con = listener.next() => if let Some(connection) = maybe_con {
                     match connection {
                         Ok((tx_to_iface, rx_from_iface)) => {
-                            send_sockets.push(tx_to_iface);
+                            send_sockets.push(tx_to_iface.clone());
                             listener_handles.push(tokio::task::spawn(recv_incoming_admin_msgs(
                                 api.clone(),
                                 rx_from_iface,
+                                signal_broadcaster.subscribe(),
+                                startup_signals.clone(),
+                                tx_to_iface,
                             )));
                         }
                         Err(err) => {
@@ -167,16 +176,62 @@ async fn handle_shutdown(listener_handles: Vec<JoinHandle<InterfaceResult<()>>>)
     }
 }
 
-/// Polls for messages coming in from the external client.
+/// Polls for messages coming in from the external client while simultaneously
+/// polling for system signals being broadcast from the conductor.
 /// Used by Admin interface.
-async fn recv_incoming_admin_msgs<A: InterfaceApi>(api: A, mut rx_from_iface: WebsocketReceiver) {
-    while let Some(msg) = rx_from_iface.next().await {
-        match handle_incoming_message(msg, api.clone()).await {
-            Err(InterfaceError::Closed) => break,
-            Err(e) => error!(error = &e as &dyn std::error::Error),
-            Ok(()) => (),
+async fn recv_incoming_admin_msgs<A: InterfaceApi>(
+    api: A,
+    mut rx_from_iface: WebsocketReceiver,
+    mut rx_from_conductor: broadcast::Receiver<Signal>,
+    startup_signals: Arc<parking_lot::Mutex<Vec<Signal>>>,
+    mut tx_to_iface: WebsocketSender,
+) {
+    let _connection = InterfaceConnection::open(InterfaceKind::Admin);
+    let startup_signals = std::mem::take(&mut *startup_signals.lock());
+    for signal in startup_signals {
+        if let Err(e) = send_signal(&mut tx_to_iface, signal).await {
+            error!(
+                error = &e as &dyn std::error::Error,
+                "Failed to send admin signal"
+            );
         }
     }
+    loop {
+        tokio::select! {
+            // If the conductor broadcasts a Signal, push it out
+            // across the interface
+            signal = rx_from_conductor.next() => match signal {
+                Some(Ok(signal)) => {
+                    if let Err(e) = send_signal(&mut tx_to_iface, signal).await {
+                        error!(error = &e as &dyn std::error::Error, "Failed to send admin signal");
+                    }
+                }
+                // A slow client missing some signals shouldn't
+                // stop it from making requests
+                Some(Err(e)) => warn!(?e, "Admin interface missed some signals"),
+                None => {
+                    debug!("Closing interface: signal stream empty");
+                    break;
+                }
+            },
+
+            // If we receive a message from outside, handle it
+            msg = rx_from_iface.next() => match msg {
+                Some(msg) => match handle_incoming_message(msg, api.clone()).await {
+                    Err(InterfaceError::Closed) => break,
+                    Err(e) => error!(error = &e as &dyn std::error::Error),
+                    Ok(()) => (),
+                },
+                None => break,
+            },
+        }
+    }
+}
+
+async fn send_signal(tx_to_iface: &mut WebsocketSender, signal: Signal) -> InterfaceResult<()> {
+    let bytes = SerializedBytes::try_from(signal)?;
+    tx_to_iface.signal(bytes).await?;
+    Ok(())
 }
 
 /// Polls for messages coming in from the external client while simultaneously
@@ -189,6 +244,7 @@ async fn recv_incoming_msgs_and_outgoing_signals<A: InterfaceApi>(
     mut tx_to_iface: WebsocketSender,
 ) -> InterfaceResult<()> {
     trace!("CONNECTION: {}", rx_from_iface.remote_addr());
+    let _connection = InterfaceConnection::open(InterfaceKind::App);
 
     loop {
         tokio::select! {
@@ -0,0 +1,527 @@
+//! Counters and gauges describing what the conductor is doing, served in the
+//! [Prometheus text format] from an optional local HTTP port.
+//!
+//! The values are process-wide, so several conductors running in one process
+//! will share them. Per-cell values are labelled with the cell's DNA hash and
+//! agent key.
+//!
+//! [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use crate::conductor::{
+    conductor::StopReceiver,
+    manager::{ManagedTaskHandle, ManagedTaskResult},
+};
+use holochain_types::cell::CellId;
+use holochain_zome_types::zome::{FunctionName, ZomeName};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt::Write, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::*;
+
+/// The largest request head we bother reading before answering
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// How long a client has to send its request head before we hang up
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static::lazy_static! {
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::default());
+}
+
+/// The kind of interface a connection was made to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InterfaceKind {
+    /// An admin websocket interface
+    Admin,
+    /// An app websocket interface
+    App,
+}
+
+impl InterfaceKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            InterfaceKind::Admin => "admin",
+            InterfaceKind::App => "app",
+        }
+    }
+}
+
+#[derive(Default)]
+struct CallTimes {
+    count: u64,
+    errors: u64,
+    total: Duration,
+}
+
+/// A registry of the recorded values.
+/// The conductor records into a single process-wide registry.
+#[derive(Default)]
+struct Metrics {
+    validation_limbo: HashMap<CellId, usize>,
+    integration_limbo: HashMap<CellId, usize>,
+    publish_attempts: u64,
+    published_ops: u64,
+    zome_calls: HashMap<(CellId, ZomeName, FunctionName), CallTimes>,
+    interface_connections: HashMap<InterfaceKind, u64>,
+}
+
+/// Record how many ops a cell has waiting in its limbos
+pub fn set_pending_ops(cell_id: &CellId, awaiting_validation: usize, awaiting_integration: usize) {
+    METRICS
+        .lock()
+        .set_pending_ops(cell_id, awaiting_validation, awaiting_integration);
+}
+
+/// Record an attempt to publish these many ops to one basis
+pub fn record_publish(num_ops: usize) {
+    METRICS.lock().record_publish(num_ops);
+}
+
+/// Record how long a zome call took, and whether it failed
+pub fn record_zome_call(
+    cell_id: &CellId,
+    zome_name: &ZomeName,
+    fn_name: &FunctionName,
+    elapsed: Duration,
+    failed: bool,
+) {
+    METRICS
+        .lock()
+        .record_zome_call(cell_id, zome_name, fn_name, elapsed, failed);
+}
+
+/// Forget everything recorded for a cell which has been removed
+pub fn remove_cell(cell_id: &CellId) {
+    METRICS.lock().remove_cell(cell_id);
+}
+
+/// Counts a connection to an interface for as long as it is held
+pub struct InterfaceConnection(InterfaceKind);
+
+impl InterfaceConnection {
+    /// Start counting a new connection
+    pub fn open(kind: InterfaceKind) -> Self {
+        METRICS.lock().open_connection(kind);
+        Self(kind)
+    }
+}
+
+impl Drop for InterfaceConnection {
+    fn drop(&mut self) {
+        METRICS.lock().close_connection(self.0);
+    }
+}
+
+/// Render all metrics in the Prometheus text format
+pub fn render() -> String {
+    METRICS.lock().render()
+}
+
+impl Metrics {
+    fn set_pending_ops(
+        &mut self,
+        cell_id: &CellId,
+        awaiting_validation: usize,
+        awaiting_integration: usize,
+    ) {
+        self.validation_limbo
+            .insert(cell_id.clone(), awaiting_validation);
+        self.integration_limbo
+            .insert(cell_id.clone(), awaiting_integration);
+    }
+
+    fn record_publish(&mut self, num_ops: usize) {
+        self.publish_attempts += 1;
+        self.published_ops += num_ops as u64;
+    }
+
+    fn record_zome_call(
+        &mut self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+        elapsed: Duration,
+        failed: bool,
+    ) {
+        let times = self
+            .zome_calls
+            .entry((cell_id.clone(), zome_name.clone(), fn_name.clone()))
+            .or_default();
+        times.count += 1;
+        times.total += elapsed;
+        if failed {
+            times.errors += 1;
+        }
+    }
+
+    fn remove_cell(&mut self, cell_id: &CellId) {
+        self.validation_limbo.remove(cell_id);
+        self.integration_limbo.remove(cell_id);
+        self.zome_calls.retain(|(id, _, _), _| id != cell_id);
+    }
+
+    fn open_connection(&mut self, kind: InterfaceKind) {
+        *self.interface_connections.entry(kind).or_default() += 1;
+    }
+
+    fn close_connection(&mut self, kind: InterfaceKind) {
+        if let Some(count) = self.interface_connections.get_mut(&kind) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "holochain_validation_limbo_ops",
+            "Ops waiting to be validated",
+            "gauge",
+        );
+        for (cell_id, count) in self.validation_limbo.iter() {
+            writeln!(
+                out,
+                "holochain_validation_limbo_ops{{{}}} {}",
+                cell_labels(cell_id),
+                count
+            )
+            .ok();
+        }
+
+        header(
+            &mut out,
+            "holochain_integration_limbo_ops",
+            "Validated ops waiting to be integrated",
+            "gauge",
+        );
+        for (cell_id, count) in self.integration_limbo.iter() {
+            writeln!(
+                out,
+                "holochain_integration_limbo_ops{{{}}} {}",
+                cell_labels(cell_id),
+                count
+            )
+            .ok();
+        }
+
+        header(
+            &mut out,
+            "holochain_publish_attempts_total",
+            "Publish requests sent to the network, one per basis",
+            "counter",
+        );
+        writeln!(
+            out,
+            "holochain_publish_attempts_total {}",
+            self.publish_attempts
+        )
+        .ok();
+
+        header(
+            &mut out,
+            "holochain_published_ops_total",
+            "Ops included in publish requests",
+            "counter",
+        );
+        writeln!(out, "holochain_published_ops_total {}", self.published_ops).ok();
+
+        let gossip = kitsune_p2p::metrics::gossip_metrics();
+        header(
+            &mut out,
+            "holochain_gossip_rounds_total",
+            "Gossip rounds run between pairs of agents",
+            "counter",
+        );
+        writeln!(out, "holochain_gossip_rounds_total {}", gossip.rounds).ok();
+        header(
+            &mut out,
+            "holochain_gossip_ops_total",
+            "Ops forwarded by gossip",
+            "counter",
+        );
+        writeln!(out, "holochain_gossip_ops_total {}", gossip.ops).ok();
+        header(
+            &mut out,
+            "holochain_gossip_bytes_total",
+            "Bytes of op data forwarded by gossip",
+            "counter",
+        );
+        writeln!(out, "holochain_gossip_bytes_total {}", gossip.bytes).ok();
+
+        header(
+            &mut out,
+            "holochain_zome_call_duration_seconds",
+            "Time taken by zome calls",
+            "summary",
+        );
+        for ((cell_id, zome_name, fn_name), times) in self.zome_calls.iter() {
+            let labels = format!(
+                "{},zome=\"{}\",fn=\"{}\"",
+                cell_labels(cell_id),
+                escape(&zome_name.to_string()),
+                escape(&fn_name.to_string())
+            );
+            writeln!(
+                out,
+                "holochain_zome_call_duration_seconds_sum{{{}}} {}",
+                labels,
+                times.total.as_secs_f64()
+            )
+            .ok();
+            writeln!(
+                out,
+                "holochain_zome_call_duration_seconds_count{{{}}} {}",
+                labels, times.count
+            )
+            .ok();
+        }
+
+        header(
+            &mut out,
+            "holochain_zome_call_errors_total",
+            "Zome calls which returned an error",
+            "counter",
+        );
+        for ((cell_id, zome_name, fn_name), times) in self.zome_calls.iter() {
+            writeln!(
+                out,
+                "holochain_zome_call_errors_total{{{},zome=\"{}\",fn=\"{}\"}} {}",
+                cell_labels(cell_id),
+                escape(&zome_name.to_string()),
+                escape(&fn_name.to_string()),
+                times.errors
+            )
+            .ok();
+        }
+
+        header(
+            &mut out,
+            "holochain_interface_connections",
+            "Open websocket connections to interfaces",
+            "gauge",
+        );
+        for (kind, count) in self.interface_connections.iter() {
+            writeln!(
+                out,
+                "holochain_interface_connections{{kind=\"{}\"}} {}",
+                kind.as_str(),
+                count
+            )
+            .ok();
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(out, "# HELP {} {}", name, help).ok();
+    writeln!(out, "# TYPE {} {}", name, kind).ok();
+}
+
+fn cell_labels(cell_id: &CellId) -> String {
+    format!(
+        "dna=\"{}\",agent=\"{}\"",
+        cell_id.dna_hash(),
+        cell_id.agent_pubkey()
+    )
+}
+
+/// Escape a label value as the text format requires
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve the metrics over HTTP on this local port until the stop signal.
+/// Returns the port actually bound, which is useful when passing 0.
+pub async fn spawn_metrics_endpoint(
+    port: u16,
+    mut stop_rx: StopReceiver,
+) -> std::io::Result<(u16, ManagedTaskHandle)> {
+    let mut listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let port = listener.local_addr()?.port();
+    info!(port, "Serving metrics");
+    let task = tokio::task::spawn(async move {
+        loop {
+            tokio::select! {
+                // break if we receive on the stop channel
+                _ = stop_rx.recv() => { break; },
+
+                connection = listener.accept() => match connection {
+                    Ok((stream, _)) => {
+                        tokio::task::spawn(async move {
+                            if let Err(e) = respond(stream, READ_TIMEOUT).await {
+                                debug!(?e, "Failed to respond to metrics request");
+                            }
+                        });
+                    }
+                    Err(e) => warn!(?e, "Metrics connection failed"),
+                },
+            }
+        }
+        ManagedTaskResult::Ok(())
+    });
+    Ok((port, task))
+}
+
+/// Answer a single HTTP request: the metrics for `GET /metrics`
+/// and a 404 for anything else. Gives up if the request head takes
+/// longer than `read_timeout` to arrive.
+async fn respond(mut stream: TcpStream, read_timeout: Duration) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        let n = tokio::time::timeout(read_timeout, stream.read(&mut buf))
+            .await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render()),
+        _ => ("404 Not Found", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_types::test_utils::fake_cell_id;
+
+    #[test]
+    fn render_includes_recorded_values() {
+        let mut metrics = Metrics::default();
+        let cell_id = fake_cell_id(1);
+        metrics.set_pending_ops(&cell_id, 3, 2);
+        metrics.record_zome_call(
+            &cell_id,
+            &"metrics_zome".into(),
+            &"metrics_fn".into(),
+            Duration::from_millis(500),
+            false,
+        );
+        metrics.record_zome_call(
+            &cell_id,
+            &"metrics_zome".into(),
+            &"metrics_fn".into(),
+            Duration::from_millis(100),
+            true,
+        );
+        metrics.record_publish(4);
+        metrics.open_connection(InterfaceKind::Admin);
+        metrics.open_connection(InterfaceKind::Admin);
+        metrics.close_connection(InterfaceKind::Admin);
+
+        let text = metrics.render();
+        assert!(text.contains(&format!(
+            "holochain_validation_limbo_ops{{{}}} 3",
+            cell_labels(&cell_id)
+        )));
+        assert!(text.contains(&format!(
+            "holochain_integration_limbo_ops{{{}}} 2",
+            cell_labels(&cell_id)
+        )));
+        let labels = format!(
+            "{},zome=\"metrics_zome\",fn=\"metrics_fn\"",
+            cell_labels(&cell_id)
+        );
+        assert!(text.contains(&format!(
+            "holochain_zome_call_duration_seconds_count{{{}}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!("holochain_zome_call_errors_total{{{}}} 1", labels)));
+        assert!(text.contains("holochain_publish_attempts_total 1\n"));
+        assert!(text.contains("holochain_published_ops_total 4\n"));
+        assert!(text.contains("# TYPE holochain_gossip_rounds_total counter"));
+        assert!(text.contains("holochain_interface_connections{kind=\"admin\"} 1\n"));
+    }
+
+    #[test]
+    fn removed_cells_are_forgotten() {
+        let mut metrics = Metrics::default();
+        let cell_id = fake_cell_id(2);
+        metrics.set_pending_ops(&cell_id, 1, 1);
+        metrics.record_zome_call(
+            &cell_id,
+            &"metrics_zome".into(),
+            &"metrics_fn".into(),
+            Duration::from_millis(1),
+            false,
+        );
+        assert!(metrics.render().contains(&cell_labels(&cell_id)));
+
+        metrics.remove_cell(&cell_id);
+        assert!(!metrics.render().contains(&cell_labels(&cell_id)));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn serves_metrics_over_http() {
+        let (stop_tx, _) = tokio::sync::broadcast::channel(1);
+        let (port, task) = spawn_metrics_endpoint(0, stop_tx.subscribe())
+            .await
+            .unwrap();
+
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            stream
+                .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE holochain_publish_attempts_total counter"));
+
+        let response = get("/nothing").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+
+        stop_tx.send(()).unwrap();
+        task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn unfinished_requests_time_out() {
+        let mut listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        client.write_all(b"GET /metrics").await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        let err = respond(server, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
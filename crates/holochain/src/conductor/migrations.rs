@@ -0,0 +1,65 @@
+//! The schema migrations of each kind of environment.
+//!
+//! Migrations run when the conductor opens an environment. To change how
+//! something is persisted, append a [Migration] to the list of each kind of
+//! environment which stores it, rewriting the old values into the new layout.
+//! Never change or remove a migration once it has been released, because
+//! environments which have already run it won't run it again.
+
+use holochain_state::{
+    env::{EnvironmentKind, EnvironmentWrite},
+    error::DatabaseResult,
+    prelude::Writer,
+    schema::{latest_version, Migration, SchemaVersion},
+};
+
+/// Environments from before versions were recorded already have this layout
+fn baseline(_: &EnvironmentWrite, _: &mut Writer) -> DatabaseResult<()> {
+    Ok(())
+}
+
+const BASELINE: Migration = Migration {
+    version: 1,
+    description: "record the schema version",
+    run: baseline,
+};
+
+const CELL_MIGRATIONS: &[Migration] = &[BASELINE];
+const CONDUCTOR_MIGRATIONS: &[Migration] = &[BASELINE];
+const WASM_MIGRATIONS: &[Migration] = &[BASELINE];
+const P2P_MIGRATIONS: &[Migration] = &[BASELINE];
+
+/// The migrations of a kind of environment, in order
+pub fn migrations(kind: &EnvironmentKind) -> &'static [Migration] {
+    match kind {
+        EnvironmentKind::Cell(_) => CELL_MIGRATIONS,
+        EnvironmentKind::Conductor => CONDUCTOR_MIGRATIONS,
+        EnvironmentKind::Wasm => WASM_MIGRATIONS,
+        EnvironmentKind::P2p => P2P_MIGRATIONS,
+    }
+}
+
+/// The schema version this build writes a kind of environment with
+pub fn schema_version(kind: &EnvironmentKind) -> SchemaVersion {
+    latest_version(migrations(kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_types::test_utils::fake_cell_id;
+
+    #[test]
+    fn migrations_are_numbered_in_order() {
+        for kind in &[
+            EnvironmentKind::Cell(fake_cell_id(1)),
+            EnvironmentKind::Conductor,
+            EnvironmentKind::Wasm,
+            EnvironmentKind::P2p,
+        ] {
+            let versions: Vec<_> = migrations(kind).iter().map(|m| m.version).collect();
+            let expected: Vec<_> = (1..=versions.len() as SchemaVersion).collect();
+            assert_eq!(versions, expected);
+        }
+    }
+}
@@ -173,6 +173,23 @@ pub fn all_agent_infos(env: EnvironmentRead) -> DatabaseResult<Vec<AgentInfoSign
     })
 }
 
+/// Helper function to get the peer data of a single space
+pub fn space_agent_infos(
+    env: EnvironmentRead,
+    space: DnaHash,
+) -> DatabaseResult<Vec<AgentInfoSigned>> {
+    use kitsune_p2p::KitsuneBinType;
+    let space = holochain_p2p::space_holo_to_kit(space);
+    let p2p_store = AgentKv::new(env.clone())?;
+    fresh_reader!(env, |r| {
+        p2p_store
+            .iter(&r)?
+            .filter(|(k, _)| Ok(k.as_ref()[..AGENT_KEY_COMPONENT_LEN] == space.get_bytes()[..]))
+            .map(|(_, v)| Ok(v))
+            .collect()
+    })
+}
+
 /// Helper function to get a single agent info
 pub fn get_single_agent_info(
     env: EnvironmentRead,
@@ -3,7 +3,10 @@
 
 use crate::conductor::interface::InterfaceDriver;
 
-use holochain_types::app::{InstalledApp, InstalledAppId, InstalledCell};
+use holochain_types::{
+    app::{InstalledApp, InstalledAppId, InstalledCell},
+    cell::CellId,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -51,6 +54,18 @@ impl ConductorState {
             })
     }
 
+    /// Retrieve the cells of each active App which this cell is part of
+    pub fn get_app_cells(&self, cell_id: &CellId) -> Vec<InstalledApp> {
+        self.active_apps
+            .iter()
+            .filter(|(_, cells)| cells.iter().any(|cell| cell.as_id() == cell_id))
+            .map(|(installed_app_id, cells)| InstalledApp {
+                installed_app_id: installed_app_id.clone(),
+                cell_data: cells.clone(),
+            })
+            .collect()
+    }
+
     /// Returns the interface configuration with the given ID if present
     pub fn interface_by_id(&self, id: &AppInterfaceId) -> Option<AppInterfaceConfig> {
         self.app_interfaces.get(id).cloned()
@@ -80,3 +95,39 @@ pub struct AppInterfaceConfig {
 // We need to add these back in when we've landed the new Dna format
 // See https://github.com/holochain/holochain/blob/7750a0291e549be006529e4153b3b6cf0d686462/crates/holochain/src/conductor/state/tests.rs#L1
 // for all old tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::fixt::prelude::*;
+    use holochain_types::fixt::CellIdFixturator;
+
+    #[test]
+    fn app_cells_are_found_in_every_app() {
+        let cell_id = fixt!(CellId);
+        let other_cell_id = fixt!(CellId);
+        let mut state = ConductorState::default();
+        state.active_apps.insert(
+            "a".to_string(),
+            vec![
+                InstalledCell::new(cell_id.clone(), "cell".into()),
+                InstalledCell::new(other_cell_id.clone(), "other".into()),
+            ],
+        );
+        assert_eq!(state.get_app_cells(&cell_id).len(), 1);
+        assert_eq!(state.get_app_cells(&fixt!(CellId)).len(), 0);
+
+        state.active_apps.insert(
+            "b".to_string(),
+            vec![InstalledCell::new(cell_id.clone(), "cell".into())],
+        );
+        let mut apps: Vec<_> = state
+            .get_app_cells(&cell_id)
+            .into_iter()
+            .map(|app| app.installed_app_id)
+            .collect();
+        apps.sort();
+        assert_eq!(apps, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(state.get_app_cells(&other_cell_id).len(), 1);
+    }
+}
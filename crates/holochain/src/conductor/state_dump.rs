@@ -0,0 +1,302 @@
+//! Structured dumps of a cell's state, for inspecting it with tooling.
+//!
+//! Unlike `AdminRequest::DumpState`, which returns the source chain as a
+//! JSON string, a [FullStateDump] can be queried field by field, and
+//! narrowed down with a [StateDumpFilter].
+
+use super::p2p_store::space_agent_infos;
+use crate::core::state::{
+    dht_op_integration::{AuthoredDhtOpsValue, IntegrationLimboValue},
+    source_chain::{SourceChainBuf, SourceChainResult},
+    validation_db::{ValidationLimboStore, ValidationLimboValue},
+};
+use fallible_iterator::FallibleIterator;
+use holo_hash::{AnyDhtHash, DhtOpHash, HeaderHash, HOLO_HASH_FULL_LEN};
+use holochain_p2p::kitsune_p2p::agent_store::AgentInfoSigned;
+use holochain_state::{
+    backend::RawIter,
+    buffer::{KvBufFresh, KvBufUsed},
+    db::*,
+    env::{EnvironmentWrite, ReadManager},
+    error::{DatabaseError, DatabaseResult},
+    key::BufVal,
+    prelude::{GetDb, Readable},
+};
+use holochain_types::{cell::CellId, dht_op::DhtOpLight};
+use holochain_zome_types::Header;
+use serde::{Deserialize, Serialize};
+
+/// Narrows down what a [FullStateDump] contains.
+/// Every filter which is set must match.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateDumpFilter {
+    /// Only dump the source chain from this sequence number onwards
+    pub from_seq: Option<u32>,
+    /// Only dump the ops sent to this basis
+    pub basis: Option<AnyDhtHash>,
+    /// Only dump the ops of this header
+    pub header: Option<HeaderHash>,
+    /// Dump at most this many source chain headers, and this many ops
+    /// from each store of ops
+    pub limit: Option<usize>,
+}
+
+impl StateDumpFilter {
+    fn matches(&self, op: &DhtOpLight) -> bool {
+        self.basis.as_ref().map_or(true, |b| op.dht_basis() == b)
+            && self.header.as_ref().map_or(true, |h| op.header_hash() == h)
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(usize::MAX)
+    }
+}
+
+/// A header of the source chain and where it is on the chain
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SourceChainDumpItem {
+    /// The position of the header on the chain, starting at 0 for the Dna header
+    pub seq: u32,
+    /// The hash of the header
+    pub header_address: HeaderHash,
+    /// The header
+    pub header: Header,
+}
+
+/// The state of a cell
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FullStateDump {
+    /// The headers of the source chain, in order
+    pub source_chain: Vec<SourceChainDumpItem>,
+    /// The number of records in each of the cell's databases, regardless of
+    /// the filter. Each value of a multi-value database counts as a record.
+    pub db_counts: Vec<(DbName, usize)>,
+    /// Ops waiting to be validated, with what they are waiting for
+    pub validation_limbo: Vec<(DhtOpHash, ValidationLimboValue)>,
+    /// Validated ops waiting to be integrated, with their validation status
+    pub integration_limbo: Vec<(DhtOpHash, IntegrationLimboValue)>,
+    /// Ops authored by the cell, with when they were last published
+    /// and how many validation receipts they have
+    pub authored_ops: Vec<(DhtOpHash, AuthoredDhtOpsValue)>,
+    /// The peers this conductor knows of in the cell's space
+    pub peers: Vec<AgentInfoSigned>,
+}
+
+/// Dump the state of a cell, and the peers the conductor knows in its space
+pub fn dump_state(
+    cell_env: &EnvironmentWrite,
+    p2p_env: &EnvironmentWrite,
+    cell_id: &CellId,
+    filter: &StateDumpFilter,
+) -> SourceChainResult<FullStateDump> {
+    let source_chain = dump_source_chain(cell_env, filter)?;
+
+    let validation_limbo = ValidationLimboStore::new(cell_env.clone().into())?;
+    let integration_limbo: KvBufFresh<DhtOpHash, IntegrationLimboValue> = KvBufFresh::new(
+        cell_env.clone().into(),
+        cell_env.get_db(&*INTEGRATION_LIMBO)?,
+    );
+    let authored_ops: KvBufFresh<DhtOpHash, AuthoredDhtOpsValue> = KvBufFresh::new(
+        cell_env.clone().into(),
+        cell_env.get_db(&*AUTHORED_DHT_OPS)?,
+    );
+
+    let g = cell_env.guard();
+    let r = g.reader()?;
+    let dump = FullStateDump {
+        source_chain,
+        db_counts: count_records(cell_env, &r)?,
+        validation_limbo: dump_ops(&*validation_limbo.0, &r, filter, |v| &v.op)?,
+        integration_limbo: dump_ops(&*integration_limbo, &r, filter, |v| &v.op)?,
+        authored_ops: dump_ops(&*authored_ops, &r, filter, |v| &v.op)?,
+        peers: space_agent_infos(p2p_env.clone().into(), cell_id.dna_hash().clone())?,
+    };
+    Ok(dump)
+}
+
+fn dump_source_chain(
+    env: &EnvironmentWrite,
+    filter: &StateDumpFilter,
+) -> SourceChainResult<Vec<SourceChainDumpItem>> {
+    let source_chain = SourceChainBuf::new(env.clone().into())?;
+    let mut items = Vec::new();
+    let mut seq = filter.from_seq.unwrap_or(0);
+    while items.len() < filter.limit() {
+        match source_chain.get_at_index(seq)? {
+            Some(element) => {
+                let (signed, _) = element.into_inner();
+                let (header, _) = signed.into_header_and_signature();
+                let (header, header_address) = header.into_inner();
+                items.push(SourceChainDumpItem {
+                    seq,
+                    header_address,
+                    header,
+                });
+            }
+            None => break,
+        }
+        seq += 1;
+    }
+    Ok(items)
+}
+
+fn dump_ops<R: Readable, V: BufVal>(
+    store: &KvBufUsed<DhtOpHash, V>,
+    r: &R,
+    filter: &StateDumpFilter,
+    op: fn(&V) -> &DhtOpLight,
+) -> DatabaseResult<Vec<(DhtOpHash, V)>> {
+    store
+        .iter(r)?
+        .filter(|(_, v)| Ok(filter.matches(op(v))))
+        .take(filter.limit())
+        .map(|(k, v)| Ok((op_hash(k)?, v)))
+        .collect()
+}
+
+fn op_hash(k: &[u8]) -> DatabaseResult<DhtOpHash> {
+    let invalid = || DatabaseError::InvalidHashKey(k.to_vec());
+    if k.len() != HOLO_HASH_FULL_LEN {
+        return Err(invalid());
+    }
+    DhtOpHash::from_raw_39(k.to_vec()).map_err(|_| invalid())
+}
+
+fn count_records<R: Readable>(
+    env: &EnvironmentWrite,
+    r: &R,
+) -> DatabaseResult<Vec<(DbName, usize)>> {
+    fn count(mut iter: RawIter) -> DatabaseResult<usize> {
+        iter.try_fold(0, |n, item| item.map(|_| n + 1))
+    }
+
+    let single = [
+        &*ELEMENT_VAULT_PUBLIC_ENTRIES,
+        &*ELEMENT_VAULT_PRIVATE_ENTRIES,
+        &*ELEMENT_VAULT_HEADERS,
+        &*META_VAULT_LINKS,
+        &*META_VAULT_MISC,
+        &*ELEMENT_CACHE_ENTRIES,
+        &*ELEMENT_CACHE_HEADERS,
+        &*CACHE_LINKS_META,
+        &*CACHE_STATUS_META,
+        &*AUTHORED_DHT_OPS,
+        &*INTEGRATED_DHT_OPS,
+        &*INTEGRATION_LIMBO,
+        &*VALIDATION_LIMBO,
+    ];
+    let mut counts = vec![(
+        CHAIN_SEQUENCE.key().clone(),
+        count(env.get_db(&*CHAIN_SEQUENCE)?.iter_start(r)?)?,
+    )];
+    for key in single.iter() {
+        let db = env.get_db(*key)?;
+        counts.push((key.key().clone(), count(db.iter_start(r)?)?));
+    }
+    let multi = [
+        &*META_VAULT_SYS,
+        &*CACHE_SYSTEM_META,
+        &*VALIDATION_RECEIPTS,
+        &*WARRANTS,
+    ];
+    for key in multi.iter() {
+        let db = env.get_db(*key)?;
+        counts.push((key.key().clone(), count(db.iter_start(r)?)?));
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        conductor::p2p_store::inject_agent_infos,
+        core::state::dht_op_integration::IntegrationLimboStore,
+    };
+    use fixt::prelude::*;
+    use holo_hash::fixt::DhtOpHashFixturator;
+    use holochain_state::{
+        buffer::BufferedStore,
+        env::WriteManager,
+        test_utils::{test_cell_env, test_p2p_env},
+    };
+    use holochain_types::{
+        test_utils::{fake_agent_pubkey_1, fake_dna_file},
+        validate::ValidationStatus,
+    };
+    use kitsune_p2p::fixt::AgentInfoSignedFixturator;
+
+    #[tokio::test(threaded_scheduler)]
+    async fn dump_is_filtered() -> SourceChainResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        let p2p_test_env = test_p2p_env();
+        let p2p_env = p2p_test_env.env();
+
+        let dna_hash = fake_dna_file("a").dna_hash().clone();
+        let agent_pubkey = fake_agent_pubkey_1();
+        let cell_id = CellId::new(dna_hash.clone(), agent_pubkey.clone());
+        // a peer in some other space
+        inject_agent_infos(p2p_env.clone(), vec![fixt!(AgentInfoSigned)])?;
+
+        let mut source_chain = SourceChainBuf::new(env.clone().into())?;
+        source_chain.genesis(dna_hash, agent_pubkey, None).await?;
+        env.guard()
+            .with_commit(|w| source_chain.flush_to_txn_ref(w))?;
+
+        let header_hashes: Vec<_> = SourceChainBuf::new(env.clone().into())?
+            .iter_back()
+            .map(|h| Ok(h.header_address().clone()))
+            .collect()?;
+        let mut integration_limbo: IntegrationLimboStore =
+            KvBufFresh::new(env.clone().into(), env.get_db(&*INTEGRATION_LIMBO)?);
+        for header_hash in &header_hashes {
+            let op =
+                DhtOpLight::RegisterAgentActivity(header_hash.clone(), header_hash.clone().into());
+            integration_limbo.put(
+                fixt!(DhtOpHash),
+                IntegrationLimboValue {
+                    validation_status: ValidationStatus::Valid,
+                    op,
+                },
+            )?;
+        }
+        env.guard()
+            .with_commit(|w| integration_limbo.flush_to_txn_ref(w))?;
+        // every value of a multi-value database is counted
+        let receipts = env.get_db(&*VALIDATION_RECEIPTS)?;
+        env.guard().with_commit(|w| {
+            receipts.put(w, b"op", b"receipt 1", true)?;
+            receipts.put(w, b"op", b"receipt 2", true)
+        })?;
+
+        let dump = dump_state(&env, &p2p_env, &cell_id, &StateDumpFilter::default())?;
+        let seqs: Vec<_> = dump.source_chain.iter().map(|i| i.seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2]);
+        assert_eq!(dump.integration_limbo.len(), 3);
+        assert!(dump.validation_limbo.is_empty());
+        assert!(dump.peers.is_empty());
+        assert!(dump.db_counts.contains(&(DbName::IntegrationLimbo, 3)));
+        assert!(dump.db_counts.contains(&(DbName::ChainSequence, 3)));
+        assert!(dump.db_counts.contains(&(DbName::ValidationReceipts, 2)));
+        assert!(dump.db_counts.contains(&(DbName::Warrants, 0)));
+
+        let filter = StateDumpFilter {
+            from_seq: Some(1),
+            header: Some(header_hashes[0].clone()),
+            ..Default::default()
+        };
+        let dump = dump_state(&env, &p2p_env, &cell_id, &filter)?;
+        let seqs: Vec<_> = dump.source_chain.iter().map(|i| i.seq).collect();
+        assert_eq!(seqs, vec![1, 2]);
+        assert_eq!(dump.integration_limbo.len(), 1);
+        assert_eq!(
+            dump.integration_limbo[0].1.op.header_hash(),
+            &header_hashes[0]
+        );
+        // the counts aren't filtered
+        assert!(dump.db_counts.contains(&(DbName::IntegrationLimbo, 3)));
+        Ok(())
+    }
+}
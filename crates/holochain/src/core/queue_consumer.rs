@@ -25,7 +25,10 @@
 //! Implicitly, every workflow also writes to its own source queue, i.e. to
 //! remove the item it has just processed.
 
-use std::sync::{Arc, Once};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Once,
+};
 
 use derive_more::{Constructor, Display, From};
 use futures::future::Either;
@@ -45,8 +48,8 @@ use app_validation_consumer::*;
 mod produce_dht_ops_consumer;
 use produce_dht_ops_consumer::*;
 mod publish_dht_ops_consumer;
-use super::state::workspace::WorkspaceError;
-use crate::conductor::{api::CellConductorApiT, manager::ManagedTaskAdd};
+use super::{signal::SystemSignal, state::workspace::WorkspaceError};
+use crate::conductor::{api::CellConductorApiT, manager::ManagedTaskAdd, metrics};
 use holochain_p2p::HolochainP2pCell;
 use publish_dht_ops_consumer::*;
 
@@ -71,10 +74,16 @@ pub async fn spawn_queue_consumer_tasks(
         .expect("Failed to manage workflow handle");
 
     let (create_tx_sys, get_tx_sys) = tokio::sync::oneshot::channel();
+    let pending_ops = Arc::new(PendingOps::default());
 
     // Integration
-    let (tx_integration, handle) =
-        spawn_integrate_dht_ops_consumer(env.clone(), stop.subscribe(), get_tx_sys);
+    let (tx_integration, handle) = spawn_integrate_dht_ops_consumer(
+        env.clone(),
+        stop.subscribe(),
+        get_tx_sys,
+        conductor_api.clone(),
+        pending_ops.clone(),
+    );
     task_sender
         .send(ManagedTaskAdd::dont_handle(handle))
         .await
@@ -87,6 +96,7 @@ pub async fn spawn_queue_consumer_tasks(
         tx_integration.clone(),
         conductor_api.clone(),
         cell_network.clone(),
+        pending_ops.clone(),
     );
     task_sender
         .send(ManagedTaskAdd::dont_handle(handle))
@@ -100,6 +110,7 @@ pub async fn spawn_queue_consumer_tasks(
         tx_app.clone(),
         cell_network,
         conductor_api,
+        pending_ops,
     );
     task_sender
         .send(ManagedTaskAdd::dont_handle(handle))
@@ -283,3 +294,77 @@ async fn next_job_or_exit(
         Job::Run
     }
 }
+
+/// How many ops a cell has waiting in its limbos.
+///
+/// Rather than scanning the limbos, the workflows which already drain them
+/// report what they left behind. Every one of them runs when the cell starts,
+/// so the counts are accurate from then on.
+#[derive(Debug, Default)]
+pub struct PendingOps {
+    awaiting_validation: AtomicUsize,
+    awaiting_integration: AtomicUsize,
+}
+
+/// What a validation workflow run left in the limbos
+#[derive(Debug, Default)]
+pub struct ValidationTally {
+    /// Every op that was in the validation limbo when the run started
+    pub in_validation_limbo: usize,
+    /// The ops this run moved on to the integration limbo
+    pub to_integration: usize,
+}
+
+impl PendingOps {
+    /// Record a committed validation run
+    pub fn validated(&self, tally: ValidationTally) {
+        self.awaiting_validation.store(
+            tally
+                .in_validation_limbo
+                .saturating_sub(tally.to_integration),
+            Ordering::Relaxed,
+        );
+        self.awaiting_integration
+            .fetch_add(tally.to_integration, Ordering::Relaxed);
+    }
+
+    /// Record a committed integration run which left these many ops
+    /// in the integration limbo
+    pub fn integrated(&self, awaiting_integration: usize) {
+        self.awaiting_integration
+            .store(awaiting_integration, Ordering::Relaxed);
+    }
+
+    /// The ops awaiting validation and awaiting integration
+    pub fn counts(&self) -> (usize, usize) {
+        (
+            self.awaiting_validation.load(Ordering::Relaxed),
+            self.awaiting_integration.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Tell admin clients and the metrics endpoint how many ops this cell
+/// still has to validate and integrate
+async fn emit_integration_progress(
+    pending_ops: &PendingOps,
+    conductor_api: &impl CellConductorApiT,
+) {
+    let (awaiting_validation, awaiting_integration) = pending_ops.counts();
+    metrics::set_pending_ops(
+        conductor_api.cell_id(),
+        awaiting_validation,
+        awaiting_integration,
+    );
+    let signal = SystemSignal::IntegrationProgress {
+        cell_id: conductor_api.cell_id().clone(),
+        awaiting_validation,
+        awaiting_integration,
+    };
+    // Sending only fails when no admin clients are connected
+    conductor_api
+        .admin_signal_broadcaster()
+        .await
+        .send(signal.into())
+        .ok();
+}
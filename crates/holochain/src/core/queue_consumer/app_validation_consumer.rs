@@ -11,13 +11,14 @@ use tokio::task::JoinHandle;
 use tracing::*;
 
 /// Spawn the QueueConsumer for AppValidation workflow
-#[instrument(skip(env, stop, trigger_integration, conductor_api, network))]
+#[instrument(skip(env, stop, trigger_integration, conductor_api, network, pending_ops))]
 pub fn spawn_app_validation_consumer(
     env: EnvironmentWrite,
     mut stop: sync::broadcast::Receiver<()>,
     mut trigger_integration: TriggerSender,
     conductor_api: impl CellConductorApiT + 'static,
     network: HolochainP2pCell,
+    pending_ops: Arc<PendingOps>,
 ) -> (TriggerSender, JoinHandle<ManagedTaskResult>) {
     let (tx, mut rx) = TriggerSender::new();
     let mut trigger_self = tx.clone();
@@ -40,12 +41,14 @@ pub fn spawn_app_validation_consumer(
                 &mut trigger_integration,
                 conductor_api.clone(),
                 network.clone(),
+                &pending_ops,
             )
             .await
             .expect("Error running Workflow")
             {
                 trigger_self.trigger()
             };
+            emit_integration_progress(&pending_ops, &conductor_api).await;
         }
         Ok(())
     });
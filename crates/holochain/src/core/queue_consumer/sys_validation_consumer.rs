@@ -10,13 +10,14 @@ use tokio::task::JoinHandle;
 use tracing::*;
 
 /// Spawn the QueueConsumer for SysValidation workflow
-#[instrument(skip(env, stop, trigger_app_validation, network, conductor_api))]
+#[instrument(skip(env, stop, trigger_app_validation, network, conductor_api, pending_ops))]
 pub fn spawn_sys_validation_consumer(
     env: EnvironmentWrite,
     mut stop: sync::broadcast::Receiver<()>,
     mut trigger_app_validation: TriggerSender,
     network: HolochainP2pCell,
     conductor_api: impl CellConductorApiT + 'static,
+    pending_ops: Arc<PendingOps>,
 ) -> (TriggerSender, JoinHandle<ManagedTaskResult>) {
     let (tx, mut rx) = TriggerSender::new();
     let mut trigger_self = tx.clone();
@@ -40,12 +41,14 @@ pub fn spawn_sys_validation_consumer(
                 trigger_self.clone(),
                 network.clone(),
                 conductor_api.clone(),
+                &pending_ops,
             )
             .await
             .expect("Error running Workflow")
             {
                 trigger_self.trigger()
             };
+            emit_integration_progress(&pending_ops, &conductor_api).await;
         }
         Ok(())
     });
@@ -10,7 +10,11 @@
 pub mod error;
 pub mod guest_callback;
 pub mod host_fn;
+pub mod limits;
+pub mod wasm_cache;
+pub mod wasm_cancel;
 pub mod wasm_ribosome;
+pub mod wasm_threads;
 
 use crate::core::ribosome::guest_callback::entry_defs::EntryDefsResult;
 use crate::core::ribosome::guest_callback::init::InitInvocation;
@@ -46,12 +50,13 @@ use guest_callback::{
 };
 use holo_hash::fixt::AgentPubKeyFixturator;
 use holo_hash::AgentPubKey;
+use holo_hash::DnaHash;
 use holochain_keystore::KeystoreSender;
 use holochain_p2p::HolochainP2pCell;
 use holochain_serialized_bytes::prelude::*;
 use holochain_types::cell::CellId;
 use holochain_types::dna::zome::HostFnAccess;
-use holochain_types::dna::DnaFile;
+use holochain_types::dna::DnaDef;
 use holochain_types::fixt::CapSecretFixturator;
 use holochain_types::fixt::CellIdFixturator;
 use holochain_wasm_test_utils::TestWasm;
@@ -426,7 +431,9 @@ impl From<&ZomeCallHostAccess> for HostFnAccess {
 /// real concrete type is [WasmRibosome]
 #[automock]
 pub trait RibosomeT: Sized + std::fmt::Debug {
-    fn dna_file(&self) -> &DnaFile;
+    fn dna_def(&self) -> &DnaDef;
+
+    fn dna_hash(&self) -> &DnaHash;
 
     fn zomes_to_invoke(&self, zomes_to_invoke: ZomesToInvoke) -> Vec<ZomeName>;
 
@@ -440,19 +447,11 @@ pub trait RibosomeT: Sized + std::fmt::Debug {
         to_call: &FunctionName,
     ) -> Result<Option<ExternOutput>, RibosomeError>;
 
-    /// @todo list out all the available callbacks and maybe cache them somewhere
-    fn list_callbacks(&self) {
-        unimplemented!()
-        // pseudocode
-        // self.instance().exports().filter(|e| e.is_callback())
-    }
+    /// The callbacks each zome implements
+    fn list_callbacks(&self) -> RibosomeResult<Vec<(ZomeName, Vec<FunctionName>)>>;
 
-    /// @todo list out all the available zome functions and maybe cache them somewhere
-    fn list_zome_fns(&self) {
-        unimplemented!()
-        // pseudocode
-        // self.instance().exports().filter(|e| !e.is_callback())
-    }
+    /// The functions each zome exposes to zome calls
+    fn list_zome_fns(&self) -> RibosomeResult<Vec<(ZomeName, Vec<FunctionName>)>>;
 
     fn run_init(
         &self,
@@ -557,7 +556,7 @@ pub mod wasm_test {
 
                 // Required because otherwise the network will return routing errors
                 let test_network = crate::test_utils::test_network(
-                    Some(ribosome.dna_file().dna_hash().clone()),
+                    Some(ribosome.dna_hash().clone()),
                     Some(author),
                 )
                 .await;
@@ -3,6 +3,7 @@
 
 use crate::{
     conductor::{api::error::ConductorApiError, interface::error::InterfaceError},
+    core::ribosome::limits::WasmLimitExceeded,
     core::state::{cascade::error::CascadeError, source_chain::SourceChainError},
 };
 use holo_hash::AnyDhtHash;
@@ -26,6 +27,10 @@ pub enum RibosomeError {
     #[error("Wasm error while working with Ribosome: {0}")]
     WasmError(#[from] WasmError),
 
+    /// A call into the wasm went over one of the ribosome's limits
+    #[error("Wasm call stopped: {0}")]
+    WasmLimitExceeded(WasmLimitExceeded),
+
     /// Serialization error while working with Ribosome.
     #[error("Serialization error while working with Ribosome: {0}")]
     SerializationError(#[from] SerializedBytesError),
@@ -14,6 +14,8 @@ use fallible_iterator::FallibleIterator;
 use holochain_zome_types::zome::ZomeName;
 use holochain_zome_types::ExternOutput;
 
+pub use holochain_types::dna::zome::{callback_names, is_callback, is_common_callback};
+
 pub struct CallIterator<R: RibosomeT, I: Invocation> {
     host_access: HostAccess,
     ribosome: R,
@@ -278,6 +278,7 @@ mod slow_tests {
                         crdt_type: CrdtType,
                         required_validations: 5.into(),
                         required_validation_type: Default::default(),
+                        properties: None,
                     },
                     EntryDef {
                         id: "comment".into(),
@@ -285,6 +286,7 @@ mod slow_tests {
                         crdt_type: CrdtType,
                         required_validations: 5.into(),
                         required_validation_type: Default::default(),
+                        properties: None,
                     },
                 ]
                 .into();
@@ -1,6 +1,7 @@
 use crate::core::ribosome::error::RibosomeResult;
 use crate::core::ribosome::CallContext;
 use crate::core::ribosome::RibosomeT;
+use crate::core::state::source_chain::SourceChainResult;
 use holochain_zome_types::agent_info::AgentInfo;
 use holochain_zome_types::AgentInfoInput;
 use holochain_zome_types::AgentInfoOutput;
@@ -12,13 +13,17 @@ pub fn agent_info<'a>(
     call_context: Arc<CallContext>,
     _input: AgentInfoInput,
 ) -> RibosomeResult<AgentInfoOutput> {
-    let agent_pubkey = tokio_safe_block_on::tokio_safe_block_forever_on(async move {
-        let lock = call_context.host_access.workspace().read().await;
-        lock.source_chain.agent_pubkey()
-    })?;
+    let (agent_initial_pubkey, agent_latest_pubkey) =
+        tokio_safe_block_on::tokio_safe_block_forever_on(async move {
+            let lock = call_context.host_access.workspace().read().await;
+            SourceChainResult::Ok((
+                lock.source_chain.agent_pubkey()?,
+                lock.source_chain.latest_agent_pubkey()?,
+            ))
+        })?;
     Ok(AgentInfoOutput::new(AgentInfo {
-        agent_initial_pubkey: agent_pubkey.clone(),
-        agent_latest_pubkey: agent_pubkey,
+        agent_initial_pubkey,
+        agent_latest_pubkey,
     }))
 }
 
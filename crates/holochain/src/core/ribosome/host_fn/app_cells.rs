@@ -0,0 +1,26 @@
+use crate::core::ribosome::error::RibosomeResult;
+use crate::core::ribosome::CallContext;
+use crate::core::ribosome::RibosomeT;
+use holochain_zome_types::AppCellsInput;
+use holochain_zome_types::AppCellsOutput;
+use std::sync::Arc;
+
+pub fn app_cells(
+    _ribosome: Arc<impl RibosomeT>,
+    call_context: Arc<CallContext>,
+    _input: AppCellsInput,
+) -> RibosomeResult<AppCellsOutput> {
+    let conductor_handle = call_context.host_access().call_zome_handle();
+    let app_cells = tokio_safe_block_on::tokio_safe_block_forever_on(async move {
+        conductor_handle.list_app_cells().await.map_err(Box::new)
+    })?;
+    Ok(AppCellsOutput::new(
+        app_cells
+            .into_iter()
+            .map(|cell| {
+                let (cell_id, cell_nick) = cell.into_inner();
+                (cell_nick, cell_id)
+            })
+            .collect(),
+    ))
+}
@@ -1,6 +1,8 @@
+use crate::conductor::api::error::ConductorApiError;
 use crate::core::ribosome::CallContext;
 use crate::core::ribosome::RibosomeT;
 use crate::core::ribosome::{error::RibosomeResult, ZomeCallInvocation};
+use holochain_zome_types::call::CallTarget;
 use holochain_zome_types::{CallInput, ZomeCallResponse};
 use holochain_zome_types::{CallOutput, ExternInput};
 use std::sync::Arc;
@@ -18,10 +20,27 @@ pub fn call(
     let conductor_handle = host_access.call_zome_handle();
     let workspace = host_access.workspace();
 
-    // Get the cell id if it's not passed in
-    let cell_id = call
-        .to_cell
-        .unwrap_or_else(|| conductor_handle.cell_id().clone());
+    // Find the cell being called
+    let cell_id = match call.target {
+        CallTarget::ThisCell => conductor_handle.cell_id().clone(),
+        CallTarget::Cell(cell_id) => cell_id,
+        // Nicks are looked up now so the call reaches whichever
+        // cell currently has the nick in this cell's app
+        CallTarget::CellNick(cell_nick) => {
+            let app_cells = tokio_safe_block_on::tokio_safe_block_forever_on(async move {
+                conductor_handle.list_app_cells().await.map_err(Box::new)
+            })?;
+            app_cells
+                .into_iter()
+                .find(|cell| *cell.as_nick() == cell_nick)
+                .map(|cell| cell.into_id())
+                .ok_or_else(|| Box::new(ConductorApiError::CellNickMissing(cell_nick)))?
+        }
+    };
+
+    // The call is always made as the calling agent so the called
+    // cell's capability check can't be bypassed by the guest
+    let provenance = conductor_handle.cell_id().agent_pubkey().clone();
 
     // Create the invocation for this call
     let invocation = ZomeCallInvocation {
@@ -30,10 +49,11 @@ pub fn call(
         cap: call.cap,
         fn_name: call.fn_name,
         payload: ExternInput::new(call.request),
-        provenance: call.provenance,
+        provenance,
     };
 
-    // Make the call using this workspace
+    // Make the call using this workspace if it's to the same cell,
+    // otherwise the called cell uses its own workspace
     let result: ZomeCallResponse = tokio_safe_block_on::tokio_safe_block_forever_on(async move {
         conductor_handle
             .call_zome(invocation, workspace)
@@ -56,7 +76,9 @@ pub mod wasm_test {
         dna::{DnaDef, DnaFile},
     };
     use holochain_wasm_test_utils::TestWasm;
-    use holochain_zome_types::{test_utils::fake_agent_pubkey_2, ExternInput, ZomeCallResponse};
+    use holochain_zome_types::{
+        test_utils::fake_agent_pubkey_2, AppCellsOutput, ExternInput, ZomeCallResponse,
+    };
     use matches::assert_matches;
 
     use crate::{
@@ -124,6 +146,32 @@ pub mod wasm_test {
         conductor_test.shutdown_conductor().await;
     }
 
+    /// Calling another agent's cell without a grant from them is unauthorized
+    #[tokio::test(threaded_scheduler)]
+    async fn call_without_grant_is_unauthorized() {
+        observability::test_run().ok();
+
+        let zomes = vec![TestWasm::WhoAmI];
+        let mut conductor_test = ConductorTestData::two_agents(zomes, true).await;
+        let handle = conductor_test.handle();
+        let bob_cell_id = conductor_test.bob_call_data().unwrap().cell_id.clone();
+        let alice_call_data = conductor_test.alice_call_data();
+        let alice_cell_id = &alice_call_data.cell_id;
+
+        // Bob hasn't granted access so alice's call to bob fails
+        let invocation = new_invocation(
+            &alice_cell_id,
+            "who_are_they_local",
+            bob_cell_id.clone(),
+            TestWasm::WhoAmI,
+        )
+        .unwrap();
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Err(_)));
+
+        conductor_test.shutdown_conductor().await;
+    }
+
     /// When calling the same cell we need to make sure
     /// the "as at" doesn't cause the original zome call to fail
     /// when they are both writing (moving the source chain forward)
@@ -203,12 +251,99 @@ pub mod wasm_test {
         conductor_test.shutdown_conductor().await;
     }
 
-    async fn install_new_app(
-        dna_name: &str,
-        zomes: Vec<TestWasm>,
-        handle: &ConductorHandle,
-    ) -> CellId {
-        let dna_file = DnaFile::new(
+    /// Sibling cells can be called by the nick they were installed with
+    #[tokio::test(threaded_scheduler)]
+    async fn bridge_call_by_nick() {
+        observability::test_run().ok();
+
+        let zomes = vec![TestWasm::WhoAmI];
+        let mut conductor_test = ConductorTestData::two_agents(zomes, false).await;
+        let handle = conductor_test.handle();
+
+        // Install an app for bob with a cell of each dna
+        let whoami_dna = new_dna_file("whoami_dna", vec![TestWasm::WhoAmI]).await;
+        let create_dna = new_dna_file("create_dna", vec![TestWasm::Create]).await;
+        let bob_agent_id = fake_agent_pubkey_2();
+        let whoami_cell_id = CellId::new(whoami_dna.dna_hash().clone(), bob_agent_id.clone());
+        let create_cell_id = CellId::new(create_dna.dna_hash().clone(), bob_agent_id.clone());
+        let cell_data = vec![
+            (
+                InstalledCell::new(whoami_cell_id.clone(), "whoami".into()),
+                None,
+            ),
+            (
+                InstalledCell::new(create_cell_id.clone(), "create".into()),
+                None,
+            ),
+        ];
+        install_app(
+            "bob_app",
+            cell_data,
+            vec![whoami_dna, create_dna],
+            handle.clone(),
+        )
+        .await;
+
+        // The whoami cell can see both cells in the app
+        let invocation =
+            new_invocation(&whoami_cell_id, "list_app_cells", (), TestWasm::WhoAmI).unwrap();
+        let result = handle.call_zome(invocation).await.unwrap().unwrap();
+        let mut app_cells = AppCellsOutput::try_from(
+            unwrap_to::unwrap_to!(result => ZomeCallResponse::Ok)
+                .clone()
+                .into_inner(),
+        )
+        .unwrap()
+        .into_inner();
+        app_cells.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            app_cells,
+            vec![
+                ("create".to_string(), create_cell_id.clone()),
+                ("whoami".to_string(), whoami_cell_id.clone()),
+            ]
+        );
+
+        // Call create_entry in the sibling cell by its nick
+        let invocation = new_invocation(
+            &whoami_cell_id,
+            "call_create_entry_by_nick",
+            (),
+            TestWasm::WhoAmI,
+        )
+        .unwrap();
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Ok(ZomeCallResponse::Ok(_))));
+        let header_hash: HeaderHash =
+            unwrap_to::unwrap_to!(result.unwrap().unwrap() => ZomeCallResponse::Ok)
+                .clone()
+                .into_inner()
+                .try_into()
+                .unwrap();
+
+        // Check the create cell's source chain contains the new value
+        let create_env = handle.get_cell_env(&create_cell_id).await.unwrap();
+        let source_chain = ElementBuf::authored(create_env.into(), true).unwrap();
+        let el = source_chain.get_element(&header_hash).unwrap();
+        assert_matches!(el, Some(_));
+
+        // Alice's app has no cell with that nick
+        let alice_cell_id = &conductor_test.alice_call_data().cell_id;
+        let invocation = new_invocation(
+            alice_cell_id,
+            "call_create_entry_by_nick",
+            (),
+            TestWasm::WhoAmI,
+        )
+        .unwrap();
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Err(_)));
+
+        conductor_test.shutdown_conductor().await;
+    }
+
+    async fn new_dna_file(dna_name: &str, zomes: Vec<TestWasm>) -> DnaFile {
+        DnaFile::new(
             DnaDef {
                 name: dna_name.to_string(),
                 uuid: "ba1d046d-ce29-4778-914b-47e6010d2faf".to_string(),
@@ -218,7 +353,15 @@ pub mod wasm_test {
             zomes.into_iter().map(Into::into),
         )
         .await
-        .unwrap();
+        .unwrap()
+    }
+
+    async fn install_new_app(
+        dna_name: &str,
+        zomes: Vec<TestWasm>,
+        handle: &ConductorHandle,
+    ) -> CellId {
+        let dna_file = new_dna_file(dna_name, zomes).await;
         let bob_agent_id = fake_agent_pubkey_2();
         let bob_cell_id = CellId::new(dna_file.dna_hash().to_owned(), bob_agent_id.clone());
         let bob_installed_cell = InstalledCell::new(bob_cell_id.clone(), "bob_handle".into());
@@ -1,14 +1,187 @@
+use crate::conductor::entry_def_store::get_entry_def_by_id;
+use crate::core::ribosome::error::RibosomeError;
 use crate::core::ribosome::error::RibosomeResult;
 use crate::core::ribosome::CallContext;
+use crate::core::ribosome::HostAccess;
 use crate::core::ribosome::RibosomeT;
 use holochain_zome_types::EntryTypePropertiesInput;
 use holochain_zome_types::EntryTypePropertiesOutput;
 use std::sync::Arc;
 
 pub fn entry_type_properties(
-    _ribosome: Arc<impl RibosomeT>,
-    _call_context: Arc<CallContext>,
-    _input: EntryTypePropertiesInput,
+    ribosome: Arc<impl RibosomeT>,
+    call_context: Arc<CallContext>,
+    input: EntryTypePropertiesInput,
 ) -> RibosomeResult<EntryTypePropertiesOutput> {
-    unimplemented!();
+    let entry_def_id = input.into_inner();
+    let zome = ribosome
+        .dna_def()
+        .get_zome(&call_context.zome_name)?
+        .clone();
+    // Only zome calls can reach the conductor's entry def store
+    let call_zome_handle = match call_context.host_access() {
+        HostAccess::ZomeCall(access) => Some(access.call_zome_handle),
+        _ => None,
+    };
+    let id = entry_def_id.clone();
+    let entry_def = tokio_safe_block_on::tokio_safe_block_forever_on(async move {
+        get_entry_def_by_id(&id, zome, ribosome.as_ref(), call_zome_handle.as_ref()).await
+    })
+    .map_err(|e| RibosomeError::EntryDefs(call_context.zome_name.clone(), e.to_string()))?;
+    match entry_def {
+        Some(entry_def) => Ok(EntryTypePropertiesOutput::new(entry_def.properties)),
+        None => Err(RibosomeError::EntryDefs(
+            call_context.zome_name.clone(),
+            format!("entry def not found for {:?}", entry_def_id),
+        )),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::entry_type_properties;
+    use crate::conductor::api::CellConductorApi;
+    use crate::conductor::entry_def_store::EntryDefBufferKey;
+    use crate::conductor::handle::MockConductorHandleT;
+    use crate::core::ribosome::error::RibosomeError;
+    use crate::core::ribosome::CallContext;
+    use crate::core::ribosome::MockRibosomeT;
+    use crate::fixt::ZomeCallHostAccessFixturator;
+    use ::fixt::prelude::*;
+    use holochain_serialized_bytes::prelude::*;
+    use holochain_types::fixt::*;
+    use holochain_zome_types::entry_def::EntryDefId;
+    use holochain_zome_types::EntryTypePropertiesInput;
+    use matches::assert_matches;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    #[tokio::test(threaded_scheduler)]
+    async fn entry_type_properties_are_read_from_the_store() {
+        let zome = fixt!(Zome);
+        let mut dna_def = fixt!(DnaDef);
+        dna_def.zomes = vec![("foo".into(), zome.clone())];
+        let properties = SerializedBytes::try_from(()).unwrap();
+        let mut post_def = fixt!(EntryDef);
+        post_def.id = EntryDefId::App("post".into());
+        post_def.properties = Some(properties.clone());
+        let post_key = EntryDefBufferKey::new(zome, 0.into());
+
+        // Running the entry defs callback would panic
+        let mut ribosome = MockRibosomeT::new();
+        ribosome.expect_dna_def().return_const(dna_def);
+        let ribosome = Arc::new(ribosome);
+
+        let mut handle = MockConductorHandleT::new();
+        handle.expect_get_entry_def().returning(move |key| {
+            if *key == post_key {
+                Some(post_def.clone())
+            } else {
+                None
+            }
+        });
+        let mut host_access = fixt!(ZomeCallHostAccess);
+        host_access.call_zome_handle = Arc::new(CellConductorApi::new(
+            Arc::new(handle),
+            host_access.cell_id.clone(),
+        ));
+        let call_context = Arc::new(CallContext::new("foo".into(), host_access.into()));
+
+        let input = EntryTypePropertiesInput::new(EntryDefId::App("post".into()));
+        assert_eq!(
+            entry_type_properties(ribosome.clone(), call_context.clone(), input)
+                .unwrap()
+                .into_inner(),
+            Some(properties)
+        );
+
+        let input = EntryTypePropertiesInput::new(EntryDefId::App("comment".into()));
+        assert_matches!(
+            entry_type_properties(ribosome, call_context, input),
+            Err(RibosomeError::EntryDefs(_, _))
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "slow_tests")]
+pub mod wasm_test {
+    use super::entry_type_properties;
+    use crate::conductor::api::CellConductorApi;
+    use crate::conductor::handle::MockConductorHandleT;
+    use crate::core::ribosome::error::RibosomeError;
+    use crate::fixt::CallContextFixturator;
+    use crate::fixt::EntryDefsHostAccessFixturator;
+    use crate::fixt::WasmRibosomeFixturator;
+    use crate::fixt::ZomeCallHostAccessFixturator;
+    use ::fixt::prelude::*;
+    use holochain_serialized_bytes::prelude::*;
+    use holochain_wasm_test_utils::TestWasm;
+    use holochain_zome_types::entry_def::EntryDefId;
+    use holochain_zome_types::EntryTypePropertiesInput;
+    use matches::assert_matches;
+    use std::convert::TryInto;
+    use std::sync::Arc;
+
+    /// The properties the zome info test wasm declares on its post entry def
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
+    struct PostLimits {
+        max_length: u32,
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn entry_type_properties_test() {
+        let ribosome =
+            WasmRibosomeFixturator::new(crate::fixt::curve::Zomes(vec![TestWasm::ZomeInfo]))
+                .next()
+                .unwrap();
+        let ribosome = Arc::new(ribosome);
+        let mut call_context = CallContextFixturator::new(Unpredictable).next().unwrap();
+        call_context.zome_name = TestWasm::ZomeInfo.into();
+        // Without the conductor's entry def store the zome is run
+        call_context.host_access = fixt!(EntryDefsHostAccess).into();
+        let call_context = Arc::new(call_context);
+
+        let input = EntryTypePropertiesInput::new(EntryDefId::App("post".into()));
+        let properties: PostLimits =
+            entry_type_properties(ribosome.clone(), call_context.clone(), input)
+                .unwrap()
+                .into_inner()
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(properties, PostLimits { max_length: 140 });
+
+        let input = EntryTypePropertiesInput::new(EntryDefId::App("comment".into()));
+        assert_matches!(
+            entry_type_properties(ribosome, call_context, input),
+            Err(RibosomeError::EntryDefs(_, _))
+        );
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn entry_type_properties_from_wasm_test() {
+        let test_env = holochain_state::test_utils::test_cell_env();
+        let env = test_env.env();
+        let mut workspace =
+            crate::core::workflow::CallZomeWorkspace::new(env.clone().into()).unwrap();
+        crate::core::workflow::fake_genesis(&mut workspace.source_chain)
+            .await
+            .unwrap();
+
+        let workspace_lock = crate::core::workflow::CallZomeWorkspaceLock::new(workspace);
+
+        // The entry defs aren't stored so the zome is run
+        let mut handle = MockConductorHandleT::new();
+        handle.expect_get_entry_def().return_const(None);
+        let mut host_access = fixt!(ZomeCallHostAccess);
+        host_access.workspace = workspace_lock;
+        host_access.call_zome_handle = Arc::new(CellConductorApi::new(
+            Arc::new(handle),
+            host_access.cell_id.clone(),
+        ));
+        let properties: PostLimits =
+            crate::call_test_ribosome!(host_access, TestWasm::ZomeInfo, "post_limits", ());
+        assert_eq!(properties, PostLimits { max_length: 140 });
+    }
 }
@@ -1,14 +1,112 @@
 use crate::core::ribosome::error::RibosomeResult;
 use crate::core::ribosome::CallContext;
 use crate::core::ribosome::RibosomeT;
+use holochain_serialized_bytes::prelude::*;
+use holochain_types::dna::JsonProperties;
 use holochain_zome_types::PropertyInput;
 use holochain_zome_types::PropertyOutput;
 use std::sync::Arc;
 
 pub fn property(
-    _ribosome: Arc<impl RibosomeT>,
+    ribosome: Arc<impl RibosomeT>,
     _call_context: Arc<CallContext>,
-    _input: PropertyInput,
+    input: PropertyInput,
 ) -> RibosomeResult<PropertyOutput> {
-    unimplemented!();
+    let path = input.into_inner();
+    Ok(PropertyOutput::new(property_at_path(
+        &ribosome.dna_def().properties,
+        &path,
+    )?))
+}
+
+/// Look up a value in the dna properties by a path of object keys and array
+/// indexes. An empty path is the whole of the properties.
+/// Returns `None` if there is nothing, or null, at the path.
+pub(crate) fn property_at_path(
+    properties: &SerializedBytes,
+    path: &[String],
+) -> RibosomeResult<Option<SerializedBytes>> {
+    let properties: serde_json::Value = holochain_serialized_bytes::decode(properties.bytes())
+        .map_err(|e| SerializedBytesError::FromBytes(e.to_string()))?;
+    let mut value = &properties;
+    for key in path {
+        let next = match value {
+            serde_json::Value::Object(map) => map.get(key),
+            serde_json::Value::Array(array) => key.parse::<usize>().ok().and_then(|i| array.get(i)),
+            _ => None,
+        };
+        value = match next {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+    }
+    if value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(JsonProperties::new(value.clone()).try_into()?))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::property_at_path;
+    use holochain_serialized_bytes::prelude::*;
+    use holochain_types::dna::JsonProperties;
+    use std::convert::TryInto;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
+    struct Limits {
+        max_posts: u32,
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
+    struct AdminKey(String);
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn property_at_path_test() {
+        let properties: SerializedBytes = JsonProperties::new(serde_json::json!({
+            "limits": { "max_posts": 10 },
+            "admin_keys": ["alice", "bob"],
+            "unset": null,
+        }))
+        .try_into()
+        .unwrap();
+
+        let limits: Limits = property_at_path(&properties, &path(&["limits"]))
+            .unwrap()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(limits, Limits { max_posts: 10 });
+
+        let admin: AdminKey = property_at_path(&properties, &path(&["admin_keys", "1"]))
+            .unwrap()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(admin, AdminKey("bob".to_string()));
+
+        for missing in &[
+            path(&["unset"]),
+            path(&["nothing"]),
+            path(&["admin_keys", "2"]),
+            path(&["admin_keys", "first"]),
+            path(&["limits", "max_posts", "deeper"]),
+        ] {
+            assert_eq!(property_at_path(&properties, missing).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn no_properties_test() {
+        let properties = SerializedBytes::try_from(()).unwrap();
+        assert_eq!(property_at_path(&properties, &[]).unwrap(), None);
+        assert_eq!(
+            property_at_path(&properties, &path(&["limits"])).unwrap(),
+            None
+        );
+    }
 }
@@ -12,11 +12,11 @@ pub fn zome_info(
     _input: ZomeInfoInput,
 ) -> RibosomeResult<ZomeInfoOutput> {
     Ok(ZomeInfoOutput::new(ZomeInfo {
-        dna_name: ribosome.dna_file().dna().name.clone(),
+        dna_name: ribosome.dna_def().name.clone(),
         zome_name: call_context.zome_name.clone(),
-        dna_hash: ribosome.dna_file().dna_hash().clone(), // @TODO
+        dna_hash: ribosome.dna_hash().clone(), // @TODO
         zome_id: ribosome.zome_name_to_id(&call_context.zome_name)?,
-        properties: ribosome.dna_file().dna().properties.clone(),
+        properties: ribosome.dna_def().properties.clone(),
         // @TODO
         // public_token: "".into(),
     }))
@@ -0,0 +1,313 @@
+//! Limits on the resources a single call into a zome's wasm may use.
+//!
+//! Every zome function or callback invocation gets a fresh [WasmMeter].
+//! Host function calls are the points where the guest hands control back
+//! to us, so the meter is checked there and once more when the call returns.
+//! The deadline is additionally enforced by running the guest on one of the
+//! [wasm_threads](super::wasm_threads) and cancelling it when the time runs
+//! out, see [wasm_cancel](super::wasm_cancel).
+//!
+//! The memory limit is written into the module itself by [cap_memory] so the
+//! guest can't grow its memory more than a page past it, whether or not it
+//! calls the host. That page is what tells a guest which went over the limit
+//! from one which is using exactly the limit.
+//!
+//! None of these limits are the same on every conductor, so going over one
+//! only says something about this conductor, never that the data is invalid.
+
+use holochain_wasmer_host::prelude::WasmError;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Limits applied to each call into a zome's wasm.
+/// Omitted limits are not enforced.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WasmLimits {
+    /// The most wall clock time a call may take, in milliseconds.
+    /// This depends on how fast and busy the machine is, so it should
+    /// be set well above what honest zomes need.
+    pub max_duration_ms: Option<u64>,
+    /// The most 64KiB pages of linear memory the guest may grow to
+    pub max_memory_pages: Option<u32>,
+    /// The most host functions a call may invoke
+    pub max_host_calls: Option<u32>,
+}
+
+impl WasmLimits {
+    /// The deadline as a [Duration]
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration_ms.map(Duration::from_millis)
+    }
+}
+
+/// Which limit a call went over
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum WasmLimitExceeded {
+    /// The call ran for longer than this many milliseconds
+    #[error("The wasm call ran for longer than {0}ms")]
+    Duration(u64),
+    /// The guest grew its memory past this many pages
+    #[error("The wasm grew its memory past {0} pages")]
+    MemoryPages(u32),
+    /// The guest made more than this many host calls
+    #[error("The wasm made more than {0} host calls")]
+    HostCalls(u32),
+}
+
+/// Tracks a single call into the wasm against the limits
+pub(crate) struct WasmMeter {
+    limits: WasmLimits,
+    started: Instant,
+    host_calls: AtomicU32,
+    exceeded: Mutex<Option<WasmLimitExceeded>>,
+}
+
+impl WasmMeter {
+    /// Start metering a call now
+    pub(crate) fn new(limits: WasmLimits) -> Self {
+        Self {
+            limits,
+            started: Instant::now(),
+            host_calls: AtomicU32::new(0),
+            exceeded: Mutex::new(None),
+        }
+    }
+
+    /// Count a host call made while the guest had this much memory.
+    /// Returns an error for the guest if this call goes over a limit.
+    pub(crate) fn host_call(&self, memory_pages: u32) -> Result<(), WasmError> {
+        let host_calls = self.host_calls.fetch_add(1, Ordering::SeqCst) + 1;
+        match self.limits.max_host_calls {
+            Some(max) if host_calls > max => self.exceed(WasmLimitExceeded::HostCalls(max))?,
+            _ => (),
+        }
+        match self.limits.max_duration_ms {
+            Some(max) if self.started.elapsed() > Duration::from_millis(max) => {
+                self.exceed(WasmLimitExceeded::Duration(max))?
+            }
+            _ => (),
+        }
+        self.check_memory(memory_pages)
+    }
+
+    /// Check the guest hasn't grown its memory past the limit
+    pub(crate) fn check_memory(&self, memory_pages: u32) -> Result<(), WasmError> {
+        match self.limits.max_memory_pages {
+            Some(max) if memory_pages > max => self.exceed(WasmLimitExceeded::MemoryPages(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The first limit this call went over, if any
+    pub(crate) fn exceeded(&self) -> Option<WasmLimitExceeded> {
+        self.exceeded.lock().clone()
+    }
+
+    fn exceed(&self, limit: WasmLimitExceeded) -> Result<(), WasmError> {
+        let reason = limit.to_string();
+        self.exceeded.lock().get_or_insert(limit);
+        Err(WasmError::Zome(reason))
+    }
+}
+
+/// The id of the memory section in the wasm binary format
+const MEMORY_SECTION: u8 = 5;
+/// The magic number and version every wasm module starts with
+const WASM_PREAMBLE_LEN: usize = 8;
+/// The most pages a wasm memory can have
+const MAX_WASM_PAGES: u32 = 65536;
+
+/// Rewrite the wasm so each memory it defines has a maximum of at most
+/// one page more than `max_pages`. The guest's `memory.grow` then fails
+/// just past the limit, and a guest that went over it is seen to have
+/// more than `max_pages` when the call returns.
+/// Memories the guest imports are provided by the host so aren't changed.
+pub(crate) fn cap_memory(wasm: &[u8], max_pages: u32) -> Result<Vec<u8>, WasmError> {
+    let malformed = || WasmError::Compile("Malformed wasm while capping memory".to_string());
+    if wasm.len() < WASM_PREAMBLE_LEN {
+        return Err(malformed());
+    }
+    let mut out = wasm[..WASM_PREAMBLE_LEN].to_vec();
+    let mut pos = WASM_PREAMBLE_LEN;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let len = read_leb128(wasm, &mut pos).ok_or_else(malformed)? as usize;
+        let end = pos.checked_add(len).filter(|end| *end <= wasm.len());
+        let contents = &wasm[pos..end.ok_or_else(malformed)?];
+        pos += len;
+        if id != MEMORY_SECTION {
+            out.push(id);
+            write_leb128(&mut out, len as u32);
+            out.extend_from_slice(contents);
+            continue;
+        }
+        let mut section = Vec::new();
+        let mut p = 0;
+        let count = read_leb128(contents, &mut p).ok_or_else(malformed)?;
+        write_leb128(&mut section, count);
+        for _ in 0..count {
+            let flags = *contents.get(p).ok_or_else(malformed)?;
+            p += 1;
+            let initial = read_leb128(contents, &mut p).ok_or_else(malformed)?;
+            let max = if flags & 1 == 1 {
+                read_leb128(contents, &mut p).ok_or_else(malformed)?
+            } else {
+                u32::MAX
+            };
+            if initial > max_pages {
+                return Err(WasmError::Compile(
+                    WasmLimitExceeded::MemoryPages(max_pages).to_string(),
+                ));
+            }
+            section.push(flags | 1);
+            write_leb128(&mut section, initial);
+            let cap = max_pages.saturating_add(1).min(MAX_WASM_PAGES);
+            write_leb128(&mut section, max.min(cap));
+        }
+        if p != contents.len() {
+            return Err(malformed());
+        }
+        out.push(id);
+        write_leb128(&mut out, section.len() as u32);
+        out.extend(section);
+    }
+    Ok(out)
+}
+
+pub(super) fn read_leb128(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut result: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+pub(super) fn write_leb128(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_limits_allows_everything() {
+        let meter = WasmMeter::new(WasmLimits::default());
+        for _ in 0..1000 {
+            assert!(meter.host_call(u32::MAX).is_ok());
+        }
+        assert_eq!(meter.exceeded(), None);
+    }
+
+    #[test]
+    fn host_calls_are_limited() {
+        let meter = WasmMeter::new(WasmLimits {
+            max_host_calls: Some(2),
+            ..Default::default()
+        });
+        assert!(meter.host_call(1).is_ok());
+        assert!(meter.host_call(1).is_ok());
+        assert!(meter.host_call(1).is_err());
+        assert_eq!(meter.exceeded(), Some(WasmLimitExceeded::HostCalls(2)));
+    }
+
+    #[test]
+    fn memory_is_limited() {
+        let meter = WasmMeter::new(WasmLimits {
+            max_memory_pages: Some(17),
+            ..Default::default()
+        });
+        assert!(meter.host_call(17).is_ok());
+        assert!(meter.check_memory(18).is_err());
+        // The first limit exceeded is the one reported
+        assert!(meter.host_call(20).is_err());
+        assert_eq!(meter.exceeded(), Some(WasmLimitExceeded::MemoryPages(17)));
+    }
+
+    #[test]
+    fn using_exactly_the_memory_limit_is_allowed() {
+        let meter = WasmMeter::new(WasmLimits {
+            max_memory_pages: Some(17),
+            ..Default::default()
+        });
+        assert!(meter.check_memory(17).is_ok());
+        assert_eq!(meter.exceeded(), None);
+        // The page past the limit the guest can grow into
+        assert!(meter.check_memory(18).is_err());
+        assert_eq!(meter.exceeded(), Some(WasmLimitExceeded::MemoryPages(17)));
+    }
+
+    /// A module with a custom section and a memory section with these limits
+    fn module_with_memory(limits: &[u8]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.extend_from_slice(&[0, 2, 1, b'x']);
+        wasm.extend_from_slice(&[MEMORY_SECTION, limits.len() as u8 + 1, 1]);
+        wasm.extend_from_slice(limits);
+        wasm
+    }
+
+    #[test]
+    fn memory_without_a_maximum_is_capped() {
+        assert_eq!(
+            cap_memory(&module_with_memory(&[0, 17]), 300).unwrap(),
+            // one page past the limit, 301 takes two bytes as leb128
+            module_with_memory(&[1, 17, 0xad, 0x02]),
+        );
+    }
+
+    #[test]
+    fn lower_maximum_is_kept() {
+        assert_eq!(
+            cap_memory(&module_with_memory(&[1, 17, 18]), 20).unwrap(),
+            module_with_memory(&[1, 17, 18]),
+        );
+        assert_eq!(
+            cap_memory(&module_with_memory(&[1, 17, 30]), 20).unwrap(),
+            module_with_memory(&[1, 17, 21]),
+        );
+    }
+
+    #[test]
+    fn initial_memory_over_the_limit_fails() {
+        assert!(cap_memory(&module_with_memory(&[0, 21]), 20).is_err());
+        assert!(cap_memory(&module_with_memory(&[0]), 20).is_err());
+    }
+
+    #[test]
+    fn capped_test_wasm_compiles() {
+        let wasm: holochain_types::dna::wasm::DnaWasm =
+            holochain_wasm_test_utils::TestWasm::Foo.into();
+        let capped = cap_memory(&wasm.code, 256).unwrap();
+        assert!(holochain_wasmer_host::instantiate::module(b"capped", &capped, None).is_ok());
+    }
+
+    #[test]
+    fn deadline_is_checked_on_host_calls() {
+        let meter = WasmMeter::new(WasmLimits {
+            max_duration_ms: Some(0),
+            ..Default::default()
+        });
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(meter.host_call(1).is_err());
+        assert_eq!(meter.exceeded(), Some(WasmLimitExceeded::Duration(0)));
+    }
+}
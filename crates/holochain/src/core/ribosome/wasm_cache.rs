@@ -0,0 +1,289 @@
+//! In-memory caches of compiled wasm shared by every ribosome on the conductor.
+//!
+//! Compiling a zome's wasm is by far the slowest part of calling into it, so
+//! each module is compiled once per wasm hash and memory limit and kept until
+//! it is one of the least recently used when the cache is full. Cells running
+//! the same wasm share the module.
+//!
+//! Every module is instrumented so a call running past its deadline can be
+//! cancelled, see [wasm_cancel](super::wasm_cancel).
+//!
+//! The functions each module exports are listed once and cached alongside it.
+//!
+//! Zome calls can also reuse idle instances of a module. A pooled instance's
+//! host functions read the ribosome, call context and meter from a
+//! [HostContextSlot] which is filled in for each call. An instance's memory
+//! outlives its calls so instances are only reused by the cell they were
+//! created for, and a cell's instances are dropped when it is removed.
+
+use super::{
+    limits::{cap_memory, WasmMeter},
+    wasm_cancel,
+    wasm_ribosome::WasmRibosome,
+    CallContext,
+};
+use holo_hash::WasmHash;
+use holochain_types::cell::CellId;
+use holochain_wasmer_host::prelude::*;
+use holochain_zome_types::zome::FunctionName;
+use parking_lot::{Mutex, RwLock};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use wasmer_runtime_core::module::ExportIndex;
+
+/// Path to the on-disk wasm cache, used to skip compiling on first use
+/// after a restart
+const WASM_CACHE_PATH_ENV: &str = "HC_WASM_CACHE_PATH";
+
+/// How many compiled modules are kept in memory.
+/// Past this the least recently used module is dropped, and is loaded
+/// from the on-disk cache or compiled again the next time it is needed.
+pub const MAX_CACHED_MODULES: usize = 128;
+
+/// A module is compiled for each memory limit its wasm is run with
+/// because the limit is written into the module
+pub type ModuleKey = (WasmHash, Option<u32>);
+
+/// Idle instances are kept for each cell running the module
+type InstanceKey = (CellId, ModuleKey);
+
+/// A compiled module and when it was last used
+struct CachedModule {
+    module: Module,
+    last_used: AtomicU64,
+}
+
+lazy_static::lazy_static! {
+    static ref MODULES: RwLock<HashMap<ModuleKey, CachedModule>> = RwLock::new(HashMap::new());
+    static ref EXPORTED_FNS: RwLock<HashMap<WasmHash, Vec<FunctionName>>> =
+        RwLock::new(HashMap::new());
+    static ref INSTANCES: Mutex<HashMap<InstanceKey, Vec<ZomeInstance>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Counts module uses so the least recently used can be found
+static MODULE_USES: AtomicU64 = AtomicU64::new(0);
+
+fn next_use() -> u64 {
+    MODULE_USES.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Everything a host function needs for the call its instance is running
+#[derive(Clone)]
+pub(crate) struct HostContext {
+    pub(crate) ribosome: Arc<WasmRibosome>,
+    pub(crate) call_context: Arc<CallContext>,
+    pub(crate) meter: Arc<WasmMeter>,
+}
+
+/// Where an instance's host functions find the [HostContext].
+/// It is only filled in while the instance is running a call.
+pub(crate) type HostContextSlot = Arc<RwLock<Option<HostContext>>>;
+
+/// An instance of a zome's wasm along with the slot its imports read from
+pub(crate) struct ZomeInstance {
+    pub(crate) instance: Instance,
+    slot: HostContextSlot,
+}
+
+impl ZomeInstance {
+    /// Instantiate the module with imports that read from this slot
+    pub(crate) fn new(
+        module: &Module,
+        imports: &ImportObject,
+        slot: HostContextSlot,
+    ) -> Result<Self, WasmError> {
+        let instance = module
+            .instantiate(imports)
+            .map_err(|e| WasmError::Compile(e.to_string()))?;
+        Ok(Self { instance, slot })
+    }
+
+    /// Give the host functions the context for the next call
+    pub(crate) fn set_context(&self, host_context: HostContext) {
+        *self.slot.write() = Some(host_context);
+    }
+
+    /// Drop the context of the last call so it isn't kept alive in the pool
+    pub(crate) fn clear_context(&self) {
+        self.slot.write().take();
+    }
+}
+
+/// Get the compiled module for this wasm with its memory capped at
+/// `max_memory_pages`, compiling it on first use
+pub fn module(
+    wasm_hash: &WasmHash,
+    wasm: &[u8],
+    max_memory_pages: Option<u32>,
+) -> Result<Module, WasmError> {
+    let key = (wasm_hash.clone(), max_memory_pages);
+    if let Some(cached) = MODULES.read().get(&key) {
+        cached.last_used.store(next_use(), Ordering::Relaxed);
+        return Ok(cached.module.clone());
+    }
+    // Compile without holding the lock. If two calls race to compile the
+    // same wasm only the first module is kept.
+    // The instrumented module is a different module on disk too.
+    let mut cache_key = wasm_hash.get_raw_39().to_vec();
+    cache_key.extend_from_slice(wasm_cancel::CANCEL_EXPORT.as_bytes());
+    let wasm = match max_memory_pages {
+        Some(max_pages) => {
+            // So is the capped module
+            cache_key.extend_from_slice(&max_pages.to_le_bytes());
+            wasm_cancel::instrument(&cap_memory(wasm, max_pages)?)?
+        }
+        None => wasm_cancel::instrument(wasm)?,
+    };
+    let module = holochain_wasmer_host::instantiate::module(
+        &cache_key,
+        &wasm,
+        std::env::var_os(WASM_CACHE_PATH_ENV),
+    )?;
+    let mut modules = MODULES.write();
+    if !modules.contains_key(&key) && modules.len() >= MAX_CACHED_MODULES {
+        let least_recent = modules
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used.load(Ordering::Relaxed))
+            .map(|(key, _)| key.clone());
+        if let Some(least_recent) = least_recent {
+            modules.remove(&least_recent);
+        }
+    }
+    Ok(modules
+        .entry(key)
+        .or_insert_with(|| CachedModule {
+            module,
+            last_used: AtomicU64::new(next_use()),
+        })
+        .module
+        .clone())
+}
+
+/// List the functions this wasm exports, inspecting its module on first use.
+/// Which of them are callbacks depends on the dna the wasm is in, see
+/// [WasmRibosome::zome_externs].
+pub fn exported_fns(wasm_hash: &WasmHash, wasm: &[u8]) -> Result<Vec<FunctionName>, WasmError> {
+    if let Some(fns) = EXPORTED_FNS.read().get(wasm_hash) {
+        return Ok(fns.clone());
+    }
+    let module = module(wasm_hash, wasm, None)?;
+    let mut fns: Vec<FunctionName> = module
+        .info()
+        .exports
+        .iter()
+        // Only functions can be called, not memories, tables or globals.
+        // Skip the functions the guest and compiler export for the
+        // host's own use.
+        .filter(|(name, index)| matches!(index, ExportIndex::Func(_)) && !name.starts_with("__"))
+        .map(|(name, _)| name.clone().into())
+        .collect();
+    fns.sort();
+    EXPORTED_FNS.write().insert(wasm_hash.clone(), fns.clone());
+    Ok(fns)
+}
+
+/// Take an idle instance of this module that was created for this cell,
+/// if there is one
+pub(crate) fn take_instance(cell_id: &CellId, key: &ModuleKey) -> Option<ZomeInstance> {
+    INSTANCES
+        .lock()
+        .get_mut(&(cell_id.clone(), key.clone()))
+        .and_then(|idle| idle.pop())
+}
+
+/// Return an instance to this cell's pool for reuse.
+/// It is dropped instead if the cell already has `max_idle` idle instances
+/// of this module.
+pub(crate) fn return_instance(
+    cell_id: CellId,
+    key: ModuleKey,
+    instance: ZomeInstance,
+    max_idle: usize,
+) {
+    instance.clear_context();
+    let mut instances = INSTANCES.lock();
+    let idle = instances.entry((cell_id, key)).or_insert_with(Vec::new);
+    if idle.len() < max_idle {
+        idle.push(instance);
+    }
+}
+
+/// Drop the idle instances of a cell which has been removed
+pub fn remove_cell(cell_id: &CellId) {
+    INSTANCES.lock().retain(|(id, _), _| id != cell_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::fixt::prelude::*;
+    use holo_hash::HasHash;
+    use holochain_types::{
+        dna::{
+            wasm::{DnaWasm, DnaWasmHashed},
+            zome::HostFnAccess,
+        },
+        fixt::CellIdFixturator,
+        test_utils::fake_dna_zomes,
+    };
+    use holochain_wasm_test_utils::TestWasm;
+    use holochain_zome_types::zome::ZomeName;
+
+    #[tokio::test(threaded_scheduler)]
+    async fn module_is_cached_by_hash() {
+        let wasm: DnaWasm = TestWasm::Foo.into();
+        let wasm_hash = DnaWasmHashed::from_content(wasm.clone()).await.into_hash();
+
+        module(&wasm_hash, &wasm.code, None).unwrap();
+        assert!(MODULES.read().contains_key(&(wasm_hash.clone(), None)));
+
+        // The cached module is used even if the bytes can't be compiled
+        module(&wasm_hash, &[], None).unwrap();
+
+        // A memory limit needs its own module
+        assert!(module(&wasm_hash, &[], Some(256)).is_err());
+        module(&wasm_hash, &wasm.code, Some(256)).unwrap();
+        assert!(MODULES.read().contains_key(&(wasm_hash, Some(256))));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn only_exported_fns_are_listed() {
+        let wasm: DnaWasm = TestWasm::Foo.into();
+        let wasm_hash = DnaWasmHashed::from_content(wasm.clone()).await.into_hash();
+
+        // The guest's memory and allocation fns aren't listed
+        let fns = exported_fns(&wasm_hash, &wasm.code).unwrap();
+        assert_eq!(fns, vec!["foo".into(), "init".into()]);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn idle_instances_are_kept_per_cell() {
+        let ribosome = WasmRibosome::new(fake_dna_zomes(
+            "",
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        ));
+        let zome_name: ZomeName = TestWasm::Foo.into();
+        let key = (ribosome.wasm_hash(&zome_name).unwrap().clone(), None);
+        let alice = fixt!(CellId);
+        let bob = fixt!(CellId);
+
+        let instance = ribosome.instance(&zome_name, HostFnAccess::none()).unwrap();
+        return_instance(alice.clone(), key.clone(), instance, 1);
+
+        // Another cell can't reuse alice's instance
+        assert!(take_instance(&bob, &key).is_none());
+        assert!(take_instance(&alice, &key).is_some());
+
+        // A removed cell's instances are dropped
+        let instance = ribosome.instance(&zome_name, HostFnAccess::none()).unwrap();
+        return_instance(alice.clone(), key.clone(), instance, 1);
+        remove_cell(&alice);
+        assert!(take_instance(&alice, &key).is_none());
+    }
+}
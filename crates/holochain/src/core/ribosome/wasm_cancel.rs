@@ -0,0 +1,441 @@
+//! Stopping a guest that runs past its deadline.
+//!
+//! A guest that never calls the host can't be interrupted by the host, so
+//! every module is rewritten by [instrument] to check a flag at the start of
+//! each function and each loop iteration and trap once it is set. Any guest
+//! that runs for long has to be looping or calling functions, so it stops
+//! soon after the flag is set. The flag is a global the module exports as
+//! [CANCEL_EXPORT], which a [CancelToken] sets from the thread waiting on
+//! the call when the deadline passes, freeing the guest's wasm thread.
+
+use super::limits::{read_leb128, write_leb128};
+use holochain_wasmer_host::prelude::WasmError;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use wasmer_runtime_core::{export::Export, global::Global, types::Value, Instance};
+
+/// The name the cancel flag global is exported under
+pub const CANCEL_EXPORT: &str = "__hc_cancelled";
+
+const IMPORT_SECTION: u8 = 2;
+const GLOBAL_SECTION: u8 = 6;
+const EXPORT_SECTION: u8 = 7;
+const CODE_SECTION: u8 = 10;
+const WASM_PREAMBLE_LEN: usize = 8;
+
+/// What an import or export is
+const KIND_FUNC: u8 = 0;
+const KIND_TABLE: u8 = 1;
+const KIND_MEMORY: u8 = 2;
+const KIND_GLOBAL: u8 = 3;
+
+const OP_LOOP: u8 = 0x03;
+const OP_GLOBAL_GET: u8 = 0x23;
+const OP_IF: u8 = 0x04;
+const OP_UNREACHABLE: u8 = 0x00;
+const OP_END: u8 = 0x0b;
+const BLOCK_TYPE_EMPTY: u8 = 0x40;
+const TYPE_I32: u8 = 0x7f;
+
+/// Rewrite the wasm to trap when the exported cancel flag is set.
+/// Fails for wasm using instructions this doesn't know.
+pub(crate) fn instrument(wasm: &[u8]) -> Result<Vec<u8>, WasmError> {
+    let malformed = || WasmError::Compile("Malformed wasm while instrumenting".to_string());
+    if wasm.len() < WASM_PREAMBLE_LEN {
+        return Err(malformed());
+    }
+    let mut sections = Vec::new();
+    let mut pos = WASM_PREAMBLE_LEN;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let len = read_leb128(wasm, &mut pos).ok_or_else(malformed)? as usize;
+        let end = pos.checked_add(len).filter(|end| *end <= wasm.len());
+        sections.push((id, &wasm[pos..end.ok_or_else(malformed)?]));
+        pos += len;
+    }
+
+    // The flag goes after the imported and defined globals so no other
+    // global's index changes
+    let mut flag_index = 0;
+    for (id, contents) in &sections {
+        match *id {
+            IMPORT_SECTION => flag_index += imported_globals(contents).ok_or_else(malformed)?,
+            GLOBAL_SECTION => flag_index += read_leb128(contents, &mut 0).ok_or_else(malformed)?,
+            _ => (),
+        }
+    }
+    let flag_global = [TYPE_I32, 1, 0x41, 0, OP_END];
+    let mut flag_export = Vec::new();
+    write_leb128(&mut flag_export, CANCEL_EXPORT.len() as u32);
+    flag_export.extend_from_slice(CANCEL_EXPORT.as_bytes());
+    flag_export.push(KIND_GLOBAL);
+    write_leb128(&mut flag_export, flag_index);
+    let mut check = vec![OP_GLOBAL_GET];
+    write_leb128(&mut check, flag_index);
+    check.extend_from_slice(&[OP_IF, BLOCK_TYPE_EMPTY, OP_UNREACHABLE, OP_END]);
+
+    let mut out = wasm[..WASM_PREAMBLE_LEN].to_vec();
+    let mut has_globals = false;
+    let mut has_exports = false;
+    for (id, contents) in sections {
+        // Custom sections can go anywhere, the others are in order
+        if id != 0 {
+            if id > GLOBAL_SECTION && !has_globals {
+                push_section(&mut out, GLOBAL_SECTION, &append_entry(&[0], &flag_global)?);
+                has_globals = true;
+            }
+            if id > EXPORT_SECTION && !has_exports {
+                push_section(&mut out, EXPORT_SECTION, &append_entry(&[0], &flag_export)?);
+                has_exports = true;
+            }
+        }
+        match id {
+            GLOBAL_SECTION => {
+                push_section(&mut out, id, &append_entry(contents, &flag_global)?);
+                has_globals = true;
+            }
+            EXPORT_SECTION => {
+                push_section(&mut out, id, &append_entry(contents, &flag_export)?);
+                has_exports = true;
+            }
+            CODE_SECTION => push_section(&mut out, id, &instrument_code(contents, &check)?),
+            _ => push_section(&mut out, id, contents),
+        }
+    }
+    if !has_globals {
+        push_section(&mut out, GLOBAL_SECTION, &append_entry(&[0], &flag_global)?);
+    }
+    if !has_exports {
+        push_section(&mut out, EXPORT_SECTION, &append_entry(&[0], &flag_export)?);
+    }
+    Ok(out)
+}
+
+fn push_section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    out.push(id);
+    write_leb128(out, contents.len() as u32);
+    out.extend_from_slice(contents);
+}
+
+/// Add an entry to the end of a section that is a vector of entries
+fn append_entry(contents: &[u8], entry: &[u8]) -> Result<Vec<u8>, WasmError> {
+    let mut pos = 0;
+    let count = read_leb128(contents, &mut pos)
+        .ok_or_else(|| WasmError::Compile("Malformed wasm section".to_string()))?;
+    let mut out = Vec::new();
+    write_leb128(&mut out, count + 1);
+    out.extend_from_slice(&contents[pos..]);
+    out.extend_from_slice(entry);
+    Ok(out)
+}
+
+/// Count the globals in an import section
+fn imported_globals(contents: &[u8]) -> Option<u32> {
+    let mut pos = 0;
+    let mut globals = 0;
+    for _ in 0..read_leb128(contents, &mut pos)? {
+        // The module and field names
+        for _ in 0..2 {
+            let len = read_leb128(contents, &mut pos)? as usize;
+            pos = pos.checked_add(len)?;
+        }
+        let kind = *contents.get(pos)?;
+        pos += 1;
+        match kind {
+            KIND_FUNC => {
+                read_leb128(contents, &mut pos)?;
+            }
+            KIND_TABLE | KIND_MEMORY => {
+                if kind == KIND_TABLE {
+                    // The element type
+                    pos += 1;
+                }
+                let flags = *contents.get(pos)?;
+                pos += 1;
+                read_leb128(contents, &mut pos)?;
+                if flags & 1 == 1 {
+                    read_leb128(contents, &mut pos)?;
+                }
+            }
+            KIND_GLOBAL => {
+                // The value type and mutability
+                pos += 2;
+                globals += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(globals)
+}
+
+/// Put the check at the start of every function body and loop
+fn instrument_code(contents: &[u8], check: &[u8]) -> Result<Vec<u8>, WasmError> {
+    let malformed = || WasmError::Compile("Malformed wasm code while instrumenting".to_string());
+    let mut pos = 0;
+    let count = read_leb128(contents, &mut pos).ok_or_else(malformed)?;
+    let mut out = Vec::new();
+    write_leb128(&mut out, count);
+    for _ in 0..count {
+        let len = read_leb128(contents, &mut pos).ok_or_else(malformed)? as usize;
+        let end = pos.checked_add(len).filter(|end| *end <= contents.len());
+        let body = &contents[pos..end.ok_or_else(malformed)?];
+        pos += len;
+
+        let mut p = 0;
+        for _ in 0..read_leb128(body, &mut p).ok_or_else(malformed)? {
+            // Each run of locals is a count and a value type
+            read_leb128(body, &mut p).ok_or_else(malformed)?;
+            p += 1;
+        }
+        let mut new_body = body[..p.min(body.len())].to_vec();
+        new_body.extend_from_slice(check);
+        while p < body.len() {
+            let start = p;
+            let op = body[p];
+            p += 1;
+            skip_immediates(op, body, &mut p).ok_or_else(|| {
+                WasmError::Compile(format!("Unsupported wasm instruction {:#04x}", op))
+            })?;
+            new_body.extend_from_slice(body.get(start..p).ok_or_else(malformed)?);
+            if op == OP_LOOP {
+                new_body.extend_from_slice(check);
+            }
+        }
+        write_leb128(&mut out, new_body.len() as u32);
+        out.extend(new_body);
+    }
+    if pos != contents.len() {
+        return Err(malformed());
+    }
+    Ok(out)
+}
+
+/// Move past the immediates of an instruction
+fn skip_immediates(op: u8, body: &[u8], pos: &mut usize) -> Option<()> {
+    let u32_imm = |pos: &mut usize| read_leb128(body, pos).map(|_| ());
+    match op {
+        // control instructions without immediates, parametric instructions
+        // and numeric instructions
+        0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x1a | 0x1b | 0x45..=0xc4 => Some(()),
+        // block, loop and if take a block type, which is an empty marker,
+        // a value type or a signed type index
+        0x02..=0x04 => skip_signed_leb128(body, pos),
+        // br, br_if, call, local and global instructions, table.get and table.set
+        0x0c | 0x0d | 0x10 | 0x20..=0x26 => u32_imm(pos),
+        // br_table
+        0x0e => {
+            for _ in 0..read_leb128(body, pos)? {
+                u32_imm(pos)?;
+            }
+            u32_imm(pos)
+        }
+        // call_indirect takes a type index and a table index
+        0x11 => {
+            u32_imm(pos)?;
+            u32_imm(pos)
+        }
+        // typed select
+        0x1c => {
+            let count = read_leb128(body, pos)? as usize;
+            *pos = pos.checked_add(count)?;
+            Some(())
+        }
+        // loads and stores take an alignment and an offset
+        0x28..=0x3e => {
+            u32_imm(pos)?;
+            u32_imm(pos)
+        }
+        // memory.size and memory.grow
+        0x3f | 0x40 => u32_imm(pos),
+        // i32.const and i64.const
+        0x41 | 0x42 => skip_signed_leb128(body, pos),
+        // f32.const and f64.const
+        0x43 => {
+            *pos = pos.checked_add(4)?;
+            Some(())
+        }
+        0x44 => {
+            *pos = pos.checked_add(8)?;
+            Some(())
+        }
+        // ref.null takes a reference type
+        0xd0 => {
+            *pos = pos.checked_add(1)?;
+            Some(())
+        }
+        // ref.is_null
+        0xd1 => Some(()),
+        // ref.func
+        0xd2 => u32_imm(pos),
+        // saturating truncation, bulk memory and table instructions
+        0xfc => match read_leb128(body, pos)? {
+            0..=7 => Some(()),
+            // memory.init, memory.copy, table.init and table.copy
+            8 | 10 | 12 | 14 => {
+                u32_imm(pos)?;
+                u32_imm(pos)
+            }
+            // data.drop, memory.fill, elem.drop, table.grow, table.size and table.fill
+            9 | 11 | 13 | 15..=17 => u32_imm(pos),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn skip_signed_leb128(bytes: &[u8], pos: &mut usize) -> Option<()> {
+    for _ in 0..10 {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        if byte & 0x80 == 0 {
+            return Some(());
+        }
+    }
+    None
+}
+
+/// Lets the thread waiting on a call stop the guest running it
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(Arc<Mutex<CancelState>>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: bool,
+    flag: Option<Global>,
+}
+
+impl CancelToken {
+    /// Watch the instance about to run the call, clearing a flag left set
+    /// by an earlier call. Returns false if the call is already cancelled.
+    pub(crate) fn watch(&self, instance: &Instance) -> bool {
+        let mut state = self.0.lock();
+        let flag = instance
+            .exports()
+            .find(|(name, _)| name == CANCEL_EXPORT)
+            .and_then(|(_, export)| match export {
+                Export::Global(global) => Some(global),
+                _ => None,
+            });
+        if let Some(flag) = &flag {
+            flag.set(Value::I32(state.cancelled as i32));
+        }
+        state.flag = flag;
+        !state.cancelled
+    }
+
+    /// Stop the guest at its next function call or loop iteration
+    pub(crate) fn cancel(&self) {
+        let mut state = self.0.lock();
+        state.cancelled = true;
+        if let Some(flag) = &state.flag {
+            flag.set(Value::I32(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_types::dna::wasm::DnaWasm;
+    use holochain_wasm_test_utils::TestWasm;
+
+    /// A module exporting `f`, which takes no arguments and runs `code`
+    fn module_with_fn(code: &[u8]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // type 0: [] -> []
+        wasm.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+        // function 0 has type 0
+        wasm.extend_from_slice(&[3, 2, 1, 0]);
+        // export function 0 as "f"
+        wasm.extend_from_slice(&[EXPORT_SECTION, 5, 1, 1, b'f', 0, 0]);
+        // one body without locals
+        let body_len = code.len() as u8 + 1;
+        wasm.extend_from_slice(&[CODE_SECTION, body_len + 2, 1, body_len, 0]);
+        wasm.extend_from_slice(code);
+        wasm
+    }
+
+    fn check(index: u8) -> Vec<u8> {
+        vec![
+            OP_GLOBAL_GET,
+            index,
+            OP_IF,
+            BLOCK_TYPE_EMPTY,
+            OP_UNREACHABLE,
+            OP_END,
+        ]
+    }
+
+    #[test]
+    fn functions_and_loops_check_the_flag() {
+        // loop br 0 end end
+        let wasm = module_with_fn(&[OP_LOOP, BLOCK_TYPE_EMPTY, 0x0c, 0, OP_END, OP_END]);
+        let instrumented = instrument(&wasm).unwrap();
+
+        let mut code = check(0);
+        code.extend_from_slice(&[OP_LOOP, BLOCK_TYPE_EMPTY]);
+        code.extend(check(0));
+        code.extend_from_slice(&[0x0c, 0, OP_END, OP_END]);
+        let mut expected = b"\0asm\x01\0\0\0".to_vec();
+        expected.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+        expected.extend_from_slice(&[3, 2, 1, 0]);
+        expected.extend_from_slice(&[GLOBAL_SECTION, 6, 1, TYPE_I32, 1, 0x41, 0, OP_END]);
+        let mut exports = vec![2, 1, b'f', 0, 0, CANCEL_EXPORT.len() as u8];
+        exports.extend_from_slice(CANCEL_EXPORT.as_bytes());
+        exports.extend_from_slice(&[KIND_GLOBAL, 0]);
+        expected.extend_from_slice(&[EXPORT_SECTION, exports.len() as u8]);
+        expected.extend(exports);
+        expected.extend_from_slice(&[CODE_SECTION, code.len() as u8 + 3, 1]);
+        expected.extend_from_slice(&[code.len() as u8 + 1, 0]);
+        expected.extend(code);
+        assert_eq!(instrumented, expected);
+    }
+
+    #[test]
+    fn cancelling_stops_an_endless_loop() {
+        // loop br 0 end end
+        let wasm = module_with_fn(&[OP_LOOP, BLOCK_TYPE_EMPTY, 0x0c, 0, OP_END, OP_END]);
+        let wasm = instrument(&wasm).unwrap();
+        let cancel = CancelToken::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn({
+            let cancel = cancel.clone();
+            move || {
+                let module =
+                    holochain_wasmer_host::instantiate::module(b"endless", &wasm, None).unwrap();
+                let instance = module
+                    .instantiate(&wasmer_runtime_core::import::ImportObject::new())
+                    .unwrap();
+                cancel.watch(&instance);
+                tx.send(instance.call("f", &[]).is_err()).ok();
+            }
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        cancel.cancel();
+        assert_eq!(
+            rx.recv_timeout(std::time::Duration::from_secs(10)),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn unknown_instructions_fail() {
+        // a simd instruction
+        assert!(instrument(&module_with_fn(&[0xfd, 0, OP_END])).is_err());
+    }
+
+    #[test]
+    fn instrumented_test_wasm_compiles() {
+        let wasm: DnaWasm = TestWasm::Foo.into();
+        let instrumented = instrument(&wasm.code).unwrap();
+        let module =
+            holochain_wasmer_host::instantiate::module(b"instrumented", &instrumented, None)
+                .unwrap();
+        assert!(module
+            .info()
+            .exports
+            .iter()
+            .any(|(name, _)| name == CANCEL_EXPORT));
+    }
+}
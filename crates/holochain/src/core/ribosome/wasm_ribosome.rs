@@ -1,11 +1,14 @@
 use super::{
     guest_callback::{
-        entry_defs::EntryDefsHostAccess, init::InitHostAccess,
+        entry_defs::EntryDefsHostAccess, init::InitHostAccess, is_callback,
         migrate_agent::MigrateAgentHostAccess, post_commit::PostCommitHostAccess,
         validate::ValidateHostAccess, validation_package::ValidationPackageHostAccess,
     },
     host_fn::get_agent_activity::get_agent_activity,
-    HostAccess, ZomeCallHostAccess,
+    limits::{WasmLimitExceeded, WasmLimits, WasmMeter},
+    wasm_cache::{self, HostContext, HostContextSlot, ZomeInstance},
+    wasm_cancel::CancelToken,
+    wasm_threads, HostAccess, ZomeCallHostAccess,
 };
 use crate::core::ribosome::error::RibosomeError;
 use crate::core::ribosome::error::RibosomeResult;
@@ -26,6 +29,7 @@ use crate::core::ribosome::guest_callback::validation_package::ValidationPackage
 use crate::core::ribosome::guest_callback::validation_package::ValidationPackageResult;
 use crate::core::ribosome::guest_callback::CallIterator;
 use crate::core::ribosome::host_fn::agent_info::agent_info;
+use crate::core::ribosome::host_fn::app_cells::app_cells;
 use crate::core::ribosome::host_fn::call::call;
 use crate::core::ribosome::host_fn::call_remote::call_remote;
 use crate::core::ribosome::host_fn::capability_claims::capability_claims;
@@ -39,6 +43,7 @@ use crate::core::ribosome::host_fn::delete::delete;
 use crate::core::ribosome::host_fn::delete_link::delete_link;
 use crate::core::ribosome::host_fn::emit_signal::emit_signal;
 use crate::core::ribosome::host_fn::encrypt::encrypt;
+use crate::core::ribosome::host_fn::entry_type_properties::entry_type_properties;
 use crate::core::ribosome::host_fn::get::get;
 use crate::core::ribosome::host_fn::get_details::get_details;
 use crate::core::ribosome::host_fn::get_link_details::get_link_details;
@@ -61,13 +66,15 @@ use crate::core::ribosome::RibosomeT;
 use crate::core::ribosome::ZomeCallInvocation;
 use crate::core::ribosome::ZomesToInvoke;
 use fallible_iterator::FallibleIterator;
+use holo_hash::DnaHash;
 use holochain_types::dna::DnaError;
 use holochain_types::dna::{
-    zome::{HostFnAccess, Permission},
-    DnaFile,
+    wasm::DnaWasm,
+    zome::{HostFnAccess, Permission, ZomeExterns},
+    DnaDef, DnaFile, WasmHash, Wasms,
 };
 use holochain_wasmer_host::prelude::*;
-use holochain_zome_types::entry_def::EntryDefsCallbackResult;
+use holochain_zome_types::entry_def::{EntryDefId, EntryDefsCallbackResult};
 use holochain_zome_types::init::InitCallbackResult;
 use holochain_zome_types::migrate_agent::MigrateAgentCallbackResult;
 use holochain_zome_types::post_commit::PostCommitCallbackResult;
@@ -78,77 +85,158 @@ use holochain_zome_types::zome::FunctionName;
 use holochain_zome_types::zome::ZomeName;
 use holochain_zome_types::CallbackResult;
 use holochain_zome_types::ZomeCallResponse;
-use holochain_zome_types::{header::ZomeId, ExternOutput};
+use holochain_zome_types::{header::ZomeId, ExternInput, ExternOutput};
 use std::sync::Arc;
 
-/// Path to the wasm cache path
-const WASM_CACHE_PATH_ENV: &str = "HC_WASM_CACHE_PATH";
-
 /// The only WasmRibosome is a Wasm ribosome.
 /// note that this is cloned on every invocation so keep clones cheap!
-// TODO: maackle:
-//       how can this possibly be cheap to clone when it contains wasm bytecode?
+/// The wasm bytecode is behind an `Arc` and compiled modules live in the
+/// shared [wasm_cache], so a clone is little more than the `DnaDef`.
 #[derive(Clone, Debug)]
 pub struct WasmRibosome {
-    // NOTE - Currently taking a full DnaFile here.
-    //      - It would be an optimization to pre-ensure the WASM bytecode
-    //      - is already in the wasm cache, and only include the DnaDef portion
-    //      - here in the ribosome.
-    pub dna_file: DnaFile,
+    pub dna_def: DnaDef,
+    pub dna_hash: DnaHash,
+    /// The wasm of every zome in the dna, by hash
+    pub wasms: Arc<Wasms>,
+    /// Limits applied to every call into the wasm
+    pub limits: WasmLimits,
+    /// How many idle instances of each zome to keep for reuse by zome calls.
+    /// Zero disables the pool.
+    pub max_pooled_instances: usize,
 }
 
 impl WasmRibosome {
     /// Create a new instance
     pub fn new(dna_file: DnaFile) -> Self {
-        Self { dna_file }
+        let DnaFile {
+            dna,
+            dna_hash,
+            code,
+        } = dna_file;
+        Self {
+            dna_def: dna,
+            dna_hash,
+            wasms: Arc::new(code),
+            limits: WasmLimits::default(),
+            max_pooled_instances: 0,
+        }
     }
 
-    pub fn module(&self, call_context: CallContext) -> RibosomeResult<Module> {
-        let zome_name: ZomeName = call_context.zome_name();
-        let wasm: Arc<Vec<u8>> = self.dna_file.get_wasm_for_zome(&zome_name)?.code();
-        Ok(holochain_wasmer_host::instantiate::module(
-            &self.wasm_cache_key(&zome_name)?,
-            &wasm,
-            std::env::var_os(WASM_CACHE_PATH_ENV),
-        )?)
+    /// Apply these limits to every call into the wasm
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
-    pub fn wasm_cache_key(&self, zome_name: &ZomeName) -> Result<&[u8], DnaError> {
-        // TODO: make this actually the hash of the wasm once we can do that
-        // watch out for cache misses in the tests that make things slooow if you change this!
-        // format!("{}{}", &self.dna.dna_hash(), zome_name).into_bytes()
-        Ok(self
-            .dna_file
-            .dna()
-            .get_zome(zome_name)?
-            .wasm_hash
-            .get_raw_39())
+    /// Reuse up to this many idle instances of each zome for zome calls
+    pub fn with_instance_pool(mut self, max_pooled_instances: usize) -> Self {
+        self.max_pooled_instances = max_pooled_instances;
+        self
     }
 
-    pub fn instance(&self, call_context: CallContext) -> RibosomeResult<Instance> {
-        let zome_name: ZomeName = call_context.zome_name();
-        let wasm: Arc<Vec<u8>> = self.dna_file.get_wasm_for_zome(&zome_name)?.code();
-        let imports: ImportObject = Self::imports(self, call_context);
-        Ok(holochain_wasmer_host::instantiate::instantiate(
-            self.wasm_cache_key(&zome_name)?,
-            &wasm,
-            &imports,
-            std::env::var_os(WASM_CACHE_PATH_ENV),
+    /// The wasm bytecode of this zome
+    pub fn wasm(&self, zome_name: &ZomeName) -> Result<&DnaWasm, DnaError> {
+        let wasm_hash = self.wasm_hash(zome_name)?;
+        self.wasms
+            .get(wasm_hash)
+            .ok_or_else(|| DnaError::InvalidWasmHash)
+    }
+
+    /// The compiled module for this zome, from the shared cache
+    pub fn module(&self, zome_name: &ZomeName) -> RibosomeResult<Module> {
+        let wasm = self.wasm(zome_name)?;
+        Ok(wasm_cache::module(
+            self.wasm_hash(zome_name)?,
+            &wasm.code,
+            self.limits.max_memory_pages,
         )?)
     }
 
-    fn imports(&self, call_context: CallContext) -> ImportObject {
-        let host_fn_access = (&call_context.host_access()).into();
+    /// The hash of this zome's wasm, which modules are cached by
+    pub fn wasm_hash(&self, zome_name: &ZomeName) -> Result<&WasmHash, DnaError> {
+        Ok(&self.dna_def.get_zome(zome_name)?.wasm_hash)
+    }
+
+    /// The functions this zome's wasm exports, split into the callbacks
+    /// this dna calls the zome by and the zome functions
+    pub fn zome_externs(&self, zome_name: &ZomeName) -> RibosomeResult<ZomeExterns> {
+        let wasm = self.wasm(zome_name)?;
+        let fns = wasm_cache::exported_fns(self.wasm_hash(zome_name)?, &wasm.code)?;
+        let zome_id = self.zome_name_to_id(zome_name)?;
+        let entry_def_ids = self.entry_def_ids(zome_name, &fns)?;
+        let (callbacks, zome_fns) = fns
+            .into_iter()
+            .partition(|name| is_callback(name.as_ref(), zome_id, &entry_def_ids));
+        Ok(ZomeExterns {
+            zome_fns,
+            callbacks,
+        })
+    }
+
+    /// The ids of the entry types this zome defines, which some of its
+    /// `validate` callbacks are named after
+    fn entry_def_ids(
+        &self,
+        zome_name: &ZomeName,
+        exported_fns: &[FunctionName],
+    ) -> RibosomeResult<Vec<String>> {
+        let to_call: FunctionName = "entry_defs".into();
+        if !exported_fns.contains(&to_call) {
+            return Ok(Vec::new());
+        }
+        let output = self.maybe_call(
+            EntryDefsHostAccess.into(),
+            &EntryDefsInvocation,
+            zome_name,
+            &to_call,
+        )?;
+        match output.map(EntryDefsCallbackResult::from) {
+            Some(EntryDefsCallbackResult::Defs(defs)) => Ok(defs
+                .into_iter()
+                .filter_map(|def| match def.id {
+                    EntryDefId::App(id) => Some(id),
+                    _ => None,
+                })
+                .collect()),
+            Some(EntryDefsCallbackResult::Err(e)) => {
+                Err(RibosomeError::EntryDefs(zome_name.clone(), e))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The functions each zome's wasm exports, in zome order
+    pub fn list_zome_externs(&self) -> RibosomeResult<Vec<(ZomeName, ZomeExterns)>> {
+        self.dna_def
+            .zomes
+            .iter()
+            .map(|(zome_name, _)| Ok((zome_name.clone(), self.zome_externs(zome_name)?)))
+            .collect()
+    }
 
-        // it is important that WasmRibosome and ZomeCallInvocation are cheap to clone here
-        let self_arc = std::sync::Arc::new((*self).clone());
-        let call_context_arc = std::sync::Arc::new(call_context);
+    /// Create a new instance of this zome with imports for this access
+    pub(crate) fn instance(
+        &self,
+        zome_name: &ZomeName,
+        host_fn_access: HostFnAccess,
+    ) -> RibosomeResult<ZomeInstance> {
+        let module = self.module(zome_name)?;
+        let slot: HostContextSlot = Default::default();
+        let imports: ImportObject = Self::imports(host_fn_access, slot.clone());
+        Ok(ZomeInstance::new(&module, &imports, slot)?)
+    }
 
+    fn imports(host_fn_access: HostFnAccess, slot: HostContextSlot) -> ImportObject {
         macro_rules! invoke_host_function {
             ( $host_function:ident ) => {{
-                let closure_self_arc = std::sync::Arc::clone(&self_arc);
-                let closure_call_context_arc = std::sync::Arc::clone(&call_context_arc);
+                let closure_slot = std::sync::Arc::clone(&slot);
                 move |ctx: &mut Ctx, guest_allocation_ptr: GuestPtr| -> Result<Len, WasmError> {
+                    // Only hold the lock long enough to clone the Arcs,
+                    // host functions may call back into other instances.
+                    let host_context = closure_slot.read().clone().ok_or_else(|| {
+                        WasmError::Zome("Host function called outside of a call".to_string())
+                    })?;
+                    host_context.meter.host_call(ctx.memory(0).size().0)?;
                     let input = $crate::holochain_wasmer_host::guest::from_guest_ptr(
                         ctx,
                         guest_allocation_ptr,
@@ -156,13 +244,9 @@ impl WasmRibosome {
                     // this will be run in a tokio background thread
                     // designed for doing blocking work.
                     let output_sb: holochain_wasmer_host::prelude::SerializedBytes =
-                        $host_function(
-                            std::sync::Arc::clone(&closure_self_arc),
-                            std::sync::Arc::clone(&closure_call_context_arc),
-                            input,
-                        )
-                        .map_err(|e| WasmError::Zome(format!("{:?}", e)))?
-                        .try_into()?;
+                        $host_function(host_context.ribosome, host_context.call_context, input)
+                            .map_err(|e| WasmError::Zome(format!("{:?}", e)))?
+                            .try_into()?;
 
                     Ok($crate::holochain_wasmer_host::import::set_context_data(
                         ctx, output_sb,
@@ -213,9 +297,17 @@ impl WasmRibosome {
         {
             ns.insert("__zome_info", func!(invoke_host_function!(zome_info)));
             ns.insert("__property", func!(invoke_host_function!(property)));
+            ns.insert(
+                "__entry_type_properties",
+                func!(invoke_host_function!(entry_type_properties)),
+            );
         } else {
             ns.insert("__zome_info", func!(invoke_host_function!(unreachable)));
             ns.insert("__property", func!(invoke_host_function!(unreachable)));
+            ns.insert(
+                "__entry_type_properties",
+                func!(invoke_host_function!(unreachable)),
+            );
         }
 
         if let HostFnAccess {
@@ -314,6 +406,7 @@ impl WasmRibosome {
         } = host_fn_access
         {
             ns.insert("__call", func!(invoke_host_function!(call)));
+            ns.insert("__app_cells", func!(invoke_host_function!(app_cells)));
             ns.insert("__create", func!(invoke_host_function!(create)));
             ns.insert("__emit_signal", func!(invoke_host_function!(emit_signal)));
             ns.insert("__create_link", func!(invoke_host_function!(create_link)));
@@ -323,6 +416,7 @@ impl WasmRibosome {
             ns.insert("__schedule", func!(invoke_host_function!(schedule)));
         } else {
             ns.insert("__call", func!(invoke_host_function!(unreachable)));
+            ns.insert("__app_cells", func!(invoke_host_function!(unreachable)));
             ns.insert("__create", func!(invoke_host_function!(unreachable)));
             ns.insert("__emit_signal", func!(invoke_host_function!(unreachable)));
             ns.insert("__create_link", func!(invoke_host_function!(unreachable)));
@@ -337,6 +431,110 @@ impl WasmRibosome {
     }
 }
 
+impl WasmRibosome {
+    /// Instantiate the zome and call this function in it, checking the
+    /// result against the limits. The call stops early if the token is
+    /// cancelled.
+    fn call_metered(
+        &self,
+        call_context: CallContext,
+        to_call: &FunctionName,
+        input: ExternInput,
+        cancel: &CancelToken,
+    ) -> RibosomeResult<ExternOutput> {
+        let meter = Arc::new(WasmMeter::new(self.limits.clone()));
+        let zome_name = call_context.zome_name();
+        let module_key = (
+            self.wasm_hash(&zome_name)?.clone(),
+            self.limits.max_memory_pages,
+        );
+        // Only zome calls are pooled. Callbacks such as validation
+        // must not see state left behind by an earlier call, and a cell
+        // must not see state left behind by another cell's calls.
+        let pool_cell_id = match &call_context.host_access {
+            HostAccess::ZomeCall(access) if self.max_pooled_instances > 0 => {
+                Some(access.cell_id.clone())
+            }
+            _ => None,
+        };
+        let host_fn_access: HostFnAccess = (&call_context.host_access).into();
+        let idle = pool_cell_id
+            .as_ref()
+            .and_then(|cell_id| wasm_cache::take_instance(cell_id, &module_key));
+        let mut instance = match idle {
+            Some(instance) => instance,
+            None => self.instance(&zome_name, host_fn_access)?,
+        };
+        if !cancel.watch(&instance.instance) {
+            return Err(RibosomeError::WasmLimitExceeded(
+                WasmLimitExceeded::Duration(self.limits.max_duration_ms.unwrap_or_default()),
+            ));
+        }
+        instance.set_context(HostContext {
+            ribosome: Arc::new(self.clone()),
+            call_context: Arc::new(call_context),
+            meter: meter.clone(),
+        });
+        let result: Result<ExternOutput, WasmError> =
+            holochain_wasmer_host::guest::call(&mut instance.instance, to_call.as_ref(), input);
+        // The guest can grow its memory one page past the limit, so one
+        // that ran out of memory has more than the limit whether or not
+        // it handled failing to grow further.
+        meter
+            .check_memory(instance.instance.context().memory(0).size().0)
+            .ok();
+        match meter.exceeded() {
+            Some(limit) => Err(RibosomeError::WasmLimitExceeded(limit)),
+            None => {
+                // An instance that errored may be in a bad state so only
+                // instances that completed their call go back in the pool.
+                if let (Some(cell_id), true) = (pool_cell_id, result.is_ok()) {
+                    wasm_cache::return_instance(
+                        cell_id,
+                        module_key,
+                        instance,
+                        self.max_pooled_instances,
+                    );
+                }
+                Ok(result?)
+            }
+        }
+    }
+
+    /// Run the call on a wasm thread and cancel it at the deadline.
+    /// A guest that is stuck without calling the host stops at its next
+    /// function call or loop iteration, freeing its thread.
+    fn call_with_deadline(
+        &self,
+        call_context: CallContext,
+        to_call: FunctionName,
+        input: ExternInput,
+        max_duration: std::time::Duration,
+    ) -> RibosomeResult<ExternOutput> {
+        let ribosome = self.clone();
+        let cancel = CancelToken::default();
+        let call_cancel = cancel.clone();
+        // Host functions need the tokio runtime to block on
+        let runtime = tokio::runtime::Handle::try_current().ok();
+        let call = move || {
+            let call = || ribosome.call_metered(call_context, &to_call, input, &call_cancel);
+            match runtime {
+                Some(runtime) => runtime.enter(call),
+                None => call(),
+            }
+        };
+        match wasm_threads::run_with_timeout(call, max_duration) {
+            Some(result) => result,
+            None => {
+                cancel.cancel();
+                Err(RibosomeError::WasmLimitExceeded(
+                    WasmLimitExceeded::Duration(max_duration.as_millis() as u64),
+                ))
+            }
+        }
+    }
+}
+
 macro_rules! do_callback {
     ( $self:ident, $access:ident, $invocation:ident, $callback_result:ty ) => {{
         let mut results: Vec<(ZomeName, $callback_result)> = Vec::new();
@@ -358,15 +556,18 @@ macro_rules! do_callback {
 }
 
 impl RibosomeT for WasmRibosome {
-    fn dna_file(&self) -> &DnaFile {
-        &self.dna_file
+    fn dna_def(&self) -> &DnaDef {
+        &self.dna_def
+    }
+
+    fn dna_hash(&self) -> &DnaHash {
+        &self.dna_hash
     }
 
     fn zomes_to_invoke(&self, zomes_to_invoke: ZomesToInvoke) -> Vec<ZomeName> {
         match zomes_to_invoke {
             ZomesToInvoke::All => self
-                .dna_file
-                .dna
+                .dna_def
                 .zomes
                 .iter()
                 .map(|(zome_name, _)| zome_name.clone())
@@ -377,8 +578,7 @@ impl RibosomeT for WasmRibosome {
 
     fn zome_name_to_id(&self, zome_name: &ZomeName) -> RibosomeResult<ZomeId> {
         match self
-            .dna_file()
-            .dna
+            .dna_def
             .zomes
             .iter()
             .position(|(name, _)| name == zome_name)
@@ -401,22 +601,20 @@ impl RibosomeT for WasmRibosome {
             zome_name: zome_name.clone(),
             host_access,
         };
-        let module = self.module(call_context.clone())?;
+        let module = self.module(zome_name)?;
 
         if module.info().exports.contains_key(to_call.as_ref()) {
             // there is a callback to_call and it is implemented in the wasm
-            // it is important to fully instantiate this (e.g. don't try to use the module above)
-            // because it builds guards against memory leaks and handles imports correctly
-            let mut instance = self.instance(call_context)?;
-
-            let result: ExternOutput = holochain_wasmer_host::guest::call(
-                &mut instance,
-                to_call.as_ref(),
-                // be aware of this clone!
-                // the whole invocation is cloned!
-                // @todo - is this a problem for large payloads like entries?
-                invocation.to_owned().host_input()?,
-            )?;
+            // be aware of this clone!
+            // the whole invocation is cloned!
+            // @todo - is this a problem for large payloads like entries?
+            let input = invocation.to_owned().host_input()?;
+            let result = match self.limits.max_duration() {
+                Some(max_duration) => {
+                    self.call_with_deadline(call_context, to_call.clone(), input, max_duration)
+                }
+                None => self.call_metered(call_context, to_call, input, &CancelToken::default()),
+            }?;
 
             Ok(Some(result))
         } else {
@@ -426,6 +624,22 @@ impl RibosomeT for WasmRibosome {
         }
     }
 
+    fn list_callbacks(&self) -> RibosomeResult<Vec<(ZomeName, Vec<FunctionName>)>> {
+        Ok(self
+            .list_zome_externs()?
+            .into_iter()
+            .map(|(zome_name, externs)| (zome_name, externs.callbacks))
+            .collect())
+    }
+
+    fn list_zome_fns(&self) -> RibosomeResult<Vec<(ZomeName, Vec<FunctionName>)>> {
+        Ok(self
+            .list_zome_externs()?
+            .into_iter()
+            .map(|(zome_name, externs)| (zome_name, externs.zome_fns))
+            .collect())
+    }
+
     fn call_iterator<R: RibosomeT, I: crate::core::ribosome::Invocation>(
         &self,
         access: HostAccess,
@@ -0,0 +1,91 @@
+//! The threads calls into wasm run on when they have a deadline.
+//!
+//! A call that runs past its deadline is cancelled by the caller, see
+//! [wasm_cancel](super::wasm_cancel), and its thread is free again once
+//! the guest reaches its next function call or loop iteration.
+//! There is a fixed number of these threads so guests can only tie up
+//! so many of them at once, instead of every call spawning a new thread.
+
+use parking_lot::Mutex;
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<mpsc::Sender<Job>> = Mutex::new(spawn_threads());
+}
+
+/// Start the threads, which run jobs from the returned queue for the
+/// life of the process
+fn spawn_threads() -> mpsc::Sender<Job> {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let rx = Arc::new(Mutex::new(rx));
+    for i in 0..num_cpus::get().max(2) {
+        let rx = rx.clone();
+        std::thread::Builder::new()
+            .name(format!("wasm-{}", i))
+            .spawn(move || loop {
+                // Only hold the lock while waiting for the next job
+                let job = match rx.lock().recv() {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+                // A panicking guest mustn't take the thread with it
+                std::panic::catch_unwind(AssertUnwindSafe(job)).ok();
+            })
+            .expect("Failed to spawn a wasm thread");
+    }
+    tx
+}
+
+/// Run `f` on a wasm thread and wait up to `timeout` for its result.
+/// Returns `None` if the time ran out. If `f` hadn't started by then
+/// it is never run.
+pub(crate) fn run_with_timeout<T, F>(f: F, timeout: Duration) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(1);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let job_cancelled = cancelled.clone();
+    let job: Job = Box::new(move || {
+        if job_cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+        // The caller may have stopped waiting
+        tx.send(f()).ok();
+    });
+    JOBS.lock().send(job).ok()?;
+    let result = rx.recv_timeout(timeout).ok();
+    if result.is_none() {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_the_result() {
+        assert_eq!(run_with_timeout(|| 1 + 1, Duration::from_secs(10)), Some(2));
+    }
+
+    #[test]
+    fn gives_up_at_the_timeout() {
+        let result = run_with_timeout(
+            || std::thread::sleep(Duration::from_millis(500)),
+            Duration::from_millis(10),
+        );
+        assert_eq!(result, None);
+    }
+}
@@ -2,9 +2,12 @@
 //! There are two main kinds of Signal: system-defined, and app-defined:
 //! - App-defined signals are produced via the `emit_signal` host function.
 //! - System-defined signals are produced in various places in the system
+//!
+//! System signals about the conductor itself, like apps being installed or
+//! cells failing to start, are sent across admin interfaces.
 
 use holochain_serialized_bytes::prelude::*;
-use holochain_types::{cell::CellId, impl_from};
+use holochain_types::{app::InstalledAppId, cell::CellId, impl_from};
 
 /// A Signal is some information emitted from within Holochain out through
 /// an Interface
@@ -18,14 +21,46 @@ pub enum Signal {
 
 /// A Signal which originates from within the Holochain system, as opposed to
 /// from within a Cell
-///
-/// TODO, decide what these will be. For instance, maybe there is a
-/// DataAvailable signal for doing async network requests
 #[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes, PartialEq, Eq)]
 pub enum SystemSignal {
-    /// Since we have no real system signals, we use a test signal for testing
-    /// TODO: replace instances of this with something real
+    /// A signal used only for testing
     Test(String),
+    /// An app was installed and genesis ran on all of its cells
+    AppInstalled(InstalledAppId),
+    /// An installed app was activated
+    AppActivated(InstalledAppId),
+    /// An active app was deactivated and its cells removed
+    AppDeactivated(InstalledAppId),
+    /// Genesis failed while installing an app, so the app was not installed
+    CellGenesisFailed {
+        /// The app which was being installed
+        installed_app_id: InstalledAppId,
+        /// A description of each cell's failure
+        errors: Vec<String>,
+    },
+    /// The cells of an active app could not be created
+    CellCreationFailed {
+        /// The app whose cells failed
+        installed_app_id: InstalledAppId,
+        /// A description of each cell's failure
+        errors: Vec<String>,
+    },
+    /// An app interface was attached on this port
+    AppInterfaceAttached {
+        /// The port the interface is listening on
+        port: u16,
+    },
+    /// The number of ops a cell still has to validate and integrate.
+    /// Sent each time one of the cell's validation or integration
+    /// workflows runs.
+    IntegrationProgress {
+        /// The cell doing the work
+        cell_id: CellId,
+        /// Ops waiting in the validation limbo
+        awaiting_validation: usize,
+        /// Ops validated and waiting to be integrated
+        awaiting_integration: usize,
+    },
 }
 
 pub fn test_signal(s: &str) -> Signal {
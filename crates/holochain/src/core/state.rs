@@ -44,6 +44,7 @@ pub mod metadata;
 pub mod source_chain;
 pub mod validation_db;
 pub mod validation_receipts_db;
+pub mod warrants_db;
 #[allow(missing_docs)]
 pub mod wasm;
 pub mod workspace;
@@ -7,6 +7,7 @@
 use super::{
     element_buf::ElementBuf,
     metadata::{ChainItemKey, LinkMetaKey, MetadataBuf, MetadataBufT},
+    warrants_db::WarrantsBuf,
 };
 use crate::core::workflow::integrate_dht_ops_workflow::integrate_single_metadata;
 use either::Either;
@@ -38,8 +39,10 @@ use holochain_zome_types::{
     link::Link,
     metadata::{Details, ElementDetails, EntryDetails},
     query::ChainQueryFilter,
+    query::ChainQueryRange,
     query::ChainStatus,
     validate::{ValidationPackage, ValidationStatus},
+    warrant::SignedWarrant,
 };
 use std::collections::HashSet;
 use std::collections::{BTreeMap, BTreeSet};
@@ -1495,13 +1498,16 @@ where
         agent: AgentPubKey,
         query: ChainQueryFilter,
         options: GetActivityOptions,
-    ) -> CascadeResult<()> {
-        let network = ok_or_return!(self.network.as_mut());
+    ) -> CascadeResult<Vec<SignedWarrant>> {
+        let network = ok_or_return!(self.network.as_mut(), Vec::new());
         let all_agent_activity = network.get_agent_activity(agent, query, options).await?;
-        for agent_activity in all_agent_activity {
+        // Warrants aren't cached so they are passed back to the caller
+        let mut warrants = Vec::new();
+        for mut agent_activity in all_agent_activity {
+            warrants.append(&mut agent_activity.warrants);
             self.update_agent_activity_stores(agent_activity).await?;
         }
-        Ok(())
+        Ok(warrants)
     }
 
     async fn fetch_agent_activity_status(
@@ -1509,42 +1515,43 @@ where
         agent: AgentPubKey,
         query: ChainQueryFilter,
         mut options: GetActivityOptions,
-    ) -> CascadeResult<()> {
+    ) -> CascadeResult<Vec<SignedWarrant>> {
         options.include_valid_activity = false;
         options.include_rejected_activity = false;
         options.include_full_headers = false;
         self.fetch_agent_activity(agent.clone(), query.clone(), options)
-            .await?;
-        Ok(())
+            .await
     }
 
     fn get_agent_activity_from_cache(
         agent: AgentPubKey,
-        range: &Option<std::ops::Range<u32>>,
+        range: &Option<ChainQueryRange>,
         cache_data: &DbPairMut<'a, MetaCache>,
         env: &EnvironmentRead,
     ) -> CascadeResult<Vec<(u32, HeaderHash)>> {
-        match range {
-            Some(range) => {
+        match range.as_ref().map(|r| (r, r.exclusive_end())) {
+            Some((range, Some(end))) => {
                 // One less than the end of an exclusive range is actually
                 // the last header we want in the chain.
                 fresh_reader!(env, |r| {
                     // Check if we have up to that header in the metadata store.
-                    if cache_data
-                        .meta
-                        .get_activity(
-                            &r,
-                            ChainItemKey::AgentStatusSequence(
-                                agent.clone(),
-                                ValidationStatus::Valid,
-                                range.end - 1,
-                            ),
-                        )?
-                        .next()?
-                        .is_some()
+                    if end > 0
+                        && cache_data
+                            .meta
+                            .get_activity(
+                                &r,
+                                ChainItemKey::AgentStatusSequence(
+                                    agent.clone(),
+                                    ValidationStatus::Valid,
+                                    end - 1,
+                                ),
+                            )?
+                            .next()?
+                            .is_some()
                     {
                         // We have the chain so collect the hashes in order of header sequence.
                         // Note if the chain is forked there could be multiple headers at each sequence number.
+                        let start = range.inclusive_start();
                         Ok(cache_data
                             .meta
                             .get_activity_sequence(
@@ -1552,8 +1559,8 @@ where
                                 ChainItemKey::AgentStatus(agent, ValidationStatus::Valid),
                             )?
                             // TODO: PERF: Use an iter from to start from the correct sequence
-                            .skip_while(|(s, _)| Ok(*s < range.start))
-                            .take_while(|(s, _)| Ok(*s < range.end))
+                            .skip_while(|(s, _)| Ok(*s < start))
+                            .take_while(|(s, _)| Ok(*s < end))
                             .collect()?)
                     } else {
                         // The requested chain is not in our cache.
@@ -1561,6 +1568,18 @@ where
                     }
                 })
             }
+            // No upper bound so return everything we have from the start of the range
+            Some((range, None)) => fresh_reader!(env, |r| {
+                let start = range.inclusive_start();
+                Ok(cache_data
+                    .meta
+                    .get_activity_sequence(
+                        &r,
+                        ChainItemKey::AgentStatus(agent, ValidationStatus::Valid),
+                    )?
+                    .skip_while(|(s, _)| Ok(*s < start))
+                    .collect()?)
+            }),
             // Requesting full chain so return all everything we have
             None => fresh_reader!(env, |r| {
                 Ok(cache_data
@@ -1579,21 +1598,20 @@ where
     fn find_valid_activity_cache_hit(
         &self,
         agent: AgentPubKey,
-        sequence_range: &Option<std::ops::Range<u32>>,
+        sequence_range: &Option<ChainQueryRange>,
     ) -> CascadeResult<Option<Vec<(u32, HeaderHash)>>> {
         let cache_data = ok_or_return!(self.cache_data.as_ref(), None);
         let env = ok_or_return!(self.env.as_ref(), None);
 
         // Check if the range contains any values.
-        // This also makes it safe to do `range.end - 1`
-        match sequence_range {
+        match sequence_range.as_ref().map(|r| r.exclusive_end()) {
             // The range is empty so there's not hashes to get
-            Some(r) if r.end == 0 => return Ok(Some(vec![])),
+            Some(Some(0)) => return Ok(Some(vec![])),
             // It only makes sense to check the cache first if
-            // a range has been requested otherwise
+            // a bounded range has been requested otherwise
             // we must go to the network because we don't
             // know how long the chain is.
-            None => return Ok(None),
+            None | Some(None) => return Ok(None),
             _ => (),
         }
         // Try getting the activity from the cache.
@@ -1625,11 +1643,24 @@ where
         options: GetActivityOptions,
     ) -> CascadeResult<AgentActivity<Element>> {
         // Fetch the activity from the network
-        self.fetch_agent_activity(agent.clone(), query.clone(), options)
+        let warrants = self
+            .fetch_agent_activity(agent.clone(), query.clone(), options)
             .await?;
 
-        let cache_data = ok_or_return!(self.cache_data.as_ref(), AgentActivity::empty(&agent));
-        let env = ok_or_return!(self.env.as_ref(), AgentActivity::empty(&agent));
+        let cache_data = ok_or_return!(
+            self.cache_data.as_ref(),
+            AgentActivity {
+                warrants,
+                ..AgentActivity::empty(&agent)
+            }
+        );
+        let env = ok_or_return!(
+            self.env.as_ref(),
+            AgentActivity {
+                warrants,
+                ..AgentActivity::empty(&agent)
+            }
+        );
         // Now try getting the latest activity from cache
         let hashes = Self::get_agent_activity_from_cache(
             agent.clone(),
@@ -1637,7 +1668,25 @@ where
             cache_data,
             env,
         )?;
-        self.create_activity(agent, hashes)
+        self.create_activity(agent, hashes, warrants)
+    }
+
+    /// Combine warrants fetched from authorities with any we are holding
+    fn merge_warrants(
+        &self,
+        agent: &AgentPubKey,
+        mut warrants: Vec<SignedWarrant>,
+    ) -> CascadeResult<Vec<SignedWarrant>> {
+        if let Some(env) = self.env.as_ref() {
+            let warrants_buf = WarrantsBuf::new(env)?;
+            let held = fresh_reader!(env, |r| warrants_buf
+                .list_warrants(&r, agent)?
+                .collect::<Vec<_>>())?;
+            warrants.extend(held);
+        }
+        warrants.sort();
+        warrants.dedup();
+        Ok(warrants)
     }
 
     /// Turn the hashes into agent activity with status and highest_observed
@@ -1652,8 +1701,16 @@ where
         &self,
         agent: AgentPubKey,
         hashes: Vec<(u32, HeaderHash)>,
+        warrants: Vec<SignedWarrant>,
     ) -> CascadeResult<AgentActivity<Element>> {
-        let cache_data = ok_or_return!(self.cache_data.as_ref(), AgentActivity::empty(&agent));
+        let warrants = self.merge_warrants(&agent, warrants)?;
+        let cache_data = ok_or_return!(
+            self.cache_data.as_ref(),
+            AgentActivity {
+                warrants,
+                ..AgentActivity::empty(&agent)
+            }
+        );
         // Now try getting the latest activity from cache
         let highest_observed = cache_data.meta.get_activity_observed(&agent)?;
         match cache_data.meta.get_activity_status(&agent)? {
@@ -1663,6 +1720,7 @@ where
                 rejected_activity: ChainItems::NotRequested,
                 status,
                 highest_observed,
+                warrants,
             }),
             // If we don't have any status then we must return an empty chain
             None => Ok(AgentActivity {
@@ -1671,6 +1729,7 @@ where
                 rejected_activity: ChainItems::NotRequested,
                 status: ChainStatus::Empty,
                 highest_observed,
+                warrants,
             }),
         }
     }
@@ -1708,7 +1767,7 @@ where
             .or(Some(DEFAULT_ACTIVITY_TIMEOUT_MS));
 
         // See if we have a cache hit
-        let chain_hashes = match &query.sequence_range {
+        let (chain_hashes, warrants) = match &query.sequence_range {
             Some(_) => {
                 // If we have some cached agent activity then don't fetch the activity.
                 // Instead fetch just the status and see if the chain is still valid
@@ -1718,13 +1777,17 @@ where
                 // Fetch status without activity
 
                 // Fetch just the status
-                self.fetch_agent_activity_status(agent.clone(), query.clone(), options.clone())
+                let warrants = self
+                    .fetch_agent_activity_status(agent.clone(), query.clone(), options.clone())
                     .await?;
 
                 // See if our cache is still valid
-                self.find_valid_activity_cache_hit(agent.clone(), &query.sequence_range)?
+                (
+                    self.find_valid_activity_cache_hit(agent.clone(), &query.sequence_range)?,
+                    warrants,
+                )
             }
-            None => None,
+            None => (None, Vec::new()),
         };
 
         // Create the activity
@@ -1735,7 +1798,7 @@ where
                     .await?
             }
             // Create the activity from the hashes
-            Some(chain_hashes) => self.create_activity(agent.clone(), chain_hashes)?,
+            Some(chain_hashes) => self.create_activity(agent.clone(), chain_hashes, warrants)?,
         };
 
         // Check if we are done
@@ -7,7 +7,12 @@ use holochain_state::{
     env::ReadManager,
     test_utils::{test_cell_env, TestEnvironment},
 };
-use holochain_zome_types::{test_utils::fake_agent_pubkey_1, validate::ValidationStatus, Header};
+use holochain_zome_types::{
+    query::{ChainFork, ChainStatus},
+    test_utils::fake_agent_pubkey_1,
+    validate::ValidationStatus,
+    Header,
+};
 
 use super::{ChainItemKey, MetadataBuf, MetadataBufT};
 
@@ -162,3 +167,63 @@ async fn check_large_seq_queries() {
         &[(1, h2_hash), (256, h1_hash)]
     );
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn check_fork_sets_forked_status() {
+    let (_te, mut meta_buf, mut h1, mut h2, agent_pubkey) = setup();
+    let mut root = fixt!(Create);
+    root.author = agent_pubkey.clone();
+    root.header_seq = 0;
+    h1.header_seq = 1;
+    h2.header_seq = 1;
+    let root: Header = root.into();
+    let h1: Header = h1.into();
+    let h2: Header = h2.into();
+
+    meta_buf
+        .register_activity(&root, ValidationStatus::Valid)
+        .unwrap();
+    meta_buf
+        .register_activity(&h1, ValidationStatus::Valid)
+        .unwrap();
+    assert!(matches!(
+        meta_buf.get_activity_status(&agent_pubkey).unwrap(),
+        Some(ChainStatus::Valid(_))
+    ));
+
+    // A second header at the same position forks the chain
+    meta_buf
+        .register_activity(&h2, ValidationStatus::Valid)
+        .unwrap();
+    let status = meta_buf.get_activity_status(&agent_pubkey).unwrap();
+    let mut expected = vec![
+        HeaderHash::with_data_sync(&h1),
+        HeaderHash::with_data_sync(&h2),
+    ];
+    expected.sort();
+    match status {
+        Some(ChainStatus::Forked(ChainFork {
+            fork_seq,
+            first_header,
+            second_header,
+        })) => {
+            assert_eq!(fork_seq, 1);
+            let mut found = vec![first_header, second_header];
+            found.sort();
+            assert_eq!(found, expected);
+        }
+        s => panic!("Expected a forked chain but got {:?}", s),
+    }
+
+    // Continuing either side of the fork doesn't hide it
+    let mut h3 = fixt!(Create);
+    h3.author = agent_pubkey.clone();
+    h3.header_seq = 2;
+    meta_buf
+        .register_activity(&h3.into(), ValidationStatus::Valid)
+        .unwrap();
+    assert!(matches!(
+        meta_buf.get_activity_status(&agent_pubkey).unwrap(),
+        Some(ChainStatus::Forked(ChainFork { fork_seq: 1, .. }))
+    ));
+}
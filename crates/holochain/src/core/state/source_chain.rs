@@ -13,7 +13,7 @@ use holochain_zome_types::{
     element::Element,
     entry::{CapClaimEntry, Entry},
     header::{builder, EntryType, Header, HeaderBuilder, HeaderBuilderCommon, HeaderInner},
-    query::ChainQueryFilter,
+    query::{ChainQueryFilter, ChainQueryOrder},
 };
 use shrinkwraprs::Shrinkwrap;
 pub use source_chain_buffer::*;
@@ -37,6 +37,15 @@ impl SourceChain {
             ))
     }
 
+    /// The key the chain is currently authored with
+    pub fn latest_agent_pubkey(&self) -> SourceChainResult<AgentPubKey> {
+        self.0
+            .latest_agent_pubkey()?
+            .ok_or(SourceChainError::InvalidStructure(
+                ChainInvalidReason::GenesisDataMissing,
+            ))
+    }
+
     pub fn chain_head(&self) -> SourceChainResult<&HeaderHash> {
         self.0.chain_head().ok_or(SourceChainError::ChainEmpty)
     }
@@ -60,7 +69,7 @@ impl SourceChain {
         maybe_entry: Option<Entry>,
     ) -> SourceChainResult<HeaderHash> {
         let common = HeaderBuilderCommon {
-            author: self.agent_pubkey()?,
+            author: self.latest_agent_pubkey()?,
             timestamp: Timestamp::now().into(),
             header_seq: self.len() as u32,
             prev_header: self.chain_head()?.to_owned(),
@@ -83,6 +92,42 @@ impl SourceChain {
         self.put(header_builder, Some(entry)).await
     }
 
+    /// Replace the key this chain is authored with by updating the agent entry.
+    /// The update is the last header authored by the old key,
+    /// every header after it is authored by the new key.
+    pub async fn put_agent_key_update(
+        &mut self,
+        new_key: AgentPubKey,
+    ) -> SourceChainResult<HeaderHash> {
+        let query = ChainQueryFilter::new()
+            .entry_type(EntryType::AgentPubKey)
+            .descending()
+            .limit(1);
+        let current =
+            self.query(&query)?
+                .into_iter()
+                .next()
+                .ok_or(SourceChainError::InvalidStructure(
+                    ChainInvalidReason::GenesisDataMissing,
+                ))?;
+        let original_entry_address = current
+            .header()
+            .entry_data()
+            .map(|(entry_hash, _)| entry_hash.clone())
+            .ok_or(SourceChainError::InvalidStructure(
+                ChainInvalidReason::MalformedGenesisData,
+            ))?;
+        let (entry, entry_hash) =
+            EntryHashed::from_content_sync(Entry::Agent(new_key)).into_inner();
+        let header_builder = builder::Update {
+            original_entry_address,
+            original_header_address: current.header_address().clone(),
+            entry_type: EntryType::AgentPubKey,
+            entry_hash,
+        };
+        self.put(header_builder, Some(entry)).await
+    }
+
     /// Fetch a relevant CapGrant from the private entries.
     ///
     /// If a function has an Unrestricted grant against it, this may be returned.
@@ -290,18 +335,59 @@ impl SourceChain {
     /// Query Headers in the source chain.
     /// This returns a Vec rather than an iterator because it is intended to be
     /// used by the `query` host function, which crosses the wasm boundary
+    ///
+    /// The chain sequence is used as an index so only headers within the
+    /// requested sequence range are visited, in the requested order, and
+    /// the walk stops as soon as the limit is reached.
     pub fn query(&self, query: &ChainQueryFilter) -> SourceChainResult<Vec<Element>> {
-        let include_entries = query.include_entries;
-        self.iter_back()
-            .filter(|shh| Ok(query.check(shh.header())))
-            .map(|shh| {
-                let entry = match shh.header().entry_hash() {
-                    Some(eh) if include_entries => self.0.get_entry(eh)?,
-                    _ => None,
-                };
-                Ok(Element::new(shh, entry.map(|e| e.into_content())))
-            })
-            .collect()
+        let len = self.len() as u32;
+        let (start, end) = match &query.sequence_range {
+            Some(range) => (
+                range.inclusive_start().min(len),
+                range.exclusive_end().unwrap_or(len).min(len),
+            ),
+            None => (0, len),
+        };
+        let start = start.min(end);
+        let indices: Box<dyn Iterator<Item = u32>> = match query.order {
+            ChainQueryOrder::Ascending => Box::new(start..end),
+            ChainQueryOrder::Descending => Box::new((start..end).rev()),
+        };
+        let limit = query.limit.map(|l| l as usize).unwrap_or(usize::MAX);
+
+        let mut elements = Vec::new();
+        for i in indices {
+            if elements.len() >= limit {
+                break;
+            }
+            let header_address = match self.sequence().get(i)? {
+                Some(h) => h,
+                None => continue,
+            };
+            let shh = match self.get_header(&header_address)? {
+                Some(shh) => shh,
+                None => continue,
+            };
+            // Timestamps only move forward on a chain so once we
+            // are past the requested range there is nothing left to find.
+            if let Some(range) = &query.timestamp_range {
+                let timestamp = shh.header().timestamp();
+                match query.order {
+                    ChainQueryOrder::Ascending if timestamp >= range.end => break,
+                    ChainQueryOrder::Descending if timestamp < range.start => break,
+                    _ => (),
+                }
+            }
+            if !query.check(shh.header()) {
+                continue;
+            }
+            let entry = match shh.header().entry_hash() {
+                Some(eh) if query.include_entries => self.0.get_entry(eh)?,
+                _ => None,
+            };
+            elements.push(Element::new(shh, entry.map(|e| e.into_content())));
+        }
+        Ok(elements)
     }
 }
 
@@ -489,6 +575,86 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_query_order_and_limit() -> SourceChainResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        let alice = fixt!(AgentPubKey);
+        {
+            let mut store = SourceChainBuf::new(env.clone().into())?;
+            store.genesis(fake_dna_hash(1), alice.clone(), None).await?;
+            env.guard()
+                .with_commit(|writer| store.flush_to_txn(writer))?;
+        }
+
+        let mut chain = SourceChain::new(env.clone().into())?;
+        for tag in &["a", "b", "c"] {
+            let grant =
+                ZomeCallCapGrant::new((*tag).into(), CapAccess::Unrestricted, HashSet::new());
+            let (entry, entry_hash) =
+                EntryHashed::from_content_sync(Entry::CapGrant(grant)).into_inner();
+            let header_builder = builder::Create {
+                entry_type: EntryType::CapGrant,
+                entry_hash,
+            };
+            chain.put(header_builder, Some(entry)).await?;
+        }
+
+        let seqs = |elements: Vec<Element>| {
+            elements
+                .iter()
+                .map(|e| e.header().header_seq())
+                .collect::<Vec<_>>()
+        };
+
+        // Default is the whole chain from the head back
+        assert_eq!(
+            seqs(chain.query(&ChainQueryFilter::new())?),
+            vec![5, 4, 3, 2, 1, 0]
+        );
+        // Last two grants
+        assert_eq!(
+            seqs(
+                chain.query(
+                    &ChainQueryFilter::new()
+                        .entry_type(EntryType::CapGrant)
+                        .limit(2)
+                )?
+            ),
+            vec![5, 4]
+        );
+        // First two grants
+        assert_eq!(
+            seqs(
+                chain.query(
+                    &ChainQueryFilter::new()
+                        .entry_type(EntryType::CapGrant)
+                        .ascending()
+                        .limit(2)
+                )?
+            ),
+            vec![3, 4]
+        );
+        // Unbounded range with a set of entry types
+        assert_eq!(
+            seqs(
+                chain.query(
+                    &ChainQueryFilter::new()
+                        .sequence_range(2..)
+                        .entry_types(vec![EntryType::AgentPubKey, EntryType::CapGrant])
+                        .ascending()
+                )?
+            ),
+            vec![2, 3, 4, 5]
+        );
+        // Ranges past the end of the chain are clamped
+        assert_eq!(
+            seqs(chain.query(&ChainQueryFilter::new().sequence_range(4..100))?),
+            vec![5, 4]
+        );
+        Ok(())
+    }
+
     // @todo bring all this back when we want to administer cap claims better
     // #[tokio::test(threaded_scheduler)]
     // async fn test_get_cap_claim() -> SourceChainResult<()> {
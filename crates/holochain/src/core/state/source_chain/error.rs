@@ -66,6 +66,10 @@ pub enum SourceChainError {
     /// Element signature doesn't validate against the header
     #[error("Element associated with header {0} was not found on the source chain")]
     ElementMissing(String),
+
+    /// A source chain can only be imported into an empty chain
+    #[error("Attempted to import a source chain into a chain which already has elements")]
+    ChainNotEmpty,
 }
 
 // serde_json::Error does not implement PartialEq - why is that a requirement??
@@ -1,8 +1,11 @@
 use super::ChainInvalidReason;
-use crate::core::state::{
-    chain_sequence::ChainSequenceBuf,
-    element_buf::{ElementBuf, HeaderCas},
-    source_chain::{SourceChainError, SourceChainResult},
+use crate::core::{
+    check_prev_author,
+    state::{
+        chain_sequence::ChainSequenceBuf,
+        element_buf::{ElementBuf, HeaderCas},
+        source_chain::{SourceChainError, SourceChainResult},
+    },
 };
 use fallible_iterator::FallibleIterator;
 use holochain_state::{buffer::BufferedStore, error::DatabaseResult, fresh_reader, prelude::*};
@@ -13,9 +16,20 @@ use holochain_types::{
     prelude::*,
     HeaderHashed,
 };
-use holochain_zome_types::{header, Entry, Header};
+use holochain_zome_types::{
+    element::SignedHeader, entry_def::EntryVisibility, header, Entry, Header,
+};
 use tracing::*;
 
+/// A portable copy of a whole source chain, for backing it up and restoring
+/// it into another conductor. Private entries are only included if asked for.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, SerializedBytes)]
+pub struct SourceChainExport {
+    /// Each header with its signature, and its entry if it has one
+    /// and it was included, from the Dna header to the chain head
+    pub elements: Vec<(SignedHeader, Option<Entry>)>,
+}
+
 pub struct SourceChainBuf {
     elements: ElementBuf<AuthoredPrefix>,
     sequence: ChainSequenceBuf,
@@ -172,6 +186,25 @@ impl SourceChainBuf {
         }
     }
 
+    /// Get the AgentPubKey the chain is currently authored with.
+    /// This is the initial key unless it was replaced by updating the agent entry.
+    /// If this returns None, the chain was not initialized.
+    pub fn latest_agent_pubkey(&self) -> SourceChainResult<Option<AgentPubKey>> {
+        let element = match self.chain_head() {
+            Some(head) => self.get_element(&head.clone())?,
+            None => None,
+        };
+        match element {
+            // The header that replaces the key is the only one
+            // authored by a key other than the latest
+            Some(element) => match element.entry().as_option() {
+                Some(Entry::Agent(agent_pubkey)) => Ok(Some(agent_pubkey.clone())),
+                _ => Ok(Some(element.header().author().clone())),
+            },
+            None => Ok(None),
+        }
+    }
+
     pub fn iter_back(&self) -> SourceChainBackwardIterator {
         SourceChainBackwardIterator::new(self)
     }
@@ -219,6 +252,113 @@ impl SourceChainBuf {
         Ok(serde_json::to_string_pretty(&out)?)
     }
 
+    /// Copy every element of the chain, from the Dna header to the head.
+    /// The headers of private entries are always copied, but the entries
+    /// themselves only with `include_private_entries`, except for
+    /// capability grants which are always copied.
+    pub fn export(&self, include_private_entries: bool) -> SourceChainResult<SourceChainExport> {
+        let mut iter = self.iter_back();
+        let mut elements = Vec::with_capacity(self.len());
+
+        while let Some(h) = iter.next()? {
+            let element = self
+                .get_element(h.header_address())?
+                .ok_or_else(|| SourceChainError::ElementMissing(h.header_address().to_string()))?;
+            let (signed, entry) = element.into_inner();
+            let (header, signature) = signed.into_header_and_signature();
+            let (header, _) = header.into_inner();
+            let is_private = header
+                .entry_type()
+                .map_or(false, |t| *t.visibility() == EntryVisibility::Private);
+            // The restored chain can't honour its grants without their secrets
+            let is_grant = header.entry_type() == Some(&header::EntryType::CapGrant);
+            let entry = if is_private && !is_grant && !include_private_entries {
+                None
+            } else {
+                entry.into_option()
+            };
+            elements.push((SignedHeader(header, signature), entry));
+        }
+        elements.reverse();
+
+        Ok(SourceChainExport { elements })
+    }
+
+    /// Put the elements of an exported chain onto this empty chain as they are,
+    /// without signing them again. The export must begin with the genesis of
+    /// this Dna and agent, each element must be signed by its author,
+    /// each header must follow the one before it and be authored by the
+    /// agent key the chain has at that point.
+    /// Private entries left out of the export stay missing.
+    pub async fn import(
+        &mut self,
+        dna_hash: &DnaHash,
+        agent_pubkey: &AgentPubKey,
+        export: SourceChainExport,
+    ) -> SourceChainResult<()> {
+        if !self.is_empty() {
+            return Err(SourceChainError::ChainNotEmpty);
+        }
+
+        let mut prev_header: Option<HeaderHashed> = None;
+        for (i, (signed_header, maybe_entry)) in export.elements.into_iter().enumerate() {
+            let signed_header = SignedHeaderHashed::from_content_sync(signed_header);
+            let header = signed_header.header();
+            if i == 0 {
+                match header {
+                    Header::Dna(dna) if &dna.hash == dna_hash && &dna.author == agent_pubkey => {}
+                    Header::Dna(_) => {
+                        return Err(SourceChainError::InvalidStructure(
+                            ChainInvalidReason::MalformedGenesisData,
+                        ))
+                    }
+                    _ => {
+                        return Err(SourceChainError::InvalidStructure(
+                            ChainInvalidReason::GenesisDataMissing,
+                        ))
+                    }
+                }
+            }
+            if header.header_seq() as usize != i
+                || header.prev_header() != prev_header.as_ref().map(|h| h.as_hash())
+            {
+                return Err(SourceChainError::InvalidPreviousHeader(format!(
+                    "{:?}",
+                    header
+                )));
+            }
+            if let Some(prev_header) = &prev_header {
+                check_prev_author(header, prev_header)
+                    .map_err(|e| SourceChainError::InvalidPreviousHeader(e.to_string()))?;
+            }
+            signed_header
+                .validate()
+                .await
+                .map_err(|_| SourceChainError::InvalidSignature)?;
+
+            let maybe_entry = maybe_entry.map(EntryHashed::from_content_sync);
+            if let Some(entry) = &maybe_entry {
+                if header.entry_hash() != Some(entry.as_hash()) {
+                    return Err(SourceChainError::InvalidStructure(
+                        ChainInvalidReason::HeaderAndEntryMismatch(entry.as_hash().clone()),
+                    ));
+                }
+            }
+
+            let header_hashed = signed_header.header_hashed().clone();
+            self.sequence.put_header(header_hashed.as_hash().clone())?;
+            self.elements.put(signed_header, maybe_entry)?;
+            prev_header = Some(header_hashed);
+        }
+
+        if !self.has_genesis() {
+            return Err(SourceChainError::InvalidStructure(
+                ChainInvalidReason::GenesisDataMissing,
+            ));
+        }
+        Ok(())
+    }
+
     /// Commit the genesis entries to this source chain, making the chain ready
     /// to use as a `SourceChain`
     pub async fn genesis(
@@ -311,15 +451,25 @@ impl<'a> FallibleIterator for SourceChainBackwardIterator<'a> {
 pub mod tests {
 
     use super::SourceChainBuf;
-    use crate::core::state::source_chain::SourceChainResult;
+    use crate::{
+        core::state::source_chain::{SourceChain, SourceChainError, SourceChainResult},
+        fixt::CapSecretFixturator,
+    };
+    use ::fixt::prelude::*;
     use fallible_iterator::FallibleIterator;
     use holochain_state::{prelude::*, test_utils::test_cell_env};
     use holochain_types::{
+        element::{SignedHeaderHashed, SignedHeaderHashedExt},
         prelude::*,
-        test_utils::{fake_agent_pubkey_1, fake_dna_file},
+        test_utils::{fake_agent_pubkey_1, fake_agent_pubkey_2, fake_dna_file},
         HeaderHashed,
     };
-    use holochain_zome_types::{header, Entry, Header};
+    use holochain_zome_types::{
+        capability::{CapAccess, CapClaim, ZomeCallCapGrant},
+        header, Entry, Header,
+    };
+    use matches::assert_matches;
+    use std::collections::HashSet;
 
     fn fixtures() -> (
         AgentPubKey,
@@ -492,4 +642,103 @@ pub mod tests {
         assert_eq!(signed_header.as_hash(), hashed.as_hash());
         assert_eq!(signed_header.as_hash(), signed_header.header_address());
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn source_chain_buffer_export_import() -> SourceChainResult<()> {
+        let test_env = test_cell_env();
+        let arc = test_env.env();
+        let restored_env = test_cell_env();
+        let restored = restored_env.env();
+
+        let (agent_pubkey, _, _, _, _) = fixtures();
+        let dna_hash = fake_dna_file("a").dna_hash().clone();
+
+        {
+            let mut store = SourceChainBuf::new(arc.clone().into())?;
+            store
+                .genesis(dna_hash.clone(), agent_pubkey.clone(), None)
+                .await?;
+            let mut chain = SourceChain::from(store);
+            let secret = CapSecretFixturator::new(Unpredictable).next().unwrap();
+            chain
+                .put_cap_claim(CapClaim::new("tag".into(), agent_pubkey.clone(), secret))
+                .await?;
+            let grant =
+                ZomeCallCapGrant::new("tag".into(), CapAccess::from(secret), HashSet::new());
+            let (entry, entry_hash) =
+                EntryHashed::from_content_sync(Entry::CapGrant(grant)).into_inner();
+            let header_builder = header::builder::Create {
+                entry_type: header::EntryType::CapGrant,
+                entry_hash,
+            };
+            chain.put(header_builder, Some(entry)).await?;
+            arc.guard()
+                .with_commit(|writer| chain.flush_to_txn(writer))?;
+        }
+        let with_private = SourceChainBuf::new(arc.clone().into())?.export(true)?;
+        assert_eq!(with_private.elements.len(), 5);
+        assert_matches!(with_private.elements[3].1, Some(Entry::CapClaim(_)));
+
+        // Private entries are left out unless they are asked for,
+        // but capability grants are always kept
+        let export = SourceChainBuf::new(arc.clone().into())?.export(false)?;
+        assert_eq!(export.elements.len(), 5);
+        assert_eq!(export.elements[3].1, None);
+        assert_matches!(export.elements[4].1, Some(Entry::CapGrant(_)));
+        assert_eq!(export.elements[..3], with_private.elements[..3]);
+
+        // An element signed by someone else is refused
+        let mut tampered = export.clone();
+        tampered.elements[1].0 .1 = tampered.elements[0].0 .1.clone();
+        let mut store = SourceChainBuf::new(restored.clone().into())?;
+        assert_matches!(
+            store.import(&dna_hash, &agent_pubkey, tampered).await,
+            Err(SourceChainError::InvalidSignature)
+        );
+
+        // A header authored by a key the chain doesn't have is refused,
+        // even with a valid signature
+        let mut forged = export.clone();
+        let mut forged_header = forged.elements[3].0 .0.clone();
+        if let Header::Create(create) = &mut forged_header {
+            create.author = fake_agent_pubkey_2();
+        }
+        let keystore = arc.keystore().clone();
+        let (forged_header, _) =
+            SignedHeaderHashed::new(&keystore, HeaderHashed::from_content_sync(forged_header))
+                .await?
+                .into_inner();
+        forged.elements[3].0 = forged_header;
+        let mut store = SourceChainBuf::new(restored.clone().into())?;
+        assert_matches!(
+            store.import(&dna_hash, &agent_pubkey, forged).await,
+            Err(SourceChainError::InvalidPreviousHeader(_))
+        );
+
+        {
+            let mut store = SourceChainBuf::new(restored.clone().into())?;
+            store
+                .import(&dna_hash, &agent_pubkey, export.clone())
+                .await?;
+            restored
+                .guard()
+                .with_commit(|writer| store.flush_to_txn(writer))?;
+        }
+
+        let store = SourceChainBuf::new(restored.clone().into())?;
+        assert!(store.has_genesis());
+        assert_eq!(
+            store.chain_head(),
+            SourceChainBuf::new(arc.clone().into())?.chain_head()
+        );
+        assert_eq!(store.export(true)?, export);
+
+        // The chain can't be imported twice
+        let mut store = SourceChainBuf::new(restored.clone().into())?;
+        assert_matches!(
+            store.import(&dna_hash, &agent_pubkey, export).await,
+            Err(SourceChainError::ChainNotEmpty)
+        );
+        Ok(())
+    }
 }
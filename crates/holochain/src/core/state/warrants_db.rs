@@ -0,0 +1,172 @@
+//! Module for items related to storing warrants against agents
+
+use fallible_iterator::FallibleIterator;
+use holo_hash::{AgentPubKey, HeaderHash};
+use holochain_keystore::{AgentPubKeyExt, KeystoreSender};
+use holochain_state::{
+    buffer::{BufferedStore, KvvBufUsed},
+    db::GetDb,
+    error::{DatabaseError, DatabaseResult},
+    prelude::{Readable, Writer},
+};
+use holochain_types::Timestamp;
+use holochain_zome_types::warrant::{SignedWarrant, Warrant};
+
+/// Create a warrant against the author of this header and sign it
+/// with the issuing validator's key.
+pub async fn issue_warrant(
+    keystore: &KeystoreSender,
+    issuer: AgentPubKey,
+    offender: AgentPubKey,
+    header: HeaderHash,
+    reason: String,
+) -> DatabaseResult<SignedWarrant> {
+    let warrant = Warrant {
+        offender,
+        header,
+        reason,
+        issuer,
+        timestamp: Timestamp::now(),
+    };
+    let signature = warrant.issuer.sign(keystore, warrant.clone()).await?;
+    Ok(SignedWarrant(warrant, signature))
+}
+
+/// Check the issuer really signed this warrant
+pub async fn verify_warrant(warrant: &SignedWarrant) -> DatabaseResult<bool> {
+    let SignedWarrant(warrant, signature) = warrant;
+    Ok(warrant
+        .issuer
+        .verify_signature(signature, warrant.clone())
+        .await?)
+}
+
+/// The database/buffer of warrants we have issued or received,
+/// keyed by the agent they are against.
+pub struct WarrantsBuf(KvvBufUsed<AgentPubKey, SignedWarrant>);
+
+impl WarrantsBuf {
+    /// Constructor given read-only transaction and db ref.
+    pub fn new(dbs: &impl GetDb) -> DatabaseResult<WarrantsBuf> {
+        Ok(Self(KvvBufUsed::new_opts(
+            dbs.get_db(&*holochain_state::db::WARRANTS)?,
+            true, // set to no_dup_data mode
+        )))
+    }
+
+    /// List all the warrants against an agent.
+    pub fn list_warrants<'r, R: Readable>(
+        &'r self,
+        r: &'r R,
+        offender: &AgentPubKey,
+    ) -> DatabaseResult<
+        impl fallible_iterator::FallibleIterator<Item = SignedWarrant, Error = DatabaseError> + '_,
+    > {
+        Ok(fallible_iterator::convert(self.0.get(r, offender)?))
+    }
+
+    /// Whether we hold a warrant against this agent for this header,
+    /// from any issuer.
+    pub fn has_warrant_for<R: Readable>(
+        &self,
+        r: &R,
+        offender: &AgentPubKey,
+        header: &HeaderHash,
+    ) -> DatabaseResult<bool> {
+        self.list_warrants(r, offender)?
+            .any(|w| Ok(&w.warrant().header == header))
+    }
+
+    /// Add this warrant if it isn't already in the database.
+    pub fn add_if_unique(&mut self, warrant: SignedWarrant) -> DatabaseResult<()> {
+        // The underlying KvvBufUsed manages the uniqueness
+        self.0.insert(warrant.warrant().offender.clone(), warrant);
+        Ok(())
+    }
+}
+
+impl BufferedStore for WarrantsBuf {
+    type Error = DatabaseError;
+
+    fn flush_to_txn_ref(&mut self, writer: &mut Writer) -> DatabaseResult<()> {
+        self.0.flush_to_txn_ref(writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_keystore::KeystoreSenderExt;
+    use holochain_state::{env::ReadManager, prelude::*};
+    use holochain_types::test_utils::fake_header_hash;
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_warrants_db_populate_and_list() -> DatabaseResult<()> {
+        holochain_types::observability::test_run().ok();
+
+        let test_env = holochain_state::test_utils::test_cell_env();
+        let env = test_env.env();
+        let keystore = holochain_state::test_utils::test_keystore();
+
+        let issuer = keystore
+            .clone()
+            .generate_sign_keypair_from_pure_entropy()
+            .await
+            .unwrap();
+        let offender = keystore
+            .clone()
+            .generate_sign_keypair_from_pure_entropy()
+            .await
+            .unwrap();
+
+        let w1 = issue_warrant(
+            &keystore,
+            issuer.clone(),
+            offender.clone(),
+            fake_header_hash(1),
+            "bad entry".to_string(),
+        )
+        .await?;
+        let w2 = issue_warrant(
+            &keystore,
+            issuer.clone(),
+            offender.clone(),
+            fake_header_hash(2),
+            "bad link".to_string(),
+        )
+        .await?;
+        assert!(verify_warrant(&w1).await?);
+
+        // A warrant with a tampered reason no longer verifies
+        let mut tampered = w1.clone();
+        tampered.0.reason = "something else".to_string();
+        assert!(!verify_warrant(&tampered).await?);
+
+        let env_ref = env.guard();
+        {
+            let mut buf = WarrantsBuf::new(&env)?;
+            buf.add_if_unique(w1.clone())?;
+            buf.add_if_unique(w1.clone())?;
+            buf.add_if_unique(w2.clone())?;
+            env_ref.with_commit(|writer| buf.flush_to_txn(writer))?;
+        }
+
+        let reader = env_ref.reader()?;
+        let buf = WarrantsBuf::new(&env)?;
+
+        let mut list = buf.list_warrants(&reader, &offender)?.collect::<Vec<_>>()?;
+        list.sort();
+        let mut expects = vec![w1, w2];
+        expects.sort();
+        assert_eq!(expects, list);
+
+        assert_eq!(0, buf.list_warrants(&reader, &issuer)?.count()?);
+
+        assert!(buf.has_warrant_for(&reader, &offender, &fake_header_hash(1))?);
+        assert!(!buf.has_warrant_for(&reader, &offender, &fake_header_hash(3))?);
+        assert!(!buf.has_warrant_for(&reader, &issuer, &fake_header_hash(1))?);
+
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 
 use super::{
     queue_consumer::TriggerSender,
+    ribosome::wasm_ribosome::WasmRibosome,
     state::metadata::{ChainItemKey, MetadataBufT},
     workflow::incoming_dht_ops_workflow::incoming_dht_ops_workflow,
     workflow::sys_validation_workflow::SysValidationWorkspace,
@@ -14,7 +15,7 @@ use holochain_p2p::HolochainP2pCell;
 use holochain_state::{env::EnvironmentWrite, error::DatabaseResult, fresh_reader};
 use holochain_types::{dht_op::DhtOp, header::NewEntryHeaderRef, Entry};
 use holochain_zome_types::{
-    element::ElementEntry, signature::Signature, validate::ValidationStatus,
+    dpki::KeyState, element::ElementEntry, signature::Signature, validate::ValidationStatus,
 };
 use holochain_zome_types::{
     entry_def::{EntryDef, EntryVisibility},
@@ -59,11 +60,54 @@ pub async fn verify_header_signature(
     }
 }
 
-/// Verify the author key was valid at the time
-/// of signing with dpki
-/// TODO: This is just a stub until we have dpki.
-pub async fn author_key_is_valid(_author: &AgentPubKey) -> SysValidationResult<bool> {
-    Ok(true)
+/// Check with DPKI that the author's key hadn't been revoked when
+/// the header was signed, so history from before a revocation stays valid.
+/// Which key may author a header is decided by its place in the
+/// chain, see [check_prev_author], so a replaced key is still
+/// valid for the headers before it was replaced.
+/// Every key is valid if the conductor has no DPKI app.
+pub async fn check_author_key_valid(
+    header: &Header,
+    conductor_api: &impl CellConductorApiT,
+) -> SysValidationResult<()> {
+    let author = header.author();
+    match conductor_api
+        .dpki_key_state(author)
+        .await
+        .map_err(Box::new)?
+    {
+        None => Ok(()),
+        // DPKI may not have seen the key yet
+        Some(KeyState::NotFound) => Err(ValidationOutcome::AuthorKeyUnknown(author.clone()).into()),
+        Some(state) if state.is_revoked_at(header.timestamp()) => {
+            Err(ValidationOutcome::AuthorKeyRevoked(author.clone()).into())
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// The key that must author the header after this one.
+/// A chain keeps its author until the agent key entry is updated.
+/// That update is the last header authored by the old key.
+pub fn next_author(prev_header: &Header) -> AgentPubKey {
+    match prev_header {
+        Header::Update(update) if update.entry_type == EntryType::AgentPubKey => {
+            update.entry_hash.clone().into()
+        }
+        _ => prev_header.author().clone(),
+    }
+}
+
+/// Check the header is authored by the chain's key
+/// at this position
+pub fn check_prev_author(header: &Header, prev_header: &Header) -> SysValidationResult<()> {
+    let expected = next_author(prev_header);
+    if header.author() == &expected {
+        Ok(())
+    } else {
+        Err(PrevHeaderError::Author(expected, header.author().clone()))
+            .map_err(|e| ValidationOutcome::from(e).into())
+    }
 }
 
 /// Check that previous header makes sense
@@ -108,7 +152,11 @@ pub async fn check_valid_if_dna(
 // TODO: I think this can be removed now as rollbacks are detected when inserting
 // metadata into the metadata buf.
 /// Check if there are other headers at this
-/// sequence number
+/// sequence number.
+/// Activity is kept per author, so after a key rotation the chain
+/// continues under the new key. The old key can't fork the chain past
+/// the rotation because [check_prev_author] rejects any header it
+/// authors after the update, which leaves only forks under one key.
 pub async fn check_chain_rollback(
     header: &Header,
     workspace: &SysValidationWorkspace,
@@ -142,9 +190,10 @@ pub async fn check_chain_rollback(
     } else {
         let s = tracing::warn_span!("agent_activity");
         let _g = s.enter();
-        // TODO: implement real rollback detection once we know what that looks like
-        tracing::error!(
-            "Chain rollback detected at position {} for agent {:?} from header {:?}
+        // The fork itself is recorded as a ChainStatus::Forked
+        // when this header's activity is integrated
+        tracing::warn!(
+            "Chain fork detected at position {} for agent {:?} from header {:?}
             There were {} headers at this position",
             header.header_seq(),
             header.author(),
@@ -155,12 +204,6 @@ pub async fn check_chain_rollback(
     Ok(())
 }
 
-/// Placeholder for future spam check.
-/// Check header timestamps don't exceed MAX_PUBLISH_FREQUENCY
-pub async fn check_spam(_header: &Header) -> SysValidationResult<()> {
-    Ok(())
-}
-
 /// Check previous header timestamp is before this header
 pub fn check_prev_timestamp(header: &Header, prev_header: &Header) -> SysValidationResult<()> {
     if header.timestamp() > prev_header.timestamp() {
@@ -215,7 +258,8 @@ pub async fn check_app_entry_type(
         .1
         .clone();
 
-    let entry_def = get_entry_def(entry_type.id(), zome, &dna_file, conductor_api).await?;
+    let ribosome = WasmRibosome::new(dna_file);
+    let entry_def = get_entry_def(entry_type.id(), zome, &ribosome, conductor_api).await?;
 
     // Check the visibility and return
     match entry_def {
@@ -2,11 +2,12 @@ use std::convert::TryFrom;
 
 use super::SourceChainError;
 use crate::{
+    conductor::api::error::ConductorApiError,
     conductor::entry_def_store::error::EntryDefStoreError,
     core::state::cascade::error::CascadeError, core::state::workspace::WorkspaceError,
     core::validation::OutcomeOrError, core::workflow::error::WorkflowError, from_sub_error,
 };
-use holo_hash::{AnyDhtHash, HeaderHash};
+use holo_hash::{AgentPubKey, AnyDhtHash, HeaderHash};
 use holochain_keystore::KeystoreError;
 use holochain_state::error::DatabaseError;
 use holochain_types::cell::CellId;
@@ -30,6 +31,8 @@ pub enum SysValidationError {
     #[error(transparent)]
     CascadeError(#[from] CascadeError),
     #[error(transparent)]
+    ConductorApiError(#[from] Box<ConductorApiError>),
+    #[error(transparent)]
     DatabaseError(#[from] DatabaseError),
     #[error(transparent)]
     EntryDefStoreError(#[from] EntryDefStoreError),
@@ -86,6 +89,10 @@ impl<E> TryFrom<OutcomeOrError<ValidationOutcome, E>> for ValidationOutcome {
 /// failed validation.
 #[derive(Error, Debug)]
 pub enum ValidationOutcome {
+    #[error("The author key {0} has been revoked in DPKI")]
+    AuthorKeyRevoked(AgentPubKey),
+    #[error("The author key {0} is not known to DPKI yet")]
+    AuthorKeyUnknown(AgentPubKey),
     #[error("The element with signature {0:?} and header {1:?} was found to be counterfeit")]
     Counterfeit(Signature, Header),
     #[error("The dependency {0:?} was not found on the DHT")]
@@ -137,6 +144,8 @@ impl ValidationOutcome {
 
 #[derive(Error, Debug)]
 pub enum PrevHeaderError {
+    #[error("The chain's key at this position is {0} but the header is authored by {1}")]
+    Author(AgentPubKey, AgentPubKey),
     #[error("Root of source chain must be Dna")]
     InvalidRoot,
     #[error("Previous header sequence number {1} is not {0} - 1")]
@@ -10,11 +10,11 @@ use holochain_types::{
     dna::{DnaDef, DnaFile},
     fixt::*,
     observability,
-    test_utils::fake_agent_pubkey_1,
+    test_utils::{fake_agent_pubkey_1, fake_agent_pubkey_2},
     Timestamp,
 };
 use holochain_wasm_test_utils::TestWasm;
-use holochain_zome_types::Header;
+use holochain_zome_types::{dpki::KeyState, Header};
 use matches::assert_matches;
 use std::convert::TryFrom;
 
@@ -39,6 +39,117 @@ async fn verify_header_signature_test() {
     );
 }
 
+#[tokio::test(threaded_scheduler)]
+async fn check_author_key_valid_test() {
+    let mut header = fixt!(CreateLink);
+    header.timestamp = Timestamp(2, 0).into();
+    let header = Header::CreateLink(header);
+    let mut conductor_api = MockCellConductorApi::new();
+
+    // # No DPKI
+    conductor_api
+        .expect_sync_dpki_key_state()
+        .returning(|_| Ok(None));
+    assert_matches!(
+        check_author_key_valid(&header, &conductor_api).await,
+        Ok(())
+    );
+
+    // # DPKI hasn't seen the key
+    conductor_api.checkpoint();
+    conductor_api
+        .expect_sync_dpki_key_state()
+        .returning(|_| Ok(Some(KeyState::NotFound)));
+    assert_matches!(
+        check_author_key_valid(&header, &conductor_api).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::AuthorKeyUnknown(_)
+        ))
+    );
+
+    // # Key was replaced, which the chain itself checks
+    conductor_api.checkpoint();
+    conductor_api.expect_sync_dpki_key_state().returning(|_| {
+        Ok(Some(KeyState::Replaced {
+            registered_at: Timestamp(0, 0).into(),
+            replaced_at: Timestamp(1, 0).into(),
+            new_key: fake_agent_pubkey_1(),
+        }))
+    });
+    assert_matches!(
+        check_author_key_valid(&header, &conductor_api).await,
+        Ok(())
+    );
+
+    // # Key was revoked after the header was signed
+    conductor_api.checkpoint();
+    conductor_api.expect_sync_dpki_key_state().returning(|_| {
+        Ok(Some(KeyState::Revoked {
+            registered_at: Timestamp(0, 0).into(),
+            revoked_at: Timestamp(3, 0).into(),
+        }))
+    });
+    assert_matches!(
+        check_author_key_valid(&header, &conductor_api).await,
+        Ok(())
+    );
+
+    // # Key was revoked before the header was signed
+    conductor_api.checkpoint();
+    conductor_api.expect_sync_dpki_key_state().returning(|_| {
+        Ok(Some(KeyState::Revoked {
+            registered_at: Timestamp(0, 0).into(),
+            revoked_at: Timestamp(2, 0).into(),
+        }))
+    });
+    assert_matches!(
+        check_author_key_valid(&header, &conductor_api).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::AuthorKeyRevoked(_)
+        ))
+    );
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn check_prev_author_test() {
+    let old_key = fake_agent_pubkey_1();
+    let new_key = fake_agent_pubkey_2();
+    let mut prev = fixt!(Create);
+    prev.author = old_key.clone();
+    let prev = Header::Create(prev);
+    let mut header = fixt!(CreateLink);
+
+    // # Same author as the previous header
+    header.author = old_key.clone();
+    assert_matches!(check_prev_author(&header.clone().into(), &prev), Ok(()));
+
+    // # Author changed without a key update
+    header.author = new_key.clone();
+    assert_matches!(
+        check_prev_author(&header.clone().into(), &prev),
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::Author(_, _))
+        ))
+    );
+
+    // # The previous header updated the agent key
+    let mut rotation = fixt!(Update);
+    rotation.author = old_key.clone();
+    rotation.entry_type = EntryType::AgentPubKey;
+    rotation.entry_hash = new_key.clone().into();
+    let rotation = Header::Update(rotation);
+    assert_matches!(check_prev_author(&header.clone().into(), &rotation), Ok(()));
+
+    // # The old key can't author after its update
+    header.author = old_key;
+    assert_matches!(
+        check_prev_author(&header.into(), &rotation),
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::Author(_, _))
+        ))
+    );
+}
+
 #[tokio::test(threaded_scheduler)]
 async fn check_previous_header() {
     let mut header = fixt!(CreateLink);
@@ -2,12 +2,18 @@
 use std::convert::TryFrom;
 
 use derivative::Derivative;
-use holo_hash::DhtOpHash;
+use holo_hash::{DhtOpHash, HeaderHash};
+use holochain_p2p::{HolochainP2pCell, HolochainP2pCellT};
 use holochain_types::dht_op::DhtOp;
+use holochain_zome_types::warrant::SignedWarrant;
+use tracing::*;
 
 use super::{
-    workflow::error::WorkflowResult, SourceChainError, SysValidationError, ValidationOutcome,
+    state::warrants_db::{issue_warrant, WarrantsBuf},
+    workflow::error::WorkflowResult,
+    SourceChainError, SysValidationError, ValidationOutcome,
 };
+use crate::conductor::api::CellConductorApiT;
 
 /// Exit early with either an outcome or an error
 pub enum OutcomeOrError<T, E> {
@@ -75,6 +81,51 @@ impl From<&DhtOp> for DhtOpOrder {
     }
 }
 
+/// Sign a warrant against the author of an op that failed validation
+/// and hold it against their agent activity.
+/// Several ops are produced from one header so the warrant is
+/// skipped if one was already `issued` for the same header.
+/// Only call this when the op's signature has been verified, otherwise
+/// the warrant would blame an agent that may never have authored it,
+/// and only for rejections every validator holding the same data reaches.
+/// Other validators re-validate the header before holding the warrant.
+pub async fn warrant_rejected_op(
+    op: &DhtOp,
+    reason: String,
+    conductor_api: &impl CellConductorApiT,
+    warrants: &mut WarrantsBuf,
+    issued: &mut Vec<SignedWarrant>,
+) -> WorkflowResult<()> {
+    let header = op.header();
+    let header_hash = HeaderHash::with_data_sync(&header);
+    if issued.iter().any(|w| w.warrant().header == header_hash) {
+        return Ok(());
+    }
+    let warrant = issue_warrant(
+        conductor_api.keystore(),
+        conductor_api.cell_id().agent_pubkey().clone(),
+        header.author().clone(),
+        header_hash,
+        reason,
+    )
+    .await?;
+    warrants.add_if_unique(warrant.clone())?;
+    issued.push(warrant);
+    Ok(())
+}
+
+/// Send warrants to the authorities holding the offenders' agent activity.
+/// We still hold the warrants if this fails so they can be served
+/// from our own agent activity responses.
+pub async fn publish_warrants(network: &mut HolochainP2pCell, warrants: Vec<SignedWarrant>) {
+    for warrant in warrants {
+        let offender = warrant.warrant().offender.clone();
+        if let Err(e) = network.publish_warrant(warrant, None).await {
+            warn!(?offender, ?e, "Failed to publish warrant");
+        }
+    }
+}
+
 impl OutcomeOrError<ValidationOutcome, SysValidationError> {
     /// Convert an OutcomeOrError<ValidationOutcome, SysValidationError> into
     /// a InvalidCommit and exit the call zome workflow early
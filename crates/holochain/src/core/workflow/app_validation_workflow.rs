@@ -5,12 +5,16 @@ use std::{collections::BinaryHeap, convert::TryInto, sync::Arc};
 use self::validation_package::{get_as_author_custom, get_as_author_full, get_as_author_sub_chain};
 
 use super::{
-    error::WorkflowError, error::WorkflowResult,
-    produce_dht_ops_workflow::dht_op_light::light_to_op, CallZomeWorkspace, CallZomeWorkspaceLock,
+    error::WorkflowError,
+    error::WorkflowResult,
+    produce_dht_ops_workflow::dht_op_light::light_to_op,
+    sys_validation_workflow::{is_warranted, sys_validate_element},
+    CallZomeWorkspace, CallZomeWorkspaceLock,
 };
 use crate::{
     conductor::api::CellConductorApiT,
     conductor::entry_def_store::get_entry_def,
+    core::ribosome::error::RibosomeError,
     core::ribosome::guest_callback::validate_link::ValidateCreateLinkInvocation,
     core::ribosome::guest_callback::validate_link::ValidateDeleteLinkInvocation,
     core::ribosome::guest_callback::validate_link::ValidateLinkHostAccess,
@@ -24,7 +28,7 @@ use crate::{
     core::ribosome::ZomesToInvoke,
     core::state::cascade::Cascade,
     core::{
-        queue_consumer::{OneshotWriter, TriggerSender, WorkComplete},
+        queue_consumer::{OneshotWriter, PendingOps, TriggerSender, ValidationTally, WorkComplete},
         ribosome::guest_callback::validate::ValidateHostAccess,
         ribosome::guest_callback::validate::ValidateInvocation,
         ribosome::guest_callback::validate::ValidateResult,
@@ -39,10 +43,12 @@ use crate::{
             element_buf::ElementBuf,
             metadata::MetadataBuf,
             validation_db::{ValidationLimboStatus, ValidationLimboStore, ValidationLimboValue},
+            warrants_db::WarrantsBuf,
             workspace::{Workspace, WorkspaceResult},
         },
         validation::DhtOpOrder,
         validation::OrderedOp,
+        validation::{publish_warrants, warrant_rejected_op, OutcomeOrError},
     },
 };
 use error::AppValidationResult;
@@ -60,7 +66,7 @@ use holochain_types::{
     activity::{AgentActivity, ChainItems},
     dht_op::DhtOp,
     dna::zome::Zome,
-    dna::DnaFile,
+    dna::DnaDef,
     test_utils::which_agent,
     validate::ValidationStatus,
     Entry, HeaderHashed, Timestamp,
@@ -91,19 +97,34 @@ mod error;
 mod types;
 pub mod validation_package;
 
-#[instrument(skip(workspace, writer, trigger_integration, conductor_api, network))]
+/// How many validation attempts, counting sys validation, an op this
+/// conductor can't finish validating gets before it is abandoned
+pub const MAX_ABANDON_TRIES: u32 = 10;
+
+#[instrument(skip(
+    workspace,
+    writer,
+    trigger_integration,
+    conductor_api,
+    network,
+    pending_ops
+))]
 pub async fn app_validation_workflow(
     mut workspace: AppValidationWorkspace,
     writer: OneshotWriter,
     trigger_integration: &mut TriggerSender,
     conductor_api: impl CellConductorApiT,
     network: HolochainP2pCell,
+    pending_ops: &PendingOps,
 ) -> WorkflowResult<WorkComplete> {
-    let complete = app_validation_workflow_inner(&mut workspace, conductor_api, &network).await?;
+    let mut tally = ValidationTally::default();
+    let complete =
+        app_validation_workflow_inner(&mut workspace, conductor_api, &network, &mut tally).await?;
     // --- END OF WORKFLOW, BEGIN FINISHER BOILERPLATE ---
 
     // commit the workspace
     writer.with_writer(|writer| Ok(workspace.flush_to_txn(writer)?))?;
+    pending_ops.validated(tally);
 
     // trigger other workflows
     trigger_integration.trigger();
@@ -114,17 +135,22 @@ async fn app_validation_workflow_inner(
     workspace: &mut AppValidationWorkspace,
     conductor_api: impl CellConductorApiT,
     network: &HolochainP2pCell,
+    tally: &mut ValidationTally,
 ) -> WorkflowResult<WorkComplete> {
     let env = workspace.validation_limbo.env().clone();
+    let mut issued_warrants = Vec::new();
 
     // Drain the ops into a sorted binary heap
     let sorted_ops: BinaryHeap<OrderedOp<ValidationLimboValue>> = fresh_reader!(env, |r| {
         let validation_limbo = &mut workspace.validation_limbo;
         let element_pending = &workspace.element_pending;
 
+        let in_validation_limbo = &mut tally.in_validation_limbo;
+
         let sorted_ops: Result<BinaryHeap<OrderedOp<ValidationLimboValue>>, WorkflowError> =
             validation_limbo
                 .drain_iter_filter(&r, |(_, vlv)| {
+                    *in_validation_limbo += 1;
                     match vlv.status {
                         // We only want sys validated or awaiting app dependency ops
                         ValidationLimboStatus::SysValidated
@@ -184,26 +210,88 @@ async fn app_validation_workflow_inner(
                             op: vlv.op,
                         };
                         workspace.put_int_limbo(hash, iv, op)?;
+                        tally.to_integration += 1;
                     }
                     Outcome::AwaitingDeps(deps) => {
                         vlv.status = ValidationLimboStatus::AwaitingAppDeps(deps);
                         workspace.put_val_limbo(hash, vlv)?;
                     }
-                    Outcome::Rejected(_) => {
+                    Outcome::Abandoned(_) if vlv.num_tries < MAX_ABANDON_TRIES => {
+                        workspace.put_val_limbo(hash, vlv)?;
+                    }
+                    Outcome::Abandoned(_) => {
+                        let iv = IntegrationLimboValue {
+                            op: vlv.op,
+                            validation_status: ValidationStatus::Abandoned,
+                        };
+                        workspace.put_int_limbo(hash, iv, op)?;
+                        tally.to_integration += 1;
+                    }
+                    Outcome::Rejected(reason) => {
+                        warrant_rejected_op(
+                            &op,
+                            reason,
+                            &conductor_api,
+                            &mut workspace.warrants,
+                            &mut issued_warrants,
+                        )
+                        .await?;
                         let iv = IntegrationLimboValue {
                             op: vlv.op,
                             validation_status: ValidationStatus::Rejected,
                         };
                         workspace.put_int_limbo(hash, iv, op)?;
+                        tally.to_integration += 1;
                     }
                 }
             }
             _ => unreachable!("Should not contain any other status"),
         }
     }
+
+    publish_warrants(&mut network.clone(), issued_warrants).await;
     Ok(WorkComplete::Complete)
 }
 
+/// Validate an element which a warrant claims is invalid, so the claim can
+/// be checked before the warrant is held. Nothing is written.
+/// Returns whether the element fails sys or app validation in a way that
+/// warrants its author.
+pub async fn confirm_warranted_element(
+    element: Element,
+    conductor_api: &impl CellConductorApiT,
+    env: EnvironmentRead,
+    network: HolochainP2pCell,
+) -> WorkflowResult<bool> {
+    let mut call_zome_workspace = CallZomeWorkspace::new(env.clone())?;
+    match sys_validate_element(
+        &element,
+        &mut call_zome_workspace,
+        network.clone(),
+        conductor_api,
+    )
+    .await
+    {
+        Ok(()) => (),
+        Err(OutcomeOrError::Outcome(outcome)) => return Ok(is_warranted(outcome)),
+        Err(OutcomeOrError::Err(e)) => return Err(e.into()),
+    }
+
+    let (shh, entry) = element.into_inner();
+    let (header, signature) = shh.into_header_and_signature();
+    let op = DhtOp::StoreElement(
+        signature,
+        header.into_content(),
+        entry.into_option().map(Box::new),
+    );
+    let mut workspace = AppValidationWorkspace::new(env)?;
+    match validate_op(op, None, conductor_api, &mut workspace, &network).await {
+        Ok(Outcome::Rejected(_)) | Err(OutcomeOrError::Outcome(Outcome::Rejected(_))) => Ok(true),
+        Ok(_) | Err(OutcomeOrError::Outcome(_)) => Ok(false),
+        Err(OutcomeOrError::Err(e)) => Err(e.into()),
+    }
+}
+
 fn to_zome_name(zomes_to_invoke: ZomesToInvoke) -> AppValidationResult<ZomeName> {
     match zomes_to_invoke {
         ZomesToInvoke::All => Err(AppValidationError::LinkMultipleZomes),
@@ -232,15 +320,15 @@ async fn validate_op(
     let dna_file =
         dna_file.ok_or_else(|| AppValidationError::DnaMissing(conductor_api.cell_id().clone()))?;
 
+    // Create the ribosome
+    let ribosome = WasmRibosome::new(dna_file).with_limits(conductor_api.wasm_limits().clone());
+
     // Get the EntryDefId associated with this Element if there is one
     let entry_def = {
         let cascade = workspace.full_cascade(network.clone());
-        get_associated_entry_def(&element, &dna_file, conductor_api, cascade).await?
+        get_associated_entry_def(&element, &ribosome, conductor_api, cascade).await?
     };
 
-    // Create the ribosome
-    let ribosome = WasmRibosome::new(dna_file);
-
     // Get the validation package
     let validation_package = get_validation_package(
         &element,
@@ -258,7 +346,7 @@ async fn validate_op(
 
     // Get the zome names
     let zomes_to_invoke =
-        get_zomes_to_invoke(&element, ribosome.dna_file(), workspace, network).await?;
+        get_zomes_to_invoke(&element, ribosome.dna_def(), workspace, network).await?;
 
     let outcome = match element.header() {
         Header::DeleteLink(delete_link) => {
@@ -321,7 +409,7 @@ async fn validate_op(
             )?
         }
     };
-    if let Outcome::AwaitingDeps(_) | Outcome::Rejected(_) = &outcome {
+    if let Outcome::AwaitingDeps(_) | Outcome::Rejected(_) | Outcome::Abandoned(_) = &outcome {
         warn!(
             agent = %which_agent(conductor_api.cell_id().agent_pubkey()),
             msg = "DhtOp has failed app validation",
@@ -344,14 +432,14 @@ async fn validate_op(
 /// Other header types will None.
 async fn get_associated_entry_def(
     element: &Element,
-    dna_file: &DnaFile,
+    ribosome: &impl RibosomeT,
     conductor_api: &impl CellConductorApiT,
     cascade: Cascade<'_>,
 ) -> AppValidationOutcome<Option<EntryDef>> {
     match get_app_entry_type(element, cascade).await? {
         Some(aet) => {
-            let zome = get_zome_info(&aet, dna_file)?.1.clone();
-            Ok(get_entry_def(aet.id(), zome, dna_file, conductor_api).await?)
+            let zome = get_zome_info(&aet, ribosome.dna_def())?.1.clone();
+            Ok(get_entry_def(aet.id(), zome, ribosome, conductor_api).await?)
         }
         None => Ok(None),
     }
@@ -418,7 +506,7 @@ fn check_for_caps(element: &Element) -> AppValidationOutcome<()> {
 /// or get all zome names.
 async fn get_zomes_to_invoke(
     element: &Element,
-    dna_file: &DnaFile,
+    dna_def: &DnaDef,
     workspace: &mut AppValidationWorkspace,
     network: &HolochainP2pCell,
 ) -> AppValidationOutcome<ZomesToInvoke> {
@@ -427,10 +515,10 @@ async fn get_zomes_to_invoke(
         get_app_entry_type(element, cascade).await?
     };
     match aet {
-        Some(aet) => Ok(ZomesToInvoke::One(get_zome_name(&aet, &dna_file)?)),
+        Some(aet) => Ok(ZomesToInvoke::One(get_zome_name(&aet, dna_def)?)),
         None => match element.header() {
             Header::CreateLink(_) | Header::DeleteLink(_) => {
-                get_link_zome(element, dna_file, workspace, network).await
+                get_link_zome(element, dna_def, workspace, network).await
             }
             _ => Ok(ZomesToInvoke::All),
         },
@@ -439,24 +527,22 @@ async fn get_zomes_to_invoke(
 
 fn get_zome_info<'a>(
     entry_type: &AppEntryType,
-    dna_file: &'a DnaFile,
+    dna_def: &'a DnaDef,
 ) -> AppValidationResult<&'a (ZomeName, Zome)> {
     let zome_index = u8::from(entry_type.zome_id()) as usize;
-    Ok(dna_file
-        .dna()
+    Ok(dna_def
         .zomes
         .get(zome_index)
         .ok_or_else(|| AppValidationError::ZomeId(entry_type.zome_id()))?)
 }
 
-fn get_zome_name(entry_type: &AppEntryType, dna_file: &DnaFile) -> AppValidationResult<ZomeName> {
-    zome_id_to_zome_name(entry_type.zome_id(), dna_file)
+fn get_zome_name(entry_type: &AppEntryType, dna_def: &DnaDef) -> AppValidationResult<ZomeName> {
+    zome_id_to_zome_name(entry_type.zome_id(), dna_def)
 }
 
-fn zome_id_to_zome_name(zome_id: ZomeId, dna_file: &DnaFile) -> AppValidationResult<ZomeName> {
+fn zome_id_to_zome_name(zome_id: ZomeId, dna_def: &DnaDef) -> AppValidationResult<ZomeName> {
     let zome_index = u8::from(zome_id) as usize;
-    Ok(dna_file
-        .dna()
+    Ok(dna_def
         .zomes
         .get(zome_index)
         .ok_or_else(|| AppValidationError::ZomeId(zome_id))?
@@ -481,13 +567,13 @@ async fn get_app_entry_type(
 
 async fn get_link_zome(
     element: &Element,
-    dna_file: &DnaFile,
+    dna_def: &DnaDef,
     workspace: &mut AppValidationWorkspace,
     network: &HolochainP2pCell,
 ) -> AppValidationOutcome<ZomesToInvoke> {
     match element.header() {
         Header::CreateLink(cl) => {
-            let zome_name = zome_id_to_zome_name(cl.zome_id, dna_file)?;
+            let zome_name = zome_id_to_zome_name(cl.zome_id, dna_def)?;
             Ok(ZomesToInvoke::One(zome_name))
         }
         Header::DeleteLink(dl) => {
@@ -499,7 +585,7 @@ async fn get_link_zome(
 
             match shh.header() {
                 Header::CreateLink(cl) => {
-                    let zome_name = zome_id_to_zome_name(cl.zome_id, dna_file)?;
+                    let zome_name = zome_id_to_zome_name(cl.zome_id, dna_def)?;
                     Ok(ZomesToInvoke::One(zome_name))
                 }
                 // The header that was found was the wrong type
@@ -706,10 +792,19 @@ async fn get_validation_package_remote(
                 cascade.get_agent_activity(agent_id, query, options).await?
             };
             match activity {
+                // A forked chain is still handed to the validation callback
+                // so it can see the fork with `get_agent_activity` and decide
+                // what that means for the app rather than waiting forever
+                // on a package that will never arrive.
                 AgentActivity {
                     status: ChainStatus::Valid(_),
                     valid_activity: ChainItems::Full(elements),
                     ..
+                }
+                | AgentActivity {
+                    status: ChainStatus::Forked(_),
+                    valid_activity: ChainItems::Full(elements),
+                    ..
                 } => {
                     // Cache this as a validation package
                     workspace.meta_cache.register_validation_package(
@@ -759,8 +854,7 @@ async fn get_validation_package_remote(
                         _ => return Ok(None),
                     };
                     let zome_name = ribosome
-                        .dna_file()
-                        .dna()
+                        .dna_def()
                         .zomes
                         .get(app_entry_type.zome_id().index())
                         .ok_or_else(|| AppValidationError::ZomeId(app_entry_type.zome_id()))?
@@ -790,7 +884,7 @@ pub async fn run_validation_callback_direct(
     let outcome = {
         let mut workspace = workspace_lock.write().await;
         let cascade = workspace.cascade(network.clone());
-        get_associated_entry_def(&element, ribosome.dna_file(), conductor_api, cascade).await
+        get_associated_entry_def(&element, ribosome, conductor_api, cascade).await
     };
 
     // The outcome could be awaiting a dependency to get the entry def
@@ -840,7 +934,7 @@ fn run_validation_callback_inner(
     workspace_lock: CallZomeWorkspaceLock,
     network: HolochainP2pCell,
 ) -> AppValidationResult<Outcome> {
-    let validate: ValidateResult = ribosome.run_validate(
+    let validate = ribosome.run_validate(
         ValidateHostAccess::new(workspace_lock, network),
         ValidateInvocation {
             zomes_to_invoke,
@@ -848,7 +942,15 @@ fn run_validation_callback_inner(
             validation_package,
             entry_def_id,
         },
-    )?;
+    );
+    // The wasm limits are set by each conductor, so going over them
+    // says nothing about whether the op is valid.
+    let validate: ValidateResult = match validate {
+        Err(RibosomeError::WasmLimitExceeded(limit)) => {
+            return Ok(Outcome::Abandoned(limit.to_string()))
+        }
+        r => r?,
+    };
     match validate {
         ValidateResult::Valid => Ok(Outcome::Accepted),
         ValidateResult::Invalid(reason) => Ok(Outcome::Rejected(reason)),
@@ -897,7 +999,12 @@ pub fn run_link_validation_callback<I: Invocation + 'static>(
     network: HolochainP2pCell,
 ) -> AppValidationResult<Outcome> {
     let access = ValidateLinkHostAccess::new(workspace_lock, network);
-    let validate = ribosome.run_validate_link(access, invocation)?;
+    let validate = match ribosome.run_validate_link(access, invocation) {
+        Err(RibosomeError::WasmLimitExceeded(limit)) => {
+            return Ok(Outcome::Abandoned(limit.to_string()))
+        }
+        r => r?,
+    };
     match validate {
         ValidateLinkResult::Valid => Ok(Outcome::Accepted),
         ValidateLinkResult::Invalid(reason) => Ok(Outcome::Rejected(reason)),
@@ -924,6 +1031,8 @@ pub struct AppValidationWorkspace {
     // Cached data
     pub element_cache: ElementBuf,
     pub meta_cache: MetadataBuf,
+    // Warrants issued for rejected ops
+    pub warrants: WarrantsBuf,
     pub call_zome_workspace_lock: Option<CallZomeWorkspaceLock>,
 }
 
@@ -955,7 +1064,9 @@ impl AppValidationWorkspace {
         let element_authored = ElementBuf::authored(env.clone(), false)?;
         let meta_authored = MetadataBuf::authored(env.clone())?;
         let element_rejected = ElementBuf::rejected(env.clone())?;
-        let meta_rejected = MetadataBuf::rejected(env)?;
+        let meta_rejected = MetadataBuf::rejected(env.clone())?;
+
+        let warrants = WarrantsBuf::new(&env)?;
 
         Ok(Self {
             integrated_dht_ops,
@@ -971,6 +1082,7 @@ impl AppValidationWorkspace {
             meta_rejected,
             element_cache,
             meta_cache,
+            warrants,
             call_zome_workspace_lock,
         })
     }
@@ -1044,6 +1156,7 @@ impl Workspace for AppValidationWorkspace {
         self.integration_limbo.flush_to_txn_ref(writer)?;
         self.element_pending.flush_to_txn_ref(writer)?;
         self.meta_pending.flush_to_txn_ref(writer)?;
+        self.warrants.flush_to_txn_ref(writer)?;
 
         // Flush for cascade
         self.element_cache.flush_to_txn_ref(writer)?;
@@ -17,6 +17,11 @@ pub enum Outcome {
     AwaitingDeps(Vec<AnyDhtHash>),
     /// Moves to integration with status rejected
     Rejected(String),
+    /// This conductor couldn't finish validating the op, e.g. because
+    /// a callback went over its wasm limits. Other validators may well
+    /// succeed so it's never a rejection. Stays in limbo to be retried
+    /// and is abandoned after [MAX_ABANDON_TRIES](super::MAX_ABANDON_TRIES).
+    Abandoned(String),
 }
 
 impl Outcome {
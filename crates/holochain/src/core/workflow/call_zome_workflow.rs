@@ -348,7 +348,7 @@ pub mod tests {
     ) -> WorkflowResult<ZomeCallInvocationResult> {
         let keystore = fixt!(KeystoreSender);
         let network = fixt!(HolochainP2pCell);
-        let cell_id = CellId::new(ribosome.dna_file().dna_hash().clone(), fixt!(AgentPubKey));
+        let cell_id = CellId::new(ribosome.dna_hash().clone(), fixt!(AgentPubKey));
         let conductor_api = Arc::new(MockConductorHandleT::new());
         let conductor_api = CellConductorApi::new(conductor_api, cell_id);
         let args = CallZomeWorkflowArgs {
@@ -512,7 +512,7 @@ pub mod tests {
         let env = test_env.env();
         let workspace = CallZomeWorkspace::new(env.clone().into()).unwrap();
         let mut ribosome = MockRibosomeT::new();
-        ribosome.expect_dna_file().return_const(fixt!(DnaFile));
+        ribosome.expect_dna_hash().return_const(fixt!(DnaHash));
         ribosome.expect_call_zome_function().returning(|_, _| {
             Ok(ZomeCallResponse::Ok(ExternOutput::new(
                 ().try_into().unwrap(),
@@ -20,6 +20,7 @@ use derive_more::Constructor;
 use holochain_state::prelude::*;
 use holochain_types::dna::DnaFile;
 use holochain_types::prelude::*;
+use holochain_zome_types::dpki::KeyState;
 use tracing::*;
 
 /// The struct which implements the genesis Workflow
@@ -58,14 +59,17 @@ async fn genesis_workflow_inner<Api: CellConductorApiT>(
         membrane_proof,
     } = args;
 
-    // TODO: this is a placeholder for a real DPKI request to show intent
-    if api
-        .dpki_request("is_agent_pubkey_valid".into(), agent_pubkey.to_string())
-        .await
-        .expect("TODO: actually implement this")
-        == "INVALID"
-    {
-        return Err(WorkflowError::AgentInvalid(agent_pubkey.clone()));
+    // A key DPKI has never seen is registered so its use can be
+    // validated, while a key that was replaced or revoked can't be reused
+    match api.dpki_key_state(&agent_pubkey).await.map_err(Box::new)? {
+        Some(KeyState::NotFound) => api
+            .dpki_register_key(&agent_pubkey)
+            .await
+            .map_err(Box::new)?,
+        Some(key_state) if !key_state.is_current() => {
+            return Err(WorkflowError::AgentInvalid(agent_pubkey));
+        }
+        _ => (),
     }
 
     workspace
@@ -117,7 +121,7 @@ pub mod tests {
         observability,
         test_utils::{fake_agent_pubkey_1, fake_dna_file},
     };
-    use holochain_zome_types::Header;
+    use holochain_zome_types::{timestamp::Timestamp, Header};
     use matches::assert_matches;
 
     pub async fn fake_genesis(source_chain: &mut SourceChain) -> SourceChainResult<()> {
@@ -139,8 +143,7 @@ pub mod tests {
         {
             let workspace = GenesisWorkspace::new(arc.clone().into()).await?;
             let mut api = MockCellConductorApi::new();
-            api.expect_sync_dpki_request()
-                .returning(|_, _| Ok("mocked dpki request response".to_string()));
+            api.expect_sync_dpki_key_state().returning(|_| Ok(None));
             let args = GenesisWorkflowArgs {
                 dna_file: dna.clone(),
                 agent_pubkey: agent_pubkey.clone(),
@@ -171,6 +174,33 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn genesis_rejects_revoked_key() -> Result<(), anyhow::Error> {
+        observability::test_run()?;
+        let test_env = test_cell_env();
+        let arc = test_env.env();
+
+        let workspace = GenesisWorkspace::new(arc.clone().into()).await?;
+        let mut api = MockCellConductorApi::new();
+        api.expect_sync_dpki_key_state().returning(|_| {
+            Ok(Some(KeyState::Revoked {
+                registered_at: Timestamp(0, 0),
+                revoked_at: Timestamp(1, 0),
+            }))
+        });
+        let args = GenesisWorkflowArgs {
+            dna_file: fake_dna_file("a"),
+            agent_pubkey: fake_agent_pubkey_1(),
+            membrane_proof: None,
+        };
+        let result = genesis_workflow(workspace, arc.clone().into(), api, args).await;
+        assert_matches!(result, Err(WorkflowError::AgentInvalid(_)));
+
+        let source_chain = SourceChain::new(arc.clone().into())?;
+        assert!(source_chain.chain_head().is_err());
+        Ok(())
+    }
 }
 
 /* TODO: make doc-able
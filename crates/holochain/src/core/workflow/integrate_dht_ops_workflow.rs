@@ -2,7 +2,7 @@
 
 use super::*;
 use crate::core::{
-    queue_consumer::{OneshotWriter, TriggerSender, WorkComplete},
+    queue_consumer::{OneshotWriter, PendingOps, TriggerSender, WorkComplete},
     state::{
         cascade::error::CascadeResult,
         cascade::Cascade,
@@ -53,11 +53,12 @@ pub use disintegrate::*;
 mod disintegrate;
 mod tests;
 
-#[instrument(skip(workspace, writer, trigger_sys))]
+#[instrument(skip(workspace, writer, trigger_sys, pending_ops))]
 pub async fn integrate_dht_ops_workflow(
     mut workspace: IntegrateDhtOpsWorkspace,
     writer: OneshotWriter,
     trigger_sys: &mut TriggerSender,
+    pending_ops: &PendingOps,
 ) -> WorkflowResult<WorkComplete> {
     // one of many possible ways to access the env
     let env = workspace.elements.headers().env().clone();
@@ -128,6 +129,7 @@ pub async fn integrate_dht_ops_workflow(
         }
     }
 
+    let awaiting_integration = sorted_ops.len();
     let result = if sorted_ops.is_empty() {
         // There were no ops deferred, meaning we exhausted the queue
         WorkComplete::Complete
@@ -147,6 +149,7 @@ pub async fn integrate_dht_ops_workflow(
 
     // commit the workspace
     writer.with_writer(|writer| Ok(workspace.flush_to_txn(writer)?))?;
+    pending_ops.integrated(awaiting_integration);
 
     // trigger other workflows
 
@@ -542,7 +542,7 @@ impl Db {
 async fn call_workflow<'env>(env: EnvironmentWrite) {
     let workspace = IntegrateDhtOpsWorkspace::new(env.clone().into()).unwrap();
     let (mut qt, _rx) = TriggerSender::new();
-    integrate_dht_ops_workflow(workspace, env.clone().into(), &mut qt)
+    integrate_dht_ops_workflow(workspace, env.clone().into(), &mut qt, &Default::default())
         .await
         .unwrap();
 }
@@ -895,7 +895,8 @@ async fn commit_entry<'env>(
     // Create ribosome mock to return fixtures
     // This is a lot faster then compiling a zome
     let mut ribosome = MockRibosomeT::new();
-    ribosome.expect_dna_file().return_const(dna_file);
+    ribosome.expect_dna_def().return_const(dna_file.dna.clone());
+    ribosome.expect_dna_hash().return_const(dna_file.dna_hash);
     ribosome
         .expect_zome_name_to_id()
         .returning(|_| Ok(ZomeId::from(1)));
@@ -991,7 +992,8 @@ async fn create_link(
     // Create ribosome mock to return fixtures
     // This is a lot faster then compiling a zome
     let mut ribosome = MockRibosomeT::new();
-    ribosome.expect_dna_file().return_const(dna_file);
+    ribosome.expect_dna_def().return_const(dna_file.dna.clone());
+    ribosome.expect_dna_hash().return_const(dna_file.dna_hash);
     ribosome
         .expect_zome_name_to_id()
         .returning(|_| Ok(ZomeId::from(1)));
@@ -1046,7 +1048,8 @@ async fn get_links(
     // Create ribosome mock to return fixtures
     // This is a lot faster then compiling a zome
     let mut ribosome = MockRibosomeT::new();
-    ribosome.expect_dna_file().return_const(dna_file);
+    ribosome.expect_dna_def().return_const(dna_file.dna.clone());
+    ribosome.expect_dna_hash().return_const(dna_file.dna_hash);
     ribosome
         .expect_zome_name_to_id()
         .returning(|_| Ok(ZomeId::from(1)));
@@ -14,6 +14,7 @@ use super::{
     error::WorkflowResult,
     produce_dht_ops_workflow::dht_op_light::{error::DhtOpConvertError, light_to_op},
 };
+use crate::conductor::metrics;
 use crate::core::{
     queue_consumer::{OneshotWriter, WorkComplete},
     state::{
@@ -67,6 +68,7 @@ pub async fn publish_dht_ops_workflow(
 
     // Commit to the network
     for (basis, ops) in to_publish {
+        metrics::record_publish(ops.len());
         network.publish(true, basis, ops, None).await?;
     }
     // --- END OF WORKFLOW, BEGIN FINISHER BOILERPLATE ---
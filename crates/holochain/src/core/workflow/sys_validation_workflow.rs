@@ -5,7 +5,7 @@ use super::*;
 use crate::{
     conductor::api::CellConductorApiT,
     core::{
-        queue_consumer::{OneshotWriter, TriggerSender, WorkComplete},
+        queue_consumer::{OneshotWriter, PendingOps, TriggerSender, ValidationTally, WorkComplete},
         state::{
             cascade::Cascade,
             cascade::DbPair,
@@ -14,6 +14,7 @@ use crate::{
             element_buf::ElementBuf,
             metadata::MetadataBuf,
             validation_db::{ValidationLimboStatus, ValidationLimboStore, ValidationLimboValue},
+            warrants_db::WarrantsBuf,
             workspace::{Workspace, WorkspaceError, WorkspaceResult},
         },
         sys_validate::*,
@@ -60,7 +61,8 @@ mod tests;
     trigger_app_validation,
     sys_validation_trigger,
     network,
-    conductor_api
+    conductor_api,
+    pending_ops
 ))]
 pub async fn sys_validation_workflow(
     mut workspace: SysValidationWorkspace,
@@ -69,12 +71,15 @@ pub async fn sys_validation_workflow(
     sys_validation_trigger: TriggerSender,
     network: HolochainP2pCell,
     conductor_api: impl CellConductorApiT,
+    pending_ops: &PendingOps,
 ) -> WorkflowResult<WorkComplete> {
+    let mut tally = ValidationTally::default();
     let complete = sys_validation_workflow_inner(
         &mut workspace,
         network,
         conductor_api,
         sys_validation_trigger,
+        &mut tally,
     )
     .await?;
 
@@ -82,6 +87,7 @@ pub async fn sys_validation_workflow(
 
     // commit the workspace
     writer.with_writer(|writer| Ok(workspace.flush_to_txn_ref(writer)?))?;
+    pending_ops.validated(tally);
 
     // trigger other workflows
     trigger_app_validation.trigger();
@@ -91,19 +97,24 @@ pub async fn sys_validation_workflow(
 
 async fn sys_validation_workflow_inner(
     workspace: &mut SysValidationWorkspace,
-    network: HolochainP2pCell,
+    mut network: HolochainP2pCell,
     conductor_api: impl CellConductorApiT,
     sys_validation_trigger: TriggerSender,
+    tally: &mut ValidationTally,
 ) -> WorkflowResult<WorkComplete> {
     let env = workspace.validation_limbo.env().clone();
+    let mut issued_warrants = Vec::new();
     // Drain all the ops
     let sorted_ops: BinaryHeap<OrderedOp<ValidationLimboValue>> = fresh_reader!(env, |r| {
         let validation_limbo = &mut workspace.validation_limbo;
         let element_pending = &workspace.element_pending;
 
+        let in_validation_limbo = &mut tally.in_validation_limbo;
+
         let sorted_ops: Result<BinaryHeap<OrderedOp<ValidationLimboValue>>, WorkflowError> =
             validation_limbo
                 .drain_iter_filter(&r, |(_, vlv)| {
+                    *in_validation_limbo += 1;
                     match vlv.status {
                         // We only want pending or awaiting sys dependency ops
                         ValidationLimboStatus::Pending
@@ -167,6 +178,7 @@ async fn sys_validation_workflow_inner(
                     validation_status: ValidationStatus::Valid,
                 };
                 workspace.put_int_limbo(op_hash, iv)?;
+                tally.to_integration += 1;
             }
             Outcome::AwaitingOpDep(missing_dep) => {
                 // TODO: Try and get this dependency to add to limbo
@@ -185,15 +197,42 @@ async fn sys_validation_workflow_inner(
                 vlv.status = ValidationLimboStatus::Pending;
                 workspace.put_val_limbo(op_hash, vlv)?;
             }
-            Outcome::Rejected => {
+            Outcome::Rejected(reason) => {
+                warrant_rejected_op(
+                    &op,
+                    reason,
+                    &conductor_api,
+                    &mut workspace.warrants,
+                    &mut issued_warrants,
+                )
+                .await?;
+                let iv = IntegrationLimboValue {
+                    op: vlv.op,
+                    validation_status: ValidationStatus::Rejected,
+                };
+                workspace.put_int_limbo(op_hash, iv)?;
+                tally.to_integration += 1;
+            }
+            Outcome::Abandoned => {
+                let iv = IntegrationLimboValue {
+                    op: vlv.op,
+                    validation_status: ValidationStatus::Abandoned,
+                };
+                workspace.put_int_limbo(op_hash, iv)?;
+                tally.to_integration += 1;
+            }
+            Outcome::RejectedBadSignature => {
                 let iv = IntegrationLimboValue {
                     op: vlv.op,
                     validation_status: ValidationStatus::Rejected,
                 };
                 workspace.put_int_limbo(op_hash, iv)?;
+                tally.to_integration += 1;
             }
         }
     }
+
+    publish_warrants(&mut network, issued_warrants).await;
     Ok(WorkComplete::Complete)
 }
 
@@ -234,34 +273,45 @@ async fn validate_op(
     }
 }
 
-/// For now errors result in an outcome but in the future
-/// we might find it useful to include the reason something
-/// was rejected etc.
-/// This is why the errors contain data but is currently unread.
+/// Whether an element failing sys validation for this reason is
+/// rejected with a warrant against its author
+pub fn is_warranted(outcome: ValidationOutcome) -> bool {
+    match outcome {
+        // A counterfeit doesn't prove who authored it
+        ValidationOutcome::Counterfeit(_, _) => false,
+        outcome => matches!(handle_failed(outcome), Outcome::Rejected(_)),
+    }
+}
+
+/// Turn a failed validation into an outcome.
+/// Rejections carry the error as the reason for the warrant.
 fn handle_failed(error: ValidationOutcome) -> Outcome {
     use Outcome::*;
+    let reason = error.to_string();
     match error {
+        ValidationOutcome::AuthorKeyRevoked(_) => Abandoned,
+        ValidationOutcome::AuthorKeyUnknown(_) => MissingDhtDep,
         ValidationOutcome::Counterfeit(_, _) => {
             unreachable!("Counterfeit ops are dropped before sys validation")
         }
         ValidationOutcome::DepMissingFromDht(_) => MissingDhtDep,
-        ValidationOutcome::EntryDefId(_) => Rejected,
-        ValidationOutcome::EntryHash => Rejected,
-        ValidationOutcome::EntryTooLarge(_, _) => Rejected,
-        ValidationOutcome::EntryType => Rejected,
-        ValidationOutcome::EntryVisibility(_) => Rejected,
-        ValidationOutcome::TagTooLarge(_, _) => Rejected,
-        ValidationOutcome::NotCreateLink(_) => Rejected,
-        ValidationOutcome::NotNewEntry(_) => Rejected,
+        ValidationOutcome::EntryDefId(_) => Rejected(reason),
+        ValidationOutcome::EntryHash => Rejected(reason),
+        ValidationOutcome::EntryTooLarge(_, _) => Rejected(reason),
+        ValidationOutcome::EntryType => Rejected(reason),
+        ValidationOutcome::EntryVisibility(_) => Rejected(reason),
+        ValidationOutcome::TagTooLarge(_, _) => Rejected(reason),
+        ValidationOutcome::NotCreateLink(_) => Rejected(reason),
+        ValidationOutcome::NotNewEntry(_) => Rejected(reason),
         ValidationOutcome::NotHoldingDep(dep) => AwaitingOpDep(dep),
         ValidationOutcome::PrevHeaderError(PrevHeaderError::MissingMeta(dep)) => {
             AwaitingOpDep(dep.into())
         }
-        ValidationOutcome::PrevHeaderError(_) => Rejected,
-        ValidationOutcome::PrivateEntry => Rejected,
-        ValidationOutcome::UpdateTypeMismatch(_, _) => Rejected,
-        ValidationOutcome::VerifySignature(_, _) => Rejected,
-        ValidationOutcome::ZomeId(_) => Rejected,
+        ValidationOutcome::PrevHeaderError(_) => Rejected(reason),
+        ValidationOutcome::PrivateEntry => Rejected(reason),
+        ValidationOutcome::UpdateTypeMismatch(_, _) => Rejected(reason),
+        ValidationOutcome::VerifySignature(_, _) => RejectedBadSignature,
+        ValidationOutcome::ZomeId(_) => Rejected(reason),
     }
 }
 
@@ -272,6 +322,7 @@ async fn validate_op_inner(
     conductor_api: &impl CellConductorApiT,
     incoming_dht_ops_sender: Option<IncomingDhtOpSender>,
 ) -> SysValidationResult<()> {
+    check_author_key_valid(&op.header(), conductor_api).await?;
     match op {
         DhtOp::StoreElement(_, header, entry) => {
             store_element(header, workspace, network.clone()).await?;
@@ -410,6 +461,7 @@ async fn sys_validate_element_inner(
     if !counterfeit_check(signature, header).await? {
         return Err(ValidationOutcome::Counterfeit(signature.clone(), header.clone()).into());
     }
+    check_author_key_valid(header, conductor_api).await?;
     store_element(header, workspace, network.clone()).await?;
     if let Some((entry, EntryVisibility::Public)) =
         &entry.and_then(|e| header.entry_type().map(|et| (e, et.visibility())))
@@ -444,14 +496,15 @@ async fn sys_validate_element_inner(
     Ok(())
 }
 
-/// Check if the op has valid signature and author.
+/// Check if the op has a valid signature from its author.
 /// Ops that fail this check should be dropped.
+/// Whether the author's key was valid is checked during
+/// sys validation, as that needs the conductor's DPKI app.
 pub async fn counterfeit_check(
     signature: &Signature,
     header: &Header,
 ) -> SysValidationResult<bool> {
-    Ok(verify_header_signature(&signature, &header).await?
-        && author_key_is_valid(header.author()).await?)
+    verify_header_signature(&signature, &header).await
 }
 
 async fn register_agent_activity(
@@ -467,12 +520,19 @@ async fn register_agent_activity(
     check_prev_header(&header)?;
     check_valid_if_dna(&header, &workspace.meta_vault).await?;
     if let Some(prev_header_hash) = prev_header_hash {
+        // Every header that shares a previous header must also share a
+        // sequence number so forks can be found by sequence alone.
         check_and_hold_register_agent_activity(
             prev_header_hash,
             workspace,
             network,
             incoming_dht_ops_sender,
-            |_| Ok(()),
+            |prev_header| {
+                check_prev_author(&header, prev_header.header())?;
+                check_prev_timestamp(&header, prev_header.header())?;
+                check_prev_seq(&header, prev_header.header())?;
+                Ok(())
+            },
         )
         .await?;
     }
@@ -496,6 +556,7 @@ async fn store_element(
             .retrieve_header(prev_header_hash.clone(), Default::default())
             .await?
             .ok_or_else(|| ValidationOutcome::DepMissingFromDht(prev_header_hash.clone().into()))?;
+        check_prev_author(&header, prev_header.header())?;
         check_prev_timestamp(&header, prev_header.header())?;
         check_prev_seq(&header, prev_header.header())?;
     }
@@ -709,6 +770,8 @@ pub struct SysValidationWorkspace {
     /// Cached data
     pub element_cache: ElementBuf,
     pub meta_cache: MetadataBuf,
+    /// Warrants issued for rejected ops
+    pub warrants: WarrantsBuf,
     pub env: EnvironmentRead,
 }
 
@@ -751,6 +814,8 @@ impl SysValidationWorkspace {
         let element_rejected = ElementBuf::rejected(env.clone())?;
         let meta_rejected = MetadataBuf::rejected(env.clone())?;
 
+        let warrants = WarrantsBuf::new(&env)?;
+
         Ok(Self {
             integration_limbo,
             validation_limbo,
@@ -764,6 +829,7 @@ impl SysValidationWorkspace {
             meta_authored,
             element_cache,
             meta_cache,
+            warrants,
             env,
         })
     }
@@ -847,6 +913,7 @@ impl Workspace for SysValidationWorkspace {
 
         self.element_pending.flush_to_txn_ref(writer)?;
         self.meta_pending.flush_to_txn_ref(writer)?;
+        self.warrants.flush_to_txn_ref(writer)?;
         Ok(())
     }
 }
@@ -2,18 +2,23 @@ use std::{convert::TryFrom, time::Duration};
 
 use holochain_keystore::AgentPubKeyExt;
 use holochain_serialized_bytes::SerializedBytes;
+use holochain_state::test_utils::test_cell_env;
 use holochain_types::{
     app::InstalledCell,
     cell::CellId,
     dna::{DnaDef, DnaFile},
+    element::SignedHeaderHashed,
+    fixt::SignatureFixturator,
 };
 use holochain_wasm_test_utils::TestWasm;
-use holochain_zome_types::test_utils::fake_agent_pubkey_1;
+use holochain_zome_types::{header::Create, test_utils::fake_agent_pubkey_1};
+use matches::assert_matches;
 
 use super::*;
 use crate::{
-    conductor::ConductorHandle, core::state::source_chain::SourceChain, test_utils::setup_app,
-    test_utils::wait_for_integration,
+    conductor::ConductorHandle,
+    core::state::source_chain::SourceChain,
+    test_utils::{setup_app, test_network, wait_for_integration},
 };
 use ::fixt::prelude::*;
 use holochain_zome_types::fixt::*;
@@ -191,3 +196,97 @@ async fn run_test(alice_cell_id: CellId, handle: ConductorHandle) {
     // Check you **do** see any warning output
     // TODO: When we add invalid chains put a real check here
 }
+
+/// Put a previous header in the vault and register activity for a
+/// header built on top of it.
+async fn register_after(prev: Create, header: Create) -> SysValidationResult<()> {
+    let test_env = test_cell_env();
+    let env: EnvironmentRead = test_env.env().into();
+    let mut workspace = SysValidationWorkspace::new(env).unwrap();
+    let prev = SignedHeaderHashed::with_presigned(
+        HeaderHashed::from_content_sync(prev.into()),
+        fixt!(Signature),
+    );
+    workspace.element_vault.put(prev, None).unwrap();
+
+    let test_network = test_network(None, Some(header.author.clone())).await;
+
+    register_agent_activity(
+        &header.into(),
+        &mut workspace,
+        test_network.cell_network(),
+        None,
+    )
+    .await
+}
+
+/// Make a header that follows `prev` with the
+/// given sequence number and timestamp.
+fn next_header(prev: &Create, header_seq: u32, timestamp: Timestamp) -> Create {
+    let mut header = fixt!(Create);
+    header.author = prev.author.clone();
+    header.prev_header = HeaderHash::with_data_sync(&Header::Create(prev.clone()));
+    header.header_seq = header_seq;
+    header.timestamp = timestamp.into();
+    header
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn register_agent_activity_checks_prev_header() {
+    observability::test_run().ok();
+    let start = Timestamp::now();
+    let mut prev = fixt!(Create);
+    prev.author = fake_agent_pubkey_1();
+    prev.header_seq = 3;
+    prev.timestamp = start.clone().into();
+    let later = Timestamp(start.0, start.1 + 1);
+
+    // Follows the previous header
+    let header = next_header(&prev, 4, later.clone());
+    assert_matches!(register_after(prev.clone(), header).await, Ok(()));
+
+    // Sequence number goes backwards
+    let header = next_header(&prev, 2, later.clone());
+    assert_matches!(
+        register_after(prev.clone(), header).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::InvalidSeq(2, 3))
+        ))
+    );
+
+    // Sequence number repeats the previous header's
+    let header = next_header(&prev, 3, later.clone());
+    assert_matches!(
+        register_after(prev.clone(), header).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::InvalidSeq(3, 3))
+        ))
+    );
+
+    // Sequence number skips ahead
+    let header = next_header(&prev, 5, later);
+    assert_matches!(
+        register_after(prev.clone(), header).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::InvalidSeq(5, 3))
+        ))
+    );
+
+    // Timestamp is the same as the previous header's
+    let header = next_header(&prev, 4, start.clone());
+    assert_matches!(
+        register_after(prev.clone(), header).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::Timestamp)
+        ))
+    );
+
+    // Timestamp goes backwards
+    let header = next_header(&prev, 4, Timestamp(start.0 - 1, start.1));
+    assert_matches!(
+        register_after(prev, header).await,
+        Err(SysValidationError::ValidationOutcome(
+            ValidationOutcome::PrevHeaderError(PrevHeaderError::Timestamp)
+        ))
+    );
+}
@@ -1,8 +1,16 @@
 use crate::{
-    conductor::ConductorHandle,
+    conductor::{api::CellConductorApi, ConductorHandle},
     core::{
-        state::{element_buf::ElementBuf, validation_db::ValidationLimboStatus},
-        workflow::incoming_dht_ops_workflow::IncomingDhtOpsWorkspace,
+        state::{
+            element_buf::ElementBuf,
+            source_chain::SourceChain,
+            validation_db::ValidationLimboStatus,
+            warrants_db::{verify_warrant, WarrantsBuf},
+        },
+        workflow::{
+            app_validation_workflow::confirm_warranted_element,
+            incoming_dht_ops_workflow::{incoming_dht_ops_workflow, IncomingDhtOpsWorkspace},
+        },
     },
     test_utils::{host_fn_api::*, setup_app, wait_for_integration},
 };
@@ -10,16 +18,23 @@ use ::fixt::prelude::*;
 use fallible_iterator::FallibleIterator;
 use hdk3::prelude::LinkTag;
 use holo_hash::{AnyDhtHash, DhtOpHash, EntryHash, HeaderHash};
+use holochain_keystore::{AgentPubKeyExt, KeystoreSenderExt};
+use holochain_p2p::actor::HolochainP2pRefToCell;
 use holochain_serialized_bytes::SerializedBytes;
-use holochain_state::{fresh_reader_test, prelude::ReadManager};
+use holochain_state::{
+    fresh_reader_test,
+    prelude::{BufferedStore, ReadManager, WriteManager},
+};
 use holochain_types::{
-    app::InstalledCell, cell::CellId, dht_op::DhtOpLight, dna::DnaDef, dna::DnaFile, fixt::*,
-    test_utils::fake_agent_pubkey_1, test_utils::fake_agent_pubkey_2, validate::ValidationStatus,
-    Entry,
+    app::InstalledCell, cell::CellId, dht_op::DhtOp, dht_op::DhtOpLight, dna::DnaDef, dna::DnaFile,
+    fixt::*, test_utils::fake_agent_pubkey_1, test_utils::fake_agent_pubkey_2,
+    validate::ValidationStatus, Entry, Timestamp,
 };
 use holochain_wasm_test_utils::TestWasm;
+use holochain_zome_types::Header;
 use matches::assert_matches;
 use std::{
+    collections::HashSet,
     convert::{TryFrom, TryInto},
     time::Duration,
 };
@@ -77,7 +92,7 @@ async fn run_test(
     let num_attempts = 100;
     let delay_per_attempt = Duration::from_millis(100);
 
-    bob_links_in_a_legit_way(&bob_cell_id, &handle, &dna_file).await;
+    let legit_link_hash = bob_links_in_a_legit_way(&bob_cell_id, &handle, &dna_file).await;
 
     // Integration should have 9 ops in it.
     // Plus another 14 for genesis.
@@ -244,6 +259,41 @@ async fn run_test(
             "{:?}",
             int_limbo,
         );
+
+        // Alice holds a signed warrant against Bob for the headers she rejected
+        let warrants_buf = WarrantsBuf::new(&alice_env).unwrap();
+        let warrants: Vec<_> = fresh_reader_test!(alice_env, |r| warrants_buf
+            .list_warrants(&r, bob_cell_id.agent_pubkey())
+            .unwrap()
+            .collect()
+            .unwrap());
+        let warranted: HashSet<_> = warrants.iter().map(|w| &w.warrant().header).collect();
+        assert!(warranted.contains(&bad_update_header));
+        assert!(warranted.contains(&link_add_hash));
+        for warrant in warrants.iter() {
+            assert!(verify_warrant(warrant).await.unwrap());
+        }
+
+        // Alice can confirm a warrant's claim by validating the element again
+        let bob_env = handle.get_cell_env(&bob_cell_id).await.unwrap();
+        let bob_chain = SourceChain::new(bob_env.into()).unwrap();
+        let conductor_api = CellConductorApi::new(handle.clone(), alice_cell_id.clone());
+        let network = handle.holochain_p2p().to_cell(
+            alice_cell_id.dna_hash().clone(),
+            alice_cell_id.agent_pubkey().clone(),
+        );
+        for (header_hash, warranted) in vec![(link_add_hash, true), (legit_link_hash, false)] {
+            let element = bob_chain.get_element(&header_hash).unwrap().unwrap();
+            let confirmed = confirm_warranted_element(
+                element,
+                &conductor_api,
+                alice_env.clone().into(),
+                network.clone(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(confirmed, warranted, "{:?}", header_hash);
+        }
     }
 
     dodgy_bob(&bob_cell_id, &handle, &dna_file).await;
@@ -418,6 +468,123 @@ async fn dodgy_bob(bob_cell_id: &CellId, handle: &ConductorHandle, dna_file: &Dn
     triggers.produce_dht_ops.trigger();
 }
 
+#[tokio::test(threaded_scheduler)]
+async fn rotated_agent_key_test() {
+    observability::test_run().ok();
+
+    let dna_file = DnaFile::new(
+        DnaDef {
+            name: "rotated_agent_key_test".to_string(),
+            uuid: "ba1d046d-ce29-4778-914b-47e6010d2faf".to_string(),
+            properties: SerializedBytes::try_from(()).unwrap(),
+            zomes: vec![TestWasm::Create.into()].into(),
+        },
+        vec![TestWasm::Create.into()],
+    )
+    .await
+    .unwrap();
+
+    let alice_agent_id = fake_agent_pubkey_1();
+    let alice_cell_id = CellId::new(dna_file.dna_hash().to_owned(), alice_agent_id.clone());
+    let alice_installed_cell = InstalledCell::new(alice_cell_id.clone(), "alice_handle".into());
+
+    let (_tmpdir, _app_api, handle) = setup_app(
+        vec![("test_app", vec![(alice_installed_cell, None)])],
+        vec![dna_file.clone()],
+    )
+    .await;
+
+    let num_attempts = 100;
+    let delay_per_attempt = Duration::from_millis(100);
+    let alice_env = handle.get_cell_env(&alice_cell_id).await.unwrap();
+    let mut triggers = handle.get_cell_triggers(&alice_cell_id).await.unwrap();
+    wait_for_integration(&alice_env, 7, num_attempts, delay_per_attempt.clone()).await;
+
+    // Rotate alice's key
+    let new_key = alice_env
+        .keystore()
+        .generate_sign_keypair_from_pure_entropy()
+        .await
+        .unwrap();
+    let mut source_chain = SourceChain::new(alice_env.clone().into()).unwrap();
+    let rotation_hash = source_chain
+        .put_agent_key_update(new_key.clone())
+        .await
+        .unwrap();
+    alice_env
+        .with_commit(|writer| source_chain.flush_to_txn_ref(writer))
+        .unwrap();
+
+    // Commit with the new key
+    let call_data = HostFnApi::create(&alice_cell_id, &handle, &dna_file).await;
+    let post = Post("Alice has a new key".into());
+    let post_hash = call_data
+        .commit_entry(post.try_into().unwrap(), POST_ID)
+        .await;
+    triggers.produce_dht_ops.trigger();
+
+    // Genesis, the key update and the post
+    wait_for_integration(
+        &alice_env,
+        7 + 5 + 3,
+        num_attempts,
+        delay_per_attempt.clone(),
+    )
+    .await;
+
+    // The old key tries to carry on the chain after its update
+    let mut forged = fixt!(Create);
+    forged.author = alice_agent_id.clone();
+    forged.prev_header = rotation_hash;
+    forged.header_seq = 4;
+    forged.timestamp = Timestamp::now().into();
+    let forged = Header::Create(forged);
+    let forged_hash = HeaderHash::with_data_sync(&forged);
+    let signature = alice_agent_id
+        .sign(&alice_env.keystore(), &forged)
+        .await
+        .unwrap();
+    let op = DhtOp::RegisterAgentActivity(signature, forged);
+    incoming_dht_ops_workflow(
+        &alice_env,
+        triggers.sys_validation.clone(),
+        vec![(DhtOpHash::with_data_sync(&op), op)],
+        None,
+    )
+    .await
+    .unwrap();
+
+    wait_for_integration(&alice_env, 7 + 5 + 3 + 1, num_attempts, delay_per_attempt).await;
+
+    let workspace = IncomingDhtOpsWorkspace::new(alice_env.clone().into()).unwrap();
+    let integrated: Vec<_> = fresh_reader_test!(alice_env, |r| {
+        workspace
+            .integrated_dht_ops
+            .iter(&r)
+            .unwrap()
+            .map(|(_, i)| Ok(i))
+            .collect()
+            .unwrap()
+    });
+    let status_of = |hash: &HeaderHash| {
+        integrated
+            .iter()
+            .filter(|i| i.op.header_hash() == hash)
+            .map(|i| i.validation_status)
+            .collect::<Vec<_>>()
+    };
+    // The chain carries on under the new key
+    let post_status = status_of(&post_hash);
+    assert!(!post_status.is_empty());
+    assert!(post_status.iter().all(|s| *s == ValidationStatus::Valid));
+    // The old key was revoked by the update
+    assert_eq!(status_of(&forged_hash), vec![ValidationStatus::Rejected]);
+
+    let shutdown = handle.take_shutdown_handle().await.unwrap();
+    handle.shutdown().await;
+    shutdown.await.unwrap();
+}
+
 //////////////////////
 //// Test Ideas
 //////////////////////
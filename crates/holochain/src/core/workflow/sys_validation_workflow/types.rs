@@ -15,5 +15,15 @@ pub(super) enum Outcome {
     /// Note this is not proof it doesn't exist.
     MissingDhtDep,
     /// Moves to integration with status rejected
-    Rejected,
+    /// and a warrant is issued against the author for this reason.
+    /// Only for rejections every validator reaches from the same data.
+    Rejected(String),
+    /// Moves to integration with status rejected.
+    /// No warrant is issued because a bad signature
+    /// doesn't prove who authored the op.
+    RejectedBadSignature,
+    /// Moves to integration with status abandoned.
+    /// This conductor's DPKI app has revoked the author's key,
+    /// which other validators may not know yet, so it's never a rejection.
+    Abandoned,
 }
@@ -88,9 +88,7 @@ impl Iterator for WasmRibosomeFixturator<curve::Zomes> {
 
         // warm the module cache for each wasm in the ribosome
         for zome in self.0.curve.0.clone() {
-            let mut call_context = CallContextFixturator::new(Empty).next().unwrap();
-            call_context.zome_name = zome.into();
-            ribosome.module(call_context).unwrap();
+            ribosome.module(&zome.into()).unwrap();
         }
 
         self.0.index += 1;
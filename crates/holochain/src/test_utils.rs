@@ -53,6 +53,9 @@ pub mod host_fn_api;
 #[cfg(any(test, feature = "test_utils"))]
 pub mod conductor_setup;
 
+#[cfg(any(test, feature = "test_utils"))]
+pub mod conductor_network;
+
 /// Produce file and line number info at compile-time
 #[macro_export]
 macro_rules! here {
@@ -176,6 +179,13 @@ impl TestNetwork {
     pub fn cell_network(&self) -> HolochainP2pCell {
         self.cell_network.clone()
     }
+
+    /// The urls this network's transports are bound to.
+    /// For a [test_network_mem] these can be used to inject faults with
+    /// the functions in `kitsune_p2p_types::transport_mem`.
+    pub async fn transport_urls(&self) -> Vec<url2::Url2> {
+        self.network().list_transport_bindings().await.unwrap()
+    }
 }
 
 impl Drop for TestNetwork {
@@ -195,7 +205,24 @@ pub async fn test_network(
     dna_hash: Option<DnaHash>,
     agent_key: Option<AgentPubKey>,
 ) -> TestNetwork {
-    test_network_inner::<fn(&HolochainP2pEvent) -> bool>(dna_hash, agent_key, None).await
+    test_network_inner::<fn(&HolochainP2pEvent) -> bool>(
+        KitsuneP2pConfig::default(),
+        dna_hash,
+        agent_key,
+        None,
+    )
+    .await
+}
+
+/// Convenience constructor for cell networks bound to the mem transport
+/// so that faults like latency, message loss and partitions can be injected
+pub async fn test_network_mem(
+    dna_hash: Option<DnaHash>,
+    agent_key: Option<AgentPubKey>,
+) -> TestNetwork {
+    let mut config = KitsuneP2pConfig::default();
+    config.transport_pool = vec![kitsune_p2p::TransportConfig::Mem {}];
+    test_network_inner::<fn(&HolochainP2pEvent) -> bool>(config, dna_hash, agent_key, None).await
 }
 
 /// Convenience constructor for cell networks
@@ -209,10 +236,17 @@ pub async fn test_network_with_events<F>(
 where
     F: Fn(&HolochainP2pEvent) -> bool + Send + 'static,
 {
-    test_network_inner(dna_hash, agent_key, Some((filter, evt_send))).await
+    test_network_inner(
+        KitsuneP2pConfig::default(),
+        dna_hash,
+        agent_key,
+        Some((filter, evt_send)),
+    )
+    .await
 }
 
 async fn test_network_inner<F>(
+    config: KitsuneP2pConfig,
     dna_hash: Option<DnaHash>,
     agent_key: Option<AgentPubKey>,
     mut events: Option<(F, mpsc::Sender<HolochainP2pEvent>)>,
@@ -220,10 +254,7 @@ async fn test_network_inner<F>(
 where
     F: Fn(&HolochainP2pEvent) -> bool + Send + 'static,
 {
-    let (network, mut recv) =
-        spawn_holochain_p2p(holochain_p2p::kitsune_p2p::KitsuneP2pConfig::default())
-            .await
-            .unwrap();
+    let (network, mut recv) = spawn_holochain_p2p(config).await.unwrap();
     let respond_task = tokio::task::spawn(async move {
         use futures::future::FutureExt;
         use tokio::stream::StreamExt;
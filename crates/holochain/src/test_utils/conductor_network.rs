@@ -0,0 +1,303 @@
+//! A harness for running scenarios across many conductors in one process.
+//!
+//! Every conductor binds the in-memory transport and has a single agent
+//! running the same DNA. There is no bootstrap service on the mem transport
+//! so the harness copies peer info between the conductors' p2p stores
+//! to let them find each other.
+
+use super::{conductor_setup::CellHostFnApi, install_app};
+use crate::{
+    conductor::{
+        config::{AdminInterfaceConfig, ConductorConfig, InterfaceDriver},
+        p2p_store::{all_agent_infos, get_single_agent_info, inject_agent_infos},
+        ConductorBuilder, ConductorHandle,
+    },
+    core::workflow::incoming_dht_ops_workflow::IncomingDhtOpsWorkspace,
+};
+use fallible_iterator::FallibleIterator;
+use holochain_keystore::KeystoreSenderExt;
+use holochain_serialized_bytes::SerializedBytes;
+use holochain_state::{fresh_reader_test, test_utils::test_environments};
+use holochain_types::{app::InstalledCell, cell::CellId, dna::DnaDef, dna::DnaFile};
+use holochain_wasm_test_utils::TestWasm;
+use kitsune_p2p::{
+    agent_store::AgentInfo,
+    dependencies::kitsune_p2p_types::transport_mem::{heal_mem_partition, set_mem_partition},
+    KitsuneP2pConfig, TransportConfig,
+};
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tempdir::TempDir;
+use url2::Url2;
+
+/// How many times to check for peer info before giving up
+const PEER_INFO_ATTEMPTS: usize = 100;
+/// How long to wait between checks for peer info
+const PEER_INFO_DELAY: Duration = Duration::from_millis(50);
+
+/// Gives each network its own mem partition names
+static NEXT_NETWORK_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A single conductor in a [TestConductorNetwork] with one agent
+/// running the network's DNA.
+pub struct TestConductor {
+    __tmpdir: Arc<TempDir>,
+    handle: ConductorHandle,
+    cell_api: CellHostFnApi,
+}
+
+impl TestConductor {
+    async fn new(dna_file: &DnaFile, network: KitsuneP2pConfig) -> Self {
+        let envs = test_environments();
+        let agent = envs
+            .keystore()
+            .clone()
+            .generate_sign_keypair_from_pure_entropy()
+            .await
+            .unwrap();
+        let handle = ConductorBuilder::new()
+            .config(ConductorConfig {
+                admin_interfaces: Some(vec![AdminInterfaceConfig {
+                    driver: InterfaceDriver::Websocket { port: 0 },
+                }]),
+                network: Some(network),
+                ..Default::default()
+            })
+            .test(&envs)
+            .await
+            .unwrap();
+
+        let cell_id = CellId::new(dna_file.dna_hash().clone(), agent);
+        let cell_data = vec![(InstalledCell::new(cell_id.clone(), "agent".into()), None)];
+        install_app(
+            "test_app",
+            cell_data,
+            vec![dna_file.clone()],
+            handle.clone(),
+        )
+        .await;
+        let cell_api = CellHostFnApi::new(&cell_id, &handle, dna_file).await;
+
+        Self {
+            __tmpdir: envs.tempdir(),
+            handle,
+            cell_api,
+        }
+    }
+
+    /// A handle to this conductor
+    pub fn handle(&self) -> ConductorHandle {
+        self.handle.clone()
+    }
+
+    /// The id of this conductor's only cell
+    pub fn cell_id(&self) -> &CellId {
+        &self.cell_api.cell_id
+    }
+
+    /// Everything needed to call host functions as this conductor's cell
+    pub fn call_data(&self) -> &CellHostFnApi {
+        &self.cell_api
+    }
+
+    /// The mem transport urls this conductor's agent can be reached at.
+    /// Waits for the agent to publish its own peer info.
+    pub async fn transport_urls(&self) -> Vec<Url2> {
+        let env = self.handle.get_p2p_env().await;
+        for _ in 0..PEER_INFO_ATTEMPTS {
+            let info = get_single_agent_info(
+                env.clone().into(),
+                self.cell_id().dna_hash().clone(),
+                self.cell_id().agent_pubkey().clone(),
+            )
+            .unwrap();
+            if let Some(info) = info {
+                let info = AgentInfo::try_from(&info).unwrap();
+                return info.as_urls_ref().to_vec();
+            }
+            tokio::time::delay_for(PEER_INFO_DELAY).await;
+        }
+        panic!("Agent {:?} never published its peer info", self.cell_id());
+    }
+
+    /// The hashes of the ops this conductor has integrated,
+    /// or None while it has ops left to validate or integrate
+    fn integrated_ops(&self) -> Option<BTreeSet<Vec<u8>>> {
+        let env = &self.cell_api.env;
+        let workspace = IncomingDhtOpsWorkspace::new(env.clone().into()).unwrap();
+        fresh_reader_test!(env, |r| {
+            let in_limbo = workspace
+                .validation_limbo
+                .iter(&r)
+                .unwrap()
+                .count()
+                .unwrap()
+                + workspace
+                    .integration_limbo
+                    .iter(&r)
+                    .unwrap()
+                    .count()
+                    .unwrap();
+            if in_limbo > 0 {
+                return None;
+            }
+            Some(
+                workspace
+                    .integrated_dht_ops
+                    .iter(&r)
+                    .unwrap()
+                    .map(|(k, _)| Ok(k.to_vec()))
+                    .collect()
+                    .unwrap(),
+            )
+        })
+    }
+
+    /// Shutdown the conductor
+    pub async fn shutdown(&mut self) {
+        let shutdown = self.handle.take_shutdown_handle().await.unwrap();
+        self.handle.shutdown().await;
+        shutdown.await.unwrap();
+    }
+}
+
+/// Many conductors, each with one agent running the same DNA,
+/// connected over the mem transport.
+pub struct TestConductorNetwork {
+    conductors: Vec<TestConductor>,
+    dna_file: DnaFile,
+    id: usize,
+}
+
+impl TestConductorNetwork {
+    /// Spin up `num_conductors` conductors with a DNA made from these zomes
+    /// and let them all discover each other.
+    pub async fn new(num_conductors: usize, zomes: Vec<TestWasm>) -> Self {
+        let dna_file = DnaFile::new(
+            DnaDef {
+                name: "conductor_network_test".to_string(),
+                uuid: "0a6b7b6e-d2b4-4f6a-bf2f-66bd01d7e0b5".to_string(),
+                properties: SerializedBytes::try_from(()).unwrap(),
+                zomes: zomes.clone().into_iter().map(Into::into).collect(),
+            },
+            zomes.into_iter().map(Into::into),
+        )
+        .await
+        .unwrap();
+        Self::with_dna(num_conductors, dna_file).await
+    }
+
+    /// Spin up `num_conductors` conductors running this DNA
+    /// and let them all discover each other.
+    pub async fn with_dna(num_conductors: usize, dna_file: DnaFile) -> Self {
+        let mut network = KitsuneP2pConfig::default();
+        network.transport_pool = vec![TransportConfig::Mem {}];
+        let conductors = futures::future::join_all(
+            (0..num_conductors).map(|_| TestConductor::new(&dna_file, network.clone())),
+        )
+        .await;
+        let this = Self {
+            conductors,
+            dna_file,
+            id: NEXT_NETWORK_ID.fetch_add(1, Ordering::Relaxed),
+        };
+        this.exchange_peer_info().await;
+        this
+    }
+
+    /// The DNA every conductor is running
+    pub fn dna_file(&self) -> &DnaFile {
+        &self.dna_file
+    }
+
+    /// All the conductors, in the order they were created
+    pub fn conductors(&self) -> &[TestConductor] {
+        &self.conductors
+    }
+
+    /// The id of each conductor's cell, in the order of the conductors
+    pub fn cell_ids(&self) -> Vec<CellId> {
+        self.conductors
+            .iter()
+            .map(|c| c.cell_id().clone())
+            .collect()
+    }
+
+    /// Copy every conductor's peer info into every other conductor's
+    /// p2p store so they can all reach each other.
+    pub async fn exchange_peer_info(&self) {
+        // Make sure every agent has published its own info first
+        for c in self.conductors.iter() {
+            c.transport_urls().await;
+        }
+        let mut all = Vec::new();
+        for c in self.conductors.iter() {
+            let env = c.handle.get_p2p_env().await;
+            all.extend(all_agent_infos(env.into()).unwrap());
+        }
+        for c in self.conductors.iter() {
+            let env = c.handle.get_p2p_env().await;
+            inject_agent_infos(env, all.clone()).unwrap();
+        }
+    }
+
+    /// Wait until every conductor has integrated the same ops and has none
+    /// left to validate or integrate, checking `num_attempts` times with
+    /// `delay` in between. Panics if they never agree.
+    pub async fn wait_for_consistency(&self, num_attempts: usize, delay: Duration) {
+        for _ in 0..num_attempts {
+            let held: Vec<_> = self.conductors.iter().map(|c| c.integrated_ops()).collect();
+            if held.iter().all(|ops| ops.is_some() && ops == &held[0]) {
+                return;
+            }
+            tokio::time::delay_for(delay).await;
+        }
+        panic!("The conductors never integrated the same ops");
+    }
+
+    /// Split the network so conductors can only reach others in
+    /// the same group, replacing any earlier partition. Each group
+    /// is a list of conductor indices. Conductors not in any group
+    /// can still reach everyone.
+    pub async fn partition(&self, groups: Vec<Vec<usize>>) {
+        self.heal().await;
+        for (i, group) in groups.into_iter().enumerate() {
+            let mut urls = Vec::new();
+            for c in group {
+                urls.extend(self.conductors[c].transport_urls().await);
+            }
+            set_mem_partition(&format!("conductor-network-{}/{}", self.id, i), urls).await;
+        }
+    }
+
+    /// Remove all partitions so every conductor can reach every other
+    pub async fn heal(&self) {
+        let mut urls = Vec::new();
+        for c in self.conductors.iter() {
+            urls.extend(c.transport_urls().await);
+        }
+        heal_mem_partition(urls).await;
+    }
+
+    /// Shutdown all the conductors
+    pub async fn shutdown(&mut self) {
+        for c in self.conductors.iter_mut() {
+            c.shutdown().await;
+        }
+    }
+}
+
+impl std::ops::Index<usize> for TestConductorNetwork {
+    type Output = TestConductor;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.conductors[i]
+    }
+}
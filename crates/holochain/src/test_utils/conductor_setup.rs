@@ -201,7 +201,8 @@ impl ConductorTestData {
 
     /// Bring bob online if he isn't already
     pub async fn bring_bob_online(&mut self) {
-        let dna_file = self.alice_call_data().ribosome.dna_file().clone();
+        let dna_hash = self.alice_call_data().ribosome.dna_hash.clone();
+        let dna_file = self.handle.get_dna(&dna_hash).await.unwrap();
         if self.bob_call_data().is_none() {
             let bob_agent_id = fake_agent_pubkey_2();
             let bob_cell_id = CellId::new(dna_file.dna_hash.clone(), bob_agent_id.clone());
@@ -9,7 +9,7 @@ use holochain::{
         error::ConductorError,
         Conductor,
     },
-    core::signal::Signal,
+    core::signal::{Signal, SystemSignal},
 };
 use holochain_types::{
     app::{InstallAppDnaPayload, InstallAppPayload},
@@ -79,6 +79,12 @@ fn create_config(port: u16, environment_path: PathBuf) -> ConductorConfig {
         }),
         keystore_path: None,
         use_dangerous_test_keystore: true,
+        metrics_port: None,
+        spam_protection: None,
+        wasm_limits: None,
+        wasm_instance_pool_size: None,
+        encrypt_environments: None,
+        storage_backend: None,
     }
 }
 
@@ -157,6 +163,7 @@ async fn call_admin() {
         nick: "nick".into(),
         properties: Some(properties.clone()),
         membrane_proof: None,
+        source_chain: None,
     };
     let agent_key = fake_agent_pubkey_1();
     let payload = InstallAppPayload {
@@ -503,6 +510,88 @@ async fn conductor_admin_interface_runs_from_config() -> Result<()> {
     Ok(())
 }
 
+/// Wait for the next lifecycle signal on an admin connection,
+/// skipping any progress updates
+async fn next_lifecycle_signal(rx: &mut WebsocketReceiver) -> SystemSignal {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(1), rx.next())
+            .await
+            .expect("Timed out waiting for admin signal")
+            .unwrap();
+        let bytes: SerializedBytes = unwrap_to::unwrap_to!(msg => WebsocketMessage::Signal).clone();
+        match Signal::try_from(bytes).unwrap() {
+            Signal::System(SystemSignal::IntegrationProgress { .. }) => continue,
+            Signal::System(signal) => return signal,
+            signal => panic!("Unexpected signal on admin interface: {:?}", signal),
+        }
+    }
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn conductor_admin_interface_emits_lifecycle_signals() -> Result<()> {
+    observability::test_run().ok();
+    let tmp_dir = TempDir::new("conductor_cfg").unwrap();
+    let environment_path = tmp_dir.path().to_path_buf();
+    let config = create_config(0, environment_path);
+    let conductor_handle = Conductor::builder().config(config).build().await?;
+    let (mut client, mut rx) = websocket_client(&conductor_handle).await?;
+
+    let dna = fake_dna_zomes(
+        "".into(),
+        vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+    );
+    let (fake_dna_path, _tmpdir) = write_fake_dna_file(dna).await.unwrap();
+    let dna_payload = InstallAppDnaPayload::path_only(fake_dna_path, "".to_string());
+    let agent_key = fake_agent_pubkey_1();
+    let payload = InstallAppPayload {
+        dnas: vec![dna_payload],
+        installed_app_id: "test".to_string(),
+        agent_key,
+    };
+    let request = AdminRequest::InstallApp(Box::new(payload));
+    let response = client.request(request).await;
+    assert_matches!(response, Ok(AdminResponse::AppInstalled(_)));
+    assert_eq!(
+        next_lifecycle_signal(&mut rx).await,
+        SystemSignal::AppInstalled("test".to_string())
+    );
+
+    let request = AdminRequest::ActivateApp {
+        installed_app_id: "test".to_string(),
+    };
+    let response = client.request(request).await;
+    assert_matches!(response, Ok(AdminResponse::AppActivated));
+    assert_eq!(
+        next_lifecycle_signal(&mut rx).await,
+        SystemSignal::AppActivated("test".to_string())
+    );
+
+    let request = AdminRequest::AttachAppInterface { port: None };
+    let response: AdminResponse = client.request(request).await?;
+    let port = match response {
+        AdminResponse::AppInterfaceAttached { port } => port,
+        r => panic!("Unexpected response: {:?}", r),
+    };
+    assert_eq!(
+        next_lifecycle_signal(&mut rx).await,
+        SystemSignal::AppInterfaceAttached { port }
+    );
+
+    let request = AdminRequest::DeactivateApp {
+        installed_app_id: "test".to_string(),
+    };
+    let response = client.request(request).await;
+    assert_matches!(response, Ok(AdminResponse::AppDeactivated));
+    assert_eq!(
+        next_lifecycle_signal(&mut rx).await,
+        SystemSignal::AppDeactivated("test".to_string())
+    );
+
+    conductor_handle.shutdown().await;
+
+    Ok(())
+}
+
 #[tokio::test(threaded_scheduler)]
 async fn conductor_admin_interface_ends_with_shutdown() -> Result<()> {
     observability::test_run().ok();
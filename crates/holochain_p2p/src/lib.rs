@@ -5,7 +5,7 @@ use holo_hash::*;
 use holochain_serialized_bytes::prelude::*;
 use holochain_types::activity::AgentActivity;
 use holochain_zome_types::{capability::CapSecret, zome::ZomeName};
-use holochain_zome_types::{query::ChainQueryFilter, zome::FunctionName};
+use holochain_zome_types::{query::ChainQueryFilter, warrant::SignedWarrant, zome::FunctionName};
 use std::sync::Arc;
 
 mod types;
@@ -62,6 +62,13 @@ pub trait HolochainP2pCellT {
         timeout_ms: Option<u64>,
     ) -> actor::HolochainP2pResult<()>;
 
+    /// Publish a warrant to the authorities holding the offending agent's activity.
+    async fn publish_warrant(
+        &mut self,
+        warrant: SignedWarrant,
+        timeout_ms: Option<u64>,
+    ) -> actor::HolochainP2pResult<()>;
+
     /// Request a validation package.
     async fn get_validation_package(
         &mut self,
@@ -183,6 +190,22 @@ impl HolochainP2pCellT for HolochainP2pCell {
             .await
     }
 
+    /// Publish a warrant to the authorities holding the offending agent's activity.
+    async fn publish_warrant(
+        &mut self,
+        warrant: SignedWarrant,
+        timeout_ms: Option<u64>,
+    ) -> actor::HolochainP2pResult<()> {
+        self.sender
+            .publish_warrant(
+                (*self.dna_hash).clone(),
+                (*self.from_agent).clone(),
+                warrant,
+                timeout_ms,
+            )
+            .await
+    }
+
     /// Request a validation package.
     async fn get_validation_package(
         &mut self,
@@ -8,7 +8,7 @@ use ghost_actor::dependencies::{tracing, tracing_futures::Instrument};
 use holochain_types::{
     element::GetElementResponse, validate::ValidationPackageResponse, Timestamp,
 };
-use holochain_zome_types::zome::FunctionName;
+use holochain_zome_types::{warrant::SignedWarrant, zome::FunctionName};
 use kitsune_p2p::actor::KitsuneP2pSender;
 use kitsune_p2p::agent_store::AgentInfoSigned;
 
@@ -176,6 +176,25 @@ impl HolochainP2pActor {
         .into())
     }
 
+    /// receiving an incoming warrant from a remote node
+    fn handle_incoming_publish_warrant(
+        &mut self,
+        dna_hash: DnaHash,
+        to_agent: AgentPubKey,
+        from_agent: AgentPubKey,
+        warrant: SignedWarrant,
+    ) -> kitsune_p2p::actor::KitsuneP2pHandlerResult<()> {
+        let evt_sender = self.evt_sender.clone();
+        Ok(async move {
+            evt_sender
+                .put_warrant(dna_hash, to_agent, from_agent, warrant)
+                .await?;
+            Ok(())
+        }
+        .boxed()
+        .into())
+    }
+
     /// receiving an incoming validation receipt from a remote node
     fn handle_incoming_validation_receipt(
         &mut self,
@@ -323,7 +342,8 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
             } => self.handle_incoming_get_agent_activity(space, to_agent, agent, query, options),
             // holochain_p2p never publishes via request
             // these only occur on broadcasts
-            crate::wire::WireMessage::Publish { .. } => {
+            crate::wire::WireMessage::Publish { .. }
+            | crate::wire::WireMessage::PublishWarrant { .. } => {
                 Err(HolochainP2pError::invalid_p2p_message(
                     "invalid: publish is a broadcast type, not a request".to_string(),
                 )
@@ -378,6 +398,9 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
                 dht_hash,
                 ops,
             ),
+            crate::wire::WireMessage::PublishWarrant { warrant } => {
+                self.handle_incoming_publish_warrant(space, to_agent, from_agent, warrant)
+            }
         }
     }
 
@@ -392,14 +415,17 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
     ) -> kitsune_p2p::event::KitsuneP2pEventHandlerResult<()> {
         let space = DnaHash::from_kitsune(&space);
         let to_agent = AgentPubKey::from_kitsune(&to_agent);
-        let _from_agent = AgentPubKey::from_kitsune(&from_agent);
+        let from_agent = AgentPubKey::from_kitsune(&from_agent);
         let op_hash = DhtOpHash::from_kitsune(&op_hash);
         let op_data =
             crate::wire::WireDhtOpData::decode(op_data).map_err(HolochainP2pError::from)?;
+        // Attribute the ops to the peer gossiping them rather than
+        // the publisher named in the op data, which anyone can claim,
+        // so the cell's publish limits apply to the gossiping peer
         self.handle_incoming_publish(
             space,
             to_agent,
-            op_data.from_agent,
+            from_agent,
             false,
             op_data.dht_hash,
             vec![(op_hash, op_data.op_data)],
@@ -517,6 +543,18 @@ impl HolochainP2pHandler for HolochainP2pActor {
             .into())
     }
 
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_list_transport_bindings(
+        &mut self,
+    ) -> HolochainP2pHandlerResult<Vec<kitsune_p2p::dependencies::url2::Url2>> {
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(
+            async move { Ok(kitsune_p2p.list_transport_bindings().await?) }
+                .boxed()
+                .into(),
+        )
+    }
+
     #[tracing::instrument(skip(self), level = "trace")]
     fn handle_leave(
         &mut self,
@@ -597,6 +635,41 @@ impl HolochainP2pHandler for HolochainP2pActor {
         .into())
     }
 
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_publish_warrant(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        warrant: SignedWarrant,
+        timeout_ms: Option<u64>,
+    ) -> HolochainP2pHandlerResult<()> {
+        let space = dna_hash.into_kitsune();
+        let from_agent = from_agent.into_kitsune();
+        // Warrants are held by the authorities for the offender's activity
+        // so the offender's key is the basis
+        let offender: AnyDhtHash = warrant.warrant().offender.clone().into();
+        let basis = offender.to_kitsune();
+
+        let payload = crate::wire::WireMessage::publish_warrant(warrant).encode()?;
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(async move {
+            kitsune_p2p
+                .notify_multi(kitsune_p2p::actor::NotifyMulti {
+                    space,
+                    from_agent,
+                    basis,
+                    remote_agent_count: None, // default best-effort
+                    timeout_ms,
+                    payload,
+                })
+                .await?;
+            Ok(())
+        }
+        .boxed()
+        .into())
+    }
+
     #[tracing::instrument(skip(self), level = "trace")]
     fn handle_get_validation_package(
         &mut self,
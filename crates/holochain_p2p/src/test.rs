@@ -20,6 +20,11 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<()> {
         Err("stub".into())
     }
+    fn handle_list_transport_bindings(
+        &mut self,
+    ) -> HolochainP2pHandlerResult<Vec<kitsune_p2p::dependencies::url2::Url2>> {
+        Err("stub".into())
+    }
     fn handle_leave(
         &mut self,
         dna_hash: DnaHash,
@@ -50,6 +55,15 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<()> {
         Err("stub".into())
     }
+    fn handle_publish_warrant(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        warrant: SignedWarrant,
+        timeout_ms: Option<u64>,
+    ) -> HolochainP2pHandlerResult<()> {
+        Err("stub".into())
+    }
     fn handle_get_validation_package(
         &mut self,
         input: actor::GetValidationPackage,
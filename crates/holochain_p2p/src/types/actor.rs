@@ -3,6 +3,7 @@
 
 use crate::*;
 use holochain_zome_types::request::MetadataRequest;
+use holochain_zome_types::warrant::SignedWarrant;
 use holochain_zome_types::zome::FunctionName;
 
 /// Request a validation package.
@@ -198,6 +199,9 @@ ghost_actor::ghost_chan! {
         /// If a cell is deactivated, we'll need to \"leave\" the network module as well.
         fn leave(dna_hash: DnaHash, agent_pub_key: AgentPubKey) -> ();
 
+        /// List the urls the underlying transports are bound to.
+        fn list_transport_bindings() -> Vec<kitsune_p2p::dependencies::url2::Url2>;
+
         /// Invoke a zome function on a remote node (if you have been granted the capability).
         fn call_remote(
             dna_hash: DnaHash,
@@ -219,6 +223,14 @@ ghost_actor::ghost_chan! {
             timeout_ms: Option<u64>,
         ) -> ();
 
+        /// Publish a warrant to the authorities holding the offending agent's activity.
+        fn publish_warrant(
+            dna_hash: DnaHash,
+            from_agent: AgentPubKey,
+            warrant: SignedWarrant,
+            timeout_ms: Option<u64>,
+        ) -> ();
+
         /// Request a validation package.
         fn get_validation_package(input: GetValidationPackage) -> ValidationPackageResponse;
 
@@ -3,6 +3,7 @@
 
 use crate::*;
 use holochain_zome_types::signature::Signature;
+use holochain_zome_types::warrant::SignedWarrant;
 use kitsune_p2p::agent_store::AgentInfoSigned;
 
 /// Get options help control how the get is processed at various levels.
@@ -101,6 +102,15 @@ ghost_actor::ghost_chan! {
             ops: Vec<(holo_hash::DhtOpHash, holochain_types::dht_op::DhtOp)>,
         ) -> ();
 
+        /// A remote node is publishing a warrant against an agent
+        /// whose activity we are holding.
+        fn put_warrant(
+            dna_hash: DnaHash,
+            to_agent: AgentPubKey,
+            from_agent: AgentPubKey,
+            warrant: SignedWarrant,
+        ) -> ();
+
         /// A remote node is requesting a validation package.
         fn get_validation_package(
             // The dna_hash / space_hash context.
@@ -184,6 +194,7 @@ macro_rules! match_p2p_evt {
         match $h {
             HolochainP2pEvent::CallRemote { $i, .. } => { $($t)* }
             HolochainP2pEvent::Publish { $i, .. } => { $($t)* }
+            HolochainP2pEvent::PutWarrant { $i, .. } => { $($t)* }
             HolochainP2pEvent::GetValidationPackage { $i, .. } => { $($t)* }
             HolochainP2pEvent::Get { $i, .. } => { $($t)* }
             HolochainP2pEvent::GetMeta { $i, .. } => { $($t)* }
@@ -1,5 +1,5 @@
 use crate::*;
-use holochain_zome_types::zome::FunctionName;
+use holochain_zome_types::{warrant::SignedWarrant, zome::FunctionName};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, SerializedBytes)]
 pub(crate) struct WireDhtOpData {
@@ -34,6 +34,9 @@ pub(crate) enum WireMessage {
         dht_hash: holo_hash::AnyDhtHash,
         ops: Vec<(holo_hash::DhtOpHash, holochain_types::dht_op::DhtOp)>,
     },
+    PublishWarrant {
+        warrant: SignedWarrant,
+    },
     ValidationReceipt {
         #[serde(with = "serde_bytes")]
         receipt: Vec<u8>,
@@ -96,6 +99,10 @@ impl WireMessage {
         }
     }
 
+    pub fn publish_warrant(warrant: SignedWarrant) -> WireMessage {
+        Self::PublishWarrant { warrant }
+    }
+
     pub fn validation_receipt(receipt: SerializedBytes) -> WireMessage {
         Self::ValidationReceipt {
             receipt: UnsafeBytes::from(receipt).into(),
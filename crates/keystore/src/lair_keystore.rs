@@ -6,9 +6,27 @@ use lair_keystore_api::actor::*;
 use lair_keystore_api::*;
 
 /// Spawn a new keystore backed by lair_keystore_client.
+/// Lair is unlocked with a blank passphrase.
 pub async fn spawn_lair_keystore(
     lair_dir: Option<&std::path::Path>,
 ) -> KeystoreApiResult<KeystoreSender> {
+    spawn_lair_keystore_with_passphrase(lair_dir, || {
+        async move { Ok("[blank-passphrase]".to_string()) }
+            .boxed()
+            .into()
+    })
+    .await
+}
+
+/// Spawn a new keystore backed by lair_keystore_client,
+/// calling `passphrase` whenever lair asks for its unlock passphrase.
+pub async fn spawn_lair_keystore_with_passphrase<F>(
+    lair_dir: Option<&std::path::Path>,
+    passphrase: F,
+) -> KeystoreApiResult<KeystoreSender>
+where
+    F: Fn() -> KeystoreApiFuture<String> + Send + 'static,
+{
     let mut config = Config::builder();
     if let Some(lair_dir) = lair_dir {
         config = config.set_root_path(lair_dir);
@@ -16,14 +34,12 @@ pub async fn spawn_lair_keystore(
     let config = config.build();
     let (api, mut evt) = lair_keystore_client::assert_running_lair_and_connect(config).await?;
 
-    // TODO - actual passphrase handling
     tokio::task::spawn(async move {
         while let Some(r) = evt.next().await {
             match r {
                 LairClientEvent::RequestUnlockPassphrase { respond, .. } => {
-                    respond.respond(Ok(async move { Ok("[blank-passphrase]".to_string()) }
-                        .boxed()
-                        .into()));
+                    let passphrase = passphrase();
+                    respond.respond(Ok(async move { Ok(passphrase.await?) }.boxed().into()));
                 }
             }
         }
@@ -16,6 +16,8 @@ pub use config::*;
 mod spawn;
 pub use spawn::*;
 
+pub mod metrics;
+
 #[cfg(test)]
 pub mod test_util;
 
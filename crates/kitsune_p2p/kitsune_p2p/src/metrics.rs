@@ -0,0 +1,38 @@
+//! Process-wide counters describing kitsune's activity,
+//! for exposing to a metrics collector.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static GOSSIP_ROUNDS: AtomicU64 = AtomicU64::new(0);
+static GOSSIP_OPS: AtomicU64 = AtomicU64::new(0);
+static GOSSIP_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// A snapshot of the gossip counters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GossipMetrics {
+    /// Number of gossip rounds run between pairs of agents
+    pub rounds: u64,
+    /// Number of ops forwarded by gossip
+    pub ops: u64,
+    /// Total size of the op data forwarded by gossip
+    pub bytes: u64,
+}
+
+/// Read the current gossip counters
+pub fn gossip_metrics() -> GossipMetrics {
+    GossipMetrics {
+        rounds: GOSSIP_ROUNDS.load(Ordering::Relaxed),
+        ops: GOSSIP_OPS.load(Ordering::Relaxed),
+        bytes: GOSSIP_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn record_gossip_round() {
+    GOSSIP_ROUNDS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_gossip_ops(ops: &[(std::sync::Arc<crate::KitsuneOpHash>, Vec<u8>)]) {
+    let bytes: usize = ops.iter().map(|(_, data)| data.len()).sum();
+    GOSSIP_OPS.fetch_add(ops.len() as u64, Ordering::Relaxed);
+    GOSSIP_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
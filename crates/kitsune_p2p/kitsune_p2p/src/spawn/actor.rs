@@ -13,10 +13,14 @@ use std::{
 mod bootstrap;
 mod discover;
 mod gossip;
+mod handshake;
 mod space;
 use ghost_actor::dependencies::{must_future, tracing};
 use space::*;
 
+/// How many channels from remotes are answered at once.
+const MAX_CONCURRENT_INCOMING_CHANNELS: usize = 64;
+
 ghost_actor::ghost_chan! {
     pub(crate) chan Internal<crate::KitsuneP2pError> {
         /// Register space event handler
@@ -29,6 +33,7 @@ pub(crate) struct KitsuneP2pActor {
     internal_sender: ghost_actor::GhostSender<Internal>,
     evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     transport: ghost_actor::GhostSender<TransportListener>,
+    handshake: handshake::HandshakeTransport,
     spaces: HashMap<Arc<KitsuneSpace>, AsyncLazy<ghost_actor::GhostSender<KitsuneP2p>>>,
     config: Arc<KitsuneP2pConfig>,
 }
@@ -100,7 +105,7 @@ impl KitsuneP2pActor {
         internal_sender: ghost_actor::GhostSender<Internal>,
         evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     ) -> KitsuneP2pResult<Self> {
-        let (t_pool, transport, mut t_event) = spawn_transport_pool().await?;
+        let (t_pool, transport, t_event) = spawn_transport_pool().await?;
         for t_conf in config.transport_pool.clone() {
             let (l, e) = build_transport(t_conf).await?;
             t_pool.push_sub_transport(l, e).await?;
@@ -108,171 +113,155 @@ impl KitsuneP2pActor {
 
         tokio::task::spawn({
             let evt_sender = evt_sender.clone();
-            async move {
-                while let Some(event) = t_event.next().await {
+            // answer channels concurrently, so a slow request or a large
+            // message from one remote doesn't hold up the others
+            t_event.for_each_concurrent(MAX_CONCURRENT_INCOMING_CHANNELS, move |event| {
+                let evt_sender = evt_sender.clone();
+                async move {
                     match event {
                         TransportEvent::IncomingChannel(_url, mut write, read) => {
-                            let read = read.read_to_end().await;
-                            use kitsune_p2p_types::codec::Codec;
-                            let read = match wire::Wire::decode_ref(&read) {
-                                Err(err) => {
-                                    let reason = format!("{:?}", err);
-                                    let fail = wire::Wire::failure(reason).encode_vec().unwrap();
-                                    let _ = write.write_and_close(fail).await;
-                                    continue;
-                                }
-                                Ok((_, r)) => r,
-                            };
-                            match read {
-                                wire::Wire::Call(wire::Call {
-                                    space,
-                                    from_agent,
-                                    to_agent,
-                                    data,
-                                    ..
-                                }) => {
-                                    let res = match evt_sender
-                                        .call(space, to_agent, from_agent, data.into())
-                                        .await
-                                    {
-                                        Err(err) => {
-                                            let reason = format!("{:?}", err);
-                                            let fail =
-                                                wire::Wire::failure(reason).encode_vec().unwrap();
-                                            let _ = write.write_and_close(fail).await;
-                                            continue;
-                                        }
-                                        Ok(r) => r,
-                                    };
-                                    let resp =
-                                        wire::Wire::call_resp(res.into()).encode_vec().unwrap();
-                                    let _ = write.write_and_close(resp).await;
-                                }
-                                wire::Wire::Notify(wire::Notify {
-                                    space,
-                                    from_agent,
-                                    to_agent,
-                                    data,
-                                    ..
-                                }) => {
-                                    if let Err(err) = evt_sender
-                                        .notify(space, to_agent, from_agent, data.into())
-                                        .await
-                                    {
-                                        let reason = format!("{:?}", err);
-                                        let fail =
-                                            wire::Wire::failure(reason).encode_vec().unwrap();
-                                        let _ = write.write_and_close(fail).await;
-                                        continue;
-                                    }
-                                    let resp = wire::Wire::notify_resp().encode_vec().unwrap();
-                                    let _ = write.write_and_close(resp).await;
-                                }
-                                wire::Wire::FetchOpHashes(wire::FetchOpHashes {
-                                    space,
-                                    from_agent,
-                                    to_agent,
-                                    dht_arc,
-                                    since_utc_epoch_s,
-                                    until_utc_epoch_s,
-                                }) => {
-                                    let input = ReqOpHashesEvt::new(
-                                        from_agent,
-                                        to_agent,
-                                        dht_arc,
-                                        since_utc_epoch_s,
-                                        until_utc_epoch_s,
-                                    );
-                                    let (hashes, agent_hashes) = match local_req_op_hashes(
-                                        &evt_sender,
-                                        space,
-                                        input,
-                                    )
-                                    .await
-                                    {
-                                        Err(err) => {
-                                            let reason = format!("{:?}", err);
-                                            let fail =
-                                                wire::Wire::failure(reason).encode_vec().unwrap();
-                                            let _ = write.write_and_close(fail).await;
-                                            continue;
-                                        }
-                                        Ok(r) => r,
-                                    };
-                                    let resp =
-                                        wire::Wire::fetch_op_hashes_response(hashes, agent_hashes)
-                                            .encode_vec()
-                                            .expect("This encoding should never fail");
-                                    let _ = write.write_and_close(resp).await;
-                                }
-                                wire::Wire::FetchOpData(wire::FetchOpData {
-                                    space,
-                                    from_agent,
-                                    to_agent,
-                                    op_hashes,
-                                    peer_hashes,
-                                }) => {
-                                    let input = ReqOpDataEvt::new(
-                                        from_agent,
-                                        to_agent,
-                                        op_hashes,
-                                        peer_hashes,
-                                    );
-                                    let (op_data, agent_infos) =
-                                        match local_req_op_data(&evt_sender, space, input).await {
-                                            Err(err) => {
-                                                let reason = format!("{:?}", err);
-                                                let fail = wire::Wire::failure(reason)
-                                                    .encode_vec()
-                                                    .unwrap();
-                                                let _ = write.write_and_close(fail).await;
-                                                continue;
-                                            }
-                                            Ok(r) => r,
-                                        };
-                                    let op_data =
-                                        op_data.into_iter().map(|(h, op)| (h, op.into())).collect();
-                                    let resp =
-                                        wire::Wire::fetch_op_data_response(op_data, agent_infos)
-                                            .encode_vec()
-                                            .expect("This encoding should never fail");
-                                    let _ = write.write_and_close(resp).await;
-                                }
-                                wire::Wire::AgentInfoQuery(q) => {
-                                    match agent_info_query(q, evt_sender.clone()).await {
-                                        Ok(r) => {
-                                            let resp = wire::Wire::agent_info_query_resp(r)
-                                                .encode_vec()
-                                                .unwrap();
-                                            let _ = write.write_and_close(resp).await;
-                                        }
-                                        Err(err) => {
-                                            let reason = format!("{:?}", err);
-                                            let fail =
-                                                wire::Wire::failure(reason).encode_vec().unwrap();
-                                            let _ = write.write_and_close(fail).await;
-                                        }
-                                    }
-                                }
-                                _ => unimplemented!("{:?}", read),
-                            }
+                            let read = read.read_to_end_bounded(wire::MAX_MESSAGE_BYTES).await;
+                            let resp = answer(&evt_sender, read).await;
+                            let _ = write.write_and_close(resp).await;
                         }
                     }
                 }
-            }
+            })
         });
 
+        let handshake = handshake::HandshakeTransport::new(transport.clone());
+
         Ok(Self {
             channel_factory,
             internal_sender,
             evt_sender,
             transport,
+            handshake,
             spaces: HashMap::new(),
             config: Arc::new(config),
         })
     }
 }
 
+/// Answer a channel from a remote.
+/// Messages too large to read are answered with a failure.
+async fn answer(
+    evt_sender: &futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    read: TransportResult<Vec<u8>>,
+) -> Vec<u8> {
+    use kitsune_p2p_types::codec::Codec;
+    let read = match read {
+        Ok(read) => read,
+        Err(err) => {
+            return wire::Wire::failure(format!("{:?}", err))
+                .encode_vec()
+                .expect("This encoding should never fail")
+        }
+    };
+    let resp = match wire::Wire::decode_inflated(&read) {
+        Err(err) => wire::Wire::failure(format!("{:?}", err)).encode_vec(),
+        // remotes that speak the handshake open their first channel with a hello
+        // carrying the request, which is answered in the protocol agreed here
+        Ok((_, wire::Wire::Hello(hello))) => match handshake::accept_hello(&hello) {
+            Err(reject) => reject.encode_vec(),
+            Ok(protocol) => {
+                let resp = if hello.request.is_empty() {
+                    // only finding out the protocol
+                    Ok(Vec::new())
+                } else {
+                    let resp = match wire::Wire::decode_inflated(&hello.request) {
+                        Err(err) => wire::Wire::failure(format!("{:?}", err)),
+                        Ok((_, req)) => respond(evt_sender, req).await,
+                    };
+                    resp.encode_deflated(protocol.has_feature(wire::FEATURE_DEFLATE))
+                };
+                resp.and_then(|resp| {
+                    wire::Wire::hello_resp(
+                        protocol.protocol_version,
+                        protocol.features,
+                        resp.into(),
+                    )
+                    .encode_vec()
+                })
+            }
+        },
+        // later requests, and remotes from before the handshake, are bare,
+        // reply deflated to deflated requests
+        Ok((deflated, req)) => respond(evt_sender, req).await.encode_deflated(deflated),
+    };
+    resp.expect("This encoding should never fail")
+}
+
+/// Answer a request from a remote.
+async fn respond(
+    evt_sender: &futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    msg: wire::Wire,
+) -> wire::Wire {
+    let res = match msg {
+        wire::Wire::Call(wire::Call {
+            space,
+            from_agent,
+            to_agent,
+            data,
+            ..
+        }) => evt_sender
+            .call(space, to_agent, from_agent, data.into())
+            .await
+            .map(|res| wire::Wire::call_resp(res.into())),
+        wire::Wire::Notify(wire::Notify {
+            space,
+            from_agent,
+            to_agent,
+            data,
+            ..
+        }) => evt_sender
+            .notify(space, to_agent, from_agent, data.into())
+            .await
+            .map(|_| wire::Wire::notify_resp()),
+        wire::Wire::FetchOpHashes(wire::FetchOpHashes {
+            space,
+            from_agent,
+            to_agent,
+            dht_arc,
+            since_utc_epoch_s,
+            until_utc_epoch_s,
+        }) => {
+            let input = ReqOpHashesEvt::new(
+                from_agent,
+                to_agent,
+                dht_arc,
+                since_utc_epoch_s,
+                until_utc_epoch_s,
+            );
+            local_req_op_hashes(evt_sender, space, input)
+                .await
+                .map(|(hashes, agent_hashes)| {
+                    wire::Wire::fetch_op_hashes_response(hashes, agent_hashes)
+                })
+        }
+        wire::Wire::FetchOpData(wire::FetchOpData {
+            space,
+            from_agent,
+            to_agent,
+            op_hashes,
+            peer_hashes,
+        }) => {
+            let input = ReqOpDataEvt::new(from_agent, to_agent, op_hashes, peer_hashes);
+            local_req_op_data(evt_sender, space, input)
+                .await
+                .map(|(op_data, agent_infos)| {
+                    let op_data = op_data.into_iter().map(|(h, op)| (h, op.into())).collect();
+                    wire::Wire::fetch_op_data_response(op_data, agent_infos)
+                })
+        }
+        wire::Wire::AgentInfoQuery(q) => agent_info_query(q, evt_sender.clone())
+            .await
+            .map(wire::Wire::agent_info_query_resp),
+        msg => return wire::Wire::failure(format!("unexpected message: {:?}", msg)),
+    };
+    res.unwrap_or_else(|err| wire::Wire::failure(format!("{:?}", err)))
+}
+
 async fn agent_info_query(
     q: wire::AgentInfoQuery,
     evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
@@ -430,11 +419,12 @@ impl KitsuneP2pHandler for KitsuneP2pActor {
         let internal_sender = self.internal_sender.clone();
         let space2 = space.clone();
         let transport = self.transport.clone();
+        let handshake = self.handshake.clone();
         let config = Arc::clone(&self.config);
         let space_sender = match self.spaces.entry(space.clone()) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(AsyncLazy::new(async move {
-                let (send, evt_recv) = spawn_space(space2, transport, config)
+                let (send, evt_recv) = spawn_space(space2, transport, handshake, config)
                     .await
                     .expect("cannot fail to create space");
                 internal_sender
@@ -2,7 +2,6 @@
 use super::*;
 use crate::agent_store::AgentInfo;
 use ghost_actor::dependencies::must_future::MustBoxFuture;
-use kitsune_p2p_types::codec::Codec;
 use std::collections::HashSet;
 use std::convert::TryFrom;
 
@@ -12,11 +11,7 @@ use std::convert::TryFrom;
 /// - Err - we were not able to establish a connection within the timeout
 pub(crate) enum PeerDiscoverResult {
     OkShortcut,
-    OkRemote {
-        url: url2::Url2,
-        write: TransportChannelWrite,
-        read: TransportChannelRead,
-    },
+    OkRemote { url: url2::Url2 },
     Err(KitsuneP2pError),
 }
 
@@ -29,14 +24,14 @@ pub(crate) fn peer_discover(
 ) -> MustBoxFuture<'static, PeerDiscoverResult> {
     let i_s = space.i_s.clone();
     let evt_sender = space.evt_sender.clone();
-    let tx = space.transport.clone();
+    let tx = space.handshake.clone();
     let bootstrap_service = space.config.bootstrap_service.clone();
     let space = space.space.clone();
     async move {
-        // run tx.create_channel an conver success result into our return type
-        let try_connect = |url| async {
-            let (url, write, read) = tx.create_channel(url).await?;
-            KitsuneP2pResult::Ok(PeerDiscoverResult::OkRemote { url, write, read })
+        // handshake with the remote and convert success result into our return type
+        let try_connect = |url: url2::Url2| async {
+            tx.peer_protocol(url.clone()).await?;
+            KitsuneP2pResult::Ok(PeerDiscoverResult::OkRemote { url })
         };
 
         // check if this agent is locally joined
@@ -95,24 +90,20 @@ pub(crate) fn peer_discover(
                         .get(0)
                         .ok_or_else(|| KitsuneP2pError::from("no url"))?
                         .clone();
-                    let (_, mut write, read) = tx.create_channel(url).await?;
-
-                    // write the query request
-                    write
-                        .write_and_close(
+                    // make the query request
+                    let res = tx
+                        .request(
+                            url,
                             wire::Wire::agent_info_query(
                                 space.clone(),
                                 Arc::new(info.as_agent_ref().clone()),
                                 Some(to_agent.clone()),
                                 None,
-                            )
-                            .encode_vec()?,
+                            ),
                         )
                         .await?;
 
                     // parse the response
-                    let res = read.read_to_end().await;
-                    let (_, res) = wire::Wire::decode_ref(&res)?;
                     match res {
                         wire::Wire::AgentInfoQueryResp(wire::AgentInfoQueryResp {
                             mut agent_infos,
@@ -202,7 +193,7 @@ where
 {
     let i_s = space.i_s.clone();
     let evt_sender = space.evt_sender.clone();
-    let tx = space.transport.clone();
+    let tx = space.handshake.clone();
     let bootstrap_service = space.config.bootstrap_service.clone();
     let space = space.space.clone();
     let accept_result_cb = Arc::new(accept_result_cb);
@@ -255,12 +246,11 @@ where
                             None => continue,
                             Some(url) => url.clone(),
                         };
-                        let fut = tx.create_channel(url);
+                        let tx = tx.clone();
                         let mut payload = payload.clone();
                         let accept_result_cb = accept_result_cb.clone();
                         let out = out.clone();
                         tokio::task::spawn(async move {
-                            let (_, mut write, read) = fut.await?;
                             match &mut payload {
                                 wire::Wire::Notify(n) => {
                                     n.to_agent = to_agent.clone();
@@ -270,10 +260,7 @@ where
                                 }
                                 _ => panic!("cannot message {:?}", payload),
                             }
-                            let payload = payload.encode_vec()?;
-                            write.write_and_close(payload).await?;
-                            let res = read.read_to_end().await;
-                            let (_, res) = wire::Wire::decode_ref(&res)?;
+                            let res = tx.request(url, payload).await?;
                             if let Ok(res) = accept_result_cb(to_agent, res) {
                                 out.lock().await.push(res);
                             }
@@ -7,6 +7,14 @@ use ghost_actor::dependencies::{tracing, tracing_futures};
 use kitsune_p2p_types::dht_arc::DhtArc;
 use std::{collections::HashSet, iter::FromIterator, sync::Arc};
 
+/// A fetch of op data stops adding ops once it holds this many bytes of them,
+/// the requester asks again for the rest.
+pub(crate) const OP_DATA_BATCH_BYTES: usize = 1024 * 1024;
+
+/// How many ops a fetch of op data loads from the host at a time,
+/// which bounds how far past [OP_DATA_BATCH_BYTES] a response can go.
+pub(crate) const OP_DATA_LOAD_CHUNK: usize = 16;
+
 ghost_actor::ghost_chan! {
     /// "Event" requests emitted by the gossip module
     pub chan GossipEvent<crate::KitsuneP2pError> {
@@ -96,9 +104,61 @@ impl GossipData {
         Ok(())
     }
 
+    /// Fetch ops and agent infos that `source_agent` holds and
+    /// `dest_agent` needs, and forward them on to `dest_agent`.
+    /// The source answers with about [OP_DATA_BATCH_BYTES] of ops at a time
+    /// and each batch is forwarded as it arrives, asking again for the ops
+    /// still missing, so a large difference doesn't become one huge response.
+    /// Stops once a batch holds none of the ops still missing.
+    async fn fetch_and_gossip_ops(
+        &mut self,
+        from_agent: Arc<KitsuneAgent>,
+        source_agent: Arc<KitsuneAgent>,
+        dest_agent: Arc<KitsuneAgent>,
+        mut op_hashes: Vec<Arc<KitsuneOpHash>>,
+        mut peer_hashes: Vec<Arc<KitsuneAgent>>,
+    ) {
+        while !op_hashes.is_empty() || !peer_hashes.is_empty() {
+            // agent infos are small, send them with the first batch
+            let peer_hashes = std::mem::take(&mut peer_hashes);
+            let (r_ops, r_peers) = match self
+                .evt_send
+                .req_op_data(ReqOpDataEvt::new(
+                    from_agent.clone(),
+                    source_agent.clone(),
+                    op_hashes.clone(),
+                    peer_hashes,
+                ))
+                .await
+            {
+                Ok(r) => r,
+                Err(_) => return,
+            };
+            let missing = op_hashes.len();
+            let got: HashSet<_> = r_ops.iter().map(|(hash, _)| hash.clone()).collect();
+            op_hashes.retain(|hash| !got.contains(hash));
+            // the source held none of the rest, or answered with other ops
+            let stalled = op_hashes.len() == missing;
+            if !r_ops.is_empty() || !r_peers.is_empty() {
+                crate::metrics::record_gossip_ops(&r_ops);
+                if let Err(e) = self
+                    .evt_send
+                    .gossip_ops(source_agent.clone(), dest_agent.clone(), r_ops, r_peers)
+                    .await
+                {
+                    tracing::error!(?e);
+                }
+            }
+            if stalled {
+                break;
+            }
+        }
+    }
+
     async fn process_next_gossip(&mut self) -> KitsuneP2pResult<()> {
         // !is_empty() checked above in take_action
         let (from_agent, to_agent) = self.pending_gossip_list.remove(0);
+        crate::metrics::record_gossip_round();
 
         // required so from_iters below know the build_hasher type
         type S = HashSet<Arc<KitsuneOpHash>>;
@@ -155,57 +215,24 @@ impl GossipData {
             .collect::<Vec<_>>();
 
         // fetch values that to_agent needs from from_agent
-        if !to_needs.is_empty() || !to_needs_agents.is_empty() {
-            if let Ok((r_ops, r_peers)) = self
-                .evt_send
-                .req_op_data(ReqOpDataEvt::new(
-                    from_agent.clone(), // from not to because we're initiating
-                    from_agent.clone(),
-                    to_needs,
-                    to_needs_agents,
-                ))
-                .await
-            {
-                if !r_ops.is_empty() || !r_peers.is_empty() {
-                    if let Err(e) = self
-                        .evt_send
-                        .gossip_ops(from_agent.clone(), to_agent.clone(), r_ops, r_peers)
-                        .await
-                    {
-                        tracing::error!(?e);
-                    }
-                }
-            }
-        }
+        self.fetch_and_gossip_ops(
+            from_agent.clone(), // from not to because we're initiating
+            from_agent.clone(),
+            to_agent.clone(),
+            to_needs,
+            to_needs_agents,
+        )
+        .await;
 
         // fetch values that from_agent needs from to_agent
-        if !from_needs.is_empty() || !from_needs_agents.is_empty() {
-            if let Ok((r_ops, r_peers)) = self
-                .evt_send
-                .req_op_data(ReqOpDataEvt::new(
-                    from_agent.clone(),
-                    to_agent.clone(),
-                    from_needs,
-                    from_needs_agents,
-                ))
-                .await
-            {
-                if !r_ops.is_empty() || !r_peers.is_empty() {
-                    if let Err(e) = self
-                        .evt_send
-                        .gossip_ops(
-                            to_agent.clone(), // we fetched from to
-                            from_agent.clone(),
-                            r_ops,
-                            r_peers,
-                        )
-                        .await
-                    {
-                        tracing::error!(?e);
-                    }
-                }
-            }
-        }
+        self.fetch_and_gossip_ops(
+            from_agent.clone(),
+            to_agent.clone(),
+            from_agent.clone(),
+            from_needs,
+            from_needs_agents,
+        )
+        .await;
 
         Ok(())
     }
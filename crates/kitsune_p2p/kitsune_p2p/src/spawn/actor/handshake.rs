@@ -0,0 +1,485 @@
+//! Wire protocol handshake.
+//!
+//! The first channel we open to a remote starts with a `Hello` advertising
+//! the protocol versions and features we speak, carrying the request.
+//! The remote agrees on a protocol and answers the request in it inside
+//! its `HelloResp`, so the handshake costs no extra round trip.
+//! Later channels carry the bare request in the protocol agreed.
+//! Remotes from before the handshake can't decode a `Hello` and answer
+//! with an unknown message `Failure`; those are sent the bare request
+//! and spoken to as protocol version 0, which keeps a rolling upgrade working.
+
+use super::*;
+use futures::{
+    channel::oneshot,
+    future::{BoxFuture, Shared},
+};
+use ghost_actor::dependencies::must_future::MustBoxFuture;
+use kitsune_p2p_types::codec::Codec;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How long a remote found to predate the handshake is spoken to as
+/// version 0 before handshaking again, so an upgrade is noticed
+/// without reconnecting.
+const HANDSHAKE_EXPIRES_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// How long the first channel to a remote may take finding out
+/// which protocol it speaks before giving up, so one hung channel
+/// doesn't stall every later request to that remote.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What we agreed to speak with a remote.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PeerProtocol {
+    /// The wire protocol version in use.
+    pub protocol_version: u32,
+    /// The features both sides support.
+    pub features: Vec<String>,
+}
+
+impl PeerProtocol {
+    /// Does this connection support the named feature?
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// The hello this build opens a channel with.
+pub(crate) fn our_hello(request: wire::WireData) -> wire::Hello {
+    wire::Hello {
+        protocol_version: wire::WIRE_PROTOCOL_VERSION,
+        min_protocol_version: wire::MIN_WIRE_PROTOCOL_VERSION,
+        features: wire::WIRE_FEATURES.iter().map(|f| f.to_string()).collect(),
+        request,
+    }
+}
+
+/// Agree on the newest protocol version both sides speak
+/// and the features both sides support.
+pub(crate) fn negotiate(
+    ours: &wire::Hello,
+    theirs: &wire::Hello,
+) -> KitsuneP2pResult<PeerProtocol> {
+    let protocol_version = std::cmp::min(ours.protocol_version, theirs.protocol_version);
+    if protocol_version < ours.min_protocol_version
+        || protocol_version < theirs.min_protocol_version
+    {
+        return Err(KitsuneP2pError::ProtocolMismatch {
+            our_version: ours.protocol_version,
+            our_min_version: ours.min_protocol_version,
+            their_version: theirs.protocol_version,
+            their_min_version: theirs.min_protocol_version,
+        });
+    }
+    let features = ours
+        .features
+        .iter()
+        .filter(|f| theirs.features.contains(f))
+        .cloned()
+        .collect();
+    Ok(PeerProtocol {
+        protocol_version,
+        features,
+    })
+}
+
+/// Agree on a protocol with a remote's hello,
+/// or the rejection to send it if there is none.
+pub(crate) fn accept_hello(theirs: &wire::Hello) -> Result<PeerProtocol, wire::Wire> {
+    let ours = our_hello(Vec::new().into());
+    negotiate(&ours, theirs).map_err(|err| {
+        tracing::warn!(?err, "rejecting handshake");
+        wire::Wire::hello_reject(ours.protocol_version, ours.min_protocol_version)
+    })
+}
+
+/// What we know about a remote.
+enum Peer {
+    /// A channel is finding out which protocol the remote speaks,
+    /// this resolves once it knows or gives up.
+    Opening(Shared<BoxFuture<'static, ()>>),
+    /// The protocol agreed with the remote, and when.
+    Agreed(Instant, PeerProtocol),
+}
+
+/// How to go about the next request to a remote.
+enum Next {
+    /// Wait for the channel finding out the remote's protocol.
+    Wait(Shared<BoxFuture<'static, ()>>),
+    /// Send the request in the protocol agreed.
+    Known(PeerProtocol),
+    /// Find out the remote's protocol, the sender is held while we do.
+    Open(oneshot::Sender<()>),
+}
+
+/// Transport wrapper that opens the first channel to a remote with
+/// the handshake and remembers what was agreed for the rest.
+#[derive(Clone)]
+pub(crate) struct HandshakeTransport {
+    transport: ghost_actor::GhostSender<TransportListener>,
+    peers: Arc<Mutex<HashMap<url2::Url2, Peer>>>,
+}
+
+impl HandshakeTransport {
+    /// Wrap a transport.
+    pub fn new(transport: ghost_actor::GhostSender<TransportListener>) -> Self {
+        Self {
+            transport,
+            peers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Send a request to a remote and read its reply.
+    /// The request is deflated if the remote supports it.
+    /// While the first channel to a remote finds out which protocol
+    /// it speaks, other requests to it wait rather than handshake again.
+    pub fn request(
+        &self,
+        url: url2::Url2,
+        msg: wire::Wire,
+    ) -> MustBoxFuture<'static, KitsuneP2pResult<wire::Wire>> {
+        let this = self.clone();
+        async move {
+            loop {
+                match this.next(&url) {
+                    Next::Wait(opening) => wait(opening).await?,
+                    Next::Known(protocol) => {
+                        let res = this.send(url.clone(), &msg, &protocol).await;
+                        if res.is_err() {
+                            this.forget(&url);
+                        }
+                        return res;
+                    }
+                    Next::Open(opening) => {
+                        return this
+                            .handshake(url, Some(&msg), opening)
+                            .await?
+                            .1
+                            .ok_or_else(|| KitsuneP2pError::from("no reply to the request"))
+                    }
+                }
+            }
+        }
+        .boxed()
+        .into()
+    }
+
+    /// The protocol agreed with a remote,
+    /// handshaking without a request if there is none yet.
+    /// Succeeds only if the remote could be reached.
+    pub fn peer_protocol(
+        &self,
+        url: url2::Url2,
+    ) -> MustBoxFuture<'static, KitsuneP2pResult<PeerProtocol>> {
+        let this = self.clone();
+        async move {
+            loop {
+                match this.next(&url) {
+                    Next::Wait(opening) => wait(opening).await?,
+                    Next::Known(protocol) => return Ok(protocol),
+                    Next::Open(opening) => return Ok(this.handshake(url, None, opening).await?.0),
+                }
+            }
+        }
+        .boxed()
+        .into()
+    }
+
+    fn next(&self, url: &url2::Url2) -> Next {
+        let mut peers = self.peers.lock().unwrap();
+        match peers.get(url) {
+            Some(Peer::Opening(opening)) => return Next::Wait(opening.clone()),
+            Some(Peer::Agreed(at, protocol))
+                if protocol.protocol_version > 0 || at.elapsed() < HANDSHAKE_EXPIRES_AFTER =>
+            {
+                return Next::Known(protocol.clone())
+            }
+            _ => (),
+        }
+        let (send, recv) = oneshot::channel();
+        peers.insert(
+            url.clone(),
+            Peer::Opening(recv.map(|_| ()).boxed().shared()),
+        );
+        Next::Open(send)
+    }
+
+    /// Handshake again on the next request to a remote that failed,
+    /// it may have restarted speaking something else.
+    fn forget(&self, url: &url2::Url2) {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some(Peer::Agreed(..)) = peers.get(url) {
+            peers.remove(url);
+        }
+    }
+
+    /// Find out the remote's protocol within [HANDSHAKE_TIMEOUT]
+    /// and remember it, then wake the requests waiting on us.
+    async fn handshake(
+        &self,
+        url: url2::Url2,
+        msg: Option<&wire::Wire>,
+        opening: oneshot::Sender<()>,
+    ) -> KitsuneP2pResult<(PeerProtocol, Option<wire::Wire>)> {
+        let res = match tokio::time::timeout(HANDSHAKE_TIMEOUT, self.hello(url.clone(), msg)).await
+        {
+            Ok(res) => res,
+            Err(_) => Err("timed out handshaking".into()),
+        };
+        {
+            let mut peers = self.peers.lock().unwrap();
+            match &res {
+                Ok((protocol, _)) => {
+                    peers.insert(url, Peer::Agreed(Instant::now(), protocol.clone()));
+                }
+                // let the next request find out instead
+                Err(_) => {
+                    peers.remove(&url);
+                }
+            }
+        }
+        // dropping the sender wakes the requests waiting on us
+        drop(opening);
+        res
+    }
+
+    /// Open a channel with the handshake, carrying the request if there is one.
+    async fn hello(
+        &self,
+        url: url2::Url2,
+        msg: Option<&wire::Wire>,
+    ) -> KitsuneP2pResult<(PeerProtocol, Option<wire::Wire>)> {
+        let request = match msg {
+            Some(msg) => msg.encode_vec()?,
+            None => Vec::new(),
+        };
+        let ours = our_hello(request.into());
+        let res = self
+            .exchange(url.clone(), wire::Wire::Hello(ours.clone()).encode_vec()?)
+            .await?;
+        match res {
+            wire::Wire::HelloResp(wire::HelloResp {
+                protocol_version,
+                features,
+                response,
+            }) => {
+                let protocol = PeerProtocol {
+                    protocol_version,
+                    features,
+                };
+                match msg {
+                    Some(_) => Ok((protocol, Some(wire::Wire::decode_inflated(&response)?.1))),
+                    None => Ok((protocol, None)),
+                }
+            }
+            wire::Wire::HelloReject(wire::HelloReject {
+                protocol_version,
+                min_protocol_version,
+            }) => Err(KitsuneP2pError::ProtocolMismatch {
+                our_version: ours.protocol_version,
+                our_min_version: ours.min_protocol_version,
+                their_version: protocol_version,
+                their_min_version: min_protocol_version,
+            }),
+            // remotes from before the handshake can't decode a hello
+            wire::Wire::Failure(wire::Failure { reason })
+                if reason.contains(wire::UNKNOWN_MESSAGE_REASON) =>
+            {
+                tracing::debug!(%url, %reason, "remote predates handshake");
+                let protocol = negotiate(&ours, &v0_hello())?;
+                match msg {
+                    Some(msg) => {
+                        let res = self.send(url, msg, &protocol).await?;
+                        Ok((protocol, Some(res)))
+                    }
+                    None => Ok((protocol, None)),
+                }
+            }
+            res => Err(format!("unexpected handshake response: {:?}", res).into()),
+        }
+    }
+
+    /// Send a bare request on a new channel in the protocol agreed.
+    async fn send(
+        &self,
+        url: url2::Url2,
+        msg: &wire::Wire,
+        protocol: &PeerProtocol,
+    ) -> KitsuneP2pResult<wire::Wire> {
+        let data = msg.encode_deflated(protocol.has_feature(wire::FEATURE_DEFLATE))?;
+        self.exchange(url, data).await
+    }
+
+    /// Write a message on a new channel and read the reply.
+    async fn exchange(&self, url: url2::Url2, data: Vec<u8>) -> KitsuneP2pResult<wire::Wire> {
+        let (_, mut write, read) = self.transport.create_channel(url).await?;
+        write.write_and_close(data).await?;
+        let res = read.read_to_end_bounded(wire::MAX_MESSAGE_BYTES).await?;
+        Ok(wire::Wire::decode_inflated(&res)?.1)
+    }
+}
+
+/// Wait for another channel to find out a remote's protocol.
+/// That channel gives up after [HANDSHAKE_TIMEOUT],
+/// this deadline only guards against it never waking us.
+async fn wait(opening: Shared<BoxFuture<'static, ()>>) -> KitsuneP2pResult<()> {
+    tokio::time::timeout(HANDSHAKE_TIMEOUT * 2, opening)
+        .await
+        .map_err(|_| "timed out waiting for the handshake".into())
+}
+
+/// What a remote from before the handshake speaks.
+fn v0_hello() -> wire::Hello {
+    wire::Hello {
+        protocol_version: 0,
+        min_protocol_version: 0,
+        features: Vec::new(),
+        request: Vec::new().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kitsune_p2p_types::transport_mem::spawn_bind_transport_mem;
+
+    fn hello(protocol_version: u32, min_protocol_version: u32, features: &[&str]) -> wire::Hello {
+        wire::Hello {
+            protocol_version,
+            min_protocol_version,
+            features: features.iter().map(|f| f.to_string()).collect(),
+            request: Vec::new().into(),
+        }
+    }
+
+    #[test]
+    fn negotiate_picks_newest_shared_version() {
+        let protocol = negotiate(&hello(3, 1, &[]), &hello(2, 0, &[])).unwrap();
+        assert_eq!(2, protocol.protocol_version);
+        let protocol = negotiate(&hello(2, 0, &[]), &hello(3, 1, &[])).unwrap();
+        assert_eq!(2, protocol.protocol_version);
+    }
+
+    #[test]
+    fn negotiate_intersects_features() {
+        let protocol = negotiate(&hello(1, 0, &["a", "b"]), &hello(1, 0, &["b", "c"])).unwrap();
+        assert_eq!(vec!["b".to_string()], protocol.features);
+        assert!(protocol.has_feature("b"));
+        assert!(!protocol.has_feature("a"));
+    }
+
+    #[test]
+    fn negotiate_rejects_disjoint_versions() {
+        let err = negotiate(&hello(5, 4, &[]), &hello(3, 1, &[])).unwrap_err();
+        assert_matches::assert_matches!(
+            err,
+            KitsuneP2pError::ProtocolMismatch {
+                our_version: 5,
+                our_min_version: 4,
+                their_version: 3,
+                their_min_version: 1,
+            }
+        );
+        assert_matches::assert_matches!(
+            negotiate(&hello(3, 1, &[]), &hello(5, 4, &[])),
+            Err(KitsuneP2pError::ProtocolMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn accept_hello_rejects_future_only_remote() {
+        let theirs = hello(
+            wire::WIRE_PROTOCOL_VERSION + 2,
+            wire::WIRE_PROTOCOL_VERSION + 1,
+            &[],
+        );
+        assert_matches::assert_matches!(accept_hello(&theirs), Err(wire::Wire::HelloReject(_)));
+        assert_matches::assert_matches!(
+            accept_hello(&our_hello(Vec::new().into())),
+            Ok(PeerProtocol { protocol_version, .. })
+                if protocol_version == wire::WIRE_PROTOCOL_VERSION
+        );
+    }
+
+    /// Answer every channel with a `NotifyResp`, counting the hellos.
+    fn spawn_remote(mut recv: TransportEventReceiver) -> Arc<std::sync::atomic::AtomicUsize> {
+        let hellos = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        tokio::task::spawn({
+            let hellos = hellos.clone();
+            async move {
+                while let Some(TransportEvent::IncomingChannel(_, mut write, read)) =
+                    recv.next().await
+                {
+                    let resp = match wire::Wire::decode_inflated(&read.read_to_end().await) {
+                        Ok((_, wire::Wire::Hello(hello))) => {
+                            hellos.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                            let protocol = accept_hello(&hello).unwrap();
+                            let resp = if hello.request.is_empty() {
+                                Vec::new()
+                            } else {
+                                wire::Wire::notify_resp().encode_vec().unwrap()
+                            };
+                            wire::Wire::hello_resp(
+                                protocol.protocol_version,
+                                protocol.features,
+                                resp.into(),
+                            )
+                        }
+                        Ok(_) => wire::Wire::notify_resp(),
+                        Err(err) => wire::Wire::failure(format!("{:?}", err)),
+                    };
+                    let _ = write.write_and_close(resp.encode_vec().unwrap()).await;
+                }
+            }
+        });
+        hellos
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn handshakes_once_per_remote() {
+        let (local, _local_evt) = spawn_bind_transport_mem().await.unwrap();
+        let (remote, remote_evt) = spawn_bind_transport_mem().await.unwrap();
+        let hellos = spawn_remote(remote_evt);
+        let url = remote.bound_url().await.unwrap();
+        let handshake = HandshakeTransport::new(local);
+
+        let protocol = handshake.peer_protocol(url.clone()).await.unwrap();
+        assert_eq!(wire::WIRE_PROTOCOL_VERSION, protocol.protocol_version);
+        assert!(protocol.has_feature(wire::FEATURE_DEFLATE));
+
+        let requests = (0..3).map(|_| handshake.request(url.clone(), wire::Wire::notify_resp()));
+        for res in futures::future::join_all(requests).await {
+            assert_matches::assert_matches!(res, Ok(wire::Wire::NotifyResp(_)));
+        }
+        assert_eq!(1, hellos.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn first_request_carries_the_handshake() {
+        let (local, _local_evt) = spawn_bind_transport_mem().await.unwrap();
+        let (remote, remote_evt) = spawn_bind_transport_mem().await.unwrap();
+        let hellos = spawn_remote(remote_evt);
+        let url = remote.bound_url().await.unwrap();
+        let handshake = HandshakeTransport::new(local);
+
+        let requests = (0..3).map(|_| handshake.request(url.clone(), wire::Wire::notify_resp()));
+        for res in futures::future::join_all(requests).await {
+            assert_matches::assert_matches!(res, Ok(wire::Wire::NotifyResp(_)));
+        }
+        assert_eq!(1, hellos.load(std::sync::atomic::Ordering::SeqCst));
+        assert_eq!(
+            wire::WIRE_PROTOCOL_VERSION,
+            handshake.peer_protocol(url).await.unwrap().protocol_version
+        );
+    }
+
+    #[test]
+    fn remotes_before_the_handshake_speak_version_0() {
+        let protocol = negotiate(&our_hello(Vec::new().into()), &v0_hello()).unwrap();
+        assert_eq!(0, protocol.protocol_version);
+        assert!(!protocol.has_feature(wire::FEATURE_DEFLATE));
+    }
+}
@@ -2,7 +2,6 @@ use crate::agent_store::AgentInfoSigned;
 
 use super::*;
 use ghost_actor::dependencies::{tracing, tracing_futures::Instrument};
-use kitsune_p2p_types::codec::Codec;
 use std::{collections::HashSet, convert::TryFrom};
 
 /// if the user specifies None or zero (0) for remote_agent_count
@@ -43,6 +42,7 @@ ghost_actor::ghost_chan! {
 pub(crate) async fn spawn_space(
     space: Arc<KitsuneSpace>,
     transport: ghost_actor::GhostSender<TransportListener>,
+    handshake: handshake::HandshakeTransport,
     config: Arc<KitsuneP2pConfig>,
 ) -> KitsuneP2pResult<(
     ghost_actor::GhostSender<KitsuneP2p>,
@@ -69,7 +69,9 @@ pub(crate) async fn spawn_space(
         .create_channel::<KitsuneP2p>()
         .await?;
 
-    tokio::task::spawn(builder.spawn(Space::new(space, i_s, evt_send, transport, config)));
+    tokio::task::spawn(builder.spawn(Space::new(
+        space, i_s, evt_send, transport, handshake, config,
+    )));
 
     Ok((sender, evt_recv))
 }
@@ -125,7 +127,7 @@ impl gossip::GossipEventHandler for Space {
                 until_utc_epoch_s,
                 from_agent,
             } = input;
-            let transport_tx = self.transport.clone();
+            let transport_tx = self.handshake.clone();
             let evt_sender = self.evt_sender.clone();
             let space = self.space.clone();
             Ok(async move {
@@ -147,14 +149,10 @@ impl gossip::GossipEventHandler for Space {
                     dht_arc,
                     since_utc_epoch_s,
                     until_utc_epoch_s,
-                )
-                .encode_vec()?;
+                );
                 let info = types::agent_store::AgentInfo::try_from(&info)?;
                 let url = info.as_urls_ref().get(0).unwrap().clone();
-                let (_, mut write, read) = transport_tx.create_channel(url).await?;
-                write.write_and_close(data.to_vec()).await?;
-                let read = read.read_to_end().await;
-                let (_, read) = wire::Wire::decode_ref(&read)?;
+                let read = transport_tx.request(url, data).await?;
                 match read {
                     wire::Wire::Failure(wire::Failure { reason }) => Err(reason.into()),
                     wire::Wire::FetchOpHashesResponse(wire::FetchOpHashesResponse {
@@ -183,7 +181,7 @@ impl gossip::GossipEventHandler for Space {
                 op_hashes,
                 peer_hashes,
             } = input;
-            let transport_tx = self.transport.clone();
+            let transport_tx = self.handshake.clone();
             let evt_sender = self.evt_sender.clone();
             let space = self.space.clone();
             Ok(async move {
@@ -199,14 +197,10 @@ impl gossip::GossipEventHandler for Space {
                     Some(i) => i,
                 };
                 let data =
-                    wire::Wire::fetch_op_data(space, from_agent, to_agent, op_hashes, peer_hashes)
-                        .encode_vec()?;
+                    wire::Wire::fetch_op_data(space, from_agent, to_agent, op_hashes, peer_hashes);
                 let info = types::agent_store::AgentInfo::try_from(&info)?;
                 let url = info.as_urls_ref().get(0).unwrap().clone();
-                let (_, mut write, read) = transport_tx.create_channel(url).await?;
-                write.write_and_close(data.to_vec()).await?;
-                let read = read.read_to_end().await;
-                let (_, read) = wire::Wire::decode_ref(&read)?;
+                let read = transport_tx.request(url, data).await?;
                 match read {
                     wire::Wire::Failure(wire::Failure { reason }) => Err(reason.into()),
                     wire::Wire::FetchOpDataResponse(wire::FetchOpDataResponse {
@@ -324,16 +318,10 @@ pub fn local_req_op_data(
         peer_hashes,
         ..
     } = input;
-    // while full-sync just redirecting to self...
-    // but eventually some of these will be outgoing remote requests
-    let fut = evt_sender.fetch_op_hash_data(FetchOpHashDataEvt {
+    let evt_sender = evt_sender.clone();
+    let peer_fut = evt_sender.query_agent_info_signed(QueryAgentInfoSignedEvt {
         space: space.clone(),
         agent: to_agent.clone(),
-        op_hashes,
-    });
-    let peer_fut = evt_sender.query_agent_info_signed(QueryAgentInfoSignedEvt {
-        space,
-        agent: to_agent,
     });
     async move {
         let agent_infos = peer_fut.await?;
@@ -345,7 +333,25 @@ pub fn local_req_op_data(
             .into_iter()
             .filter(|ai| peer_hashes.contains(ai.as_agent_ref()))
             .collect();
-        Ok((fut.await?, agent_infos))
+        // stop once the response holds enough op data,
+        // the requester asks again for the ops left out
+        let mut op_data = Vec::new();
+        let mut op_bytes = 0;
+        for op_hashes in op_hashes.chunks(gossip::OP_DATA_LOAD_CHUNK) {
+            if op_bytes >= gossip::OP_DATA_BATCH_BYTES {
+                break;
+            }
+            let ops = evt_sender
+                .fetch_op_hash_data(FetchOpHashDataEvt {
+                    space: space.clone(),
+                    agent: to_agent.clone(),
+                    op_hashes: op_hashes.to_vec(),
+                })
+                .await?;
+            op_bytes += ops.iter().map(|(_, op)| op.len()).sum::<usize>();
+            op_data.extend(ops);
+        }
+        Ok((op_data, agent_infos))
     }
 }
 
@@ -476,6 +482,7 @@ impl KitsuneP2pHandler for Space {
         timeout_ms: Option<u64>,
     ) -> KitsuneP2pHandlerResult<Vec<u8>> {
         let evt_sender = self.evt_sender.clone();
+        let transport_tx = self.handshake.clone();
 
         let timeout_ms = match timeout_ms {
             None | Some(0) => DEFAULT_RPC_SINGLE_TIMEOUT_MS,
@@ -491,19 +498,14 @@ impl KitsuneP2pHandler for Space {
                     // reflect this request locally
                     evt_sender.call(space, to_agent, from_agent, payload).await
                 }
-                discover::PeerDiscoverResult::OkRemote {
-                    mut write, read, ..
-                } => {
+                discover::PeerDiscoverResult::OkRemote { url } => {
                     let payload = wire::Wire::call(
                         space.clone(),
                         from_agent.clone(),
                         to_agent.clone(),
                         payload.into(),
-                    )
-                    .encode_vec()?;
-                    write.write_and_close(payload).await?;
-                    let res = read.read_to_end().await;
-                    let (_, res) = wire::Wire::decode_ref(&res)?;
+                    );
+                    let res = transport_tx.request(url, payload).await?;
                     match res {
                         wire::Wire::Failure(wire::Failure { reason }) => Err(reason.into()),
                         wire::Wire::CallResp(wire::CallResp { data }) => Ok(data.into()),
@@ -599,6 +601,7 @@ pub(crate) struct Space {
     pub(crate) i_s: ghost_actor::GhostSender<SpaceInternal>,
     pub(crate) evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     pub(crate) transport: ghost_actor::GhostSender<TransportListener>,
+    pub(crate) handshake: handshake::HandshakeTransport,
     pub(crate) local_joined_agents: HashSet<Arc<KitsuneAgent>>,
     pub(crate) config: Arc<KitsuneP2pConfig>,
 }
@@ -610,6 +613,7 @@ impl Space {
         i_s: ghost_actor::GhostSender<SpaceInternal>,
         evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
         transport: ghost_actor::GhostSender<TransportListener>,
+        handshake: handshake::HandshakeTransport,
         config: Arc<KitsuneP2pConfig>,
     ) -> Self {
         let i_s_c = i_s.clone();
@@ -626,6 +630,7 @@ impl Space {
             i_s,
             evt_sender,
             transport,
+            handshake,
             local_joined_agents: HashSet::new(),
             config,
         }
@@ -28,6 +28,39 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_transport_partition() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+
+        harness
+            .set_partition("left".into(), vec![a1.clone()])
+            .await?;
+        harness
+            .set_partition("right".into(), vec![a2.clone()])
+            .await?;
+        let r = p2p1
+            .rpc_single(space.clone(), a2.clone(), a1.clone(), b"m1".to_vec(), None)
+            .await;
+        assert!(r.is_err());
+
+        harness.set_partition_active("left".into(), false).await?;
+        let r = p2p1
+            .rpc_single(space.clone(), a2, a1, b"m2".to_vec(), None)
+            .await?;
+        assert_eq!(b"echo: m2".to_vec(), r);
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_transport_multi_coms() -> Result<(), KitsuneP2pError> {
         observability::test_run().ok();
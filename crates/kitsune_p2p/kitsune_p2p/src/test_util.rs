@@ -6,6 +6,7 @@ use crate::{
 };
 use futures::future::FutureExt;
 use ghost_actor::dependencies::tracing;
+pub use kitsune_p2p_types::transport_mem::MemLinkFaults;
 use std::{collections::HashMap, sync::Arc};
 use tokio::stream::StreamExt;
 
@@ -1,4 +1,7 @@
 use super::*;
+use kitsune_p2p_types::transport_mem::{
+    set_mem_link_faults, set_mem_partition, set_mem_partition_active,
+};
 
 ghost_actor::ghost_chan! {
     /// The api for the test harness controller
@@ -38,6 +41,19 @@ ghost_actor::ghost_chan! {
 
         /// Dump all local peer data from a specific agent
         fn dump_local_peer_data(agent: Arc<KitsuneAgent>) -> HashMap<Arc<KitsuneAgent>, Arc<AgentInfoSigned>>;
+
+        /// Set the faults on data sent from one agent to another.
+        /// Only applies to harnesses using the mem transport.
+        fn set_link_faults(from: Arc<KitsuneAgent>, to: Arc<KitsuneAgent>, faults: MemLinkFaults) -> ();
+
+        /// Place agents in a named network partition and activate it.
+        /// Only applies to harnesses using the mem transport.
+        /// Names are scoped to this harness, so harnesses running
+        /// in the same process don't share partitions.
+        fn set_partition(name: String, agents: Vec<Arc<KitsuneAgent>>) -> ();
+
+        /// Turn a named network partition on or off.
+        fn set_partition_active(name: String, active: bool) -> ();
     }
 }
 
@@ -108,8 +124,13 @@ ghost_actor::ghost_chan! {
     }
 }
 
+/// Gives each harness its own scope of mem partition names
+static NEXT_PARTITION_SCOPE: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
 struct HarnessActor {
     i_s: ghost_actor::GhostSender<HarnessInner>,
+    partition_scope: usize,
     harness_chan: HarnessEventChannel,
     sub_config: TransportConfig,
     space_list: Vec<Arc<KitsuneSpace>>,
@@ -130,6 +151,8 @@ impl HarnessActor {
     ) -> Self {
         Self {
             i_s,
+            partition_scope: NEXT_PARTITION_SCOPE
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
             harness_chan,
             sub_config,
             space_list: Vec::new(),
@@ -345,4 +368,98 @@ impl HarnessControlApiHandler for HarnessActor {
         let fut = ctrl.dump_local_peer_data();
         Ok(async move { fut.await }.boxed().into())
     }
+
+    fn handle_set_link_faults(
+        &mut self,
+        from: Arc<KitsuneAgent>,
+        to: Arc<KitsuneAgent>,
+        faults: MemLinkFaults,
+    ) -> HarnessControlApiHandlerResult<()> {
+        let from = self.mem_urls(&from)?;
+        let to = self.mem_urls(&to)?;
+        Ok(async move {
+            let (from, to) = futures::future::try_join(from, to).await?;
+            for f in from.iter() {
+                for t in to.iter() {
+                    set_mem_link_faults(f.clone(), t.clone(), faults.clone()).await;
+                }
+            }
+            Ok(())
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_set_partition(
+        &mut self,
+        name: String,
+        agents: Vec<Arc<KitsuneAgent>>,
+    ) -> HarnessControlApiHandlerResult<()> {
+        let name = self.partition_name(&name);
+        let urls = agents
+            .iter()
+            .map(|a| self.mem_urls(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(async move {
+            let urls = futures::future::try_join_all(urls).await?;
+            set_mem_partition(&name, urls.into_iter().flatten()).await;
+            Ok(())
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_set_partition_active(
+        &mut self,
+        name: String,
+        active: bool,
+    ) -> HarnessControlApiHandlerResult<()> {
+        let name = self.partition_name(&name);
+        Ok(
+            async move { Ok(set_mem_partition_active(&name, active).await?) }
+                .boxed()
+                .into(),
+        )
+    }
+}
+
+impl HarnessActor {
+    /// The process wide name of this harness's partition
+    fn partition_name(&self, name: &str) -> String {
+        format!("harness-{}/{}", self.partition_scope, name)
+    }
+
+    /// The underlying mem transport urls for an agent,
+    /// unwrapping any proxy urls.
+    fn mem_urls(
+        &self,
+        agent: &Arc<KitsuneAgent>,
+    ) -> KitsuneP2pResult<
+        ghost_actor::dependencies::must_future::MustBoxFuture<
+            'static,
+            KitsuneP2pResult<Vec<url2::Url2>>,
+        >,
+    > {
+        let (p2p, _) = self
+            .agents
+            .get(agent)
+            .ok_or_else(|| KitsuneP2pError::from("invalid agent"))?;
+        let fut = p2p.list_transport_bindings();
+        Ok(async move {
+            fut.await?
+                .into_iter()
+                .map(|url| {
+                    if url.scheme() == "kitsune-proxy" {
+                        Ok(kitsune_p2p_proxy::ProxyUrl::from_full(url.as_str())?
+                            .as_base()
+                            .clone())
+                    } else {
+                        Ok(url)
+                    }
+                })
+                .collect()
+        }
+        .boxed()
+        .into())
+    }
 }
@@ -20,6 +20,19 @@ pub enum KitsuneP2pError {
     #[error("Decoding Error: {0}")]
     DecodingError(Arc<String>),
 
+    /// ProtocolMismatch
+    #[error("Protocol Mismatch: we speak wire versions {our_min_version}..={our_version}, the remote speaks {their_min_version}..={their_version}")]
+    ProtocolMismatch {
+        /// The newest wire protocol version we speak.
+        our_version: u32,
+        /// The oldest wire protocol version we speak.
+        our_min_version: u32,
+        /// The newest wire protocol version the remote speaks.
+        their_version: u32,
+        /// The oldest wire protocol version the remote speaks.
+        their_min_version: u32,
+    },
+
     /// TransportError
     #[error(transparent)]
     TransportError(#[from] kitsune_p2p_types::transport::TransportError),
@@ -2,8 +2,11 @@
 
 use crate::{agent_store::AgentInfoSigned, types::*};
 use derive_more::*;
-use kitsune_p2p_types::dht_arc::DhtArc;
-use std::sync::Arc;
+use kitsune_p2p_types::{codec::Codec, dht_arc::DhtArc};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+};
 
 /// Type used for content data of wire messages.
 #[derive(
@@ -11,6 +14,38 @@ use std::sync::Arc;
 )]
 pub struct WireData(#[serde(with = "serde_bytes")] pub Vec<u8>);
 
+/// The wire protocol version this build speaks.
+/// Bump this whenever the meaning or encoding of an existing message changes.
+pub const WIRE_PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest wire protocol version this build will still speak.
+/// Version 0 is a peer from before the handshake existed.
+pub const MIN_WIRE_PROTOCOL_VERSION: u32 = 0;
+
+/// Messages may be sent wrapped in [Wire::Deflated].
+pub const FEATURE_DEFLATE: &str = "deflate";
+
+/// Optional wire features this build supports.
+/// Only features both sides advertise are used on a connection.
+pub const WIRE_FEATURES: &[&str] = &[FEATURE_DEFLATE];
+
+/// The reason in the decode [Wire::Failure] a remote replies with
+/// when it doesn't know a message type. Remotes from before the handshake
+/// answer a [Wire::Hello] with this.
+pub const UNKNOWN_MESSAGE_REASON: &str = "invalid protocol byte";
+
+/// Encoded messages larger than this are compressed inside [Wire::Deflated],
+/// smaller ones are sent plain, as compressing them isn't worth the time.
+pub const DEFLATE_THRESHOLD_BYTES: usize = 4 * 1024;
+
+/// The largest a [Wire::Deflated] message may inflate to,
+/// so a small message can't make us allocate without bound.
+pub const MAX_INFLATED_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The largest message read off a channel, deflated or not,
+/// so a remote can't make us buffer without bound.
+pub const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
 kitsune_p2p_types::write_codec_enum! {
     /// KitsuneP2p Wire Protocol Top-Level Enum.
     codec Wire {
@@ -19,6 +54,37 @@ kitsune_p2p_types::write_codec_enum! {
             reason.0: String,
         },
 
+        /// Handshake opening the first channel to a remote,
+        /// advertising the protocol versions and features we speak
+        /// and carrying the encoded request, if there is one.
+        Hello(0x01) {
+            protocol_version.0: u32,
+            min_protocol_version.1: u32,
+            features.2: Vec<String>,
+            request.3: WireData,
+        },
+
+        /// Handshake accepted, with the negotiated version and features
+        /// and the reply to the request, encoded in that protocol.
+        /// Empty if the hello carried no request.
+        HelloResp(0x02) {
+            protocol_version.0: u32,
+            features.1: Vec<String>,
+            response.2: WireData,
+        },
+
+        /// Handshake rejected, there is no protocol version we both speak.
+        HelloReject(0x03) {
+            protocol_version.0: u32,
+            min_protocol_version.1: u32,
+        },
+
+        /// Another encoded message, deflate compressed.
+        /// The reply to a deflated request is deflated too.
+        Deflated(0x04) {
+            data.0: WireData,
+        },
+
         /// "Call" to the remote.
         Call(0x010) {
             space.0: Arc<KitsuneSpace>,
@@ -89,3 +155,93 @@ kitsune_p2p_types::write_codec_enum! {
         },
     }
 }
+
+impl Wire {
+    /// Encode this message, wrapped in [Wire::Deflated] if `deflate` is set
+    /// and it is larger than [DEFLATE_THRESHOLD_BYTES].
+    /// Only deflate for remotes that negotiated [FEATURE_DEFLATE].
+    pub fn encode_deflated(&self, deflate: bool) -> std::io::Result<Vec<u8>> {
+        let data = self.encode_vec()?;
+        if !deflate || data.len() <= DEFLATE_THRESHOLD_BYTES {
+            return Ok(data);
+        }
+        let mut enc = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(&data)?;
+        Wire::deflated(enc.finish()?.into()).encode_vec()
+    }
+
+    /// Decode a message, unwrapping it if it is [Wire::Deflated].
+    /// Also returns whether it was deflated, so the reply can match.
+    /// Errors if it inflates past [MAX_INFLATED_BYTES].
+    pub fn decode_inflated(data: &[u8]) -> std::io::Result<(bool, Self)> {
+        match Wire::decode_ref(data)?.1 {
+            Wire::Deflated(Deflated { data }) => {
+                let mut inflated = Vec::new();
+                flate2::read::DeflateDecoder::new(data.as_slice())
+                    .take(MAX_INFLATED_BYTES + 1)
+                    .read_to_end(&mut inflated)?;
+                if inflated.len() as u64 > MAX_INFLATED_BYTES {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "deflated message inflates past the limit",
+                    ));
+                }
+                match Wire::decode_ref(&inflated)?.1 {
+                    Wire::Deflated(_) => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "nested deflated message",
+                    )),
+                    wire => Ok((true, wire)),
+                }
+            }
+            wire => Ok((false, wire)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deflated_round_trip() {
+        let wire = Wire::call_resp(vec![42; DEFLATE_THRESHOLD_BYTES * 4].into());
+        let plain = wire.encode_deflated(false).unwrap();
+        assert_eq!(
+            (false, wire.clone()),
+            Wire::decode_inflated(&plain).unwrap()
+        );
+        let deflated = wire.encode_deflated(true).unwrap();
+        assert_eq!((true, wire), Wire::decode_inflated(&deflated).unwrap());
+    }
+
+    #[test]
+    fn small_messages_are_sent_plain() {
+        let wire = Wire::call_resp(vec![42; 16].into());
+        assert_eq!(
+            wire.encode_deflated(false).unwrap(),
+            wire.encode_deflated(true).unwrap()
+        );
+    }
+
+    #[test]
+    fn inflating_past_the_limit_errors() {
+        let mut enc = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::fast());
+        let zeros = vec![0; 1024 * 1024];
+        for _ in 0..=MAX_INFLATED_BYTES / zeros.len() as u64 {
+            enc.write_all(&zeros).unwrap();
+        }
+        let bomb = Wire::deflated(enc.finish().unwrap().into())
+            .encode_vec()
+            .unwrap();
+        assert!(Wire::decode_inflated(&bomb).is_err());
+    }
+
+    #[test]
+    fn deflate_compresses_large_messages() {
+        let wire = Wire::call_resp(vec![42; DEFLATE_THRESHOLD_BYTES * 4].into());
+        assert!(
+            wire.encode_deflated(true).unwrap().len() < wire.encode_deflated(false).unwrap().len()
+        );
+    }
+}
@@ -58,6 +58,16 @@ pub trait TransportChannelReadExt {
     /// Read the stream to close into a single byte vec.
     fn read_to_end(self)
         -> ghost_actor::dependencies::must_future::MustBoxFuture<'static, Vec<u8>>;
+
+    /// Read the stream to close into a single byte vec, like [read_to_end],
+    /// but stop reading and error once it holds more than `max_bytes`,
+    /// so a remote can't make us buffer an unbounded message.
+    ///
+    /// [read_to_end]: TransportChannelReadExt::read_to_end
+    fn read_to_end_bounded(
+        self,
+        max_bytes: usize,
+    ) -> ghost_actor::dependencies::must_future::MustBoxFuture<'static, TransportResult<Vec<u8>>>;
 }
 
 impl<T: futures::stream::Stream<Item = Vec<u8>> + Send + Unpin + 'static> TransportChannelReadExt
@@ -76,15 +86,40 @@ impl<T: futures::stream::Stream<Item = Vec<u8>> + Send + Unpin + 'static> Transp
         .boxed()
         .into()
     }
+
+    fn read_to_end_bounded(
+        mut self,
+        max_bytes: usize,
+    ) -> ghost_actor::dependencies::must_future::MustBoxFuture<'static, TransportResult<Vec<u8>>>
+    {
+        async move {
+            let mut acc = Vec::new();
+            while let Some(x) = self.next().await {
+                if acc.len() + x.len() > max_bytes {
+                    return Err(format!("message larger than {} bytes", max_bytes).into());
+                }
+                acc.extend_from_slice(&x);
+            }
+            Ok(acc)
+        }
+        .boxed()
+        .into()
+    }
 }
 
 /// Sender side of the channel
 pub type TransportChannelWrite =
     Box<dyn futures::sink::Sink<Vec<u8>, Error = TransportError> + Send + Unpin + 'static>;
 
+/// Data larger than this is written to a channel in pieces of this size.
+pub const TRANSPORT_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Extension trait for channel writers
 pub trait TransportChannelWriteExt {
-    /// Write all data and close channel
+    /// Write all data and close channel.
+    /// Large data is sent in chunks of [TRANSPORT_CHUNK_SIZE], waiting for
+    /// the channel to accept each one, so a big message is paced by the
+    /// remote instead of being queued up whole.
     fn write_and_close<'a>(
         &'a mut self,
         data: Vec<u8>,
@@ -99,7 +134,13 @@ impl<T: futures::sink::Sink<Vec<u8>, Error = TransportError> + Send + Unpin + 's
         data: Vec<u8>,
     ) -> ghost_actor::dependencies::must_future::MustBoxFuture<'a, TransportResult<()>> {
         async move {
-            self.send(data).await?;
+            if data.len() <= TRANSPORT_CHUNK_SIZE {
+                self.send(data).await?;
+            } else {
+                for chunk in data.chunks(TRANSPORT_CHUNK_SIZE) {
+                    self.send(chunk.to_vec()).await?;
+                }
+            }
             self.close().await?;
             Ok(())
         }
@@ -1,11 +1,11 @@
 //! A mem-only transport - largely for testing
 
 use crate::transport::*;
-use futures::{future::FutureExt, sink::SinkExt};
+use futures::{future::FutureExt, sink::SinkExt, stream::StreamExt};
 
 use once_cell::sync::Lazy;
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
 };
 use tokio::sync::Mutex;
@@ -45,6 +45,230 @@ fn drop_core(url: url2::Url2) {
     });
 }
 
+/// Faults applied to data travelling in one direction
+/// between two mem transport urls.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemLinkFaults {
+    /// Delay added to every chunk of data sent over the link.
+    pub latency: std::time::Duration,
+    /// Chance between 0.0 and 1.0 that opening a channel over
+    /// the link fails as if the message was lost.
+    pub drop_rate: f64,
+    /// Maximum bytes per second sent over the link.
+    /// None is unlimited.
+    pub bandwidth: Option<u64>,
+}
+
+impl MemLinkFaults {
+    fn is_noop(&self) -> bool {
+        self.latency == std::time::Duration::default()
+            && self.drop_rate <= 0.0
+            && self.bandwidth.is_none()
+    }
+
+    /// How long a chunk of this many bytes takes to cross the link
+    fn delay_for(&self, len: usize) -> std::time::Duration {
+        let transfer = self
+            .bandwidth
+            .map(|b| std::time::Duration::from_secs_f64(len as f64 / b.max(1) as f64))
+            .unwrap_or_default();
+        self.latency + transfer
+    }
+
+    /// Wrap the read side of a channel so data arrives
+    /// no faster than this link allows.
+    fn apply(self, read: TransportChannelRead) -> TransportChannelRead {
+        if self.latency == std::time::Duration::default() && self.bandwidth.is_none() {
+            return read;
+        }
+        Box::new(
+            read.then(move |data| {
+                let delay = self.delay_for(data.len());
+                async move {
+                    tokio::time::delay_for(delay).await;
+                    data
+                }
+            })
+            .boxed(),
+        )
+    }
+}
+
+/// Wrap the read side of a channel from one url to another so it ends once
+/// the two are partitioned, as if the connection was cut. Channels opened
+/// before the partition don't keep carrying data across it.
+fn cut_on_partition(
+    from: url2::Url2,
+    to: url2::Url2,
+    read: TransportChannelRead,
+) -> TransportChannelRead {
+    Box::new(
+        read.then(move |data| {
+            let (from, to) = (from.clone(), to.clone());
+            async move {
+                let cut = FAULTS.lock().await.is_partitioned(&from, &to);
+                (cut, data)
+            }
+        })
+        .take_while(|(cut, _)| futures::future::ready(!*cut))
+        .map(|(_, data)| data)
+        .boxed(),
+    )
+}
+
+/// A named partition of mem transport urls that can be toggled at runtime.
+#[derive(Default)]
+struct MemPartition {
+    urls: HashSet<url2::Url2>,
+    active: bool,
+}
+
+/// All the faults currently injected into the mem transport.
+/// Everything is keyed by url so concurrent tests using
+/// different transports don't affect each other.
+struct MemFaults {
+    /// Faults on a single direction of a link, keyed by (from, to).
+    links: HashMap<(url2::Url2, url2::Url2), MemLinkFaults>,
+    /// Faults on every link to or from a url.
+    nodes: HashMap<url2::Url2, MemLinkFaults>,
+    partitions: HashMap<String, MemPartition>,
+    /// Xorshift state used to decide which messages are dropped.
+    rng: u64,
+}
+
+impl MemFaults {
+    fn new() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        Self {
+            links: HashMap::new(),
+            nodes: HashMap::new(),
+            partitions: HashMap::new(),
+            rng: seed | 1,
+        }
+    }
+
+    /// The faults on the link in this direction.
+    /// Link faults take priority over node faults.
+    fn link(&self, from: &url2::Url2, to: &url2::Url2) -> MemLinkFaults {
+        self.links
+            .get(&(from.clone(), to.clone()))
+            .or_else(|| self.nodes.get(from))
+            .or_else(|| self.nodes.get(to))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Two urls are partitioned if they are both in an active
+    /// partition but don't share any active partition.
+    fn is_partitioned(&self, from: &url2::Url2, to: &url2::Url2) -> bool {
+        let mut from_in_any = false;
+        let mut to_in_any = false;
+        for p in self.partitions.values().filter(|p| p.active) {
+            let has_from = p.urls.contains(from);
+            let has_to = p.urls.contains(to);
+            if has_from && has_to {
+                return false;
+            }
+            from_in_any |= has_from;
+            to_in_any |= has_to;
+        }
+        from_in_any && to_in_any
+    }
+
+    /// Decide if a message with this drop rate is lost
+    fn roll_drop(&mut self, drop_rate: f64) -> bool {
+        if drop_rate <= 0.0 {
+            return false;
+        }
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng as f64 / u64::MAX as f64) < drop_rate
+    }
+}
+
+static FAULTS: Lazy<Arc<Mutex<MemFaults>>> = Lazy::new(|| Arc::new(Mutex::new(MemFaults::new())));
+
+/// Set the faults on data sent from one mem transport url to another.
+/// This only affects one direction of the link.
+pub async fn set_mem_link_faults(from: url2::Url2, to: url2::Url2, faults: MemLinkFaults) {
+    let mut lock = FAULTS.lock().await;
+    if faults.is_noop() {
+        lock.links.remove(&(from, to));
+    } else {
+        lock.links.insert((from, to), faults);
+    }
+}
+
+/// Set the faults on every link to or from a mem transport url,
+/// e.g. to simulate a single peer on a flaky mobile network.
+/// Faults set on a specific link take priority.
+pub async fn set_mem_node_faults(url: url2::Url2, faults: MemLinkFaults) {
+    let mut lock = FAULTS.lock().await;
+    if faults.is_noop() {
+        lock.nodes.remove(&url);
+    } else {
+        lock.nodes.insert(url, faults);
+    }
+}
+
+/// Seed the generator that decides which messages are dropped
+/// so a failing run can be reproduced.
+pub async fn set_mem_fault_seed(seed: u64) {
+    FAULTS.lock().await.rng = seed | 1;
+}
+
+/// Place mem transport urls into a named partition and activate it.
+/// Urls in different partitions cannot open channels to each other,
+/// and channels already open between them stop carrying data.
+/// Partition names are shared by the whole process, so callers sharing
+/// it with others should make their names unique.
+/// Urls that have not been placed in any partition are unaffected.
+pub async fn set_mem_partition<I: IntoIterator<Item = url2::Url2>>(name: &str, urls: I) {
+    let mut lock = FAULTS.lock().await;
+    let partition = lock.partitions.entry(name.to_string()).or_default();
+    partition.urls.extend(urls);
+    partition.active = true;
+}
+
+/// Turn a named partition on or off without changing its members.
+pub async fn set_mem_partition_active(name: &str, active: bool) -> TransportResult<()> {
+    let mut lock = FAULTS.lock().await;
+    match lock.partitions.get_mut(name) {
+        Some(partition) => {
+            partition.active = active;
+            Ok(())
+        }
+        None => Err(format!("no mem partition named {}", name).into()),
+    }
+}
+
+/// Remove mem transport urls from any partition so they can
+/// reach everyone again.
+pub async fn heal_mem_partition<I: IntoIterator<Item = url2::Url2>>(urls: I) {
+    let mut lock = FAULTS.lock().await;
+    let urls = urls.into_iter().collect::<HashSet<_>>();
+    for partition in lock.partitions.values_mut() {
+        partition.urls.retain(|u| !urls.contains(u));
+    }
+    lock.partitions.retain(|_, p| !p.urls.is_empty());
+}
+
+/// Remove all faults involving these mem transport urls.
+pub async fn clear_mem_faults<I: IntoIterator<Item = url2::Url2>>(urls: I) {
+    let urls = urls.into_iter().collect::<Vec<_>>();
+    heal_mem_partition(urls.clone()).await;
+    let mut lock = FAULTS.lock().await;
+    for url in urls.iter() {
+        lock.nodes.remove(url);
+    }
+    lock.links
+        .retain(|(from, to), _| !urls.contains(from) && !urls.contains(to));
+}
+
 /// Spawn / bind the listening side of a mem-only transport - largely for testing
 pub async fn spawn_bind_transport_mem() -> TransportResult<(
     ghost_actor::GhostSender<TransportListener>,
@@ -115,9 +339,23 @@ impl TransportListenerHandler for InnerListen {
     {
         let this_url = self.url.clone();
         Ok(async move {
+            let (outgoing, incoming) = {
+                let mut faults = FAULTS.lock().await;
+                if faults.is_partitioned(&this_url, &url) {
+                    return Err(format!("{} is partitioned from {}", this_url, url).into());
+                }
+                let outgoing = faults.link(&this_url, &url);
+                if faults.roll_drop(outgoing.drop_rate) {
+                    return Err(format!("dropped channel from {} to {}", this_url, url).into());
+                }
+                (outgoing, faults.link(&url, &this_url))
+            };
             let mut evt_send = get_core(url.clone()).await?;
 
             let ((send1, recv1), (send2, recv2)) = create_transport_channel_pair();
+            // data written by us is read by the remote and vice versa
+            let recv1 = cut_on_partition(this_url.clone(), url.clone(), outgoing.apply(recv1));
+            let recv2 = cut_on_partition(url.clone(), this_url.clone(), incoming.apply(recv2));
 
             // if we don't spawn here there can be a deadlock on
             // incoming_channel trying to process all channel data
@@ -179,4 +417,192 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_send_chunked_mem_transport() -> TransportResult<()> {
+        let (bind1, evt1) = spawn_bind_transport_mem().await?;
+        test_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem().await?;
+        test_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        let big = "a".repeat(TRANSPORT_CHUNK_SIZE * 3 + 7);
+        let res = bind1
+            .request(url2.clone(), big.clone().into_bytes())
+            .await?;
+        assert_eq!(
+            &format!("echo({}): {}", url1, big),
+            &String::from_utf8_lossy(&res),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_bound_mem_reads() -> TransportResult<()> {
+        let (bind1, _evt1) = spawn_bind_transport_mem().await?;
+        let (bind2, mut evt2) = spawn_bind_transport_mem().await?;
+        let url2 = bind2.bound_url().await?;
+
+        for (len, fits) in vec![
+            (TRANSPORT_CHUNK_SIZE * 2, true),
+            (TRANSPORT_CHUNK_SIZE * 2 + 1, false),
+        ] {
+            let (_, mut write, _) = bind1.create_channel(url2.clone()).await?;
+            write.write_and_close(vec![0; len]).await?;
+            let read = match evt2.next().await {
+                Some(TransportEvent::IncomingChannel(_, _, read)) => read,
+                None => panic!("no incoming channel"),
+            };
+            let res = read.read_to_end_bounded(TRANSPORT_CHUNK_SIZE * 2).await;
+            assert_eq!(res.is_ok(), fits, "{} bytes", len);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_partition_mem_transport() -> TransportResult<()> {
+        let (bind1, evt1) = spawn_bind_transport_mem().await?;
+        test_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem().await?;
+        test_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        set_mem_partition("a", vec![url1.clone()]).await;
+        set_mem_partition("b", vec![url2.clone()]).await;
+        assert!(bind1
+            .request(url2.clone(), b"test1".to_vec())
+            .await
+            .is_err());
+        assert!(bind2
+            .request(url1.clone(), b"test2".to_vec())
+            .await
+            .is_err());
+
+        heal_mem_partition(vec![url1.clone(), url2.clone()]).await;
+        let res = bind1.request(url2.clone(), b"test3".to_vec()).await?;
+        assert_eq!(
+            &format!("echo({}): test3", url1),
+            &String::from_utf8_lossy(&res),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn partition_cuts_open_mem_channels() -> TransportResult<()> {
+        let (bind1, _evt1) = spawn_bind_transport_mem().await?;
+        let (bind2, mut evt2) = spawn_bind_transport_mem().await?;
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        let (_, mut write, _) = bind1.create_channel(url2.clone()).await?;
+        let mut read = match evt2.next().await {
+            Some(TransportEvent::IncomingChannel(_, _, read)) => read,
+            None => panic!("no incoming channel"),
+        };
+        write.send(b"before".to_vec()).await?;
+        assert_eq!(read.next().await, Some(b"before".to_vec()));
+
+        set_mem_partition("cut-a", vec![url1.clone()]).await;
+        set_mem_partition("cut-b", vec![url2.clone()]).await;
+        write.send(b"after".to_vec()).await?;
+        assert_eq!(read.next().await, None);
+
+        clear_mem_faults(vec![url1, url2]).await;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_toggle_named_mem_partition() -> TransportResult<()> {
+        let (bind1, evt1) = spawn_bind_transport_mem().await?;
+        test_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem().await?;
+        test_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        set_mem_partition("toggle-a", vec![url1.clone()]).await;
+        set_mem_partition("toggle-b", vec![url2.clone()]).await;
+        assert!(bind1
+            .request(url2.clone(), b"test1".to_vec())
+            .await
+            .is_err());
+
+        set_mem_partition_active("toggle-a", false).await?;
+        bind1.request(url2.clone(), b"test2".to_vec()).await?;
+
+        set_mem_partition_active("toggle-a", true).await?;
+        assert!(bind1
+            .request(url2.clone(), b"test3".to_vec())
+            .await
+            .is_err());
+
+        clear_mem_faults(vec![url1, url2]).await;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_drop_mem_messages() -> TransportResult<()> {
+        let (bind1, evt1) = spawn_bind_transport_mem().await?;
+        test_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem().await?;
+        test_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        set_mem_link_faults(
+            url1.clone(),
+            url2.clone(),
+            MemLinkFaults {
+                drop_rate: 1.0,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(bind1
+            .request(url2.clone(), b"test1".to_vec())
+            .await
+            .is_err());
+        // only one direction is faulty
+        bind2.request(url1.clone(), b"test2".to_vec()).await?;
+
+        clear_mem_faults(vec![url1, url2]).await;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn it_can_delay_mem_messages() -> TransportResult<()> {
+        let (bind1, evt1) = spawn_bind_transport_mem().await?;
+        test_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem().await?;
+        test_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        let latency = std::time::Duration::from_millis(50);
+        set_mem_node_faults(
+            url2.clone(),
+            MemLinkFaults {
+                latency,
+                ..Default::default()
+            },
+        )
+        .await;
+        let start = std::time::Instant::now();
+        bind1.request(url2.clone(), b"test1".to_vec()).await?;
+        // latency applies to both the request and the response
+        assert!(start.elapsed() >= latency * 2);
+
+        clear_mem_faults(vec![url1, url2]).await;
+        Ok(())
+    }
 }
@@ -0,0 +1,389 @@
+//! Storage backends underneath the buffered stores.
+//!
+//! An environment is kept in a [Backend]. This crate comes with LMDB, on disk
+//! ([lmdb]), and a pure in-memory backend ([mem]), chosen with [StorageBackend],
+//! and anything else implementing the trait can be plugged in with
+//! [EnvironmentWrite::new_with_custom_backend](crate::env::EnvironmentWrite::new_with_custom_backend).
+//! The database handles ([SingleStore], [IntegerStore], [MultiStore]) remember
+//! which backend opened them and read and write through its transactions
+//! ([Reader](crate::transaction::Reader), [Writer]), so the buffered stores
+//! above them work the same with any backend. Values are sealed and opened
+//! here too when the environment is encrypted (see [crypt]).
+
+use crate::{
+    db::DbKind,
+    error::{DatabaseError, DatabaseResult},
+    prelude::IntKey,
+    transaction::{Readable, Writer},
+};
+use crypt::EnvCipher;
+use std::{
+    borrow::Cow,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+pub mod crypt;
+pub mod lmdb;
+pub mod mem;
+
+#[cfg(test)]
+mod tests;
+
+/// Which of the built-in backends an environment is kept in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// LMDB databases in the environment's directory
+    Lmdb,
+    /// In memory only, lost when the process exits.
+    /// Useful for tests, which then never touch the disk.
+    Memory,
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        StorageBackend::Lmdb
+    }
+}
+
+impl StorageBackend {
+    /// Open this backend for the environment at `path`
+    pub fn open(&self, path: &Path) -> DatabaseResult<Arc<dyn Backend>> {
+        match self {
+            StorageBackend::Lmdb => Ok(Arc::new(lmdb::LmdbBackend::open(path)?)),
+            StorageBackend::Memory => Ok(Arc::new(mem::MemEnv::new())),
+        }
+    }
+}
+
+/// A database opened in a [Backend], numbered however the backend likes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DbId(pub usize);
+
+/// Identifies the backend instance that transactions and database handles
+/// belong to, so a handle can't be used with another environment's transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackendTag(u64);
+
+impl BackendTag {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Key-value pairs as a backend stores them
+pub type StoredIter<'t> = Box<dyn Iterator<Item = DatabaseResult<(&'t [u8], &'t [u8])>> + 't>;
+
+/// The values at one key of a multi-value database, as a backend stores them
+pub type StoredValues<'t> = Box<dyn Iterator<Item = DatabaseResult<&'t [u8]>> + 't>;
+
+/// Somewhere to keep the databases of an environment.
+///
+/// Transactions must behave like LMDB's: readers see a snapshot of the state
+/// committed when they started, and there is only one writer at a time,
+/// whose changes become visible to new readers when it commits.
+/// Keys are ordered bytewise, except in [DbKind::SingleInt] databases,
+/// whose keys order as native-endian `u32`s. The values at a key of a
+/// [DbKind::Multi] database are a set, ordered bytewise.
+pub trait Backend: Send + Sync {
+    /// Open (creating if needed) the named database
+    fn open(&self, name: &str, kind: DbKind) -> DatabaseResult<DbId>;
+
+    /// Start a read-only transaction
+    fn reader(&self) -> DatabaseResult<Box<dyn BackendRead + '_>>;
+
+    /// Start the read-write transaction, waiting for any other to finish
+    fn writer(&self) -> DatabaseResult<Box<dyn BackendWrite + '_>>;
+
+    /// Whether this backend keeps its data in the environment's directory
+    fn on_disk(&self) -> bool;
+
+    /// The wrapped LMDB environment, if this backend is LMDB
+    fn rkv(&self) -> Option<&rkv::Rkv> {
+        None
+    }
+}
+
+/// A transaction of a [Backend] which can be read from
+pub trait BackendRead {
+    /// Get the value at a key of a single-value database
+    fn get(&self, db: DbId, k: &[u8]) -> DatabaseResult<Option<&[u8]>>;
+
+    /// Iterate over every key of a single-value database in order
+    fn iter_start(&self, db: DbId) -> DatabaseResult<StoredIter<'_>>;
+
+    /// Iterate over every key of a single-value database in reverse order
+    fn iter_end(&self, db: DbId) -> DatabaseResult<StoredIter<'_>>;
+
+    /// Iterate in order from a key onwards in a single-value database
+    fn iter_from(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredIter<'_>>;
+
+    /// Get every value at a key of a multi-value database, in order
+    fn get_multi(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredValues<'_>>;
+
+    /// Iterate over every value of a multi-value database, with its key,
+    /// in order of key and then of value
+    fn iter_multi(&self, db: DbId) -> DatabaseResult<StoredIter<'_>>;
+}
+
+/// The read-write transaction of a [Backend]
+pub trait BackendWrite: BackendRead {
+    /// Put a value at a key of a single-value database, replacing any value there
+    fn put(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()>;
+
+    /// Add a value at a key of a multi-value database.
+    /// With `no_dup_data` set, adding a value which is already there is a no-op.
+    fn put_multi(&mut self, db: DbId, k: &[u8], v: &[u8], no_dup_data: bool) -> DatabaseResult<()>;
+
+    /// Delete the value, or every value of a multi-value database,
+    /// at a key, if there is one
+    fn delete(&mut self, db: DbId, k: &[u8]) -> DatabaseResult<()>;
+
+    /// Remove one value at a key of a multi-value database, if it is there
+    fn delete_multi(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()>;
+
+    /// Delete everything in a database
+    fn clear(&mut self, db: DbId) -> DatabaseResult<()>;
+
+    /// Make the changes visible to new readers
+    fn commit(self: Box<Self>) -> DatabaseResult<()>;
+
+    /// This transaction, to read through
+    fn as_read(&self) -> &dyn BackendRead;
+}
+
+/// Raw key-value pairs from a [SingleStore] or [IntegerStore].
+/// Values are only borrowed from the transaction if they didn't need opening.
+pub type RawIter<'t> = Box<dyn Iterator<Item = DatabaseResult<(&'t [u8], Cow<'t, [u8]>)>> + 't>;
+
+/// Raw values for one key of a [MultiStore]
+pub type RawMultiIter<'t> = Box<dyn Iterator<Item = DatabaseResult<Cow<'t, [u8]>>> + 't>;
+
+/// A database opened in a backend, and which backend that was
+#[derive(Clone, Copy)]
+struct Handle {
+    tag: BackendTag,
+    db: DbId,
+}
+
+impl Handle {
+    fn read<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<&'t dyn BackendRead> {
+        if r.tag() == self.tag {
+            Ok(r.txn())
+        } else {
+            Err(DatabaseError::BackendMismatch)
+        }
+    }
+
+    fn write<'a, 'env>(
+        &self,
+        writer: &'a mut Writer<'env>,
+    ) -> DatabaseResult<&'a mut (dyn BackendWrite + 'env)> {
+        if writer.tag() == self.tag {
+            Ok(writer.txn_mut())
+        } else {
+            Err(DatabaseError::BackendMismatch)
+        }
+    }
+}
+
+fn open_value<'t>(
+    cipher: Option<&EnvCipher>,
+    k: &[u8],
+    v: &'t [u8],
+) -> DatabaseResult<Cow<'t, [u8]>> {
+    match cipher {
+        Some(cipher) => Ok(Cow::Owned(cipher.open(k, v)?)),
+        None => Ok(Cow::Borrowed(v)),
+    }
+}
+
+fn seal_value<'v>(
+    cipher: Option<&EnvCipher>,
+    k: &[u8],
+    v: &'v [u8],
+) -> DatabaseResult<Cow<'v, [u8]>> {
+    match cipher {
+        Some(cipher) => Ok(Cow::Owned(cipher.seal(k, v)?)),
+        None => Ok(Cow::Borrowed(v)),
+    }
+}
+
+fn open_iter<'t>(iter: StoredIter<'t>, cipher: Option<&'t EnvCipher>) -> RawIter<'t> {
+    Box::new(iter.map(move |item| {
+        let (k, v) = item?;
+        Ok((k, open_value(cipher, k, v)?))
+    }))
+}
+
+/// A database with arbitrary keys and one value per key
+#[derive(Clone, Copy)]
+pub struct SingleStore(Handle);
+
+impl SingleStore {
+    pub(crate) fn new(tag: BackendTag, db: DbId) -> Self {
+        Self(Handle { tag, db })
+    }
+
+    /// Get the value at a key
+    pub fn get<'t, R: Readable>(
+        &self,
+        r: &'t R,
+        k: &[u8],
+    ) -> DatabaseResult<Option<Cow<'t, [u8]>>> {
+        let v = self.0.read(r)?.get(self.0.db, k)?;
+        v.map(|v| open_value(r.cipher(), k, v)).transpose()
+    }
+
+    /// Put a value at a key, replacing any value there
+    pub fn put(&self, writer: &mut Writer, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        let v = seal_value(writer.cipher(), k, v)?;
+        self.0.write(writer)?.put(self.0.db, k, &v)
+    }
+
+    /// Delete the value at a key, if there is one
+    pub fn delete(&self, writer: &mut Writer, k: &[u8]) -> DatabaseResult<()> {
+        self.0.write(writer)?.delete(self.0.db, k)
+    }
+
+    /// Delete everything in the database
+    pub fn clear(&self, writer: &mut Writer) -> DatabaseResult<()> {
+        self.0.write(writer)?.clear(self.0.db)
+    }
+
+    /// Iterate over every key in order
+    pub fn iter_start<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_start(self.0.db)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+
+    /// Iterate over every key in reverse order
+    pub fn iter_end<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_end(self.0.db)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+
+    /// Iterate in order from a key onwards
+    pub fn iter_from<'t, R: Readable>(&self, r: &'t R, k: &[u8]) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_from(self.0.db, k)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+}
+
+/// A database with [IntKey] keys, in integer order, and one value per key
+#[derive(Clone, Copy)]
+pub struct IntegerStore(Handle);
+
+impl IntegerStore {
+    pub(crate) fn new(tag: BackendTag, db: DbId) -> Self {
+        Self(Handle { tag, db })
+    }
+
+    /// Get the value at a key
+    pub fn get<'t, R: Readable>(
+        &self,
+        r: &'t R,
+        k: IntKey,
+    ) -> DatabaseResult<Option<Cow<'t, [u8]>>> {
+        let v = self.0.read(r)?.get(self.0.db, k.as_ref())?;
+        v.map(|v| open_value(r.cipher(), k.as_ref(), v)).transpose()
+    }
+
+    /// Put a value at a key, replacing any value there
+    pub fn put(&self, writer: &mut Writer, k: IntKey, v: &[u8]) -> DatabaseResult<()> {
+        let v = seal_value(writer.cipher(), k.as_ref(), v)?;
+        self.0.write(writer)?.put(self.0.db, k.as_ref(), &v)
+    }
+
+    /// Delete the value at a key, if there is one
+    pub fn delete(&self, writer: &mut Writer, k: IntKey) -> DatabaseResult<()> {
+        self.0.write(writer)?.delete(self.0.db, k.as_ref())
+    }
+
+    /// Delete everything in the database
+    pub fn clear(&self, writer: &mut Writer) -> DatabaseResult<()> {
+        self.0.write(writer)?.clear(self.0.db)
+    }
+
+    /// Iterate over every key in order
+    pub fn iter_start<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_start(self.0.db)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+
+    /// Iterate over every key in reverse order
+    pub fn iter_end<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_end(self.0.db)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+
+    /// Iterate in order from a key onwards
+    pub fn iter_from<'t, R: Readable>(&self, r: &'t R, k: IntKey) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_from(self.0.db, k.as_ref())?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+}
+
+/// A database with arbitrary keys and a sorted set of values per key
+#[derive(Clone, Copy)]
+pub struct MultiStore(Handle);
+
+impl MultiStore {
+    pub(crate) fn new(tag: BackendTag, db: DbId) -> Self {
+        Self(Handle { tag, db })
+    }
+
+    /// Get every value at a key, in order.
+    /// In an encrypted environment the order is that of the sealed values.
+    pub fn get<'t, R: Readable>(&self, r: &'t R, k: &[u8]) -> DatabaseResult<RawMultiIter<'t>> {
+        let cipher = r.cipher();
+        let key = k.to_vec();
+        let iter = self.0.read(r)?.get_multi(self.0.db, k)?;
+        Ok(Box::new(iter.map(move |v| open_value(cipher, &key, v?))))
+    }
+
+    /// Iterate over every value with its key, in order of key and then of value.
+    /// In an encrypted environment the values of a key are in the order of
+    /// the sealed values.
+    pub fn iter_start<'t, R: Readable>(&self, r: &'t R) -> DatabaseResult<RawIter<'t>> {
+        let iter = self.0.read(r)?.iter_multi(self.0.db)?;
+        Ok(open_iter(iter, r.cipher()))
+    }
+
+    /// Add a value at a key.
+    /// With `no_dup_data` set, adding a value which is already there is a no-op.
+    pub fn put(
+        &self,
+        writer: &mut Writer,
+        k: &[u8],
+        v: &[u8],
+        no_dup_data: bool,
+    ) -> DatabaseResult<()> {
+        let v = seal_value(writer.cipher(), k, v)?;
+        self.0
+            .write(writer)?
+            .put_multi(self.0.db, k, &v, no_dup_data)
+    }
+
+    /// Remove one value at a key, if it is there
+    pub fn delete(&self, writer: &mut Writer, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        // values seal deterministically, so this finds the sealed value
+        let v = seal_value(writer.cipher(), k, v)?;
+        self.0.write(writer)?.delete_multi(self.0.db, k, &v)
+    }
+
+    /// Remove every value at a key
+    pub fn delete_all(&self, writer: &mut Writer, k: &[u8]) -> DatabaseResult<()> {
+        self.0.write(writer)?.delete(self.0.db, k)
+    }
+
+    /// Delete everything in the database
+    pub fn clear(&self, writer: &mut Writer) -> DatabaseResult<()> {
+        self.0.write(writer)?.clear(self.0.db)
+    }
+}
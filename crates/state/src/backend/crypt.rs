@@ -0,0 +1,195 @@
+//! Encryption at rest.
+//!
+//! Every value in an encrypted environment is sealed with ChaCha20-Poly1305
+//! under the environment's data key, using the database key as associated data.
+//! Keys are not encrypted: they are hashes and sequence numbers, and LMDB needs
+//! them in the clear to keep them sorted.
+//!
+//! The nonce of each value is derived from its key and plaintext, so the same
+//! value always seals to the same bytes. That keeps multi-value databases able
+//! to find, deduplicate and delete values by their sealed bytes.
+//!
+//! The data key is random, and is stored next to the databases wrapped by a key
+//! derived from a signature made by a keystore key. Signatures are deterministic,
+//! so the keystore can unwrap the data key again as long as it holds that key,
+//! and only when it has been unlocked with its passphrase.
+
+use crate::error::{DatabaseError, DatabaseResult};
+use holo_hash::AgentPubKey;
+use holochain_keystore::{AgentPubKeyExt, KeystoreSender};
+use ring::{
+    aead, hkdf, hmac,
+    rand::{SecureRandom, SystemRandom},
+};
+use std::{io::Write, path::Path, sync::Arc};
+
+/// The file in an environment's directory holding its wrapped data key
+pub const KEY_FILE_NAME: &str = "encryption.key";
+
+/// Signed by the keystore to derive the key which wraps the data key
+const KEY_CONTEXT: &[u8] = b"holochain environment encryption key";
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const AEAD_KEY_LEN: usize = 32;
+const MAC_KEY_LEN: usize = 32;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeyFile {
+    /// The keystore key whose signature unwraps the data key
+    unlock_key: AgentPubKey,
+    salt: Vec<u8>,
+    /// The data key, sealed with the key derived from the signature
+    sealed_key: Vec<u8>,
+}
+
+struct Keys {
+    aead: aead::LessSafeKey,
+    mac: hmac::Key,
+}
+
+/// The key an encrypted environment's values are sealed with
+#[derive(Clone)]
+pub struct EnvCipher(Arc<Keys>);
+
+impl EnvCipher {
+    /// Unwrap the data key of the environment in `dir` with the keystore,
+    /// or, for a new environment, create one and store it wrapped.
+    /// An existing environment which was not encrypted can't be opened this way.
+    pub async fn unlock(dir: &Path, keystore: &KeystoreSender) -> DatabaseResult<Self> {
+        let key_path = dir.join(KEY_FILE_NAME);
+        if !key_path.exists() {
+            if is_unencrypted(dir) {
+                return Err(DatabaseError::EnvironmentNotEncrypted(dir.to_owned()));
+            }
+            let rng = SystemRandom::new();
+            let unlock_key = AgentPubKey::new_from_pure_entropy(keystore).await?;
+            let salt = random_bytes(&rng, 32)?;
+            let data_key = random_bytes(&rng, AEAD_KEY_LEN + MAC_KEY_LEN)?;
+            let wrapping_key = wrapping_key(&unlock_key, &salt, keystore).await?;
+            let nonce = random_bytes(&rng, NONCE_LEN)?;
+            let sealed_key = seal(&wrapping_key, &nonce, &data_key, &[])?;
+            let file = KeyFile {
+                unlock_key,
+                salt,
+                sealed_key,
+            };
+            // if the environment is being created elsewhere at the same time,
+            // whichever key is stored first wins
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&key_path)
+            {
+                Ok(mut f) => {
+                    f.write_all(&rmp_serde::to_vec_named(&file)?)?;
+                    f.sync_all()?;
+                    return Self::from_data_key(&data_key);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let file: KeyFile = rmp_serde::from_slice(&std::fs::read(&key_path)?)?;
+        let wrapping_key = wrapping_key(&file.unlock_key, &file.salt, keystore).await?;
+        let data_key = open(&wrapping_key, &file.sealed_key, &[])?;
+        Self::from_data_key(&data_key)
+    }
+
+    fn from_data_key(data_key: &[u8]) -> DatabaseResult<Self> {
+        if data_key.len() != AEAD_KEY_LEN + MAC_KEY_LEN {
+            return Err(DatabaseError::DecryptionFailed);
+        }
+        let (aead_key, mac_key) = data_key.split_at(AEAD_KEY_LEN);
+        let aead = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, aead_key)
+            .map_err(|_| DatabaseError::DecryptionFailed)?;
+        Ok(Self(Arc::new(Keys {
+            aead: aead::LessSafeKey::new(aead),
+            mac: hmac::Key::new(hmac::HMAC_SHA256, mac_key),
+        })))
+    }
+
+    /// Seal the value stored at a key
+    pub(crate) fn seal(&self, k: &[u8], v: &[u8]) -> DatabaseResult<Vec<u8>> {
+        let mut ctx = hmac::Context::with_key(&self.0.mac);
+        ctx.update(&(k.len() as u64).to_le_bytes());
+        ctx.update(k);
+        ctx.update(v);
+        let tag = ctx.sign();
+        seal(&self.0.aead, &tag.as_ref()[..NONCE_LEN], v, k)
+    }
+
+    /// Open the value stored at a key
+    pub(crate) fn open(&self, k: &[u8], v: &[u8]) -> DatabaseResult<Vec<u8>> {
+        open(&self.0.aead, v, k)
+    }
+}
+
+/// Check whether the environment in `dir` was created encrypted
+pub(crate) fn is_encrypted(dir: &Path) -> bool {
+    dir.join(KEY_FILE_NAME).exists()
+}
+
+/// Check whether the environment in `dir` exists and was created unencrypted
+pub(crate) fn is_unencrypted(dir: &Path) -> bool {
+    !is_encrypted(dir) && dir.join("data.mdb").exists()
+}
+
+async fn wrapping_key(
+    unlock_key: &AgentPubKey,
+    salt: &[u8],
+    keystore: &KeystoreSender,
+) -> DatabaseResult<aead::LessSafeKey> {
+    let signature = unlock_key
+        .sign_raw(keystore, &[KEY_CONTEXT, salt].concat())
+        .await?;
+    let okm = hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+        .extract(&signature.0)
+        .expand(&[KEY_CONTEXT], &aead::CHACHA20_POLY1305)
+        .map_err(|_| DatabaseError::EncryptionError("could not derive wrapping key".into()))?;
+    Ok(aead::LessSafeKey::new(aead::UnboundKey::from(okm)))
+}
+
+fn random_bytes(rng: &SystemRandom, len: usize) -> DatabaseResult<Vec<u8>> {
+    let mut buf = vec![0; len];
+    rng.fill(&mut buf)
+        .map_err(|_| DatabaseError::EncryptionError("no randomness available".into()))?;
+    Ok(buf)
+}
+
+/// Returns the nonce followed by the ciphertext and tag
+fn seal(key: &aead::LessSafeKey, nonce: &[u8], v: &[u8], aad: &[u8]) -> DatabaseResult<Vec<u8>> {
+    let mut nonce_bytes = [0; NONCE_LEN];
+    nonce_bytes.copy_from_slice(nonce);
+    let mut in_out = v.to_vec();
+    key.seal_in_place_append_tag(
+        aead::Nonce::assume_unique_for_key(nonce_bytes),
+        aead::Aad::from(aad),
+        &mut in_out,
+    )
+    .map_err(|_| DatabaseError::EncryptionError("value too large to seal".into()))?;
+    let mut sealed = Vec::with_capacity(NONCE_LEN + in_out.len());
+    sealed.extend_from_slice(nonce);
+    sealed.append(&mut in_out);
+    Ok(sealed)
+}
+
+fn open(key: &aead::LessSafeKey, sealed: &[u8], aad: &[u8]) -> DatabaseResult<Vec<u8>> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(DatabaseError::DecryptionFailed);
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let mut nonce_bytes = [0; NONCE_LEN];
+    nonce_bytes.copy_from_slice(nonce);
+    let mut in_out = ciphertext.to_vec();
+    let len = key
+        .open_in_place(
+            aead::Nonce::assume_unique_for_key(nonce_bytes),
+            aead::Aad::from(aad),
+            &mut in_out,
+        )
+        .map_err(|_| DatabaseError::DecryptionFailed)?
+        .len();
+    in_out.truncate(len);
+    Ok(in_out)
+}
@@ -0,0 +1,345 @@
+//! LMDB, through rkv, as a [Backend]: the databases of an environment
+//! are kept on disk in its directory.
+
+use super::{Backend, BackendRead, BackendWrite, DbId, StoredIter, StoredValues};
+use crate::{
+    db::DbKind,
+    error::{DatabaseError, DatabaseResult},
+    prelude::{BufKey, IntKey},
+};
+use parking_lot::RwLock;
+use rkv::{EnvironmentFlags, Rkv, StoreOptions};
+use std::path::Path;
+
+const DEFAULT_INITIAL_MAP_SIZE: usize = 100 * 1024 * 1024; // 100MB
+const MAX_DBS: u32 = 32;
+
+fn default_flags() -> EnvironmentFlags {
+    // The flags WRITE_MAP and MAP_ASYNC make writes waaaaay faster by async writing to disk rather than blocking
+    // There is some loss of data integrity guarantees that comes with this.
+    EnvironmentFlags::WRITE_MAP | EnvironmentFlags::MAP_ASYNC
+}
+
+#[cfg(feature = "lmdb_no_tls")]
+fn required_flags() -> EnvironmentFlags {
+    // NO_TLS associates read slots with the transaction object instead of the thread, which is crucial for us
+    // so we can have multiple read transactions per thread (since futures can run on any thread)
+    EnvironmentFlags::NO_TLS
+}
+
+#[cfg(not(feature = "lmdb_no_tls"))]
+fn required_flags() -> EnvironmentFlags {
+    EnvironmentFlags::default()
+}
+
+fn rkv_builder(
+    initial_map_size: Option<usize>,
+    flags: Option<EnvironmentFlags>,
+) -> impl (Fn(&Path) -> Result<Rkv, rkv::StoreError>) {
+    move |path: &Path| {
+        let mut env_builder = Rkv::environment_builder();
+        env_builder
+            // max size of memory map, can be changed later
+            .set_map_size(initial_map_size.unwrap_or(DEFAULT_INITIAL_MAP_SIZE))
+            // max number of DBs in this environment
+            .set_max_dbs(MAX_DBS)
+            .set_flags(flags.unwrap_or_else(default_flags) | required_flags());
+        Rkv::from_env(path, env_builder)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LmdbDb {
+    Single(rkv::SingleStore),
+    Integer(rkv::IntegerStore<IntKey>),
+    /// A multi-value database, and the same database opened as single-value
+    /// to iterate over all of it: an LMDB cursor walks every duplicate value
+    Multi(rkv::MultiStore, rkv::SingleStore),
+    /// A database which isn't in a read-only environment, read as empty
+    Missing,
+}
+
+/// An LMDB environment
+pub struct LmdbBackend {
+    rkv: Rkv,
+    dbs: RwLock<Vec<LmdbDb>>,
+    read_only: bool,
+}
+
+impl LmdbBackend {
+    /// Open the LMDB environment in a directory, creating it if needed
+    pub fn open(path: &Path) -> DatabaseResult<Self> {
+        if !path.is_dir() {
+            std::fs::create_dir(path)
+                .map_err(|_e| DatabaseError::EnvironmentMissing(path.to_owned()))?;
+        }
+        Ok(Self {
+            rkv: rkv_builder(None, None)(path)?,
+            dbs: RwLock::new(Vec::new()),
+            read_only: false,
+        })
+    }
+
+    /// Open an existing LMDB environment without writing to it.
+    /// Nothing is created: databases which aren't there read as empty.
+    pub fn open_read_only(path: &Path) -> DatabaseResult<Self> {
+        if !path.is_dir() {
+            return Err(DatabaseError::EnvironmentMissing(path.to_owned()));
+        }
+        Ok(Self {
+            rkv: rkv_builder(None, Some(EnvironmentFlags::READ_ONLY))(path)?,
+            dbs: RwLock::new(Vec::new()),
+            read_only: true,
+        })
+    }
+
+    fn store_options(&self) -> StoreOptions {
+        if self.read_only {
+            StoreOptions::default()
+        } else {
+            StoreOptions::create()
+        }
+    }
+
+    fn db(&self, db: DbId) -> DatabaseResult<LmdbDb> {
+        self.dbs
+            .read()
+            .get(db.0)
+            .copied()
+            .ok_or(DatabaseError::BackendMismatch)
+    }
+}
+
+impl Backend for LmdbBackend {
+    fn open(&self, name: &str, kind: DbKind) -> DatabaseResult<DbId> {
+        let opened = match kind {
+            DbKind::Single => self
+                .rkv
+                .open_single(name, self.store_options())
+                .map(LmdbDb::Single),
+            DbKind::SingleInt => self
+                .rkv
+                .open_integer::<&str, IntKey>(name, self.store_options())
+                .map(LmdbDb::Integer),
+            DbKind::Multi => {
+                let mut opts = self.store_options();
+
+                // This is needed for the optional put flag NO_DUP_DATA on KvvBufUsed.
+                // As far as I can tell, if we are not using NO_DUP_DATA, it will
+                // only affect the sorting of the values in case there are dups,
+                // which should be ok for our usage.
+                //
+                // NOTE - see:
+                // https://github.com/mozilla/rkv/blob/0.10.4/src/env.rs#L122-L131
+                //
+                // Aparently RKV already sets this flag, but it's not mentioned
+                // in the docs anywhere. We're going to set it too, just in case
+                // it is removed out from under us at some point in the future.
+                opts.flags.set(rkv::DatabaseFlags::DUP_SORT, true);
+
+                self.rkv.open_multi(name, opts).and_then(|multi| {
+                    // LMDB hands back the handle of the already open database,
+                    // whatever flags it is opened with here
+                    let all = self.rkv.open_single(name, StoreOptions::default())?;
+                    Ok(LmdbDb::Multi(multi, all))
+                })
+            }
+        };
+        let db = match opened {
+            Err(rkv::StoreError::LmdbError(rkv::LmdbError::NotFound)) if self.read_only => {
+                LmdbDb::Missing
+            }
+            r => r?,
+        };
+        let mut dbs = self.dbs.write();
+        dbs.push(db);
+        Ok(DbId(dbs.len() - 1))
+    }
+
+    fn reader(&self) -> DatabaseResult<Box<dyn BackendRead + '_>> {
+        Ok(Box::new(LmdbTxn {
+            backend: self,
+            txn: self.rkv.read()?,
+        }))
+    }
+
+    fn writer(&self) -> DatabaseResult<Box<dyn BackendWrite + '_>> {
+        Ok(Box::new(LmdbTxn {
+            backend: self,
+            txn: self.rkv.write()?,
+        }))
+    }
+
+    fn on_disk(&self) -> bool {
+        true
+    }
+
+    fn rkv(&self) -> Option<&Rkv> {
+        Some(&self.rkv)
+    }
+}
+
+/// A read-only or read-write LMDB transaction
+struct LmdbTxn<'env, T> {
+    backend: &'env LmdbBackend,
+    txn: T,
+}
+
+fn int_key(k: &[u8]) -> IntKey {
+    IntKey::from_key_bytes_or_friendly_panic(k)
+}
+
+fn ignore_not_found(r: Result<(), rkv::StoreError>) -> DatabaseResult<()> {
+    match r {
+        Err(rkv::StoreError::LmdbError(rkv::LmdbError::NotFound)) => Ok(()),
+        r => Ok(r?),
+    }
+}
+
+fn blob(v: Option<rkv::Value<'_>>) -> DatabaseResult<Option<&[u8]>> {
+    match v {
+        Some(rkv::Value::Blob(buf)) => Ok(Some(buf)),
+        None => Ok(None),
+        Some(_) => Err(DatabaseError::InvalidValue),
+    }
+}
+
+fn blobs(iter: rkv::store::single::Iter<'_>) -> StoredIter<'_> {
+    Box::new(iter.map(|item| match item {
+        Ok((k, Some(rkv::Value::Blob(v)))) => Ok((k, v)),
+        Ok(_) => Err(DatabaseError::InvalidValue),
+        Err(e) => Err(e.into()),
+    }))
+}
+
+impl<'env, T: rkv::Readable> BackendRead for LmdbTxn<'env, T> {
+    fn get(&self, db: DbId, k: &[u8]) -> DatabaseResult<Option<&[u8]>> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => blob(db.get(&self.txn, k)?),
+            LmdbDb::Integer(db) => blob(db.get(&self.txn, int_key(k))?),
+            LmdbDb::Multi(..) => Err(DatabaseError::BackendMismatch),
+            LmdbDb::Missing => Ok(None),
+        }
+    }
+
+    fn iter_start(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => Ok(blobs(db.iter_start(&self.txn)?)),
+            LmdbDb::Integer(db) => Ok(blobs(db.iter_start(&self.txn)?)),
+            LmdbDb::Multi(..) => Err(DatabaseError::BackendMismatch),
+            LmdbDb::Missing => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
+    fn iter_end(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => Ok(blobs(db.iter_end(&self.txn)?)),
+            LmdbDb::Integer(db) => Ok(blobs(db.iter_end(&self.txn)?)),
+            LmdbDb::Multi(..) => Err(DatabaseError::BackendMismatch),
+            LmdbDb::Missing => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
+    fn iter_from(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredIter<'_>> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => Ok(blobs(db.iter_from(&self.txn, k)?)),
+            LmdbDb::Integer(db) => Ok(blobs(db.iter_from(&self.txn, int_key(k))?)),
+            LmdbDb::Multi(..) => Err(DatabaseError::BackendMismatch),
+            LmdbDb::Missing => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
+    fn get_multi(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredValues<'_>> {
+        let db = match self.backend.db(db)? {
+            LmdbDb::Multi(db, _) => db,
+            LmdbDb::Missing => return Ok(Box::new(std::iter::empty())),
+            _ => return Err(DatabaseError::BackendMismatch),
+        };
+        match db.get(&self.txn, k) {
+            Ok(iter) => Ok(Box::new(iter.filter_map(|item| match item {
+                Ok((_, Some(rkv::Value::Blob(v)))) => Some(Ok(v)),
+                Ok((_, Some(_))) => Some(Err(DatabaseError::InvalidValue)),
+                Ok((_, None)) => None,
+                Err(e) => Some(Err(e.into())),
+            }))),
+            Err(rkv::StoreError::LmdbError(rkv::LmdbError::NotFound)) => {
+                Ok(Box::new(std::iter::empty()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn iter_multi(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        match self.backend.db(db)? {
+            LmdbDb::Multi(_, all) => Ok(blobs(all.iter_start(&self.txn)?)),
+            LmdbDb::Missing => Ok(Box::new(std::iter::empty())),
+            _ => Err(DatabaseError::BackendMismatch),
+        }
+    }
+}
+
+impl<'env> BackendWrite for LmdbTxn<'env, rkv::Writer<'env>> {
+    fn put(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        let v = rkv::Value::Blob(v);
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => Ok(db.put(&mut self.txn, k, &v)?),
+            LmdbDb::Integer(db) => Ok(db.put(&mut self.txn, int_key(k), &v)?),
+            LmdbDb::Multi(..) | LmdbDb::Missing => Err(DatabaseError::BackendMismatch),
+        }
+    }
+
+    fn put_multi(&mut self, db: DbId, k: &[u8], v: &[u8], no_dup_data: bool) -> DatabaseResult<()> {
+        let db = match self.backend.db(db)? {
+            LmdbDb::Multi(db, _) => db,
+            _ => return Err(DatabaseError::BackendMismatch),
+        };
+        let v = rkv::Value::Blob(v);
+        if no_dup_data {
+            match db.put_with_flags(&mut self.txn, k, &v, rkv::WriteFlags::NO_DUP_DATA) {
+                // This error is a little misleading...
+                // In a MultiStore with NO_DUP_DATA, it is
+                // actually returned if there is a duplicate
+                // value... which we want to ignore.
+                Err(rkv::StoreError::LmdbError(rkv::LmdbError::KeyExist)) => Ok(()),
+                r => Ok(r?),
+            }
+        } else {
+            Ok(db.put(&mut self.txn, k, &v)?)
+        }
+    }
+
+    fn delete(&mut self, db: DbId, k: &[u8]) -> DatabaseResult<()> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => ignore_not_found(db.delete(&mut self.txn, k)),
+            LmdbDb::Integer(db) => ignore_not_found(db.delete(&mut self.txn, int_key(k))),
+            LmdbDb::Multi(db, _) => ignore_not_found(db.delete_all(&mut self.txn, k)),
+            LmdbDb::Missing => Err(DatabaseError::BackendMismatch),
+        }
+    }
+
+    fn delete_multi(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        match self.backend.db(db)? {
+            LmdbDb::Multi(db, _) => {
+                ignore_not_found(db.delete(&mut self.txn, k, &rkv::Value::Blob(v)))
+            }
+            _ => Err(DatabaseError::BackendMismatch),
+        }
+    }
+
+    fn clear(&mut self, db: DbId) -> DatabaseResult<()> {
+        match self.backend.db(db)? {
+            LmdbDb::Single(db) => Ok(db.clear(&mut self.txn)?),
+            LmdbDb::Integer(db) => Ok(db.clear(&mut self.txn)?),
+            LmdbDb::Multi(db, _) => Ok(db.clear(&mut self.txn)?),
+            LmdbDb::Missing => Err(DatabaseError::BackendMismatch),
+        }
+    }
+
+    fn commit(self: Box<Self>) -> DatabaseResult<()> {
+        Ok(self.txn.commit()?)
+    }
+
+    fn as_read(&self) -> &dyn BackendRead {
+        self
+    }
+}
@@ -0,0 +1,338 @@
+//! A pure in-memory storage backend.
+//!
+//! Each database is a persistent sorted map, so copies share structure and
+//! a change only copies the path to it. Readers hold a snapshot of every
+//! database taken when the transaction opened, and the single writer works
+//! on its own copy which replaces the committed state on commit, so the
+//! transaction semantics match LMDB's without copying whole databases.
+//! Opening a database doesn't wait for the writer: it is added to the
+//! committed state, and snapshots taken before it was opened read it as empty.
+
+use super::{Backend, BackendRead, BackendWrite, DbId, StoredIter, StoredValues};
+use crate::{db::DbKind, error::DatabaseResult};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
+/// A key in a memory database.
+/// Keys of integer databases sort as native-endian `u32`s, like LMDB's `INTEGER_KEY`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MemKey {
+    bytes: Vec<u8>,
+    integer: bool,
+}
+
+impl MemKey {
+    fn as_int(&self) -> Option<u32> {
+        if self.integer && self.bytes.len() == 4 {
+            let mut buf = [0; 4];
+            buf.copy_from_slice(&self.bytes);
+            Some(u32::from_ne_bytes(buf))
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialOrd for MemKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MemKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.as_int(), other.as_int()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => self.bytes.cmp(&other.bytes),
+        }
+    }
+}
+
+/// The sorted values at one key.
+/// Single-value databases hold exactly one value per key.
+type MemValues = im::OrdSet<Vec<u8>>;
+
+/// One database: its values sorted by key, and whether its keys are integers
+#[derive(Clone)]
+struct MemTable {
+    map: im::OrdMap<MemKey, MemValues>,
+    integer: bool,
+}
+
+impl MemTable {
+    fn key(&self, k: &[u8]) -> MemKey {
+        MemKey {
+            bytes: k.to_vec(),
+            integer: self.integer,
+        }
+    }
+}
+
+lazy_static! {
+    static ref EMPTY_TABLE: MemTable = MemTable {
+        map: im::OrdMap::new(),
+        integer: false,
+    };
+}
+
+/// All databases of an environment at one point in time
+#[derive(Clone, Default)]
+struct MemTables(Vec<MemTable>);
+
+impl MemTables {
+    /// A database opened after this snapshot was taken reads as empty
+    fn table(&self, db: DbId) -> &MemTable {
+        self.0.get(db.0).unwrap_or(&EMPTY_TABLE)
+    }
+
+    /// Take in the databases opened since this snapshot was taken
+    fn extend_from(&mut self, committed: &MemTables) {
+        let known = self.0.len();
+        self.0.extend(committed.0.iter().skip(known).cloned());
+    }
+}
+
+/// An in-memory environment
+#[derive(Default)]
+pub struct MemEnv {
+    committed: RwLock<Arc<MemTables>>,
+    names: Mutex<HashMap<String, DbId>>,
+    write_lock: Mutex<()>,
+}
+
+impl MemEnv {
+    /// Create an empty environment
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Backend for MemEnv {
+    fn open(&self, name: &str, kind: DbKind) -> DatabaseResult<DbId> {
+        let mut names = self.names.lock();
+        if let Some(db) = names.get(name) {
+            return Ok(*db);
+        }
+        let mut committed = self.committed.write();
+        let mut tables = (**committed).clone();
+        let db = DbId(tables.0.len());
+        tables.0.push(MemTable {
+            map: im::OrdMap::new(),
+            integer: kind == DbKind::SingleInt,
+        });
+        *committed = Arc::new(tables);
+        names.insert(name.to_string(), db);
+        Ok(db)
+    }
+
+    fn reader(&self) -> DatabaseResult<Box<dyn BackendRead + '_>> {
+        Ok(Box::new(MemReader(self.committed.read().clone())))
+    }
+
+    fn writer(&self) -> DatabaseResult<Box<dyn BackendWrite + '_>> {
+        let lock = self.write_lock.lock();
+        let tables = (**self.committed.read()).clone();
+        Ok(Box::new(MemWriter {
+            env: self,
+            _lock: lock,
+            tables,
+        }))
+    }
+
+    fn on_disk(&self) -> bool {
+        false
+    }
+}
+
+fn get<'t>(tables: &'t MemTables, db: DbId, k: &[u8]) -> Option<&'t [u8]> {
+    let table = tables.table(db);
+    table
+        .map
+        .get(&table.key(k))
+        .and_then(|vs| vs.iter().next())
+        .map(|v| &v[..])
+}
+
+fn iter<'t>(iter: impl Iterator<Item = (&'t MemKey, &'t MemValues)> + 't) -> StoredIter<'t> {
+    Box::new(iter.filter_map(|(k, vs)| vs.iter().next().map(|v| Ok((&k.bytes[..], &v[..])))))
+}
+
+fn get_multi<'t>(tables: &'t MemTables, db: DbId, k: &[u8]) -> StoredValues<'t> {
+    let table = tables.table(db);
+    match table.map.get(&table.key(k)) {
+        Some(vs) => Box::new(vs.iter().map(|v| Ok(&v[..]))),
+        None => Box::new(std::iter::empty()),
+    }
+}
+
+fn iter_multi<'t>(tables: &'t MemTables, db: DbId) -> StoredIter<'t> {
+    Box::new(
+        tables
+            .table(db)
+            .map
+            .iter()
+            .flat_map(|(k, vs)| vs.iter().map(move |v| Ok((&k.bytes[..], &v[..])))),
+    )
+}
+
+impl BackendRead for MemTables {
+    fn get(&self, db: DbId, k: &[u8]) -> DatabaseResult<Option<&[u8]>> {
+        Ok(get(self, db, k))
+    }
+
+    fn iter_start(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        Ok(iter(self.table(db).map.iter()))
+    }
+
+    fn iter_end(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        Ok(iter(self.table(db).map.iter().rev()))
+    }
+
+    fn iter_from(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredIter<'_>> {
+        let table = self.table(db);
+        Ok(iter(table.map.range(table.key(k)..)))
+    }
+
+    fn get_multi(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredValues<'_>> {
+        Ok(get_multi(self, db, k))
+    }
+
+    fn iter_multi(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        Ok(iter_multi(self, db))
+    }
+}
+
+/// A read-only transaction on a [MemEnv]
+struct MemReader(Arc<MemTables>);
+
+impl BackendRead for MemReader {
+    fn get(&self, db: DbId, k: &[u8]) -> DatabaseResult<Option<&[u8]>> {
+        self.0.get(db, k)
+    }
+
+    fn iter_start(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.0.iter_start(db)
+    }
+
+    fn iter_end(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.0.iter_end(db)
+    }
+
+    fn iter_from(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredIter<'_>> {
+        self.0.iter_from(db, k)
+    }
+
+    fn get_multi(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredValues<'_>> {
+        self.0.get_multi(db, k)
+    }
+
+    fn iter_multi(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.0.iter_multi(db)
+    }
+}
+
+/// The read-write transaction on a [MemEnv]
+struct MemWriter<'env> {
+    env: &'env MemEnv,
+    _lock: MutexGuard<'env, ()>,
+    tables: MemTables,
+}
+
+impl<'env> MemWriter<'env> {
+    fn table_mut(&mut self, db: DbId) -> &mut MemTable {
+        if db.0 >= self.tables.0.len() {
+            self.tables.extend_from(&self.env.committed.read());
+        }
+        &mut self.tables.0[db.0]
+    }
+}
+
+impl<'env> BackendRead for MemWriter<'env> {
+    fn get(&self, db: DbId, k: &[u8]) -> DatabaseResult<Option<&[u8]>> {
+        self.tables.get(db, k)
+    }
+
+    fn iter_start(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.tables.iter_start(db)
+    }
+
+    fn iter_end(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.tables.iter_end(db)
+    }
+
+    fn iter_from(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredIter<'_>> {
+        self.tables.iter_from(db, k)
+    }
+
+    fn get_multi(&self, db: DbId, k: &[u8]) -> DatabaseResult<StoredValues<'_>> {
+        self.tables.get_multi(db, k)
+    }
+
+    fn iter_multi(&self, db: DbId) -> DatabaseResult<StoredIter<'_>> {
+        self.tables.iter_multi(db)
+    }
+}
+
+impl<'env> BackendWrite for MemWriter<'env> {
+    fn put(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        let table = self.table_mut(db);
+        let k = table.key(k);
+        table.map.insert(k, MemValues::unit(v.to_vec()));
+        Ok(())
+    }
+
+    // values are a set, so duplicates are never stored
+    fn put_multi(
+        &mut self,
+        db: DbId,
+        k: &[u8],
+        v: &[u8],
+        _no_dup_data: bool,
+    ) -> DatabaseResult<()> {
+        let table = self.table_mut(db);
+        let k = table.key(k);
+        table.map.entry(k).or_default().insert(v.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, db: DbId, k: &[u8]) -> DatabaseResult<()> {
+        let table = self.table_mut(db);
+        let k = table.key(k);
+        table.map.remove(&k);
+        Ok(())
+    }
+
+    fn delete_multi(&mut self, db: DbId, k: &[u8], v: &[u8]) -> DatabaseResult<()> {
+        let table = self.table_mut(db);
+        let k = table.key(k);
+        if let Some(vs) = table.map.get_mut(&k) {
+            vs.remove(v);
+            if vs.is_empty() {
+                table.map.remove(&k);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, db: DbId) -> DatabaseResult<()> {
+        self.table_mut(db).map.clear();
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> DatabaseResult<()> {
+        let MemWriter {
+            env,
+            _lock,
+            mut tables,
+        } = *self;
+        let mut committed = env.committed.write();
+        tables.extend_from(&committed);
+        *committed = Arc::new(tables);
+        Ok(())
+    }
+
+    fn as_read(&self) -> &dyn BackendRead {
+        self
+    }
+}
@@ -0,0 +1,299 @@
+use super::{
+    crypt::{EnvCipher, KEY_FILE_NAME},
+    lmdb::LmdbBackend,
+    mem::MemEnv,
+};
+use crate::{
+    db::{GetDb, WASM},
+    env::{EnvironmentKind, EnvironmentRead, EnvironmentWrite, ReadManager, WriteManager},
+    error::{DatabaseError, DatabaseResult},
+    prelude::*,
+    test_utils::{test_keystore, test_lmdb_env, test_memory_env, TestEnvironment},
+};
+use std::sync::Arc;
+use tempdir::TempDir;
+
+fn memory_env() -> TestEnvironment {
+    test_memory_env(EnvironmentKind::Wasm)
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn memory_reader_is_a_snapshot() -> DatabaseResult<()> {
+    let test_env = memory_env();
+    let arc = test_env.env();
+    assert!(!arc.backend().on_disk());
+    let env = arc.guard();
+    let db = env.open_single("kv")?;
+
+    env.with_commit(|w| db.put(w, b"a", b"1"))?;
+    let before = env.reader()?;
+
+    env.with_commit(|w| {
+        db.put(w, b"a", b"2")?;
+        db.put(w, b"b", b"3")?;
+        // a writer sees its own changes
+        assert_eq!(db.get(w, b"a")?.as_deref(), Some(&b"2"[..]));
+        DatabaseResult::Ok(())
+    })?;
+
+    assert_eq!(db.get(&before, b"a")?.as_deref(), Some(&b"1"[..]));
+    assert_eq!(db.get(&before, b"b")?.as_deref(), None);
+
+    let after = env.reader()?;
+    assert_eq!(db.get(&after, b"a")?.as_deref(), Some(&b"2"[..]));
+    let keys: Vec<_> = db
+        .iter_end(&after)?
+        .map(|r| r.map(|(k, _)| k.to_vec()))
+        .collect::<DatabaseResult<_>>()?;
+    assert_eq!(keys, vec![b"b".to_vec(), b"a".to_vec()]);
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn memory_integer_keys_are_ordered_as_integers() -> DatabaseResult<()> {
+    let test_env = memory_env();
+    let arc = test_env.env();
+    let env = arc.guard();
+    let db = env.open_integer("kvi")?;
+
+    env.with_commit(|w| {
+        for i in &[256u32, 1, 65536, 2] {
+            db.put(w, IntKey::from(*i), &i.to_le_bytes())?;
+        }
+        DatabaseResult::Ok(())
+    })?;
+
+    let r = env.reader()?;
+    let keys: Vec<u32> = db
+        .iter_from(&r, IntKey::from(2))?
+        .map(|r| r.map(|(k, _)| IntKey::from_key_bytes_or_friendly_panic(k).into()))
+        .collect::<DatabaseResult<_>>()?;
+    assert_eq!(keys, vec![2, 256, 65536]);
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn memory_multi_store_values_are_a_set() -> DatabaseResult<()> {
+    let test_env = memory_env();
+    let arc = test_env.env();
+    let env = arc.guard();
+    let db = env.open_multi("kvv")?;
+
+    env.with_commit(|w| {
+        db.put(w, b"k", b"b", false)?;
+        db.put(w, b"k", b"a", false)?;
+        db.put(w, b"k", b"a", true)?;
+        db.delete(w, b"missing", b"a")?;
+        db.delete_all(w, b"missing")
+    })?;
+
+    let r = env.reader()?;
+    let values: Vec<_> = db.get(&r, b"k")?.collect::<DatabaseResult<_>>()?;
+    assert_eq!(values, vec![&b"a"[..], &b"b"[..]]);
+    assert_eq!(db.get(&r, b"missing")?.count(), 0);
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn memory_databases_can_be_opened_during_a_write() -> DatabaseResult<()> {
+    let test_env = memory_env();
+    let arc = test_env.env();
+    let env = arc.guard();
+    let early = env.open_single("early")?;
+    let before = env.reader()?;
+
+    env.with_commit(|w| {
+        early.put(w, b"a", b"1")?;
+        // opening doesn't wait for the writer, which can use the new database
+        let late = env.open_single("late")?;
+        let unused = env.open_single("unused")?;
+        assert_eq!(late.get(w, b"a")?, None);
+        late.put(w, b"a", b"2")?;
+        assert_eq!(unused.get(w, b"a")?, None);
+        DatabaseResult::Ok(())
+    })?;
+
+    // databases opened during the write survive its commit
+    let late = env.open_single("late")?;
+    let unused = env.open_single("unused")?;
+    env.with_commit(|w| unused.put(w, b"a", b"3"))?;
+    let r = env.reader()?;
+    assert_eq!(early.get(&r, b"a")?.as_deref(), Some(&b"1"[..]));
+    assert_eq!(late.get(&r, b"a")?.as_deref(), Some(&b"2"[..]));
+    assert_eq!(unused.get(&r, b"a")?.as_deref(), Some(&b"3"[..]));
+    // older snapshots read them as empty
+    assert_eq!(late.get(&before, b"a")?, None);
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn multi_store_iterates_over_every_value() -> DatabaseResult<()> {
+    for test_env in vec![memory_env(), test_lmdb_env(EnvironmentKind::Wasm)] {
+        let arc = test_env.env();
+        let env = arc.guard();
+        let db = env.open_multi("kvv")?;
+
+        env.with_commit(|w| {
+            db.put(w, b"b", b"1", true)?;
+            db.put(w, b"a", b"2", true)?;
+            db.put(w, b"a", b"1", true)
+        })?;
+
+        let r = env.reader()?;
+        let all: Vec<_> = db
+            .iter_start(&r)?
+            .map(|r| r.map(|(k, v)| (k.to_vec(), v.to_vec())))
+            .collect::<DatabaseResult<_>>()?;
+        assert_eq!(
+            all,
+            vec![
+                (b"a".to_vec(), b"1".to_vec()),
+                (b"a".to_vec(), b"2".to_vec()),
+                (b"b".to_vec(), b"1".to_vec()),
+            ]
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn stores_only_work_with_their_own_backend() -> DatabaseResult<()> {
+    let mem_env = memory_env();
+    let memory = mem_env.env();
+    let memory = memory.guard();
+    let lmdb_env = test_lmdb_env(EnvironmentKind::Wasm);
+    let lmdb = lmdb_env.env();
+    assert!(lmdb.backend().on_disk());
+    let lmdb = lmdb.guard();
+
+    let db = memory.open_single("kv")?;
+    let r = lmdb.reader()?;
+    assert_eq!(db.get(&r, b"a"), Err(DatabaseError::BackendMismatch));
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn custom_backends_can_be_plugged_in() -> DatabaseResult<()> {
+    let tmpdir = TempDir::new("holochain-test-environments").unwrap();
+    let arc = EnvironmentWrite::new_with_custom_backend(
+        tmpdir.path(),
+        EnvironmentKind::Wasm,
+        test_keystore(),
+        |_| Ok(Arc::new(MemEnv::new())),
+    )?;
+    assert!(!arc.backend().on_disk());
+    assert!(!tmpdir.path().join("wasm").exists());
+    let env = arc.guard();
+    let db = env.open_single("kv")?;
+
+    env.with_commit(|w| db.put(w, b"a", b"1"))?;
+    let r = env.reader()?;
+    assert_eq!(db.get(&r, b"a")?.as_deref(), Some(&b"1"[..]));
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn encrypted_values_are_sealed_on_disk() -> DatabaseResult<()> {
+    let tmpdir = TempDir::new("holochain-test-environments").unwrap();
+    let keystore = test_keystore();
+    let arc =
+        EnvironmentWrite::new_encrypted(tmpdir.path(), EnvironmentKind::Wasm, keystore.clone())
+            .await?;
+    assert!(arc.is_encrypted());
+    let env = arc.guard();
+    let single = env.open_single("kv")?;
+    let multi = env.open_multi("kvv")?;
+
+    env.with_commit(|w| {
+        single.put(w, b"a", b"secret")?;
+        multi.put(w, b"k", b"x", true)?;
+        multi.put(w, b"k", b"x", true)?;
+        multi.put(w, b"k", b"y", true)?;
+        multi.delete(w, b"k", b"y")
+    })?;
+
+    let r = env.reader()?;
+    assert_eq!(single.get(&r, b"a")?.as_deref(), Some(&b"secret"[..]));
+    let values: Vec<_> = multi.get(&r, b"k")?.collect::<DatabaseResult<_>>()?;
+    assert_eq!(values, vec![&b"x"[..]]);
+    drop(r);
+
+    // the raw database only holds the sealed value
+    let rkv = env.rkv().unwrap();
+    let raw = rkv.open_single("kv", rkv::StoreOptions::default()).unwrap();
+    let raw_reader = rkv.read().unwrap();
+    match raw.get(&raw_reader, b"a").unwrap() {
+        Some(rkv::Value::Blob(v)) => assert!(!v.windows(6).any(|w| w == b"secret")),
+        _ => panic!("expected a sealed blob"),
+    }
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn unencrypted_environment_stays_unencrypted() -> DatabaseResult<()> {
+    let tmpdir = TempDir::new("holochain-test-environments").unwrap();
+    let dir = tmpdir.path().join("wasm");
+    // an environment from before encryption was turned on
+    drop(LmdbBackend::open(&dir)?);
+    assert!(dir.join("data.mdb").exists());
+
+    let arc =
+        EnvironmentWrite::new_encrypted(tmpdir.path(), EnvironmentKind::Wasm, test_keystore())
+            .await?;
+    assert!(!arc.is_encrypted());
+    assert!(!dir.join(KEY_FILE_NAME).exists());
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn encrypted_environment_needs_its_key() -> DatabaseResult<()> {
+    let tmpdir = TempDir::new("holochain-test-environments").unwrap();
+    let keystore = test_keystore();
+    let dir = tmpdir.path().join("wasm");
+    std::fs::create_dir(&dir)?;
+
+    // the data key unwraps to the same key every time
+    let cipher = EnvCipher::unlock(&dir, &keystore).await?;
+    let again = EnvCipher::unlock(&dir, &keystore).await?;
+    assert_eq!(cipher.seal(b"k", b"v")?, again.seal(b"k", b"v")?);
+    assert_eq!(again.open(b"k", &cipher.seal(b"k", b"v")?)?, b"v".to_vec());
+    // values are bound to their key
+    assert_eq!(
+        cipher.open(b"other", &cipher.seal(b"k", b"v")?),
+        Err(DatabaseError::DecryptionFailed)
+    );
+
+    let res = EnvironmentWrite::new(tmpdir.path(), EnvironmentKind::Wasm, keystore);
+    assert_eq!(res.err(), Some(DatabaseError::EnvironmentEncrypted(dir)));
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn read_only_environment_creates_nothing() -> DatabaseResult<()> {
+    let tmpdir = TempDir::new("holochain-test-environments").unwrap();
+    let dir = tmpdir.path().join("wasm");
+    let res =
+        EnvironmentRead::open_read_only(tmpdir.path(), EnvironmentKind::Wasm, test_keystore());
+    assert_eq!(
+        res.err(),
+        Some(DatabaseError::EnvironmentMissing(dir.clone()))
+    );
+    assert!(!dir.exists());
+
+    // an environment without any of its databases
+    drop(LmdbBackend::open(&dir)?);
+    let env =
+        EnvironmentRead::open_read_only(tmpdir.path(), EnvironmentKind::Wasm, test_keystore())?;
+    let db = env.get_db(&*WASM)?;
+    let g = env.guard();
+    let r = g.reader()?;
+    assert_eq!(db.get(&r, b"a")?, None);
+    assert_eq!(db.iter_start(&r)?.count(), 0);
+
+    // reading them didn't create them
+    let rkv = g.rkv().unwrap();
+    assert!(rkv
+        .open_single("Wasm", rkv::StoreOptions::default())
+        .is_err());
+    Ok(())
+}
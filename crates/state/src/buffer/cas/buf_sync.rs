@@ -34,7 +34,7 @@ where
     P: PrefixType,
 {
     /// Create a new CasBufUsedSync
-    pub fn new(db: rkv::SingleStore) -> Self {
+    pub fn new(db: SingleStore) -> Self {
         Self(KvBufUsed::new(db))
     }
 
@@ -132,7 +132,7 @@ where
     P: PrefixType,
 {
     /// Create a new CasBufFreshSync
-    pub fn new(env: EnvironmentRead, db: rkv::SingleStore) -> Self {
+    pub fn new(env: EnvironmentRead, db: SingleStore) -> Self {
         Self {
             env,
             inner: CasBufUsedSync::new(db),
@@ -1,9 +1,9 @@
+use crate::backend::RawIter;
 use crate::buffer::kv::KvOp;
-use crate::error::DatabaseError;
+use crate::error::{DatabaseError, DatabaseResult};
 use crate::prelude::*;
 use fallible_iterator::{DoubleEndedFallibleIterator, FallibleIterator};
-use rkv::StoreError;
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 use tracing::*;
 
 type IterItem<'env, V> = (&'env [u8], V);
@@ -296,20 +296,18 @@ where
 }
 
 pub struct SingleIterRaw<'txn, V> {
-    iter: rkv::store::single::Iter<'txn>,
-    rev: rkv::store::single::Iter<'txn>,
+    iter: RawIter<'txn>,
+    rev: RawIter<'txn>,
     key: Option<&'txn [u8]>,
     key_back: Option<&'txn [u8]>,
     __type: std::marker::PhantomData<V>,
 }
 
-type InnerItem<'a> = (&'a [u8], Option<rkv::Value<'a>>);
-
 impl<'txn, V> SingleIterRaw<'txn, V>
 where
     V: BufVal,
 {
-    pub fn new(iter: rkv::store::single::Iter<'txn>, rev: rkv::store::single::Iter<'txn>) -> Self {
+    pub fn new(iter: RawIter<'txn>, rev: RawIter<'txn>) -> Self {
         Self {
             iter,
             rev,
@@ -320,20 +318,18 @@ where
     }
 
     fn next_inner(
-        item: Option<Result<InnerItem<'txn>, StoreError>>,
+        item: Option<DatabaseResult<(&'txn [u8], Cow<'txn, [u8]>)>>,
     ) -> Result<Option<IterItem<'txn, V>>, IterError> {
         match item {
-            Some(Ok((k, Some(rkv::Value::Blob(buf))))) => Ok(Some((
+            Some(Ok((k, buf))) => Ok(Some((
                 k,
-                holochain_serialized_bytes::decode(buf).expect(
+                holochain_serialized_bytes::decode(&buf).expect(
                     "Failed to deserialize data from database. Database might be corrupted",
                 ),
             ))),
             None => Ok(None),
-            // TODO: Should this panic aswell?
-            Some(Ok(_)) => Err(DatabaseError::InvalidValue),
             // This could be a IO error so returning it makes sense
-            Some(Err(e)) => Err(DatabaseError::from(e)),
+            Some(Err(e)) => Err(e),
         }
     }
 }
@@ -13,7 +13,6 @@ use crate::{
     prelude::*,
 };
 use fallible_iterator::FallibleIterator;
-use rkv::{IntegerStore, SingleStore};
 use std::collections::BTreeMap;
 
 #[cfg(test)]
@@ -54,7 +53,7 @@ where
 {
     /// Constructor
     // FIXME: why does this conflict with the other `new` when it's called just "new"?
-    pub fn new_int(db: IntegerStore<IntKey>) -> Self {
+    pub fn new_int(db: IntegerStore) -> Self {
         Self {
             store: KvIntStore::new(db),
             scratch: BTreeMap::new(),
@@ -279,7 +278,7 @@ where
     V: BufVal,
 {
     /// Create a new Fresh
-    pub fn new(env: EnvironmentRead, db: IntegerStore<IntKey>) -> Self {
+    pub fn new(env: EnvironmentRead, db: IntegerStore) -> Self {
         Self {
             env,
             inner: Used::new_int(db),
@@ -331,13 +330,9 @@ where
             match op {
                 Put(v) => {
                     let buf = holochain_serialized_bytes::encode(v)?;
-                    let encoded = rkv::Value::Blob(&buf);
-                    self.store.db().put(writer, k, &encoded)?;
+                    self.store.db().put(writer, k, &buf)?;
                 }
-                Delete => match self.store.db().delete(writer, k) {
-                    Err(rkv::StoreError::LmdbError(rkv::LmdbError::NotFound)) => (),
-                    r => r?,
-                },
+                Delete => self.store.db().delete(writer, k)?,
             }
         }
 
@@ -366,21 +361,16 @@ where
             match op {
                 Put(v) => {
                     let buf = holochain_serialized_bytes::encode(v)?;
-                    let encoded = rkv::Value::Blob(&buf);
                     self.store.db().put(
                         writer,
                         IntKey::from_key_bytes_or_friendly_panic(k),
-                        &encoded,
+                        &buf,
                     )?;
                 }
-                Delete => match self
+                Delete => self
                     .store
                     .db()
-                    .delete(writer, IntKey::from_key_bytes_or_friendly_panic(k))
-                {
-                    Err(rkv::StoreError::LmdbError(rkv::LmdbError::NotFound)) => (),
-                    r => r?,
-                },
+                    .delete(writer, IntKey::from_key_bytes_or_friendly_panic(k))?,
             }
         }
 
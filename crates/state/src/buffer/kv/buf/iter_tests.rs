@@ -9,7 +9,6 @@ use crate::{
 };
 use ::fixt::prelude::*;
 use fallible_iterator::{DoubleEndedFallibleIterator, FallibleIterator};
-use rkv::StoreOptions;
 use std::collections::BTreeMap;
 use tracing::*;
 
@@ -20,10 +19,7 @@ async fn kv_iter_from_partial() {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env
-        .inner()
-        .open_single("kv", StoreOptions::create())
-        .unwrap();
+    let db = env.open_single("kv").unwrap();
 
     {
         let mut buf: Store = KvBufUsed::new(db);
@@ -243,10 +239,7 @@ async fn kv_single_iter() {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env
-        .inner()
-        .open_single("kv", StoreOptions::create())
-        .unwrap();
+    let db = env.open_single("kv").unwrap();
     let td = StringFixturator::new(Unpredictable)
         .zip(VFixturator::new(Unpredictable))
         .filter(|(k, _)| k.len() > 0)
@@ -488,10 +481,7 @@ async fn exhaust_both_ends() {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env
-        .inner()
-        .open_single("kv", StoreOptions::create())
-        .unwrap();
+    let db = env.open_single("kv").unwrap();
     let values = (b'a'..=b'z')
         .map(|a| DbString::from_key_bytes_or_friendly_panic(&[a]))
         .zip((0..).into_iter().map(V))
@@ -584,10 +574,7 @@ async fn kv_single_iter_runner(
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env
-        .inner()
-        .open_single("kv", StoreOptions::create())
-        .unwrap();
+    let db = env.open_single("kv").unwrap();
 
     let mut runs = vec!["Start | ".into()];
     let mut expected_state: BTreeMap<DbString, V> = BTreeMap::new();
@@ -7,7 +7,6 @@ use crate::{
 };
 use ::fixt::prelude::*;
 use fallible_iterator::FallibleIterator;
-use rkv::StoreOptions;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use tracing::*;
@@ -51,7 +50,7 @@ async fn kv_iterators() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -94,10 +93,7 @@ async fn kv_empty_iterators() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env
-        .inner()
-        .open_single("kv", StoreOptions::create())
-        .unwrap();
+    let db = env.open_single("kv").unwrap();
 
     env.with_reader(|reader| {
         let buf = Store::new(db);
@@ -117,8 +113,8 @@ async fn kv_store_sanity_check() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db1 = env.inner().open_single("kv1", StoreOptions::create())?;
-    let db2 = env.inner().open_single("kv1", StoreOptions::create())?;
+    let db1 = env.open_single("kv1")?;
+    let db2 = env.open_single("kv1")?;
 
     let testval = TestVal { name: "Joe".into() };
 
@@ -158,7 +154,7 @@ async fn kv_indicate_value_overwritten() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
 
@@ -177,7 +173,7 @@ async fn kv_deleted_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
@@ -219,7 +215,7 @@ async fn kv_deleted_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -258,7 +254,7 @@ async fn kv_get_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
@@ -279,7 +275,7 @@ async fn kv_get_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -307,7 +303,7 @@ async fn kv_get_del_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
@@ -329,7 +325,7 @@ async fn kv_get_del_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_single("kv", StoreOptions::create())?;
+    let db = env.open_single("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -1,13 +1,14 @@
 use crate::buffer::iter::SingleIterRaw;
 use crate::{error::DatabaseResult, prelude::*};
+use std::borrow::Cow;
 
 pub trait KvStoreT<K, V> {
-    /// Fetch data from DB as raw byte slice
+    /// Fetch data from DB as raw bytes
     fn get_bytes<'env, R: Readable>(
         &self,
         reader: &'env R,
         k: &K,
-    ) -> DatabaseResult<Option<&'env [u8]>>;
+    ) -> DatabaseResult<Option<Cow<'env, [u8]>>>;
 
     /// Fetch data from DB, deserialize into V type
     fn get<R: Readable>(&self, reader: &R, k: &K) -> DatabaseResult<Option<V>>;
@@ -1,45 +1,38 @@
 use crate::buffer::{iter::SingleIterRaw, kv::KvStoreT};
-use crate::{
-    error::{DatabaseError, DatabaseResult},
-    prelude::*,
-};
+use crate::{error::DatabaseResult, prelude::*};
 use fallible_iterator::FallibleIterator;
-use rkv::IntegerStore;
+use std::borrow::Cow;
 
 pub type KvIntStore<V> = KvIntStoreGeneric<IntKey, V>;
 
-/// Wrapper around an rkv IntegerStore which provides strongly typed values
+/// Wrapper around an IntegerStore which provides strongly typed values
 pub struct KvIntStoreGeneric<K, V>
 where
     K: BufIntKey,
     V: BufVal,
 {
-    db: IntegerStore<K>,
+    db: IntegerStore,
     __phantom: std::marker::PhantomData<(K, V)>,
 }
 
 impl<K, V> KvStoreT<K, V> for KvIntStoreGeneric<K, V>
 where
-    K: BufIntKey,
+    K: BufIntKey + Into<IntKey>,
     V: BufVal,
 {
-    /// Fetch data from DB as raw byte slice
+    /// Fetch data from DB as raw bytes
     fn get_bytes<'env, R: Readable>(
         &self,
         reader: &'env R,
         k: &K,
-    ) -> DatabaseResult<Option<&'env [u8]>> {
-        match self.db.get(reader, *k)? {
-            Some(rkv::Value::Blob(buf)) => Ok(Some(buf)),
-            None => Ok(None),
-            Some(_) => Err(DatabaseError::InvalidValue),
-        }
+    ) -> DatabaseResult<Option<Cow<'env, [u8]>>> {
+        self.db.get(reader, (*k).into())
     }
 
     /// Fetch data from DB, deserialize into V type
     fn get<R: Readable>(&self, reader: &R, k: &K) -> DatabaseResult<Option<V>> {
         match self.get_bytes(reader, k)? {
-            Some(bytes) => Ok(Some(holochain_serialized_bytes::decode(bytes)?)),
+            Some(bytes) => Ok(Some(holochain_serialized_bytes::decode(&bytes)?)),
             None => Ok(None),
         }
     }
@@ -47,14 +40,12 @@ where
     /// Put V into DB as serialized data
     fn put(&self, writer: &mut Writer, k: &K, v: &V) -> DatabaseResult<()> {
         let buf = holochain_serialized_bytes::encode(v)?;
-        let encoded = rkv::Value::Blob(&buf);
-        self.db.put(writer, *k, &encoded)?;
-        Ok(())
+        self.db.put(writer, (*k).into(), &buf)
     }
 
     /// Delete value from DB
     fn delete(&self, writer: &mut Writer, k: &K) -> DatabaseResult<()> {
-        Ok(self.db.delete(writer, *k)?)
+        self.db.delete(writer, (*k).into())
     }
 
     /// Iterate over the underlying persisted data
@@ -72,7 +63,7 @@ where
         k: K,
     ) -> DatabaseResult<SingleIterRaw<'env, V>> {
         Ok(SingleIterRaw::new(
-            self.db.iter_from(reader, k)?,
+            self.db.iter_from(reader, k.into())?,
             self.db.iter_end(reader)?,
         ))
     }
@@ -92,21 +83,21 @@ where
     V: BufVal,
 {
     /// Create a new KvIntBufFresh
-    pub fn new(db: IntegerStore<K>) -> Self {
+    pub fn new(db: IntegerStore) -> Self {
         Self {
             db,
             __phantom: std::marker::PhantomData,
         }
     }
 
-    /// Accessor for raw Rkv DB
-    pub fn db(&self) -> IntegerStore<K> {
+    /// Accessor for raw DB
+    pub fn db(&self) -> IntegerStore {
         self.db
     }
 
     // TODO: This should be cfg test but can't because it's in a different crate
     /// Clear db, useful for tests
     pub fn delete_all(&mut self, writer: &mut Writer) -> DatabaseResult<()> {
-        Ok(self.db.clear(writer)?)
+        self.db.clear(writer)
     }
 }
@@ -1,13 +1,10 @@
 use super::KvStoreT;
 use crate::buffer::{check_empty_key, iter::SingleIterRaw};
-use crate::{
-    error::{DatabaseError, DatabaseResult},
-    prelude::*,
-};
+use crate::{error::DatabaseResult, prelude::*};
 use fallible_iterator::FallibleIterator;
-use rkv::SingleStore;
+use std::borrow::Cow;
 
-/// Wrapper around an rkv SingleStore which provides strongly typed values
+/// Wrapper around a SingleStore which provides strongly typed values
 pub struct KvStore<K, V>
 where
     K: BufKey,
@@ -22,25 +19,21 @@ where
     K: BufKey,
     V: BufVal,
 {
-    /// Fetch data from DB as raw byte slice
+    /// Fetch data from DB as raw bytes
     fn get_bytes<'env, R: Readable>(
         &self,
         reader: &'env R,
         k: &K,
-    ) -> DatabaseResult<Option<&'env [u8]>> {
+    ) -> DatabaseResult<Option<Cow<'env, [u8]>>> {
         check_empty_key(k)?;
-        match self.db.get(reader, k)? {
-            Some(rkv::Value::Blob(buf)) => Ok(Some(buf)),
-            None => Ok(None),
-            Some(_) => Err(DatabaseError::InvalidValue),
-        }
+        self.db.get(reader, k.as_ref())
     }
 
     /// Fetch data from DB, deserialize into V type
     fn get<R: Readable>(&self, reader: &R, k: &K) -> DatabaseResult<Option<V>> {
         check_empty_key(k)?;
         match self.get_bytes(reader, k)? {
-            Some(bytes) => Ok(Some(holochain_serialized_bytes::decode(bytes)?)),
+            Some(bytes) => Ok(Some(holochain_serialized_bytes::decode(&bytes)?)),
             None => Ok(None),
         }
     }
@@ -48,14 +41,12 @@ where
     /// Put V into DB as serialized data
     fn put(&self, writer: &mut Writer, k: &K, v: &V) -> DatabaseResult<()> {
         let buf = holochain_serialized_bytes::encode(v)?;
-        let encoded = rkv::Value::Blob(&buf);
-        self.db.put(writer, k, &encoded)?;
-        Ok(())
+        self.db.put(writer, k.as_ref(), &buf)
     }
 
     /// Delete value from DB
     fn delete(&self, writer: &mut Writer, k: &K) -> DatabaseResult<()> {
-        Ok(self.db.delete(writer, k)?)
+        self.db.delete(writer, k.as_ref())
     }
 
     /// Iterate over the underlying persisted data
@@ -74,7 +65,7 @@ where
     ) -> DatabaseResult<SingleIterRaw<'env, V>> {
         check_empty_key(&k)?;
         Ok(SingleIterRaw::new(
-            self.db.iter_from(reader, k)?,
+            self.db.iter_from(reader, k.as_ref())?,
             self.db.iter_end(reader)?,
         ))
     }
@@ -101,7 +92,7 @@ where
         }
     }
 
-    /// Accessor for raw Rkv DB
+    /// Accessor for raw DB
     pub fn db(&self) -> SingleStore {
         self.db
     }
@@ -109,6 +100,6 @@ where
     // TODO: This should be cfg test but can't because it's in a different crate
     /// Clear db, useful for tests
     pub fn delete_all(&mut self, writer: &mut Writer) -> DatabaseResult<()> {
-        Ok(self.db.clear(writer)?)
+        self.db.clear(writer)
     }
 }
@@ -4,7 +4,6 @@ use crate::{
     prelude::*,
 };
 use either::Either;
-use rkv::MultiStore;
 use std::{collections::BTreeMap, fmt::Debug};
 use tracing::*;
 
@@ -97,7 +96,7 @@ where
             v
         } else {
             // Only do the persisted call if it's not in the scratch
-            let persisted = Self::check_not_found(self.get_persisted(r, k))?;
+            let persisted = self.get_persisted(r, k)?;
             trace!(?k);
 
             return Ok(Either::Left(persisted));
@@ -114,7 +113,7 @@ where
             // skipping persisted content (as it will all be deleted)
             Either::Left(from_scratch_space)
         } else {
-            let persisted = Self::check_not_found(self.get_persisted(r, k))?;
+            let persisted = self.get_persisted(r, k)?;
             Either::Right(
                 from_scratch_space
                     // Otherwise, chain it with the persisted content,
@@ -162,43 +161,14 @@ where
         let s = trace_span!("persisted");
         let _g = s.enter();
         trace!("test");
-        let iter = self.db.get(r, k)?;
-        Ok(iter.filter_map(|v| match v {
-            Ok((_, Some(rkv::Value::Blob(buf)))) => Some(
-                holochain_serialized_bytes::decode(buf)
-                    .map(|n| {
-                        trace!(?n);
-                        n
-                    })
-                    .map_err(|e| e.into()),
-            ),
-            Ok((_, Some(_))) => Some(Err(DatabaseError::InvalidValue)),
-            Ok((_, None)) => None,
-            Err(e) => Some(Err(e.into())),
+        let iter = self.db.get(r, k.as_ref())?;
+        Ok(iter.map(|v| -> DatabaseResult<V> {
+            let n = holochain_serialized_bytes::decode(&v?)?;
+            trace!(?n);
+            Ok(n)
         }))
     }
 
-    fn check_not_found(
-        persisted: DatabaseResult<impl Iterator<Item = DatabaseResult<V>>>,
-    ) -> DatabaseResult<impl Iterator<Item = DatabaseResult<V>>> {
-        let empty = std::iter::empty::<DatabaseResult<V>>();
-        trace!("{:?}", line!());
-        match persisted {
-            Ok(persisted) => {
-                trace!("{:?}", line!());
-                Ok(Either::Left(persisted))
-            }
-            Err(DatabaseError::LmdbStoreError(err)) => match err.into_inner() {
-                rkv::StoreError::LmdbError(rkv::LmdbError::NotFound) => {
-                    trace!("{:?}", line!());
-                    Ok(Either::Right(empty))
-                }
-                err => Err(err.into()),
-            },
-            Err(err) => Err(err),
-        }
-    }
-
     // TODO: This should be cfg test but can't because it's in a different crate
     /// Clear all scratch and db, useful for tests
     pub fn clear_all(&mut self, writer: &mut Writer) -> DatabaseResult<()> {
@@ -227,7 +197,7 @@ where
             // If delete_all is set, that we should delete everything persisted,
             // but then continue to add inserts from the ops, if present
             if *delete_all {
-                self.db.delete_all(writer, k.clone())?;
+                self.db.delete_all(writer, k.as_ref())?;
             }
             trace!(?k);
             trace!(?deltas);
@@ -236,46 +206,14 @@ where
                 match op {
                     Insert => {
                         let buf = holochain_serialized_bytes::encode(&v)?;
-                        let encoded = rkv::Value::Blob(&buf);
-                        if self.no_dup_data {
-                            self.db
-                                .put_with_flags(
-                                    writer,
-                                    k.clone(),
-                                    &encoded,
-                                    rkv::WriteFlags::NO_DUP_DATA,
-                                )
-                                .or_else(|err| {
-                                    // This error is a little misleading...
-                                    // In a MultiStore with NO_DUP_DATA, it is
-                                    // actually returned if there is a duplicate
-                                    // value... which we want to ignore.
-                                    if let rkv::StoreError::LmdbError(rkv::LmdbError::KeyExist) =
-                                        err
-                                    {
-                                        Ok(())
-                                    } else {
-                                        Err(err)
-                                    }
-                                })?;
-                        } else {
-                            self.db.put(writer, k.clone(), &encoded)?;
-                        }
+                        self.db.put(writer, k.as_ref(), &buf, self.no_dup_data)?;
                     }
                     // Skip deleting unnecessarily if we have already deleted
                     // everything
                     Delete if *delete_all => {}
                     Delete => {
                         let buf = holochain_serialized_bytes::encode(&v)?;
-                        let encoded = rkv::Value::Blob(&buf);
-                        self.db.delete(writer, k.clone(), &encoded).or_else(|err| {
-                            // Ignore the case where the key is not found
-                            if let rkv::StoreError::LmdbError(rkv::LmdbError::NotFound) = err {
-                                Ok(())
-                            } else {
-                                Err(err)
-                            }
-                        })?;
+                        self.db.delete(writer, k.as_ref(), &buf)?;
                     }
                 }
             }
@@ -5,7 +5,6 @@ use crate::{
     test_utils::{test_cell_env, DbString},
     transaction::Readable,
 };
-use rkv::StoreOptions;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -51,10 +50,7 @@ async fn kvvbuf_basics() {
     let arc = test_env.env();
     let env = arc.guard();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::create())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let mut store: Store = Store::new(multi_store);
@@ -91,10 +87,7 @@ async fn kvvbuf_basics() {
     })
     .unwrap();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::default())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let mut store: Store = Store::new(multi_store);
@@ -131,10 +124,7 @@ async fn kvvbuf_basics() {
     })
     .unwrap();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::default())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let store: Store = Store::new(multi_store);
@@ -157,10 +147,7 @@ async fn delete_all() {
     let arc = test_env.env();
     let env = arc.guard();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::create())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let mut store: Store = Store::new(multi_store);
@@ -194,10 +181,7 @@ async fn delete_all() {
     })
     .unwrap();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::default())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let mut store: Store = Store::new(multi_store);
@@ -237,10 +221,7 @@ async fn delete_all() {
     })
     .unwrap();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::default())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let store: Store = Store::new(multi_store);
@@ -266,10 +247,7 @@ async fn idempotent_inserts() {
     let arc = test_env.env();
     let env = arc.guard();
 
-    let multi_store = env
-        .inner()
-        .open_multi("kvv", StoreOptions::create())
-        .unwrap();
+    let multi_store = env.open_multi("kvv").unwrap();
 
     env.with_reader::<DatabaseError, _, _>(|reader| {
         let mut store: Store = Store::new(multi_store);
@@ -333,7 +311,7 @@ async fn kvv_indicate_value_appends() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kvv", StoreOptions::create())?;
+    let db = env.open_multi("kvv")?;
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
 
@@ -354,7 +332,7 @@ async fn kvv_indicate_value_overwritten() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kvv", StoreOptions::create())?;
+    let db = env.open_multi("kvv")?;
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
 
@@ -376,7 +354,7 @@ async fn kvv_deleted_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -414,7 +392,7 @@ async fn kvv_deleted_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -465,7 +443,7 @@ async fn kvv_get_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
@@ -486,7 +464,7 @@ async fn kvv_get_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -513,7 +491,7 @@ async fn kvv_get_del_buffer() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     env.with_reader(|reader| {
         let mut buf = Store::new(db);
@@ -534,7 +512,7 @@ async fn kvv_get_del_persisted() -> DatabaseResult<()> {
     let test_env = test_cell_env();
     let arc = test_env.env();
     let env = arc.guard();
-    let db = env.inner().open_multi("kv", StoreOptions::create())?;
+    let db = env.open_multi("kv")?;
 
     {
         let mut buf = Store::new(db);
@@ -1,23 +1,21 @@
 //! Functionality for safely accessing LMDB database references.
 
 use crate::{
-    env::EnvironmentKind,
+    env::{EnvironmentKind, EnvironmentReadRef},
     error::{DatabaseError, DatabaseResult},
-    exports::IntegerStore,
-    prelude::IntKey,
+    exports::{IntegerStore, MultiStore, SingleStore},
 };
 use derive_more::Display;
 use holochain_keystore::KeystoreSender;
 use holochain_types::universal_map::{Key as UmKey, UniversalMap};
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
-use rkv::{MultiStore, Rkv, SingleStore, StoreOptions};
 use std::collections::{hash_map, HashMap};
 use std::path::{Path, PathBuf};
 
 /// TODO This is incomplete
 /// Enumeration of all databases needed by Holochain
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Display)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Display, serde::Serialize, serde::Deserialize)]
 pub enum DbName {
     /// Vault database: KV store of chain entries, keyed by address
     ElementVaultPublicEntries,
@@ -63,8 +61,12 @@ pub enum DbName {
     ValidationLimbo,
     /// KVV store to accumulate validation receipts for a published EntryHash
     ValidationReceipts,
+    /// KVV store of signed warrants, keyed by the offending agent
+    Warrants,
     /// Single store for all known agents on the network
     Agent,
+    /// Single store recording the schema version of the environment
+    Schema,
 }
 
 impl DbName {
@@ -94,12 +96,14 @@ impl DbName {
             IntegrationLimbo => Single,
             ValidationLimbo => Single,
             ValidationReceipts => Multi,
+            Warrants => Multi,
             Agent => Single,
+            Schema => Single,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// The various "modes" of viewing LMDB databases
 pub enum DbKind {
     /// Single-value KV with arbitrary keys, associated with [KvBufFresh]
@@ -111,7 +115,7 @@ pub enum DbKind {
 }
 
 /// A UniversalMap key used to access persisted database references.
-/// The key type is DbName, the value can be one of the various `rkv`
+/// The key type is DbName, the value can be one of the various backend
 /// database types
 pub type DbKey<V> = UmKey<DbName, V>;
 
@@ -165,8 +169,12 @@ lazy_static! {
     pub static ref VALIDATION_LIMBO: DbKey<SingleStore> = DbKey::new(DbName::ValidationLimbo);
     /// The key to access the ValidationReceipts database
     pub static ref VALIDATION_RECEIPTS: DbKey<MultiStore> = DbKey::new(DbName::ValidationReceipts);
+    /// The key to access the Warrants database
+    pub static ref WARRANTS: DbKey<MultiStore> = DbKey::new(DbName::Warrants);
     /// The key to access the Agent database
     pub static ref AGENT: DbKey<SingleStore> = DbKey::new(DbName::Agent);
+    /// The key to access the Schema database
+    pub static ref SCHEMA: DbKey<SingleStore> = DbKey::new(DbName::Schema);
 }
 
 lazy_static! {
@@ -175,16 +183,20 @@ lazy_static! {
 
 /// Get access to the singleton database manager ([GetDb]),
 /// in order to access individual LMDB databases
-pub(super) fn initialize_databases(rkv: &Rkv, kind: &EnvironmentKind) -> DatabaseResult<()> {
+pub(super) fn initialize_databases(
+    env: &EnvironmentReadRef,
+    path: &Path,
+    kind: &EnvironmentKind,
+) -> DatabaseResult<()> {
     let mut dbmap = DB_MAP_MAP.write();
-    let path = rkv.path().to_owned();
+    let path = path.to_owned();
     match dbmap.entry(path.clone()) {
         hash_map::Entry::Occupied(_) => {
             return Err(DatabaseError::EnvironmentDoubleInitialized(path))
         }
         hash_map::Entry::Vacant(e) => e.insert({
             let mut um = UniversalMap::new();
-            register_databases(&rkv, kind, &mut um)?;
+            register_databases(env, kind, &mut um)?;
             um
         }),
     };
@@ -205,7 +217,12 @@ pub(super) fn get_db<V: 'static + Copy + Send + Sync>(
     Ok(db)
 }
 
-fn register_databases(env: &Rkv, kind: &EnvironmentKind, um: &mut DbMap) -> DatabaseResult<()> {
+fn register_databases(
+    env: &EnvironmentReadRef,
+    kind: &EnvironmentKind,
+    um: &mut DbMap,
+) -> DatabaseResult<()> {
+    register_db(env, um, &*SCHEMA)?;
     match kind {
         EnvironmentKind::Cell(_) => {
             register_db(env, um, &*ELEMENT_VAULT_PUBLIC_ENTRIES)?;
@@ -225,6 +242,7 @@ fn register_databases(env: &Rkv, kind: &EnvironmentKind, um: &mut DbMap) -> Data
             register_db(env, um, &*INTEGRATION_LIMBO)?;
             register_db(env, um, &*VALIDATION_LIMBO)?;
             register_db(env, um, &*VALIDATION_RECEIPTS)?;
+            register_db(env, um, &*WARRANTS)?;
         }
         EnvironmentKind::Conductor => {
             register_db(env, um, &*CONDUCTOR_STATE)?;
@@ -244,42 +262,16 @@ fn register_databases(env: &Rkv, kind: &EnvironmentKind, um: &mut DbMap) -> Data
 }
 
 fn register_db<V: 'static + Send + Sync>(
-    env: &Rkv,
+    env: &EnvironmentReadRef,
     um: &mut DbMap,
     key: &DbKey<V>,
 ) -> DatabaseResult<()> {
     let db_name = key.key();
     let db_str = format!("{}", db_name);
     let _ = match db_name.kind() {
-        DbKind::Single => um.insert(
-            key.with_value_type(),
-            env.open_single(db_str.as_str(), StoreOptions::create())?,
-        ),
-        DbKind::SingleInt => um.insert(
-            key.with_value_type(),
-            env.open_integer::<&str, IntKey>(db_str.as_str(), StoreOptions::create())?,
-        ),
-        DbKind::Multi => {
-            let mut opts = StoreOptions::create();
-
-            // This is needed for the optional put flag NO_DUP_DATA on KvvBufUsed.
-            // As far as I can tell, if we are not using NO_DUP_DATA, it will
-            // only affect the sorting of the values in case there are dups,
-            // which should be ok for our usage.
-            //
-            // NOTE - see:
-            // https://github.com/mozilla/rkv/blob/0.10.4/src/env.rs#L122-L131
-            //
-            // Aparently RKV already sets this flag, but it's not mentioned
-            // in the docs anywhere. We're going to set it too, just in case
-            // it is removed out from under us at some point in the future.
-            opts.flags.set(rkv::DatabaseFlags::DUP_SORT, true);
-
-            um.insert(
-                key.with_value_type(),
-                env.open_multi(db_str.as_str(), opts)?,
-            )
-        }
+        DbKind::Single => um.insert(key.with_value_type(), env.open_single(db_str.as_str())?),
+        DbKind::SingleInt => um.insert(key.with_value_type(), env.open_integer(db_str.as_str())?),
+        DbKind::Multi => um.insert(key.with_value_type(), env.open_multi(db_str.as_str())?),
     };
     Ok(())
 }
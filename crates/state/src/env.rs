@@ -1,16 +1,22 @@
-//! Functions dealing with obtaining and referencing singleton LMDB environments
+//! Functions dealing with obtaining and referencing singleton environments
 
 use crate::{
-    db::{get_db, initialize_databases, DbKey, GetDb},
+    backend::{
+        crypt::{self, EnvCipher},
+        lmdb::LmdbBackend,
+        Backend, BackendTag, StorageBackend,
+    },
+    db::{get_db, initialize_databases, DbKey, DbKind, GetDb},
     error::{DatabaseError, DatabaseResult},
+    exports::{IntegerStore, MultiStore, SingleStore},
     transaction::{Reader, Writer},
 };
 use derive_more::Into;
 use holochain_keystore::KeystoreSender;
 use holochain_types::cell::CellId;
 use lazy_static::lazy_static;
-use parking_lot::{RwLock, RwLockReadGuard};
-use rkv::{EnvironmentFlags, Rkv};
+use parking_lot::RwLock;
+use rkv::Rkv;
 use shrinkwraprs::Shrinkwrap;
 use std::{
     collections::{hash_map, HashMap},
@@ -18,9 +24,6 @@ use std::{
     sync::Arc,
 };
 
-const DEFAULT_INITIAL_MAP_SIZE: usize = 100 * 1024 * 1024; // 100MB
-const MAX_DBS: u32 = 32;
-
 lazy_static! {
     static ref ENVIRONMENTS: RwLock<HashMap<PathBuf, EnvironmentWrite>> = {
         // This is just a convenient place that we know gets initialized
@@ -46,63 +49,82 @@ lazy_static! {
 
         RwLock::new(HashMap::new())
     };
-}
-
-fn default_flags() -> EnvironmentFlags {
-    // The flags WRITE_MAP and MAP_ASYNC make writes waaaaay faster by async writing to disk rather than blocking
-    // There is some loss of data integrity guarantees that comes with this.
-    EnvironmentFlags::WRITE_MAP | EnvironmentFlags::MAP_ASYNC
-}
-
-#[cfg(feature = "lmdb_no_tls")]
-fn required_flags() -> EnvironmentFlags {
-    // NO_TLS associates read slots with the transaction object instead of the thread, which is crucial for us
-    // so we can have multiple read transactions per thread (since futures can run on any thread)
-    EnvironmentFlags::NO_TLS
-}
-
-#[cfg(not(feature = "lmdb_no_tls"))]
-fn required_flags() -> EnvironmentFlags {
-    EnvironmentFlags::default()
-}
-
-fn rkv_builder(
-    initial_map_size: Option<usize>,
-    flags: Option<EnvironmentFlags>,
-) -> impl (Fn(&Path) -> Result<Rkv, rkv::StoreError>) {
-    move |path: &Path| {
-        let mut env_builder = Rkv::environment_builder();
-        env_builder
-            // max size of memory map, can be changed later
-            .set_map_size(initial_map_size.unwrap_or(DEFAULT_INITIAL_MAP_SIZE))
-            // max number of DBs in this environment
-            .set_max_dbs(MAX_DBS)
-            .set_flags(flags.unwrap_or_else(default_flags) | required_flags());
-        Rkv::from_env(path, env_builder)
-    }
+    static ref READ_ONLY_ENVIRONMENTS: RwLock<HashMap<PathBuf, EnvironmentRead>> =
+        RwLock::new(HashMap::new());
 }
 
 /// A read-only version of [EnvironmentWrite].
 /// This environment can only generate read-only transactions, never read-write.
 #[derive(Clone)]
 pub struct EnvironmentRead {
-    arc: Arc<RwLock<Rkv>>,
+    backend: Arc<dyn Backend>,
+    tag: BackendTag,
+    cipher: Option<EnvCipher>,
     kind: EnvironmentKind,
     path: PathBuf,
     keystore: KeystoreSender,
 }
 
 impl EnvironmentRead {
+    /// Open an existing LMDB environment without writing to it, for inspection.
+    /// Nothing is created, not even the environment's directory, and databases
+    /// which aren't there read as empty. Encrypted environments can't be opened
+    /// this way. If the environment is already open for writing, this is a
+    /// read-only view of it; otherwise it can't be opened for writing afterwards.
+    pub fn open_read_only(
+        path_prefix: &Path,
+        kind: EnvironmentKind,
+        keystore: KeystoreSender,
+    ) -> DatabaseResult<EnvironmentRead> {
+        let path = path_prefix.join(kind.path());
+        if let Some(env) = ENVIRONMENTS.read().get(&path) {
+            return Ok(env.clone().into());
+        }
+        let mut map = READ_ONLY_ENVIRONMENTS.write();
+        let env = match map.entry(path.clone()) {
+            hash_map::Entry::Occupied(e) => e.get().clone(),
+            hash_map::Entry::Vacant(e) => {
+                let backend = LmdbBackend::open_read_only(&path)?;
+                if crypt::is_encrypted(&path) {
+                    return Err(DatabaseError::EnvironmentEncrypted(path));
+                }
+                let env = EnvironmentRead {
+                    backend: Arc::new(backend),
+                    tag: BackendTag::next(),
+                    cipher: None,
+                    kind,
+                    keystore,
+                    path,
+                };
+                initialize_databases(&env.guard(), &env.path, &env.kind)?;
+                e.insert(env).clone()
+            }
+        };
+        Ok(env)
+    }
+
     /// Get a read-only lock on the EnvironmentWrite. The most typical use case is
     /// to get a lock in order to create a read-only transaction. The lock guard
     /// must outlive the transaction, so it has to be returned here and managed
     /// explicitly.
     pub fn guard(&self) -> EnvironmentReadRef<'_> {
         EnvironmentReadRef {
-            rkv: self.arc.read(),
+            backend: &*self.backend,
+            tag: self.tag,
+            cipher: self.cipher.as_ref(),
         }
     }
 
+    /// The [Backend] this environment is kept in
+    pub fn backend(&self) -> &dyn Backend {
+        &*self.backend
+    }
+
+    /// Whether values in this environment are encrypted at rest
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
     /// Accessor for the [EnvironmentKind] of the EnvironmentWrite
     pub fn kind(&self) -> &EnvironmentKind {
         &self.kind
@@ -146,33 +168,105 @@ impl GetDb for EnvironmentRead {
 pub struct EnvironmentWrite(EnvironmentRead);
 
 impl EnvironmentWrite {
-    /// Create an environment,
+    /// Create an environment, persisted by LMDB
     pub fn new(
         path_prefix: &Path,
         kind: EnvironmentKind,
         keystore: KeystoreSender,
     ) -> DatabaseResult<EnvironmentWrite> {
-        let mut map = ENVIRONMENTS.write();
+        Self::new_with_backend(path_prefix, kind, keystore, StorageBackend::Lmdb)
+    }
+
+    /// Create an environment kept in the given [StorageBackend].
+    /// A memory environment is still identified by its path,
+    /// but nothing is written there.
+    /// An environment which was created encrypted must be opened with
+    /// [EnvironmentWrite::new_encrypted] instead.
+    pub fn new_with_backend(
+        path_prefix: &Path,
+        kind: EnvironmentKind,
+        keystore: KeystoreSender,
+        backend: StorageBackend,
+    ) -> DatabaseResult<EnvironmentWrite> {
+        Self::open(path_prefix, kind, keystore, |path| backend.open(path), None)
+    }
+
+    /// Create an environment kept in a [Backend] of your own.
+    /// `backend` is only called if the environment isn't open already.
+    pub fn new_with_custom_backend(
+        path_prefix: &Path,
+        kind: EnvironmentKind,
+        keystore: KeystoreSender,
+        backend: impl FnOnce(&Path) -> DatabaseResult<Arc<dyn Backend>>,
+    ) -> DatabaseResult<EnvironmentWrite> {
+        Self::open(path_prefix, kind, keystore, backend, None)
+    }
+
+    /// Create an environment, persisted by LMDB, whose values are encrypted
+    /// at rest with a key held by the keystore. See [crate::backend::crypt].
+    /// An existing environment which was created unencrypted is opened
+    /// unencrypted, as it is.
+    pub async fn new_encrypted(
+        path_prefix: &Path,
+        kind: EnvironmentKind,
+        keystore: KeystoreSender,
+    ) -> DatabaseResult<EnvironmentWrite> {
         let path = path_prefix.join(kind.path());
+        if let Some(env) = ENVIRONMENTS.read().get(&path) {
+            return Ok(env.clone());
+        }
         if !path.is_dir() {
             std::fs::create_dir(path.clone())
                 .map_err(|_e| DatabaseError::EnvironmentMissing(path.clone()))?;
         }
+        if crypt::is_unencrypted(&path) {
+            tracing::warn!(
+                "Environment at {:?} was created unencrypted, so it stays unencrypted",
+                path
+            );
+            return Self::new(path_prefix, kind, keystore);
+        }
+        let cipher = EnvCipher::unlock(&path, &keystore).await?;
+        Self::open(
+            path_prefix,
+            kind,
+            keystore,
+            |path| StorageBackend::Lmdb.open(path),
+            Some(cipher),
+        )
+    }
+
+    fn open(
+        path_prefix: &Path,
+        kind: EnvironmentKind,
+        keystore: KeystoreSender,
+        backend: impl FnOnce(&Path) -> DatabaseResult<Arc<dyn Backend>>,
+        cipher: Option<EnvCipher>,
+    ) -> DatabaseResult<EnvironmentWrite> {
+        let mut map = ENVIRONMENTS.write();
+        let path = path_prefix.join(kind.path());
         let env: EnvironmentWrite = match map.entry(path.clone()) {
             hash_map::Entry::Occupied(e) => e.get().clone(),
-            hash_map::Entry::Vacant(e) => e
-                .insert({
-                    let rkv = rkv_builder(None, None)(&path)?;
-                    tracing::debug!("Initializing databases for path {:?}", path);
-                    initialize_databases(&rkv, &kind)?;
-                    EnvironmentWrite(EnvironmentRead {
-                        arc: Arc::new(RwLock::new(rkv)),
+            hash_map::Entry::Vacant(e) => {
+                let backend = backend(&path)?;
+                if cipher.is_none() && backend.on_disk() && crypt::is_encrypted(&path) {
+                    return Err(DatabaseError::EnvironmentEncrypted(path));
+                }
+                e.insert({
+                    let env = EnvironmentRead {
+                        backend,
+                        tag: BackendTag::next(),
+                        cipher,
                         kind,
                         keystore,
                         path,
-                    })
+                    };
+                    tracing::debug!("Initializing databases for path {:?}", env.path);
+                    initialize_databases(&env.guard(), &env.path, &env.kind)?;
+                    EnvironmentWrite(env)
                 })
-                .clone(),
+                .clone()
+            }
         };
         Ok(env)
     }
@@ -198,7 +292,9 @@ impl EnvironmentWrite {
         map.remove(&self.0.path);
         // TODO remove this db from the DB_MAP_MAP?
         // remove the directory
-        std::fs::remove_dir_all(&self.0.path)?;
+        if self.0.backend.on_disk() {
+            std::fs::remove_dir_all(&self.0.path)?;
+        }
         Ok(())
     }
 }
@@ -218,7 +314,7 @@ pub enum EnvironmentKind {
 
 impl EnvironmentKind {
     /// Constuct a partial Path based on the kind
-    fn path(&self) -> PathBuf {
+    pub fn path(&self) -> PathBuf {
         match self {
             EnvironmentKind::Cell(cell_id) => PathBuf::from(cell_id.to_string()),
             EnvironmentKind::Conductor => PathBuf::from("conductor"),
@@ -231,13 +327,37 @@ impl EnvironmentKind {
 /// This has the distinction of being unable to create a read-write transaction,
 /// because unlike [EnvironmentWriteRef], this does not implement WriteManager
 pub struct EnvironmentReadRef<'e> {
-    rkv: RwLockReadGuard<'e, Rkv>,
+    backend: &'e dyn Backend,
+    tag: BackendTag,
+    cipher: Option<&'e EnvCipher>,
 }
 
 impl<'e> EnvironmentReadRef<'e> {
-    /// Access the wrapped Rkv
-    pub fn rkv(&self) -> &Rkv {
-        &self.rkv
+    /// Access the wrapped Rkv, if this environment is kept in LMDB
+    pub fn rkv(&self) -> Option<&Rkv> {
+        self.backend.rkv()
+    }
+
+    /// Open (creating if needed) a database with one value per key
+    pub(crate) fn open_single(&self, name: &str) -> DatabaseResult<SingleStore> {
+        let db = self.backend.open(name, DbKind::Single)?;
+        Ok(SingleStore::new(self.tag, db))
+    }
+
+    /// Open (creating if needed) a database with [IntKey](crate::prelude::IntKey) keys
+    pub(crate) fn open_integer(&self, name: &str) -> DatabaseResult<IntegerStore> {
+        let db = self.backend.open(name, DbKind::SingleInt)?;
+        Ok(IntegerStore::new(self.tag, db))
+    }
+
+    /// Open (creating if needed) a database with many values per key
+    pub(crate) fn open_multi(&self, name: &str) -> DatabaseResult<MultiStore> {
+        let db = self.backend.open(name, DbKind::Multi)?;
+        Ok(MultiStore::new(self.tag, db))
+    }
+
+    fn writer(&self) -> DatabaseResult<Writer<'e>> {
+        Ok(Writer::new(self.backend.writer()?, self.tag, self.cipher))
     }
 }
 
@@ -267,8 +387,7 @@ pub trait WriteManager<'e> {
 
 impl<'e> ReadManager<'e> for EnvironmentReadRef<'e> {
     fn reader(&'e self) -> DatabaseResult<Reader<'e>> {
-        let reader = Reader::from(self.rkv.read()?);
-        Ok(reader)
+        Ok(Reader::new(self.backend.reader()?, self.tag, self.cipher))
     }
 
     fn with_reader<E, R, F: Send>(&self, f: F) -> Result<R, E>
@@ -286,7 +405,7 @@ impl<'e> WriteManager<'e> for EnvironmentWriteRef<'e> {
         E: From<DatabaseError>,
         F: FnOnce(&mut Writer) -> Result<R, E>,
     {
-        let mut writer = Writer::from(self.rkv.write().map_err(Into::into)?);
+        let mut writer = self.writer()?;
         let result = f(&mut writer)?;
         writer.commit().map_err(Into::into)?;
         Ok(result)
@@ -304,18 +423,11 @@ impl<'e> WriteManager<'e> for EnvironmentWrite {
 }
 
 impl<'e> EnvironmentWriteRef<'e> {
-    /// Access the underlying Rkv lock guard
-    #[cfg(test)]
-    pub(crate) fn inner(&'e self) -> &Rkv {
-        &self.rkv
-    }
-
     /// Get a raw read-write transaction for this environment.
     /// It is preferable to use WriterManager::with_commit for database writes,
     /// which can properly recover from and manage write failures
     pub fn writer_unmanaged(&'e self) -> DatabaseResult<Writer<'e>> {
-        let writer = Writer::from(self.rkv.write()?);
-        Ok(writer)
+        self.0.writer()
     }
 }
 
@@ -82,6 +82,40 @@ pub enum DatabaseError {
 
     #[error("Unable to construct a value key")]
     KeyConstruction,
+
+    #[error("A key which should be a hash is not one: {0:?}")]
+    InvalidHashKey(Vec<u8>),
+
+    #[error("A database handle was used with a transaction from a different storage backend")]
+    BackendMismatch,
+
+    #[error("LMDB environment is encrypted, but was opened without unlocking its key: {0}")]
+    EnvironmentEncrypted(PathBuf),
+
+    #[error("LMDB environment was created unencrypted, and can't be opened as encrypted: {0}")]
+    EnvironmentNotEncrypted(PathBuf),
+
+    #[error("A value could not be decrypted: the key is wrong or the data is corrupted")]
+    DecryptionFailed,
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+
+    #[error("LMDB environment at {path} has schema version {version}, but this build only supports up to version {supported}. Upgrade to a newer build to open it")]
+    SchemaTooNew {
+        path: PathBuf,
+        version: u32,
+        supported: u32,
+    },
+
+    #[error(
+        "Migration of LMDB environment at {path} to schema version {version} failed: {reason}"
+    )]
+    MigrationFailed {
+        path: PathBuf,
+        version: u32,
+        reason: String,
+    },
 }
 
 impl PartialEq for DatabaseError {
@@ -1,12 +1,5 @@
-//! A few imports from `rkv`, to avoid consumers needing to import `rkv` explicitly
+//! The database handles, re-exported to avoid consumers needing to reach into the backend module
 
-use crate::prelude::IntKey;
-
-/// Simple type alias for re-exporting
-pub type SingleStore = rkv::SingleStore;
-/// Simple type alias for re-exporting
-pub type IntegerStore = rkv::IntegerStore<IntKey>;
-/// Simple type alias for re-exporting
-pub type MultiStore = rkv::MultiStore;
+pub use crate::backend::{IntegerStore, MultiStore, SingleStore};
 
 pub use fallible_iterator::FallibleIterator;
@@ -1,8 +1,12 @@
 //! # Building blocks for persisted Holochain state
 //!
-//! ## Backend: LMDB
+//! ## Backends: LMDB and memory
 //!
-//! Persistence is not generalized for different backends: it is targeted specifically for LMDB. In the future, if we have to change backends, or if we have to support something like IndexedDb, we will generalize the interface just enough to cover both. The interface provided by `rkv` is already somewhat generalized, with the abstract notions of Readers, Writers, and Stores.
+//! Each environment is kept in a [Backend](backend::Backend): LMDB on disk, a pure in-memory backend with the same transaction semantics, or any other implementation of the trait. The [backend] module follows the shape of the interface provided by `rkv`, with the abstract notions of Readers, Writers, and Stores, and passes each operation to the backend the store was opened in. Anything above the database handles, such as the buffered stores, doesn't need to know which backend is in use.
+//!
+//! ## Schema versions
+//!
+//! Each environment records the schema version its databases were written with. The [schema] module runs the ordered [Migration](schema::Migration)s which bring an older environment up to date, and refuses to open one written by a newer build.
 //!
 //! ## Buffered Stores
 //!
@@ -32,13 +36,13 @@
 //!
 //! ## Building blocks
 //!
-//! The rkv crate provides a few abstractions for working with LMDB stores. The ones we use are:
+//! The [backend] module provides a few abstractions for working with stores, mirroring those of the rkv crate:
 //!
 //! - SingleStore: a key-value store with arbitrary key and one value per key
 //! - IntegerStore: a key-value store with integer key and one value per key
 //! - MultiStore: a key-value store with arbitrary key and multiple values per key
 //!
-//! On top of these abstractions, the `holochain_state` crate provides three buffered store abstractions to wrap each of the rkv store types, as well as a simple CAS abstraction:
+//! On top of these abstractions, the `holochain_state` crate provides three buffered store abstractions to wrap each of the store types, as well as a simple CAS abstraction:
 //!
 //! - [KvBuffer]: a SingleStore with a scratch space
 //! - [KvIntBuffer]: an IntegerStore with a scratch space
@@ -51,6 +55,7 @@
 
 #![deny(missing_docs)]
 
+pub mod backend;
 pub mod buffer;
 pub mod db;
 pub mod env;
@@ -59,6 +64,7 @@ pub mod exports;
 pub mod fatal;
 pub mod key;
 pub mod prelude;
+pub mod schema;
 pub mod transaction;
 
 #[cfg(any(test, feature = "test_utils"))]
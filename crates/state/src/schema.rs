@@ -0,0 +1,186 @@
+//! Schema versions and migrations of persisted state.
+//!
+//! Every environment records the version of the layout its databases were
+//! written with. [migrate] brings an environment up to date by running each
+//! [Migration] newer than its version, in order, and records the new version
+//! in the same transaction, so an environment is never left half migrated.
+//! An environment written by a newer build than this one is refused, rather
+//! than read with the wrong layout.
+//!
+//! Environments from before versions were recorded are version 0.
+
+use crate::{
+    db::{GetDb, SCHEMA},
+    env::{EnvironmentWrite, ReadManager, WriteManager},
+    error::{DatabaseError, DatabaseResult},
+    transaction::{Readable, Writer},
+};
+
+/// The version of an environment's layout
+pub type SchemaVersion = u32;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// One upgrade step of an environment's databases
+pub struct Migration {
+    /// The version this step upgrades to, one more than the step before it
+    pub version: SchemaVersion,
+    /// What the step changes, for the logs
+    pub description: &'static str,
+    /// Rewrite the databases into the new layout
+    pub run: fn(&EnvironmentWrite, &mut Writer) -> DatabaseResult<()>,
+}
+
+/// The version an environment is at once all the migrations have run
+pub fn latest_version(migrations: &[Migration]) -> SchemaVersion {
+    migrations.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Get the schema version recorded in an environment
+pub fn schema_version<R: Readable>(env: &EnvironmentWrite, r: &R) -> DatabaseResult<SchemaVersion> {
+    let db = env.get_db(&*SCHEMA)?;
+    match db.get(r, SCHEMA_VERSION_KEY)? {
+        Some(bytes) if bytes.len() == 4 => {
+            let mut buf = [0; 4];
+            buf.copy_from_slice(&bytes);
+            Ok(SchemaVersion::from_le_bytes(buf))
+        }
+        Some(_) => Err(DatabaseError::InvalidValue),
+        None => Ok(0),
+    }
+}
+
+fn set_schema_version(
+    env: &EnvironmentWrite,
+    writer: &mut Writer,
+    version: SchemaVersion,
+) -> DatabaseResult<()> {
+    let db = env.get_db(&*SCHEMA)?;
+    db.put(writer, SCHEMA_VERSION_KEY, &version.to_le_bytes())
+}
+
+/// Run the migrations the environment hasn't had yet, and return its version.
+/// `migrations` must be ordered by version, with no gaps.
+/// Fails with [DatabaseError::SchemaTooNew] if the environment was written
+/// by a build that knows migrations this one doesn't.
+pub fn migrate(env: &EnvironmentWrite, migrations: &[Migration]) -> DatabaseResult<SchemaVersion> {
+    let latest = latest_version(migrations);
+    let version = env.guard().with_reader(|r| schema_version(env, &r))?;
+    if version > latest {
+        return Err(DatabaseError::SchemaTooNew {
+            path: env.path().clone(),
+            version,
+            supported: latest,
+        });
+    }
+    if version == latest {
+        return Ok(version);
+    }
+    env.guard().with_commit(|writer| {
+        for migration in migrations.iter().filter(|m| m.version > version) {
+            tracing::info!(
+                path = ?env.path(),
+                version = migration.version,
+                "Migrating environment: {}",
+                migration.description
+            );
+            (migration.run)(env, writer).map_err(|e| DatabaseError::MigrationFailed {
+                path: env.path().clone(),
+                version: migration.version,
+                reason: e.to_string(),
+            })?;
+        }
+        set_schema_version(env, writer, latest)
+    })?;
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_cell_env;
+
+    fn noop(_: &EnvironmentWrite, _: &mut Writer) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    fn rename_key(env: &EnvironmentWrite, writer: &mut Writer) -> DatabaseResult<()> {
+        let db = env.get_db(&*SCHEMA)?;
+        let old = db.get(writer, b"old")?.map(|v| v.to_vec());
+        if let Some(v) = old {
+            db.delete(writer, b"old")?;
+            db.put(writer, b"new", &v)?;
+        }
+        Ok(())
+    }
+
+    fn fail(_: &EnvironmentWrite, _: &mut Writer) -> DatabaseResult<()> {
+        Err(DatabaseError::InvalidValue)
+    }
+
+    const V1: Migration = Migration {
+        version: 1,
+        description: "baseline",
+        run: noop,
+    };
+
+    const V2: Migration = Migration {
+        version: 2,
+        description: "rename old to new",
+        run: rename_key,
+    };
+
+    #[tokio::test(threaded_scheduler)]
+    async fn migrations_run_once_in_order() -> DatabaseResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        let db = env.get_db(&*SCHEMA)?;
+        env.guard().with_commit(|w| db.put(w, b"old", b"value"))?;
+
+        assert_eq!(migrate(&env, &[V1])?, 1);
+        assert_eq!(migrate(&env, &[V1, V2])?, 2);
+        // already at the latest version, so nothing runs again
+        assert_eq!(migrate(&env, &[V1, V2])?, 2);
+
+        let g = env.guard();
+        let r = g.reader()?;
+        assert_eq!(schema_version(&env, &r)?, 2);
+        assert_eq!(db.get(&r, b"old")?, None);
+        assert_eq!(db.get(&r, b"new")?.as_deref(), Some(&b"value"[..]));
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn newer_schema_is_refused() -> DatabaseResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        migrate(&env, &[V1, V2])?;
+        assert_eq!(
+            migrate(&env, &[V1]),
+            Err(DatabaseError::SchemaTooNew {
+                path: env.path().clone(),
+                version: 2,
+                supported: 1,
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn failed_migration_changes_nothing() -> DatabaseResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        let failing = Migration {
+            version: 2,
+            description: "fails",
+            run: fail,
+        };
+        assert!(matches!(
+            migrate(&env, &[V1, failing]),
+            Err(DatabaseError::MigrationFailed { version: 2, .. })
+        ));
+        let g = env.guard();
+        assert_eq!(schema_version(&env, &g.reader()?)?, 0);
+        Ok(())
+    }
+}
@@ -1,34 +1,38 @@
 //! Helpers for unit tests
 
 use crate::{
+    backend::StorageBackend,
     env::{EnvironmentKind, EnvironmentWrite},
     prelude::BufKey,
 };
 use holochain_keystore::KeystoreSender;
 use holochain_types::test_utils::fake_cell_id;
 use shrinkwraprs::Shrinkwrap;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use tempdir::TempDir;
 
-/// Create a [TestEnvironment] of [EnvironmentKind::Cell], backed by a temp directory.
+/// Create a [TestEnvironment] of [EnvironmentKind::Cell], kept in memory.
 pub fn test_cell_env() -> TestEnvironment {
     let cell_id = fake_cell_id(1);
-    test_env(EnvironmentKind::Cell(cell_id))
+    test_memory_env(EnvironmentKind::Cell(cell_id))
 }
 
-/// Create a [TestEnvironment] of [EnvironmentKind::Conductor], backed by a temp directory.
+/// Create a [TestEnvironment] of [EnvironmentKind::Conductor], kept in memory.
 pub fn test_conductor_env() -> TestEnvironment {
-    test_env(EnvironmentKind::Conductor)
+    test_memory_env(EnvironmentKind::Conductor)
 }
 
-/// Create a [TestEnvironment] of [EnvironmentKind::Wasm], backed by a temp directory.
+/// Create a [TestEnvironment] of [EnvironmentKind::Wasm], kept in memory.
 pub fn test_wasm_env() -> TestEnvironment {
-    test_env(EnvironmentKind::Wasm)
+    test_memory_env(EnvironmentKind::Wasm)
 }
 
-/// Create a [TestEnvironment] of [EnvironmentKind::P2p], backed by a temp directory.
+/// Create a [TestEnvironment] of [EnvironmentKind::P2p], kept in memory.
 pub fn test_p2p_env() -> TestEnvironment {
-    test_env(EnvironmentKind::P2p)
+    test_memory_env(EnvironmentKind::P2p)
 }
 
 /// Generate a test keystore pre-populated with a couple test keypairs.
@@ -58,12 +62,34 @@ pub fn test_keystore() -> holochain_keystore::KeystoreSender {
     .unwrap()
 }
 
-fn test_env(kind: EnvironmentKind) -> TestEnvironment {
+/// Create a [TestEnvironment] of the given kind, kept in memory.
+/// Its path is unique but never created, so nothing touches the disk.
+pub fn test_memory_env(kind: EnvironmentKind) -> TestEnvironment {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "holochain-test-memory-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    TestEnvironment {
+        env: EnvironmentWrite::new_with_backend(
+            &path,
+            kind,
+            test_keystore(),
+            StorageBackend::Memory,
+        )
+        .expect("Couldn't create test memory environment"),
+        tmpdir: None,
+    }
+}
+
+/// Create a [TestEnvironment] of the given kind, persisted by LMDB in a temp directory.
+pub fn test_lmdb_env(kind: EnvironmentKind) -> TestEnvironment {
     let tmpdir = Arc::new(TempDir::new("holochain-test-environments").unwrap());
     TestEnvironment {
         env: EnvironmentWrite::new(tmpdir.path(), kind, test_keystore())
             .expect("Couldn't create test LMDB environment"),
-        tmpdir,
+        tmpdir: Some(tmpdir),
     }
 }
 
@@ -73,14 +99,14 @@ pub fn test_environments() -> TestEnvironments {
     TestEnvironments::new(tempdir)
 }
 
-/// A test lmdb environment with test directory
+/// A test environment, with its temp directory if it is kept in LMDB
 #[derive(Clone, Shrinkwrap)]
 pub struct TestEnvironment {
     #[shrinkwrap(main_field)]
-    /// lmdb environment
+    /// the environment
     env: EnvironmentWrite,
-    /// temp directory for this environment
-    tmpdir: Arc<TempDir>,
+    /// temp directory for this environment, if it is kept in LMDB
+    tmpdir: Option<Arc<TempDir>>,
 }
 
 impl TestEnvironment {
@@ -90,7 +116,7 @@ impl TestEnvironment {
     }
 
     /// Accessor
-    pub fn tmpdir(&self) -> Arc<TempDir> {
+    pub fn tmpdir(&self) -> Option<Arc<TempDir>> {
         self.tmpdir.clone()
     }
 }
@@ -111,14 +137,23 @@ pub struct TestEnvironments {
 
 #[allow(missing_docs)]
 impl TestEnvironments {
-    /// Create all three non-cell environments at once
+    /// Create all three non-cell environments at once, kept in memory.
+    /// The temp dir is the root for any cell environments made under it.
     pub fn new(tempdir: TempDir) -> Self {
         use EnvironmentKind::*;
         let keystore = test_keystore();
-        let conductor =
-            EnvironmentWrite::new(&tempdir.path(), Conductor, keystore.clone()).unwrap();
-        let wasm = EnvironmentWrite::new(&tempdir.path(), Wasm, keystore.clone()).unwrap();
-        let p2p = EnvironmentWrite::new(&tempdir.path(), P2p, keystore.clone()).unwrap();
+        let new = |kind| {
+            EnvironmentWrite::new_with_backend(
+                tempdir.path(),
+                kind,
+                keystore.clone(),
+                StorageBackend::Memory,
+            )
+            .unwrap()
+        };
+        let conductor = new(Conductor);
+        let wasm = new(Wasm);
+        let p2p = new(P2p);
         Self {
             conductor,
             wasm,
@@ -1,21 +1,30 @@
-//! This module is just wrappers around the rkv transaction representations.
+//! This module is just wrappers around the transaction representations of each backend.
 //! They are necessary/useful for a few reasons:
+//! - The buffered stores can read and write through them regardless of the backend
 //! - Reader is not marked Send + Sync in rkv, but we must mark it such to make
 //!     use of the threadsafe read-only transactions provided by the MDB_NOTLS flag
 //! - We can upgrade some error types from rkv::StoreError, which does not implement
 //!     std::error::Error, into error types that do
 
-use crate::error::DatabaseError;
+use crate::{
+    backend::{crypt::EnvCipher, BackendRead, BackendTag, BackendWrite},
+    error::DatabaseError,
+};
 use chrono::{offset::Local, DateTime};
-use derive_more::From;
-use rkv::{Database, RoCursor, StoreError, Value};
-use shrinkwraprs::Shrinkwrap;
 
-/// Just a trait alias for rkv::Readable
+/// Anything which can be read from: either a Reader or a Writer.
 /// It's important because it lets us use either a Reader or a Writer
 /// for read-only operations
-pub trait Readable: rkv::Readable {}
-impl<T: rkv::Readable> Readable for T {}
+pub trait Readable {
+    /// The underlying transaction, for the backend to read with
+    fn txn(&self) -> &dyn BackendRead;
+
+    /// The backend this transaction belongs to
+    fn tag(&self) -> BackendTag;
+
+    /// The key values are sealed with, if the environment is encrypted
+    fn cipher(&self) -> Option<&EnvCipher>;
+}
 
 struct ReaderSpanInfo {
     // Using a chrono timestamp here because we need duration operations
@@ -41,9 +50,14 @@ impl Drop for ReaderSpanInfo {
     }
 }
 
-/// Wrapper around `rkv::Reader`, so it can be marked as threadsafe
-#[derive(Shrinkwrap)]
-pub struct Reader<'env>(#[shrinkwrap(main_field)] rkv::Reader<'env>, ReaderSpanInfo);
+/// Wrapper around a read-only transaction of any backend,
+/// so it can be marked as threadsafe
+pub struct Reader<'env> {
+    txn: Box<dyn BackendRead + 'env>,
+    tag: BackendTag,
+    cipher: Option<&'env EnvCipher>,
+    _span: ReaderSpanInfo,
+}
 
 /// If MDB_NOTLS env flag is set, then read-only transactions are threadsafe
 /// and we can mark them as such
@@ -55,42 +69,78 @@ unsafe impl<'env> Send for Reader<'env> {}
 #[cfg(feature = "lmdb_no_tls")]
 unsafe impl<'env> Sync for Reader<'env> {}
 
-impl<'env> rkv::Readable for Reader<'env> {
-    fn get<K: AsRef<[u8]>>(&self, db: Database, k: &K) -> Result<Option<Value>, StoreError> {
-        self.0.get(db, k)
+impl<'env> Readable for Reader<'env> {
+    fn txn(&self) -> &dyn BackendRead {
+        &*self.txn
     }
 
-    fn open_ro_cursor(&self, db: Database) -> Result<RoCursor, StoreError> {
-        self.0.open_ro_cursor(db)
+    fn tag(&self) -> BackendTag {
+        self.tag
+    }
+
+    fn cipher(&self) -> Option<&EnvCipher> {
+        self.cipher
     }
 }
 
-impl<'env> From<rkv::Reader<'env>> for Reader<'env> {
-    fn from(r: rkv::Reader<'env>) -> Self {
-        Self(r, ReaderSpanInfo::new())
+impl<'env> Reader<'env> {
+    /// Wrap a backend's transaction, sealing and opening values
+    /// with `cipher` if there is one
+    pub(crate) fn new(
+        txn: Box<dyn BackendRead + 'env>,
+        tag: BackendTag,
+        cipher: Option<&'env EnvCipher>,
+    ) -> Self {
+        Self {
+            txn,
+            tag,
+            cipher,
+            _span: ReaderSpanInfo::new(),
+        }
     }
 }
 
-/// Wrapper around `rkv::Writer`, which lifts some of the return values to types recognized by this crate,
-/// rather than the rkv-specific values
-#[derive(From, Shrinkwrap)]
-#[shrinkwrap(mutable, unsafe_ignore_visibility)]
-pub struct Writer<'env>(rkv::Writer<'env>);
+/// Wrapper around a read-write transaction of any backend, which lifts some of the
+/// return values to types recognized by this crate, rather than the backend-specific values
+pub struct Writer<'env> {
+    txn: Box<dyn BackendWrite + 'env>,
+    tag: BackendTag,
+    cipher: Option<&'env EnvCipher>,
+}
+
+impl<'env> Readable for Writer<'env> {
+    fn txn(&self) -> &dyn BackendRead {
+        self.txn.as_read()
+    }
 
-impl<'env> rkv::Readable for Writer<'env> {
-    fn get<K: AsRef<[u8]>>(&self, db: Database, k: &K) -> Result<Option<Value>, StoreError> {
-        self.0.get(db, k)
+    fn tag(&self) -> BackendTag {
+        self.tag
     }
 
-    fn open_ro_cursor(&self, db: Database) -> Result<RoCursor, StoreError> {
-        self.0.open_ro_cursor(db)
+    fn cipher(&self) -> Option<&EnvCipher> {
+        self.cipher
     }
 }
 
 impl<'env> Writer<'env> {
-    /// This override exists solely to raise the Error from the rkv::StoreError,
-    /// which does not implement std::error::Error, into a DatabaseError, which does.
+    /// Wrap a backend's transaction, sealing and opening values
+    /// with `cipher` if there is one
+    pub(crate) fn new(
+        txn: Box<dyn BackendWrite + 'env>,
+        tag: BackendTag,
+        cipher: Option<&'env EnvCipher>,
+    ) -> Self {
+        Self { txn, tag, cipher }
+    }
+
+    /// The underlying transaction, for the backend to write with
+    pub(crate) fn txn_mut(&mut self) -> &mut (dyn BackendWrite + 'env) {
+        &mut *self.txn
+    }
+
+    /// This override exists solely to raise the Error from the backend
+    /// into a DatabaseError, which implements std::error::Error.
     pub fn commit(self) -> Result<(), DatabaseError> {
-        self.0.commit().map_err(DatabaseError::from)
+        self.txn.commit()
     }
 }
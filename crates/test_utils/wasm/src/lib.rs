@@ -17,6 +17,7 @@ pub enum TestWasm {
     Crd,
     Crud,
     Debug,
+    Dpki,
     EntryDefs,
     EmitSignal,
     HashEntry,
@@ -60,6 +61,7 @@ impl From<TestWasm> for ZomeName {
             TestWasm::Crd => "crd",
             TestWasm::Crud => "crud",
             TestWasm::Debug => "debug",
+            TestWasm::Dpki => "dpki",
             TestWasm::EntryDefs => "entry_defs",
             TestWasm::EmitSignal => "emit_signal",
             TestWasm::HashEntry => "hash_entry",
@@ -111,6 +113,7 @@ impl From<TestWasm> for DnaWasm {
             TestWasm::Crd => get_code("wasm32-unknown-unknown/release/test_wasm_crd.wasm"),
             TestWasm::Crud => get_code("wasm32-unknown-unknown/release/test_wasm_crud.wasm"),
             TestWasm::Debug => get_code("wasm32-unknown-unknown/release/test_wasm_debug.wasm"),
+            TestWasm::Dpki => get_code("wasm32-unknown-unknown/release/test_wasm_dpki.wasm"),
             TestWasm::EntryDefs => {
                 get_code("wasm32-unknown-unknown/release/test_wasm_entry_defs.wasm")
             }
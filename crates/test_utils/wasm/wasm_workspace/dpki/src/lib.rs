@@ -0,0 +1,81 @@
+//! A DPKI app for tests, keeping what it knows about keys on its own chain
+use hdk3::prelude::*;
+use holochain_zome_types::{
+    dpki::{KeyRotation, KeyState},
+    timestamp::Timestamp,
+};
+
+#[hdk_entry(id = "key_record")]
+struct KeyRecord {
+    key: AgentPubKey,
+    state: KeyState,
+}
+
+entry_defs![KeyRecord::entry_def()];
+
+fn now() -> ExternResult<Timestamp> {
+    let now = sys_time()?;
+    Ok(Timestamp(now.as_secs() as i64, now.subsec_nanos()))
+}
+
+fn record(key: AgentPubKey, state: KeyState) -> ExternResult<()> {
+    create_entry(&KeyRecord { key, state })?;
+    Ok(())
+}
+
+fn registered_at(key: &AgentPubKey) -> ExternResult<Timestamp> {
+    match key_state(key.clone())? {
+        KeyState::Valid { registered_at } => Ok(registered_at),
+        state => Err(HdkError::Wasm(WasmError::Zome(format!(
+            "{:?} is not valid: {:?}",
+            key, state
+        )))),
+    }
+}
+
+#[hdk_extern]
+fn key_state(key: AgentPubKey) -> ExternResult<KeyState> {
+    let mut state = KeyState::NotFound;
+    for element in query(QueryFilter::new())?.0 {
+        if let Ok(Some(record)) = element.entry().to_app_option::<KeyRecord>() {
+            if record.key == key {
+                state = record.state;
+            }
+        }
+    }
+    Ok(state)
+}
+
+#[hdk_extern]
+fn register_key(key: AgentPubKey) -> ExternResult<()> {
+    let registered_at = now()?;
+    record(key, KeyState::Valid { registered_at })
+}
+
+#[hdk_extern]
+fn rotate_key(rotation: KeyRotation) -> ExternResult<()> {
+    let registered_at = registered_at(&rotation.old_key)?;
+    let now = now()?;
+    record(
+        rotation.old_key,
+        KeyState::Replaced {
+            registered_at,
+            replaced_at: now,
+            new_key: rotation.new_key.clone(),
+        },
+    )?;
+    record(rotation.new_key, KeyState::Valid { registered_at: now })
+}
+
+#[hdk_extern]
+fn revoke_key(key: AgentPubKey) -> ExternResult<()> {
+    let registered_at = registered_at(&key)?;
+    let revoked_at = now()?;
+    record(
+        key,
+        KeyState::Revoked {
+            registered_at,
+            revoked_at,
+        },
+    )
+}
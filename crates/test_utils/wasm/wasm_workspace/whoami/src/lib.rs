@@ -61,3 +61,22 @@ fn call_create_entry(cell_id: CellId) -> ExternResult<HeaderHash> {
         &(),
     )?)
 }
+
+/// Call the create entry zome in the sibling cell
+/// that was installed with the "create" nick.
+#[hdk_extern]
+fn call_create_entry_by_nick(_: ()) -> ExternResult<HeaderHash> {
+    Ok(call_nick(
+        "create".to_string(),
+        "create_entry".to_string().into(),
+        "create_entry".to_string().into(),
+        None,
+        &(),
+    )?)
+}
+
+// returns the cells in the same app as this one
+#[hdk_extern]
+fn list_app_cells(_: ()) -> ExternResult<AppCellsOutput> {
+    Ok(AppCellsOutput::new(app_cells()?))
+}
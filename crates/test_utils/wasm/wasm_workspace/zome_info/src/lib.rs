@@ -1,6 +1,21 @@
 use hdk3::prelude::*;
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize, SerializedBytes, PartialEq)]
+pub struct PostLimits {
+    pub max_length: u32,
+}
+
+#[hdk_entry(id = "post", properties = "PostLimits { max_length: 140 }")]
+pub struct Post(String);
+
+entry_defs![Post::entry_def()];
+
 #[hdk_extern]
 fn zome_info(_: ()) -> ExternResult<ZomeInfoOutput> {
     Ok(ZomeInfoOutput::new(hdk3::prelude::zome_info()?))
 }
+
+#[hdk_extern]
+fn post_limits(_: ()) -> ExternResult<PostLimits> {
+    Ok(entry_type_properties("post")?.unwrap_or_default())
+}
@@ -1,15 +1,21 @@
 //! Collection of cells to form a holochain application
-use crate::{cell::CellId, dna::JsonProperties};
+use crate::{
+    cell::CellId,
+    dna::{zome::ZomeExterns, JsonProperties},
+};
 use derive_more::Into;
-use holo_hash::AgentPubKey;
+use holo_hash::{AgentPubKey, DnaHash};
 use holochain_serialized_bytes::SerializedBytes;
-use std::path::PathBuf;
+use holochain_zome_types::zome::ZomeName;
+use std::{collections::HashMap, path::PathBuf};
+
+pub mod bundle;
+pub mod error;
 
 /// Placeholder used to identify installed apps
 pub type InstalledAppId = String;
 
-/// A friendly (nick)name used by UIs to refer to the Cells which make up the app
-pub type CellNick = String;
+pub use holochain_zome_types::cell::CellNick;
 
 /// A collection of [DnaHash]es paired with an [AgentPubKey] and an app id
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -33,6 +39,10 @@ pub struct InstallAppDnaPayload {
     pub properties: Option<JsonProperties>,
     /// App-specific proof-of-membrane-membership, if required by this app
     pub membrane_proof: Option<MembraneProof>,
+    /// The path of a source chain exported from another conductor, to restore
+    /// instead of running genesis. Its agent must be the agent of the app.
+    #[serde(default)]
+    pub source_chain: Option<PathBuf>,
 }
 
 impl InstallAppDnaPayload {
@@ -43,10 +53,34 @@ impl InstallAppDnaPayload {
             nick,
             properties: None,
             membrane_proof: None,
+            source_chain: None,
         }
     }
 }
 
+/// Install an app from an [AppBundle](bundle::AppBundle) file
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct InstallAppBundlePayload {
+    /// The path of the bundle file
+    pub path: PathBuf,
+    /// The agent that installed this app
+    pub agent_key: AgentPubKey,
+    /// Placeholder to find the installed app.
+    /// Defaults to the name in the bundle's manifest.
+    #[serde(default)]
+    pub installed_app_id: Option<InstalledAppId>,
+    /// Properties to override the manifest's defaults with, by CellNick
+    #[serde(default)]
+    pub properties: HashMap<CellNick, JsonProperties>,
+    /// A UUID to install every Dna of the app with, overriding the manifest
+    #[serde(default)]
+    pub uuid: Option<String>,
+    /// App-specific proofs-of-membrane-membership, by CellNick.
+    /// Required for each Dna the manifest says needs one.
+    #[serde(default)]
+    pub membrane_proofs: HashMap<CellNick, MembraneProof>,
+}
+
 /// App-specific payload for proving membership in the membrane of the app
 pub type MembraneProof = SerializedBytes;
 
@@ -94,3 +128,13 @@ pub struct InstalledApp {
     /// Cell data for this app
     pub cell_data: Vec<InstalledCell>,
 }
+
+/// The functions each Dna of an installed App exports,
+/// so clients can discover what they can call
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AppExterns {
+    /// Placeholder to find the app
+    pub installed_app_id: InstalledAppId,
+    /// The zome functions and callbacks of each zome, for each Dna in this app
+    pub dna_externs: Vec<(DnaHash, Vec<(ZomeName, ZomeExterns)>)>,
+}
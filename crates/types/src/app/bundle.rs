@@ -0,0 +1,341 @@
+//! An app bundle is a single file which describes a whole app: an
+//! [AppManifest] plus the `.dna.gz` files it refers to. It can be installed
+//! with one admin call, instead of listing every Dna in an `InstallAppPayload`.
+//!
+//! A bundle is packed from a json manifest, whose Dna paths are relative to
+//! the manifest file:
+//!
+//! ```json
+//! {
+//!   "name": "chat",
+//!   "dnas": [
+//!     {
+//!       "nick": "chat",
+//!       "path": "chat.dna.gz",
+//!       "properties": { "max_message_length": 280 },
+//!       "membrane_proof_required": true
+//!     }
+//!   ]
+//! }
+//! ```
+
+use super::{
+    error::{AppBundleError, AppBundleResult},
+    CellNick, InstalledAppId, MembraneProof,
+};
+use crate::{
+    dna::{wasm::DnaWasm, DnaDef, DnaFile, JsonProperties},
+    prelude::*,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+/// Describes an app and the Dnas it is made of
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppManifest {
+    /// The name of the app, used as the InstalledAppId unless another is given
+    pub name: InstalledAppId,
+    /// What the app is for
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The Dnas of the app, each of which is installed as a Cell
+    pub dnas: Vec<AppDnaManifest>,
+}
+
+/// Describes a Dna of an app and how to install it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppDnaManifest {
+    /// The CellNick which will be assigned to this Dna when installed
+    pub nick: CellNick,
+    /// The path of the Dna file, relative to the manifest.
+    /// Within a bundle, this is the name the Dna file is embedded under.
+    pub path: String,
+    /// Properties to install this Dna with, unless others are given at install time
+    #[serde(default)]
+    pub properties: Option<JsonProperties>,
+    /// A UUID to install this Dna with instead of its own,
+    /// unless another is given at install time
+    #[serde(default)]
+    pub uuid: Option<String>,
+    /// Whether the Dna can only be installed with a membrane proof
+    #[serde(default)]
+    pub membrane_proof_required: bool,
+}
+
+/// The content of a `.dna.gz` file
+#[derive(Clone, Serialize, Deserialize)]
+struct DnaFileContent(#[serde(with = "serde_bytes")] Vec<u8>);
+
+impl std::fmt::Debug for DnaFileContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("DnaFileContent({} bytes)", self.0.len()))
+    }
+}
+
+/// An [AppManifest] with the Dna files it refers to
+#[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct AppBundle {
+    manifest: AppManifest,
+    dnas: BTreeMap<String, DnaFileContent>,
+}
+
+/// A Dna of an [AppBundle], ready to install
+#[derive(Clone, Debug)]
+pub struct BundledDna {
+    /// The CellNick which will be assigned to this Dna when installed
+    pub nick: CellNick,
+    /// The Dna, with its properties and UUID set
+    pub dna: DnaFile,
+    /// App-specific proof-of-membrane-membership, if one was given
+    pub membrane_proof: Option<MembraneProof>,
+}
+
+impl AppBundle {
+    /// Construct a bundle from a manifest and the content of the Dna files
+    /// it refers to, keyed by their path in the manifest
+    pub fn new(manifest: AppManifest, dnas: BTreeMap<String, Vec<u8>>) -> AppBundleResult<Self> {
+        let bundle = Self {
+            manifest,
+            dnas: dnas
+                .into_iter()
+                .map(|(path, content)| (path, DnaFileContent(content)))
+                .collect(),
+        };
+        bundle.check()?;
+        Ok(bundle)
+    }
+
+    /// Pack a bundle from a json manifest file and the Dna files next to it
+    pub async fn from_manifest_file(manifest_path: &Path) -> AppBundleResult<Self> {
+        let manifest = tokio::fs::read(manifest_path).await?;
+        let manifest: AppManifest = serde_json::from_slice(&manifest)
+            .map_err(|e| AppBundleError::InvalidManifest(e.to_string()))?;
+        let dir = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+        let mut dnas = BTreeMap::new();
+        for dna in &manifest.dnas {
+            let content = tokio::fs::read(dir.join(&dna.path)).await?;
+            // Catch broken Dna files when packing rather than when installing
+            DnaFile::from_file_content(&content).await?;
+            dnas.insert(dna.path.clone(), content);
+        }
+        Self::new(manifest, dnas)
+    }
+
+    /// Load a bundle from the content of a bundle file
+    pub fn from_file_content(data: &[u8]) -> AppBundleResult<Self> {
+        let sb = SerializedBytes::from(UnsafeBytes::from(data.to_vec()));
+        let bundle: Self = sb.try_into()?;
+        bundle.check()?;
+        Ok(bundle)
+    }
+
+    /// Render this bundle as the content of a bundle file
+    pub fn to_file_content(&self) -> AppBundleResult<Vec<u8>> {
+        let sb: SerializedBytes = self.clone().try_into()?;
+        Ok(sb.bytes().to_vec())
+    }
+
+    /// The manifest of the app
+    pub fn manifest(&self) -> &AppManifest {
+        &self.manifest
+    }
+
+    /// Get the Dnas of the app ready to install, with their properties and
+    /// UUID set. Properties and membrane proofs are given by nick.
+    /// Properties given here replace the defaults of the manifest, and a UUID
+    /// given here is used for every Dna instead of the UUIDs of the manifest.
+    pub async fn resolve(
+        self,
+        mut properties: HashMap<CellNick, JsonProperties>,
+        uuid: Option<String>,
+        mut membrane_proofs: HashMap<CellNick, MembraneProof>,
+    ) -> AppBundleResult<Vec<BundledDna>> {
+        // Catch typos in the nicks before installing anything
+        if let Some(nick) = properties
+            .keys()
+            .chain(membrane_proofs.keys())
+            .find(|nick| !self.manifest.dnas.iter().any(|dna| &dna.nick == *nick))
+        {
+            return Err(AppBundleError::UnknownNick(nick.clone()));
+        }
+
+        let mut dnas = Vec::with_capacity(self.manifest.dnas.len());
+        for manifest in self.manifest.dnas {
+            let membrane_proof = membrane_proofs.remove(&manifest.nick);
+            if manifest.membrane_proof_required && membrane_proof.is_none() {
+                return Err(AppBundleError::MembraneProofMissing(manifest.nick));
+            }
+            let content = self
+                .dnas
+                .get(&manifest.path)
+                .ok_or_else(|| AppBundleError::DnaFileMissing(manifest.path.clone()))?;
+
+            let dna = DnaFile::from_file_content(&content.0).await?;
+            let (mut dna_def, wasm): (DnaDef, Vec<DnaWasm>) = dna.into();
+            if let Some(properties) = properties.remove(&manifest.nick).or(manifest.properties) {
+                dna_def.properties = properties.try_into()?;
+            }
+            if let Some(uuid) = uuid.clone().or(manifest.uuid) {
+                dna_def.uuid = uuid;
+            }
+            dnas.push(BundledDna {
+                nick: manifest.nick,
+                dna: DnaFile::new(dna_def, wasm).await?,
+                membrane_proof,
+            });
+        }
+        Ok(dnas)
+    }
+
+    /// Check that the manifest's nicks are unique and its Dnas are all here
+    fn check(&self) -> AppBundleResult<()> {
+        let mut nicks = HashSet::new();
+        for dna in &self.manifest.dnas {
+            if !nicks.insert(&dna.nick) {
+                return Err(AppBundleError::DuplicateNick(dna.nick.clone()));
+            }
+            if !self.dnas.contains_key(&dna.path) {
+                return Err(AppBundleError::DnaFileMissing(dna.path.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fake_dna_file;
+
+    fn dna_manifest(nick: &str, path: &str) -> AppDnaManifest {
+        AppDnaManifest {
+            nick: nick.into(),
+            path: path.into(),
+            properties: None,
+            uuid: None,
+            membrane_proof_required: false,
+        }
+    }
+
+    async fn bundle(dnas: Vec<AppDnaManifest>) -> AppBundle {
+        let content = fake_dna_file("a").to_file_content().await.unwrap();
+        let files = dnas
+            .iter()
+            .map(|dna| (dna.path.clone(), content.clone()))
+            .collect();
+        let manifest = AppManifest {
+            name: "app".into(),
+            description: None,
+            dnas,
+        };
+        AppBundle::new(manifest, files).unwrap()
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn bundle_roundtrip() {
+        let bundle = bundle(vec![dna_manifest("one", "one.dna.gz")]).await;
+        let content = bundle.to_file_content().unwrap();
+        let bundle = AppBundle::from_file_content(&content).unwrap();
+        assert_eq!(bundle.manifest().name, "app");
+
+        let dnas = bundle
+            .resolve(HashMap::new(), None, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(dnas.len(), 1);
+        assert_eq!(dnas[0].dna.dna_hash(), fake_dna_file("a").dna_hash());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn manifest_must_match_files() {
+        let content = fake_dna_file("a").to_file_content().await.unwrap();
+        let manifest = AppManifest {
+            name: "app".into(),
+            description: None,
+            dnas: vec![
+                dna_manifest("one", "one.dna.gz"),
+                dna_manifest("two", "two.dna.gz"),
+            ],
+        };
+        let mut files = BTreeMap::new();
+        files.insert("one.dna.gz".to_string(), content);
+        assert!(matches!(
+            AppBundle::new(manifest.clone(), files.clone()),
+            Err(AppBundleError::DnaFileMissing(path)) if path == "two.dna.gz"
+        ));
+
+        let mut manifest = manifest;
+        manifest.dnas[1] = dna_manifest("one", "one.dna.gz");
+        assert!(matches!(
+            AppBundle::new(manifest, files),
+            Err(AppBundleError::DuplicateNick(nick)) if nick == "one"
+        ));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn resolve_applies_overrides() {
+        let mut one = dna_manifest("one", "one.dna.gz");
+        one.properties = Some(JsonProperties::new(serde_json::json!({ "p": "default" })));
+        one.uuid = Some("manifest".into());
+        let mut two = dna_manifest("two", "two.dna.gz");
+        two.membrane_proof_required = true;
+        let bundle = bundle(vec![one, two]).await;
+
+        let proof = SerializedBytes::from(UnsafeBytes::from(vec![1, 2, 3]));
+        let mut proofs = HashMap::new();
+        assert!(matches!(
+            bundle
+                .clone()
+                .resolve(HashMap::new(), None, proofs.clone())
+                .await,
+            Err(AppBundleError::MembraneProofMissing(nick)) if nick == "two"
+        ));
+        proofs.insert("two".to_string(), proof.clone());
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            "three".to_string(),
+            JsonProperties::new(serde_json::json!({})),
+        );
+        assert!(matches!(
+            bundle
+                .clone()
+                .resolve(properties, None, proofs.clone())
+                .await,
+            Err(AppBundleError::UnknownNick(nick)) if nick == "three"
+        ));
+
+        let dnas = bundle
+            .clone()
+            .resolve(HashMap::new(), None, proofs.clone())
+            .await
+            .unwrap();
+        let default: SerializedBytes = JsonProperties::new(serde_json::json!({ "p": "default" }))
+            .try_into()
+            .unwrap();
+        assert_eq!(dnas[0].dna.dna().properties, default);
+        assert_eq!(dnas[0].dna.dna().uuid, "manifest");
+        assert_eq!(dnas[0].membrane_proof, None);
+        assert_eq!(dnas[1].dna.dna().uuid, "a");
+        assert_eq!(dnas[1].membrane_proof, Some(proof));
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            "one".to_string(),
+            JsonProperties::new(serde_json::json!({ "p": "installed" })),
+        );
+        let dnas = bundle
+            .resolve(properties, Some("installed".into()), proofs)
+            .await
+            .unwrap();
+        let installed: SerializedBytes =
+            JsonProperties::new(serde_json::json!({ "p": "installed" }))
+                .try_into()
+                .unwrap();
+        assert_eq!(dnas[0].dna.dna().properties, installed);
+        assert_eq!(dnas[0].dna.dna().uuid, "installed");
+        assert_eq!(dnas[1].dna.dna().uuid, "installed");
+    }
+}
@@ -0,0 +1,51 @@
+//! Holochain AppBundleError type.
+
+use super::CellNick;
+use crate::dna::error::DnaError;
+use thiserror::Error;
+
+/// Errors in reading, validating or installing an [AppBundle](super::AppBundle)
+#[derive(Clone, Debug, Error)]
+pub enum AppBundleError {
+    /// The manifest lists a Dna which isn't embedded in the bundle
+    #[error("The manifest refers to {0}, which isn't in the bundle")]
+    DnaFileMissing(String),
+
+    /// Two Dnas of the manifest have the same nick
+    #[error("The manifest has more than one Dna with the nick: {0}")]
+    DuplicateNick(CellNick),
+
+    /// Properties or a membrane proof were given for a nick the manifest doesn't have
+    #[error("The manifest has no Dna with the nick: {0}")]
+    UnknownNick(CellNick),
+
+    /// The manifest requires a membrane proof which wasn't given
+    #[error("The Dna with the nick {0} requires a membrane proof")]
+    MembraneProofMissing(CellNick),
+
+    /// The manifest isn't valid json
+    #[error("The manifest is invalid: {0}")]
+    InvalidManifest(String),
+
+    /// An embedded Dna is invalid
+    #[error(transparent)]
+    DnaError(#[from] DnaError),
+
+    /// SerializedBytesError
+    #[error("SerializedBytesError: {0}")]
+    SerializedBytesError(#[from] holochain_serialized_bytes::SerializedBytesError),
+
+    /// std::io::Error
+    /// we don't #[from] the std::io::Error directly because it doesn't implement Clone
+    #[error("std::io::Error: {0}")]
+    StdIoError(String),
+}
+
+impl From<std::io::Error> for AppBundleError {
+    fn from(error: std::io::Error) -> Self {
+        Self::StdIoError(error.to_string())
+    }
+}
+
+/// Result type for AppBundleError
+pub type AppBundleResult<T> = Result<T, AppBundleError>;
@@ -2,6 +2,8 @@
 
 use derive_more::Constructor;
 use holochain_serialized_bytes::prelude::*;
+use holochain_zome_types::{header::ZomeId, zome::FunctionName};
+use std::collections::BTreeSet;
 
 /// Represents an individual "zome".
 #[derive(
@@ -12,6 +14,15 @@ pub struct Zome {
     pub wasm_hash: holo_hash::WasmHash,
 }
 
+/// The functions a zome's wasm exports
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZomeExterns {
+    /// Functions that can be called with a zome call
+    pub zome_fns: Vec<FunctionName>,
+    /// Callbacks that holochain calls itself, such as `init` and `validate`
+    pub callbacks: Vec<FunctionName>,
+}
+
 /// Access a call has to host functions
 #[derive(Debug, Copy, Clone, Constructor, PartialEq)]
 pub struct HostFnAccess {
@@ -76,3 +87,141 @@ impl HostFnAccess {
         }
     }
 }
+
+/// Callbacks which are only ever called by one name
+const CALLBACKS: &[&str] = &[
+    "entry_defs",
+    "init",
+    "migrate_agent",
+    "migrate_agent_open",
+    "migrate_agent_close",
+    "post_commit",
+    "validate_create_link",
+    "validate_delete_link",
+    "validation_package",
+];
+
+/// Every name the host calls a zome's callbacks by.
+///
+/// `validate` is called by the most specific name built from the header
+/// and entry being validated, e.g. `validate_create_entry_post` for the
+/// zome's `post` entry type, so the names depend on the ids of the zome's
+/// entry defs. `validation_package` is called with the id of the zome.
+pub fn callback_names(zome_id: ZomeId, entry_def_ids: &[String]) -> BTreeSet<String> {
+    let mut names = validate_names(entry_def_ids);
+    names.extend(CALLBACKS.iter().map(|name| name.to_string()));
+    names.insert(format!("validation_package_{}", zome_id));
+    names
+}
+
+/// The names `validate` is called by for these entry types
+fn validate_names(entry_def_ids: &[String]) -> BTreeSet<String> {
+    let mut entries = vec![None, Some("agent".to_string()), Some("entry".to_string())];
+    entries.extend(entry_def_ids.iter().map(|id| Some(format!("entry_{}", id))));
+    let mut names = BTreeSet::new();
+    for header in &[None, Some("create"), Some("update"), Some("delete")] {
+        for entry in &entries {
+            let name = std::iter::once("validate")
+                .chain(*header)
+                .chain(entry.as_deref())
+                .collect::<Vec<_>>()
+                .join("_");
+            names.insert(name);
+        }
+    }
+    names
+}
+
+/// Is this exported function a callback rather than a zome function?
+/// See [callback_names].
+pub fn is_callback(fn_name: &str, zome_id: ZomeId, entry_def_ids: &[String]) -> bool {
+    callback_names(zome_id, entry_def_ids).contains(fn_name)
+}
+
+/// Is this the name of a callback whatever the zome's entry types and
+/// position in the dna? These are the callbacks any zome can export.
+pub fn is_common_callback(fn_name: &str) -> bool {
+    CALLBACKS.contains(&fn_name) || validate_names(&[]).contains(fn_name)
+}
+
+/// The id of the entry type a `validate` callback is named after, if any,
+/// e.g. `post` for `validate_create_entry_post`.
+///
+/// Only the zome's `entry_defs` callback knows which entry types it has, so
+/// a zome exporting such a function may still not define the entry type.
+pub fn validate_callback_entry_def_id(fn_name: &str) -> Option<&str> {
+    [
+        "validate_entry_",
+        "validate_create_entry_",
+        "validate_update_entry_",
+        "validate_delete_entry_",
+    ]
+    .iter()
+    .find(|prefix| fn_name.starts_with(*prefix))
+    .map(|prefix| &fn_name[prefix.len()..])
+    .filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callbacks_are_recognised() {
+        let zome_id = ZomeId::from(1);
+        let entry_def_ids = vec!["post".to_string()];
+        for fn_name in &[
+            "init",
+            "entry_defs",
+            "migrate_agent_open",
+            "post_commit",
+            "validate",
+            "validate_agent",
+            "validate_create_entry",
+            "validate_create_entry_post",
+            "validate_update_entry_post",
+            "validate_entry_post",
+            "validate_delete_link",
+            "validation_package",
+            "validation_package_1",
+        ] {
+            assert!(is_callback(fn_name, zome_id, &entry_def_ids), "{}", fn_name);
+        }
+        for fn_name in &[
+            "foo",
+            "initialize",
+            "init_profile",
+            "validate_email",
+            "validated_posts",
+            "validate_create_entry_comment",
+            "validation_package_0",
+            "migrate_agent_later",
+            "get_init",
+        ] {
+            assert!(
+                !is_callback(fn_name, zome_id, &entry_def_ids),
+                "{}",
+                fn_name
+            );
+        }
+
+        // Callbacks named after an entry type or zome id are particular to a zome
+        assert!(is_common_callback("validate_create_entry"));
+        assert!(!is_common_callback("validate_create_entry_post"));
+        assert!(!is_common_callback("validation_package_1"));
+
+        assert_eq!(
+            validate_callback_entry_def_id("validate_create_entry_post"),
+            Some("post")
+        );
+        assert_eq!(
+            validate_callback_entry_def_id("validate_entry_post"),
+            Some("post")
+        );
+        assert_eq!(
+            validate_callback_entry_def_id("validate_create_entry"),
+            None
+        );
+        assert_eq!(validate_callback_entry_def_id("validate_email"), None);
+    }
+}
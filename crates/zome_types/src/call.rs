@@ -1,36 +1,56 @@
 use crate::capability::CapSecret;
-use crate::cell::CellId;
+use crate::cell::{CellId, CellNick};
 use crate::zome::FunctionName;
 use crate::zome::ZomeName;
-use holo_hash::AgentPubKey;
 use holochain_serialized_bytes::prelude::SerializedBytes;
 
+/// The cell a [Call] is made to
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum CallTarget {
+    /// The cell making the call
+    ThisCell,
+    /// Any cell on the conductor
+    Cell(CellId),
+    /// A cell in the same app as the calling cell, by the nick it
+    /// was installed with. The conductor finds the cell when the call is made.
+    CellNick(CellNick),
+}
+
+impl From<Option<CellId>> for CallTarget {
+    fn from(to_cell: Option<CellId>) -> Self {
+        match to_cell {
+            Some(cell_id) => Self::Cell(cell_id),
+            None => Self::ThisCell,
+        }
+    }
+}
+
+/// A call from one zome to another on the same conductor.
+/// There is no provenance because the host always makes the call
+/// as the calling cell's agent.
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Call {
-    pub to_cell: Option<CellId>,
+    pub target: CallTarget,
     pub zome_name: ZomeName,
     pub fn_name: FunctionName,
     pub cap: Option<CapSecret>,
     pub request: SerializedBytes,
-    pub provenance: AgentPubKey,
 }
 
 impl Call {
     pub fn new(
-        to_cell: Option<CellId>,
+        target: CallTarget,
         zome_name: ZomeName,
         fn_name: FunctionName,
         cap: Option<CapSecret>,
         request: SerializedBytes,
-        provenance: AgentPubKey,
     ) -> Self {
         Self {
-            to_cell,
+            target,
             zome_name,
             fn_name,
             cap,
             request,
-            provenance,
         }
     }
 }
@@ -5,6 +5,10 @@ use holo_hash::{AgentPubKey, DnaHash};
 use holochain_serialized_bytes::prelude::*;
 use std::fmt;
 
+/// A friendly (nick)name used by UIs to refer to the Cells which make up the app.
+/// Zomes can also use it to call their sibling cells in the same app.
+pub type CellNick = String;
+
 /// The unique identifier for a Cell.
 /// Cells are uniquely determined by this pair - this pair is necessary
 /// and sufficient to refer to a cell in a conductor
@@ -0,0 +1,126 @@
+//! Types for the DPKI app
+//!
+//! A conductor can be configured with an installed app which acts as its
+//! distributed public key infrastructure. The DPKI app records every agent key
+//! that is registered, which keys have been replaced by a newer key and which
+//! have been revoked. The conductor calls the functions below on the
+//! [DPKI_ZOME_NAME] zome of the app's first cell.
+
+use crate::timestamp::Timestamp;
+use holo_hash::AgentPubKey;
+pub use holochain_serialized_bytes::prelude::*;
+
+/// The zome of the DPKI app the conductor calls
+pub const DPKI_ZOME_NAME: &str = "dpki";
+
+/// Registers a new key.
+/// Takes an [AgentPubKey] and returns `()`.
+pub const REGISTER_KEY_FN: &str = "register_key";
+
+/// Replaces a key with a new one.
+/// Takes a [KeyRotation] and returns `()`.
+pub const ROTATE_KEY_FN: &str = "rotate_key";
+
+/// Revokes a key.
+/// Takes an [AgentPubKey] and returns `()`.
+pub const REVOKE_KEY_FN: &str = "revoke_key";
+
+/// Looks up what the DPKI app knows about a key.
+/// Takes an [AgentPubKey] and returns a [KeyState].
+pub const KEY_STATE_FN: &str = "key_state";
+
+/// A request to replace an agent's key with a new one
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
+pub struct KeyRotation {
+    /// The key being replaced
+    pub old_key: AgentPubKey,
+    /// The key that replaces it
+    pub new_key: AgentPubKey,
+}
+
+/// Everything the DPKI app knows about a key.
+/// Times are when the DPKI app recorded the change.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
+pub enum KeyState {
+    /// The key has never been registered
+    NotFound,
+    /// The key was registered and is still in use
+    Valid {
+        /// When the key was registered
+        registered_at: Timestamp,
+    },
+    /// The key was replaced by a newer key
+    Replaced {
+        /// When the key was registered
+        registered_at: Timestamp,
+        /// When the key was replaced
+        replaced_at: Timestamp,
+        /// The key that replaced it
+        new_key: AgentPubKey,
+    },
+    /// The key was revoked, usually because it was lost or compromised
+    Revoked {
+        /// When the key was registered
+        registered_at: Timestamp,
+        /// When the key was revoked
+        revoked_at: Timestamp,
+    },
+}
+
+impl KeyState {
+    /// Is the key still in use now?
+    pub fn is_current(&self) -> bool {
+        matches!(self, KeyState::Valid { .. })
+    }
+
+    /// Had the key been revoked by this time?
+    /// A revoked key is still valid for what it signed before it was
+    /// revoked.
+    pub fn is_revoked_at(&self, timestamp: Timestamp) -> bool {
+        match self {
+            KeyState::Revoked { revoked_at, .. } => *revoked_at <= timestamp,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fake_agent_pubkey_2;
+
+    #[test]
+    fn key_is_only_current_while_in_use() {
+        let registered_at = Timestamp(1, 0);
+        let until = Timestamp(3, 0);
+
+        assert!(!KeyState::NotFound.is_current());
+        assert!(KeyState::Valid { registered_at }.is_current());
+
+        let replaced = KeyState::Replaced {
+            registered_at,
+            replaced_at: until,
+            new_key: fake_agent_pubkey_2(),
+        };
+        let revoked = KeyState::Revoked {
+            registered_at,
+            revoked_at: until,
+        };
+        assert!(!replaced.is_current());
+        assert!(!revoked.is_current());
+    }
+
+    #[test]
+    fn key_is_revoked_from_its_revocation() {
+        let registered_at = Timestamp(1, 0);
+        let revoked = KeyState::Revoked {
+            registered_at,
+            revoked_at: Timestamp(3, 0),
+        };
+
+        assert!(!revoked.is_revoked_at(Timestamp(2, 0)));
+        assert!(revoked.is_revoked_at(Timestamp(3, 0)));
+        assert!(revoked.is_revoked_at(Timestamp(4, 0)));
+        assert!(!KeyState::Valid { registered_at }.is_revoked_at(Timestamp(4, 0)));
+    }
+}
@@ -76,6 +76,10 @@ pub struct EntryDef {
     pub required_validations: RequiredValidations,
     /// The required validation package for this entry
     pub required_validation_type: RequiredValidationType,
+    /// Arbitrary properties of this entry type which the zome can read
+    /// with `entry_type_properties`
+    #[serde(default)]
+    pub properties: Option<SerializedBytes>,
 }
 
 impl EntryDef {
@@ -92,6 +96,7 @@ impl EntryDef {
             crdt_type,
             required_validations,
             required_validation_type,
+            properties: None,
         }
     }
 }
@@ -177,6 +182,7 @@ mod tests {
                 crdt_type: CrdtType,
                 required_validations: 5.into(),
                 required_validation_type: RequiredValidationType::default(),
+                properties: None,
             }]
             .into(),
         );
@@ -21,6 +21,7 @@ pub mod cell;
 #[allow(missing_docs)]
 pub mod crdt;
 pub mod debug;
+pub mod dpki;
 pub mod element;
 pub mod entry;
 #[allow(missing_docs)]
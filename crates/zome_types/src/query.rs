@@ -2,10 +2,13 @@
 
 use crate::{
     header::{EntryType, Header, HeaderType},
-    warrant::Warrant,
+    timestamp::Timestamp,
+    warrant::SignedWarrant,
 };
 use holo_hash::HeaderHash;
 pub use holochain_serialized_bytes::prelude::*;
+use std::collections::HashSet;
+use std::ops::{Bound, RangeBounds};
 
 /// Query arguments
 #[derive(
@@ -14,15 +17,94 @@ pub use holochain_serialized_bytes::prelude::*;
 #[non_exhaustive]
 pub struct ChainQueryFilter {
     /// The range of source chain sequence numbers to match.
+    /// Either end may be unbounded.
+    pub sequence_range: Option<ChainQueryRange>,
+    /// Filter by EntryType, matching any of the types in the set
+    pub entry_type: Option<HashSet<EntryType>>,
+    /// Filter by HeaderType, matching any of the types in the set
+    pub header_type: Option<HashSet<HeaderType>>,
+    /// The range of header timestamps to match.
     /// Inclusive start, exclusive end.
-    // TODO: can we generalize this over RangeBounds to allow unbounded ranges?
-    pub sequence_range: Option<std::ops::Range<u32>>,
-    /// Filter by EntryType
-    pub entry_type: Option<EntryType>,
-    /// Filter by HeaderType
-    pub header_type: Option<HeaderType>,
+    pub timestamp_range: Option<std::ops::Range<Timestamp>>,
     /// Include the entries in the elements
     pub include_entries: bool,
+    /// The order the matching elements are returned in
+    pub order: ChainQueryOrder,
+    /// Return at most this many elements
+    pub limit: Option<u32>,
+}
+
+/// A range of source chain sequence numbers.
+/// This is the serializable form of any `RangeBounds<u32>`
+/// so that unbounded ranges can cross the wasm boundary.
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct ChainQueryRange {
+    /// The start of the range
+    pub start: Bound<u32>,
+    /// The end of the range
+    pub end: Bound<u32>,
+}
+
+/// The order that a source chain query returns elements in
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum ChainQueryOrder {
+    /// From the origin (Dna) header towards the chain head
+    Ascending,
+    /// From the chain head back towards the origin (Dna) header
+    Descending,
+}
+
+impl Default for ChainQueryOrder {
+    fn default() -> Self {
+        Self::Descending
+    }
+}
+
+impl ChainQueryRange {
+    /// Create a range from any `RangeBounds`, e.g. `0..10`, `5..` or `..=3`
+    pub fn new<R: RangeBounds<u32>>(range: R) -> Self {
+        Self {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// The first sequence number included in this range
+    pub fn inclusive_start(&self) -> u32 {
+        match self.start {
+            Bound::Included(s) => s,
+            Bound::Excluded(s) => s.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+    }
+
+    /// One past the last sequence number included in this range
+    /// or None if the range has no upper bound
+    pub fn exclusive_end(&self) -> Option<u32> {
+        match self.end {
+            Bound::Included(e) => e.checked_add(1),
+            Bound::Excluded(e) => Some(e),
+            Bound::Unbounded => None,
+        }
+    }
+}
+
+impl RangeBounds<u32> for ChainQueryRange {
+    fn start_bound(&self) -> Bound<&u32> {
+        match &self.start {
+            Bound::Included(s) => Bound::Included(s),
+            Bound::Excluded(s) => Bound::Excluded(s),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+
+    fn end_bound(&self) -> Bound<&u32> {
+        match &self.end {
+            Bound::Included(e) => Bound::Included(e),
+            Bound::Excluded(e) => Bound::Excluded(e),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
@@ -37,9 +119,9 @@ pub struct AgentActivity {
     /// The highest chain header that has
     /// been observed by this authority.
     pub highest_observed: Option<HighestObserved>,
-    /// Warrants about this AgentActivity.
-    /// Placeholder for future.
-    pub warrants: Vec<Warrant>,
+    /// Signed evidence from validators that this agent
+    /// authored invalid data.
+    pub warrants: Vec<SignedWarrant>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
@@ -116,21 +198,51 @@ impl ChainQueryFilter {
         }
     }
 
-    /// Filter on sequence range
-    pub fn sequence_range(mut self, sequence_range: std::ops::Range<u32>) -> Self {
-        self.sequence_range = Some(sequence_range);
+    /// Filter on sequence range.
+    /// Accepts any range, including unbounded ranges like `10..`
+    pub fn sequence_range<R: RangeBounds<u32>>(mut self, sequence_range: R) -> Self {
+        self.sequence_range = Some(ChainQueryRange::new(sequence_range));
         self
     }
 
-    /// Filter on entry type
+    /// Filter on entry type.
+    /// Calling this more than once matches any of the given types.
     pub fn entry_type(mut self, entry_type: EntryType) -> Self {
-        self.entry_type = Some(entry_type);
+        self.entry_type
+            .get_or_insert_with(HashSet::new)
+            .insert(entry_type);
+        self
+    }
+
+    /// Filter on a set of entry types
+    pub fn entry_types<I: IntoIterator<Item = EntryType>>(mut self, entry_types: I) -> Self {
+        self.entry_type
+            .get_or_insert_with(HashSet::new)
+            .extend(entry_types);
         self
     }
 
-    /// Filter on header type
+    /// Filter on header type.
+    /// Calling this more than once matches any of the given types.
     pub fn header_type(mut self, header_type: HeaderType) -> Self {
-        self.header_type = Some(header_type);
+        self.header_type
+            .get_or_insert_with(HashSet::new)
+            .insert(header_type);
+        self
+    }
+
+    /// Filter on a set of header types
+    pub fn header_types<I: IntoIterator<Item = HeaderType>>(mut self, header_types: I) -> Self {
+        self.header_type
+            .get_or_insert_with(HashSet::new)
+            .extend(header_types);
+        self
+    }
+
+    /// Filter on header timestamp.
+    /// Inclusive start, exclusive end.
+    pub fn timestamp_range(mut self, timestamp_range: std::ops::Range<Timestamp>) -> Self {
+        self.timestamp_range = Some(timestamp_range);
         self
     }
 
@@ -140,29 +252,58 @@ impl ChainQueryFilter {
         self
     }
 
-    /// Perform the boolean check which this filter represents
+    /// Set the order of the returned elements
+    pub fn order(mut self, order: ChainQueryOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Return elements from the origin of the chain first
+    pub fn ascending(self) -> Self {
+        self.order(ChainQueryOrder::Ascending)
+    }
+
+    /// Return elements from the head of the chain first
+    pub fn descending(self) -> Self {
+        self.order(ChainQueryOrder::Descending)
+    }
+
+    /// Return at most this many elements
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Perform the boolean check which this filter represents.
+    /// Note that `order` and `limit` apply to the whole result set
+    /// so are not checked here.
     pub fn check(&self, header: &Header) -> bool {
         let check_range = self
             .sequence_range
             .as_ref()
             .map(|range| range.contains(&header.header_seq()))
             .unwrap_or(true);
+        let check_timestamp = self
+            .timestamp_range
+            .as_ref()
+            .map(|range| range.contains(&header.timestamp()))
+            .unwrap_or(true);
         let check_header_type = self
             .header_type
             .as_ref()
-            .map(|header_type| header.header_type() == *header_type)
+            .map(|header_types| header_types.contains(&header.header_type()))
             .unwrap_or(true);
         let check_entry_type = self
             .entry_type
             .as_ref()
-            .map(|entry_type| {
+            .map(|entry_types| {
                 header
                     .entry_type()
-                    .map(|header_entry_type| *header_entry_type == *entry_type)
+                    .map(|header_entry_type| entry_types.contains(header_entry_type))
                     .unwrap_or(false)
             })
             .unwrap_or(true);
-        check_range && check_header_type && check_entry_type
+        check_range && check_timestamp && check_header_type && check_entry_type
     }
 }
 
@@ -171,6 +312,7 @@ impl ChainQueryFilter {
 mod tests {
     use crate::fixt::AppEntryTypeFixturator;
     use crate::header::EntryType;
+    use crate::timestamp::Timestamp;
     use crate::{fixt::*, Header};
     use ::fixt::prelude::*;
 
@@ -185,24 +327,30 @@ mod tests {
         let mut h1 = fixt!(Create);
         h1.entry_type = entry_type_1.clone();
         h1.header_seq = 0;
+        h1.timestamp = Timestamp(0, 0);
 
         let mut h2 = fixt!(Update);
         h2.entry_type = entry_type_2.clone();
         h2.header_seq = 1;
+        h2.timestamp = Timestamp(1, 0);
 
         let mut h3 = fixt!(CreateLink);
         h3.header_seq = 2;
+        h3.timestamp = Timestamp(2, 0);
 
         let mut h4 = fixt!(Create);
         h4.entry_type = entry_type_2.clone();
         h4.header_seq = 3;
+        h4.timestamp = Timestamp(3, 0);
 
         let mut h5 = fixt!(Update);
         h5.entry_type = entry_type_1.clone();
         h5.header_seq = 4;
+        h5.timestamp = Timestamp(4, 0);
 
         let mut h6 = fixt!(CreateLink);
         h6.header_seq = 5;
+        h6.timestamp = Timestamp(5, 0);
 
         let headers = [
             h1.into(),
@@ -323,4 +471,67 @@ mod tests {
             [true, false, false, false, true, false].to_vec()
         );
     }
+
+    #[test]
+    fn filter_by_unbounded_chain_sequence() {
+        let headers = fixtures();
+
+        let query_1 = ChainQueryFilter::new().sequence_range(3..);
+        let query_2 = ChainQueryFilter::new().sequence_range(..=1);
+        let query_3 = ChainQueryFilter::new().sequence_range(..);
+
+        assert_eq!(
+            map_query(&query_1, &headers),
+            [false, false, false, true, true, true].to_vec()
+        );
+        assert_eq!(
+            map_query(&query_2, &headers),
+            [true, true, false, false, false, false].to_vec()
+        );
+        assert_eq!(
+            map_query(&query_3, &headers),
+            [true, true, true, true, true, true].to_vec()
+        );
+    }
+
+    #[test]
+    fn filter_by_multiple_types() {
+        let headers = fixtures();
+
+        let query_1 = ChainQueryFilter::new().entry_types(vec![
+            headers[0].entry_type().unwrap().to_owned(),
+            headers[1].entry_type().unwrap().to_owned(),
+        ]);
+        let query_2 = ChainQueryFilter::new()
+            .header_type(headers[0].header_type())
+            .header_type(headers[2].header_type());
+
+        assert_eq!(
+            map_query(&query_1, &headers),
+            [true, true, false, true, true, false].to_vec()
+        );
+        assert_eq!(
+            map_query(&query_2, &headers),
+            [true, false, true, true, false, true].to_vec()
+        );
+    }
+
+    #[test]
+    fn filter_by_timestamp() {
+        let headers = fixtures();
+
+        let query_1 = ChainQueryFilter::new().timestamp_range(Timestamp(1, 0)..Timestamp(3, 0));
+        let query_2 = ChainQueryFilter::new()
+            .timestamp_range(Timestamp(2, 0)..Timestamp(100, 0))
+            .header_type(headers[2].header_type());
+
+        assert_eq!(
+            map_query(&query_1, &headers),
+            [false, true, true, false, false, false].to_vec()
+        );
+        assert_eq!(
+            map_query(&query_2, &headers),
+            [false, false, true, false, false, true].to_vec()
+        );
+    }
 }
@@ -1,6 +1,76 @@
 //! Types for warrants
+//!
+//! A warrant is a validator's signed claim that an agent authored invalid data.
+//! Anyone holding the warrant can check the issuer's signature and re-validate
+//! the offending header to confirm the claim for themselves.
+
+use crate::{signature::Signature, timestamp::Timestamp};
+use holo_hash::{AgentPubKey, HeaderHash};
 pub use holochain_serialized_bytes::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
-/// Placeholder for warrant type
-pub struct Warrant;
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    SerializedBytes,
+)]
+/// Evidence that an agent authored a header which failed validation
+pub struct Warrant {
+    /// The author of the invalid header
+    pub offender: AgentPubKey,
+    /// The header that failed validation
+    pub header: HeaderHash,
+    /// Why the header failed validation
+    pub reason: String,
+    /// The validator that issued this warrant
+    pub issuer: AgentPubKey,
+    /// When the warrant was issued
+    pub timestamp: Timestamp,
+}
+
+/// A warrant and the issuer's signature over it.
+///
+/// Has implementations From and Into its tuple form.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    SerializedBytes,
+)]
+pub struct SignedWarrant(pub Warrant, pub Signature);
+
+impl SignedWarrant {
+    /// Accessor for the Warrant
+    pub fn warrant(&self) -> &Warrant {
+        &self.0
+    }
+
+    /// Accessor for the issuer's Signature
+    pub fn signature(&self) -> &Signature {
+        &self.1
+    }
+}
+
+impl From<(Warrant, Signature)> for SignedWarrant {
+    fn from((warrant, signature): (Warrant, Signature)) -> Self {
+        Self(warrant, signature)
+    }
+}
+
+impl From<SignedWarrant> for (Warrant, Signature) {
+    fn from(signed: SignedWarrant) -> Self {
+        (signed.0, signed.1)
+    }
+}
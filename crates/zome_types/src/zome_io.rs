@@ -38,6 +38,9 @@ wasm_io_types!(
     pub struct AgentInfoOutput(agent_info::AgentInfo);
     pub struct CallInput(call::Call);
     pub struct CallOutput(ZomeCallResponse);
+    // The cells installed in the same app as the calling cell, by nick.
+    pub struct AppCellsInput(());
+    pub struct AppCellsOutput(Vec<(cell::CellNick, cell::CellId)>);
     // @todo List all the local capability claims.
     pub struct CapabilityClaimsInput(());
     pub struct CapabilityClaimsOutput(());
@@ -62,9 +65,10 @@ wasm_io_types!(
     // @todo
     pub struct ShowEnvInput(());
     pub struct ShowEnvOutput(());
-    // @todo
-    pub struct PropertyInput(());
-    pub struct PropertyOutput(());
+    // The path of keys, or array indexes, to a value in the dna properties.
+    // The value is returned serialized, or None if there is nothing at the path.
+    pub struct PropertyInput(Vec<String>);
+    pub struct PropertyOutput(Option<SerializedBytes>);
     // Query the source chain for data.
     pub struct QueryInput(query::ChainQueryFilter);
     pub struct QueryOutput(ElementVec);
@@ -120,9 +124,9 @@ wasm_io_types!(
         ),
     );
     pub struct GetAgentActivityOutput(query::AgentActivity);
-    // @todo
-    pub struct EntryTypePropertiesInput(());
-    pub struct EntryTypePropertiesOutput(());
+    // The properties declared on an entry def of the current zome, if any.
+    pub struct EntryTypePropertiesInput(entry_def::EntryDefId);
+    pub struct EntryTypePropertiesOutput(Option<SerializedBytes>);
     // Hash an entry on the host.
     pub struct HashEntryInput(entry::Entry);
     pub struct HashEntryOutput(holo_hash::EntryHash);